@@ -0,0 +1,52 @@
+// 命令入口的基准测试：from_frame 把请求帧解析为命令，以及 GET/SET 经过服务器执行（apply）的往返
+use std::time::Duration;
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nano_redis::connect::{server_start, Client, ServerConfig};
+use nano_redis::entity::Frame;
+use nano_redis::Command;
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+fn request(args: &[&str]) -> Frame {
+    Frame::Array(args.iter().map(|arg| Frame::Bulk(Bytes::from(arg.to_string()))).collect())
+}
+
+fn from_frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_frame");
+    for (name, args) in [
+        ("get", &["get", "key"][..]),
+        ("set", &["set", "key", "value"][..]),
+        ("GET", &["GET", "key"][..]),
+        ("SET", &["SET", "key", "value"][..]),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(|| request(args), Command::from_frame, BatchSize::SmallInput)
+        });
+    }
+    group.finish();
+}
+
+// 请求经过连接读取、from_frame、apply 与写回复，和客户端一次往返
+fn round_trip(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut client = runtime.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            server_start::run(listener, None, ServerConfig::default(), std::future::pending::<()>()).await
+        });
+        Client::connect(addr).await.unwrap()
+    });
+
+    let mut group = c.benchmark_group("round_trip");
+    group.measurement_time(Duration::from_secs(3));
+    group.bench_function("set", |b| {
+        b.iter(|| runtime.block_on(client.set("key", Bytes::from("value"), None)).unwrap())
+    });
+    group.bench_function("get", |b| b.iter(|| runtime.block_on(client.get("key")).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, from_frame, round_trip);
+criterion_main!(benches);
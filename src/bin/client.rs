@@ -3,8 +3,10 @@ use clap::{Parser, Subcommand};
 use std::convert::Infallible;
 use std::num::ParseIntError;
 use std::str;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use nano_redis::connect::Client;
+use nano_redis::entity::{Expiry, Frame, SetCondition, SetOptions};
+use nano_redis::utils::serialization::{bytes_to_score, fmt_score};
 use nano_redis::{DEFAULT_PORT};
 
 #[derive(Parser, Debug)]
@@ -18,6 +20,23 @@ struct Cli {
 
     #[clap(long, default_value_t = DEFAULT_PORT)]
     port: u16,
+
+    /// 连接后选择的数据库
+    #[clap(short = 'n', long, default_value_t = 0)]
+    db: usize,
+
+    /// 通过 unix 套接字连接，指定后忽略 hostname 与 port
+    #[clap(long)]
+    unixsocket: Option<String>,
+
+    /// 日志级别，也可以是 RUST_LOG 格式的过滤指令。设置了 RUST_LOG 时以 RUST_LOG 为准
+    #[clap(long, default_value = "warn")]
+    log_level: String,
+
+    /// 使用 TLS 连接，hostname 同时用于校验服务端证书
+    #[cfg(feature = "tls")]
+    #[clap(long)]
+    tls: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -27,10 +46,42 @@ enum CommandParser {
         #[clap(value_parser = bytes_from_str)]
         msg: Option<Bytes>,
     },
+    /// 服务器原样返回 message
+    Echo {
+        #[clap(value_parser = bytes_from_str)]
+        message: Bytes,
+    },
+    /// 返回服务器当前的 Unix 时间
+    Time,
     /// 获取key的值。
     Get {
         key: String,
     },
+    /// 获取字符串的值，--ex/--px 设置新的过期时间，--persist 删除过期时间
+    Getex {
+        key: String,
+        #[clap(long, conflicts_with_all = ["px", "persist"])]
+        ex: Option<u64>,
+        #[clap(long, conflicts_with = "persist")]
+        px: Option<u64>,
+        #[clap(long)]
+        persist: bool,
+    },
+    /// 获取字符串的值并删除 key
+    Getdel {
+        key: String,
+    },
+    /// 返回 key 剩余的生存时间（秒）
+    Ttl {
+        key: String,
+    },
+    /// 返回 key 过期时刻的 Unix 时间戳（秒）
+    Expiretime {
+        key: String,
+        /// 以毫秒为单位（PEXPIRETIME）
+        #[clap(long)]
+        millis: bool,
+    },
     /// 设置 key 以保存字符串值。
     Set {
         key: String,
@@ -38,6 +89,18 @@ enum CommandParser {
         value: Bytes,
         #[clap(value_parser = duration_from_ms_str)]
         expires: Option<Duration>,
+        /// 只在 key 不存在时写入
+        #[clap(long, conflicts_with = "xx")]
+        nx: bool,
+        /// 只在 key 已存在时写入
+        #[clap(long)]
+        xx: bool,
+        /// 保留 key 原有的过期时间
+        #[clap(long, conflicts_with = "expires")]
+        keepttl: bool,
+        /// 返回 key 原来的值
+        #[clap(long)]
+        get: bool,
     },
     Mset {
         datas: Vec<String>
@@ -61,11 +124,37 @@ enum CommandParser {
         key: String,
         datas: Vec<String>,
     },
+    /// 只在 key 已经是列表时插入头部
+    Lpushx {
+        key: String,
+        #[clap(required = true)]
+        datas: Vec<String>,
+    },
+    /// 只在 key 已经是列表时插入尾部
+    Rpushx {
+        key: String,
+        #[clap(required = true)]
+        datas: Vec<String>,
+    },
     Lpop {
         key: String,
+        /// 弹出的元素个数
+        count: Option<u64>,
     },
     Rpop {
         key: String,
+        /// 弹出的元素个数
+        count: Option<u64>,
+    },
+    /// 从第一个非空列表弹出元素
+    Lmpop {
+        keys: Vec<String>,
+        /// 从尾部弹出，默认从头部
+        #[clap(long)]
+        right: bool,
+        /// 弹出的元素个数
+        #[clap(long)]
+        count: Option<u64>,
     },
     Lrange {
         key: String,
@@ -94,14 +183,46 @@ enum CommandParser {
         value: String,
     },
 
-    Sismembers {
+    /// 按顺序返回每个 member 是否在集合中
+    Smismember {
+        key: String,
+        #[clap(value_parser = bytes_from_str)]
+        members: Vec<Bytes>,
+    },
+
+    /// 返回集合的所有元素
+    #[clap(alias = "sismembers")]
+    Smembers {
         key: String,
     },
 
+    /// 返回列表或集合排序后的元素
+    Sort {
+        key: String,
+        /// 按字节序排序，默认按数值排序
+        #[clap(long)]
+        alpha: bool,
+        /// 降序排列
+        #[clap(long)]
+        desc: bool,
+        /// 跳过的元素个数与返回的元素个数
+        #[clap(long, num_args = 2, value_names = ["OFFSET", "COUNT"], allow_negative_numbers = true)]
+        limit: Option<Vec<i64>>,
+    },
+
     Sinter {
         keys: Vec<String>,
     },
 
+    /// 返回多个集合交集的成员个数
+    Sintercard {
+        keys: Vec<String>,
+
+        /// 数到多少个成员就停止，0 表示不限制
+        #[clap(long)]
+        limit: Option<u64>,
+    },
+
     Sdiff {
         keys: Vec<String>,
     },
@@ -109,52 +230,432 @@ enum CommandParser {
     Sunion {
         keys: Vec<String>,
     },
+
+    /// 握手并查看服务器信息
+    Hello {
+        protover: Option<i64>,
+    },
+
+    /// 查询服务器开启的可选功能
+    Features,
+
+    /// 查询服务器支持的命令：count 返回个数，否则列出命令名
+    Command {
+        subcommand: Option<String>,
+    },
+
+    /// 查询服务器统计信息，例如 commandstats
+    Info {
+        section: Option<String>,
+    },
+
+    /// 查看 key 的内部信息，目前支持 encoding、idletime、freq 与 refcount
+    Object {
+        subcommand: String,
+        key: String,
+    },
+
+    /// 内存信息：usage <key> 估算 key 占用的字节数
+    Memory {
+        subcommand: String,
+        key: String,
+    },
+
+    /// 复制 key，--replace 时覆盖已存在的目标
+    Copy {
+        source: String,
+        destination: String,
+        #[clap(long)]
+        replace: bool,
+    },
+
+    /// 返回存在的 key 个数，并刷新它们的访问时间
+    Touch {
+        #[clap(required = true)]
+        keys: Vec<String>,
+    },
+
+    /// 慢查询日志：get [count] | len | reset
+    Slowlog {
+        subcommand: String,
+        #[clap(value_parser = u64_from_str)]
+        count: Option<u64>,
+    },
+
+    /// 设置字符串第 offset 位为 value（0 或 1）
+    Setbit {
+        key: String,
+        offset: u64,
+        #[clap(value_parser = clap::value_parser!(u8).range(0..=1))]
+        value: u8,
+    },
+
+    /// 返回字符串第 offset 位
+    Getbit {
+        key: String,
+        offset: u64,
+    },
+
+    /// 统计字符串中为 1 的位数，可以指定字节范围
+    Bitcount {
+        key: String,
+        #[clap(allow_negative_numbers = true, requires = "end")]
+        start: Option<i64>,
+        #[clap(allow_negative_numbers = true)]
+        end: Option<i64>,
+    },
+
+    /// 查找第一个值为 bit 的位：bitpos key bit [start [end]]
+    Bitpos {
+        key: String,
+        #[clap(value_parser = clap::value_parser!(u8).range(0..=1))]
+        bit: u8,
+        #[clap(allow_negative_numbers = true)]
+        start: Option<i64>,
+        #[clap(allow_negative_numbers = true)]
+        end: Option<i64>,
+    },
+
+    /// 游标式遍历 key：scan cursor [--match pattern] [--count count] [--type type]
+    Scan {
+        cursor: u64,
+        /// 只返回匹配 glob 模式的 key
+        #[clap(long = "match")]
+        pattern: Option<String>,
+        /// 每次遍历的 key 个数
+        #[clap(long)]
+        count: Option<u64>,
+        /// 只返回这种类型的 key：string、list、set 或 hash
+        #[clap(long = "type")]
+        type_name: Option<String>,
+    },
+
+    /// 游标式遍历哈希的字段与值
+    Hscan {
+        key: String,
+        cursor: u64,
+        /// 只返回匹配 glob 模式的字段
+        #[clap(long = "match")]
+        pattern: Option<String>,
+        /// 每次遍历的字段个数
+        #[clap(long)]
+        count: Option<u64>,
+    },
+
+    /// 游标式遍历集合的元素
+    Sscan {
+        key: String,
+        cursor: u64,
+        /// 只返回匹配 glob 模式的元素
+        #[clap(long = "match")]
+        pattern: Option<String>,
+        /// 每次遍历的元素个数
+        #[clap(long)]
+        count: Option<u64>,
+    },
+
+    /// 把 key 从当前数据库移动到另一个数据库
+    Move {
+        key: String,
+        db: usize,
+    },
+
+    /// 删除当前数据库中的所有 key
+    Flushdb {
+        /// 服务器在后台释放旧的数据
+        #[clap(long = "async")]
+        lazy: bool,
+    },
+
+    /// 删除所有数据库中的所有 key
+    Flushall {
+        /// 服务器在后台释放旧的数据
+        #[clap(long = "async")]
+        lazy: bool,
+    },
+
+    /// 设置哈希的多个字段：hmset key field value [field value ...]
+    Hmset {
+        key: String,
+        #[clap(required = true)]
+        pairs: Vec<String>,
+    },
+
+    /// 只在字段不存在时设置哈希的字段
+    Hsetnx {
+        key: String,
+        #[clap(value_parser = bytes_from_str)]
+        field: Bytes,
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// 随机返回哈希的字段：hrandfield key [count [--withvalues]]
+    Hrandfield {
+        key: String,
+        /// 返回的字段个数，负数时字段可能重复
+        #[clap(allow_negative_numbers = true)]
+        count: Option<i64>,
+        /// 同时返回字段的值
+        #[clap(long, requires = "count")]
+        withvalues: bool,
+    },
+
+    /// 读取哈希的多个字段
+    Hmget {
+        key: String,
+        #[clap(required = true)]
+        fields: Vec<String>,
+    },
+
+    /// 把 value 追加到字符串末尾，回复追加后的长度
+    Append {
+        key: String,
+        #[clap(value_parser = bytes_from_str)]
+        value: Bytes,
+    },
+
+    /// 设置有序集合成员的分数，参数为 score member [score member ...]
+    Zadd {
+        key: String,
+        #[clap(required = true, num_args = 2.., allow_negative_numbers = true)]
+        members: Vec<String>,
+    },
+
+    /// 把成员的分数加上 increment，回复新的分数
+    Zincrby {
+        key: String,
+        #[clap(allow_negative_numbers = true)]
+        increment: String,
+        member: String,
+    },
+
+    /// 从有序集合中删除成员，回复删除的个数
+    Zrem {
+        key: String,
+        #[clap(required = true)]
+        members: Vec<String>,
+    },
+
+    /// 有序集合的成员个数
+    Zcard {
+        key: String,
+    },
+
+    /// 按下标返回有序集合的成员，负数下标从末尾开始计数
+    Zrange {
+        key: String,
+        #[clap(allow_negative_numbers = true)]
+        start: i64,
+        #[clap(allow_negative_numbers = true)]
+        stop: i64,
+        /// 按分数从高到低
+        #[clap(long)]
+        rev: bool,
+        /// 同时返回成员的分数
+        #[clap(long)]
+        withscores: bool,
+    },
+
+    /// 按分数返回有序集合的成员，min 与 max 可以是 -inf、+inf，前面加 ( 时不包含这个分数
+    Zrangebyscore {
+        key: String,
+        #[clap(allow_hyphen_values = true)]
+        min: String,
+        #[clap(allow_hyphen_values = true)]
+        max: String,
+        /// 同时返回成员的分数
+        #[clap(long)]
+        withscores: bool,
+        /// 跳过的成员个数与返回的成员个数
+        #[clap(long, num_args = 2, value_names = ["OFFSET", "COUNT"], allow_negative_numbers = true)]
+        limit: Option<Vec<i64>>,
+    },
+
+    /// 当前值等于 expected 时把 key 设为 new
+    Cas {
+        key: String,
+        #[clap(value_parser = bytes_from_str)]
+        expected: Bytes,
+        #[clap(value_parser = bytes_from_str)]
+        new: Bytes,
+    },
+
+    /// 向频道发布消息
+    Publish {
+        channel: String,
+        #[clap(value_parser = bytes_from_str)]
+        message: Bytes,
+    },
+
+    /// 订阅频道，持续打印收到的消息直到连接关闭
+    Subscribe {
+        #[clap(required = true)]
+        channels: Vec<String>,
+    },
+
+    /// 等待副本确认之前的写命令，timeout 为毫秒，0 表示一直等待
+    Wait {
+        numreplicas: i64,
+        timeout: u64,
+    },
+
+    /// 成为 host:port 上主节点的副本，REPLICAOF NO ONE 停止复制
+    Replicaof {
+        host: String,
+        port: String,
+    },
+
+    /// 连接信息：id、getname、setname <name>
+    Client {
+        subcommand: String,
+        name: Option<String>,
+    },
+
+    /// 调试命令：sleep <seconds>、object <key> 或 set-active-expire <0|1>，服务器需要以 --enable-debug 启动
+    Debug {
+        subcommand: String,
+        arg: String,
+    },
+
+    /// 查看或修改服务器配置：config get <pattern> 或 config set <parameter> <value>
+    Config {
+        subcommand: String,
+        args: Vec<String>,
+    },
+
+    /// 发送任意命令并打印原始回复，例如 raw GET mykey
+    Raw {
+        #[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// 压测：打开多个连接，在给定时长内循环发送同一种命令，报告吞吐量与延迟分位数
+    Bench {
+        /// 发送的命令
+        #[clap(long, value_enum, default_value_t = BenchCommand::Set)]
+        command: BenchCommand,
+        /// 并发的连接数
+        #[clap(long, default_value_t = 50)]
+        clients: usize,
+        /// 压测时长（秒）
+        #[clap(long, default_value_t = 10)]
+        duration: u64,
+        /// SET 写入的值的字节数
+        #[clap(long, default_value_t = 3)]
+        data_size: usize,
+        /// 使用的 key 个数，key 为 key:0 到 key:<keyspace - 1>
+        #[clap(long, default_value_t = 10000)]
+        keyspace: u64,
+    },
+}
+
+// 压测使用的命令
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum BenchCommand {
+    Set,
+    Get,
+    Ping,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> nano_redis::Result<()> {
-    // 开启日志
-    tracing_subscriber::fmt::try_init()?;
-
     // 解析命令行参数
     let cli = Cli::parse();
 
-    // 获取要连接的远程地址
-    let addr = format!("{}:{}", cli.host, cli.port);
+    // 开启日志，设置了 RUST_LOG 时以 RUST_LOG 为准
+    let filter = match tracing_subscriber::EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => tracing_subscriber::EnvFilter::try_new(&cli.log_level)?,
+    };
+    tracing_subscriber::fmt().with_env_filter(filter).try_init()?;
+
+    // 压测自己建立连接
+    if let CommandParser::Bench { command, clients, duration, data_size, keyspace } = cli.command {
+        return bench(&cli, command, clients, Duration::from_secs(duration), data_size, keyspace).await;
+    }
 
-    let mut client = Client::connect(&addr).await?;
+    let mut client = connect(&cli).await?;
 
     match cli.command {
         CommandParser::Ping { msg } => {
             let value = client.ping(msg).await?;
-            if let Ok(string) = str::from_utf8(&value) {
-                println!("\"{}\"", string);
-            } else {
-                println!("{:?}", value);
-            }
+            print_bytes(&value);
+        }
+        CommandParser::Time => {
+            let (secs, micros) = client.time().await?;
+            println!("1) \"{}\"", secs);
+            println!("2) \"{}\"", micros);
+        }
+        CommandParser::Echo { message } => {
+            let value = client.echo(message).await?;
+            print_bytes(&value);
         }
         CommandParser::Get { key } => {
             if let Some(value) = client.get(&key).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
+                print_bytes(&value);
+            } else {
+                println!("(nil)");
+            }
+        }
+        CommandParser::Hello { protover } => {
+            for (key, value) in client.hello(protover).await? {
+                println!("{}: {}", bytes_text(&key), value);
+            }
+        }
+        CommandParser::Getex { key, ex, px, persist } => {
+            let expiry = match (ex, px) {
+                (Some(secs), _) => Expiry::After(Duration::from_secs(secs)),
+                (_, Some(ms)) => Expiry::After(Duration::from_millis(ms)),
+                _ if persist => Expiry::Persist,
+                _ => Expiry::Keep,
+            };
+            if let Some(value) = client.getex(&key, expiry).await? {
+                print_bytes(&value);
+            } else {
+                println!("(nil)");
+            }
+        }
+        CommandParser::Getdel { key } => {
+            if let Some(value) = client.getdel(&key).await? {
+                print_bytes(&value);
+            } else {
+                println!("(nil)");
+            }
+        }
+        CommandParser::Ttl { key } => {
+            println!("(integer) {}", client.ttl(&key).await?);
+        }
+        CommandParser::Expiretime { key, millis } => {
+            println!("(integer) {}", client.expiretime(&key, millis).await?);
+        }
+        CommandParser::Set { key, value, expires, nx, xx, keepttl, get } => {
+            let condition = match (nx, xx) {
+                (true, _) => SetCondition::IfAbsent,
+                (_, true) => SetCondition::IfPresent,
+                _ => SetCondition::Always,
+            };
+            let options = SetOptions { condition, expire: expires, keep_ttl: keepttl, get };
+            if get {
+                if let Some(value) = client.set_get(&key, value, options).await? {
+                    print_bytes(&value);
                 } else {
-                    println!("{:?}", value);
+                    println!("(nil)");
                 }
+            } else if client.set_with(&key, value, options).await? {
+                println!("OK");
             } else {
                 println!("(nil)");
             }
         }
-        CommandParser::Set { key, value, expires } => {
-            client.set(&key, value, expires).await?;
-            println!("OK");
-        }
         CommandParser::Mset { datas } => {
             client.mset(&datas).await?;
             println!("OK");
         }
         CommandParser::Mget { datas } => {
             if let Some(value) = client.mget(&datas).await? {
-                let data_str = str::from_utf8(&value).unwrap().trim_end_matches(',');
+                let data_str = bytes_text(&value);
+                let data_str = data_str.trim_end_matches(',');
                 for (i, item) in data_str.split(',').enumerate() {
                     println!("{:?}:\"{}\"\t", datas[i], item);
                 }
@@ -171,42 +672,53 @@ async fn main() -> nano_redis::Result<()> {
             println!("OK");
         }
         CommandParser::Lpush { key, datas } => {
-            client.push(&key, datas, false).await?;
-            println!("OK");
+            println!("(integer) {}", client.push(&key, datas, false).await?);
         }
         CommandParser::Rpush { key, datas } => {
-            client.push(&key, datas, true).await?;
-            println!("OK");
+            println!("(integer) {}", client.push(&key, datas, true).await?);
+        }
+        CommandParser::Lpushx { key, datas } => {
+            println!("(integer) {}", client.pushx(&key, datas, false).await?);
         }
-        CommandParser::Lpop { key } => {
+        CommandParser::Rpushx { key, datas } => {
+            println!("(integer) {}", client.pushx(&key, datas, true).await?);
+        }
+        CommandParser::Lpop { key, count: Some(count) } => {
+            match client.pop_count(&key, false, count).await? {
+                Some(values) => print_array(&values),
+                None => println!("(nil)"),
+            }
+        }
+        CommandParser::Lpop { key, count: None } => {
             if let Some(value) = client.pop(&key, false).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("\"{}\"", string);
-                } else {
-                    println!("{:?}", value);
-                }
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
-        CommandParser::Rpop { key } => {
+        CommandParser::Rpop { key, count: Some(count) } => {
+            match client.pop_count(&key, true, count).await? {
+                Some(values) => print_array(&values),
+                None => println!("(nil)"),
+            }
+        }
+        CommandParser::Rpop { key, count: None } => {
             if let Some(value) = client.pop(&key, true).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("\"{}\"", string);
-                } else {
-                    println!("{:?}", value);
-                }
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
+        CommandParser::Lmpop { keys, right, count } => match client.lmpop(keys, right, count).await? {
+            Some((key, values)) => {
+                println!("\"{}\"", key);
+                print_array(&values);
+            }
+            None => println!("(nil)"),
+        },
         CommandParser::Lrange { key, start, end } => {
             if let Some(value) = client.lrange(&key, start, end).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
-                }
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
@@ -218,86 +730,514 @@ async fn main() -> nano_redis::Result<()> {
         }
         CommandParser::Srem { key, datas } => {
             if let Some(value) = client.srem(key.clone(), datas.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
-                }
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
         CommandParser::Scard { key } => {
-            if let Some(value) = client.scard(key.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
-                }
+            println!("(integer) {}", client.scard(key).await?);
+        }
+        CommandParser::Sismember { key, value } => {
+            let is_member = client.sismember(key, value).await?;
+            println!("(integer) {}", is_member as i64);
+        }
+        CommandParser::Smismember { key, members } => {
+            for (i, is_member) in client.smismember(&key, members).await?.into_iter().enumerate() {
+                println!("{}) (integer) {}", i + 1, is_member as i64);
+            }
+        }
+        CommandParser::Sort { key, alpha, desc, limit } => {
+            let limit = limit.map(|limit| (limit[0], limit[1]));
+            print_array(&client.sort(&key, alpha, desc, limit).await?);
+        }
+        CommandParser::Smembers { key } => {
+            print_array(&client.smembers(key).await?);
+        }
+        CommandParser::Sinter { keys } => {
+            if let Some(value) = client.sinter(keys.clone()).await? {
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
-        CommandParser::Sismember { key, value } => {
-            if let Some(value) = client.sismember(key.clone(), value.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
-                }
+        CommandParser::Sintercard { keys, limit } => {
+            println!("(integer) {}", client.sintercard(keys, limit).await?);
+        }
+        CommandParser::Sdiff { keys } => {
+            if let Some(value) = client.sdiff(keys.clone()).await? {
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
-        CommandParser::Sismembers { key } => {
-            if let Some(value) = client.sismembers(key.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
-                }
+        CommandParser::Sunion { keys } => {
+            if let Some(value) = client.sunion(keys.clone()).await? {
+                print_bytes(&value);
             } else {
                 println!("(nil)");
             }
         }
-        CommandParser::Sinter { keys } => {
-            if let Some(value) = client.sinter(keys.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
+        CommandParser::Features => {
+            for feature in client.features().await? {
+                print_bytes(&feature);
+            }
+        }
+        CommandParser::Command { subcommand } => {
+            if subcommand.is_some_and(|s| s.eq_ignore_ascii_case("count")) {
+                println!("{}", client.command_count().await?);
+            } else {
+                for name in client.command_list().await? {
+                    print_bytes(&name);
+                }
+            }
+        }
+        CommandParser::Info { section } => {
+            let value = client.info(section).await?;
+            print!("{}", bytes_text(&value));
+        }
+        CommandParser::Slowlog { subcommand, count } => {
+            match subcommand.to_lowercase().as_str() {
+                "len" => println!("{}", client.slowlog_len().await?),
+                "reset" => {
+                    client.slowlog_reset().await?;
+                    println!("OK");
                 }
+                _ => {
+                    for entry in client.slowlog_get(count).await? {
+                        println!("{}", entry);
+                    }
+                }
+            }
+        }
+        CommandParser::Object { subcommand, key } => {
+            if subcommand.eq_ignore_ascii_case("encoding") {
+                let value = client.object_encoding(&key).await?;
+                print_bytes(&value);
+            } else if subcommand.eq_ignore_ascii_case("idletime") {
+                println!("(integer) {}", client.object_idletime(&key).await?);
+            } else if subcommand.eq_ignore_ascii_case("freq") {
+                println!("(integer) {}", client.object_freq(&key).await?);
+            } else if subcommand.eq_ignore_ascii_case("refcount") {
+                println!("(integer) {}", client.object_refcount(&key).await?);
             } else {
-                println!("(nil)");
+                println!("(error) ERR Unknown subcommand '{}'", subcommand);
             }
         }
-        CommandParser::Sdiff { keys } => {
-            if let Some(value) = client.sdiff(keys.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
+        CommandParser::Memory { subcommand, key } => {
+            if subcommand.eq_ignore_ascii_case("usage") {
+                match client.memory_usage(&key).await? {
+                    Some(bytes) => println!("(integer) {}", bytes),
+                    None => println!("(nil)"),
                 }
             } else {
-                println!("(nil)");
+                println!("(error) ERR Unknown subcommand '{}'", subcommand);
             }
         }
-        CommandParser::Sunion { keys } => {
-            if let Some(value) = client.sunion(keys.clone()).await? {
-                if let Ok(string) = str::from_utf8(&value) {
-                    println!("{}", string);
-                } else {
-                    println!("{:?}", value);
+        CommandParser::Copy { source, destination, replace } => {
+            let copied = client.copy(&source, &destination, replace).await?;
+            println!("(integer) {}", copied as i64);
+        }
+        CommandParser::Touch { keys } => {
+            println!("(integer) {}", client.touch(keys).await?);
+        }
+        CommandParser::Setbit { key, offset, value } => {
+            let old = client.setbit(&key, offset, value == 1).await?;
+            println!("(integer) {}", old as i64);
+        }
+        CommandParser::Getbit { key, offset } => {
+            println!("(integer) {}", client.getbit(&key, offset).await? as i64);
+        }
+        CommandParser::Bitcount { key, start, end } => {
+            let range = start.zip(end);
+            println!("(integer) {}", client.bitcount(&key, range).await?);
+        }
+        CommandParser::Bitpos { key, bit, start, end } => {
+            println!("(integer) {}", client.bitpos(&key, bit == 1, start, end).await?);
+        }
+        CommandParser::Scan { cursor, pattern, count, type_name } => {
+            let (next, keys) = client.scan(cursor, pattern.as_deref(), count, type_name.as_deref()).await?;
+            print_scan_page(next, &keys);
+        }
+        CommandParser::Hscan { key, cursor, pattern, count } => {
+            let (next, fields) = client.hscan(&key, cursor, pattern.as_deref(), count).await?;
+            let values: Vec<Bytes> = fields.into_iter().flat_map(|(field, value)| [field, value]).collect();
+            print_scan_page(next, &values);
+        }
+        CommandParser::Sscan { key, cursor, pattern, count } => {
+            let (next, members) = client.sscan(&key, cursor, pattern.as_deref(), count).await?;
+            print_scan_page(next, &members);
+        }
+        CommandParser::Move { key, db } => {
+            println!("(integer) {}", client.move_key(&key, db).await? as i64);
+        }
+        CommandParser::Flushdb { lazy } => {
+            client.flushdb(lazy).await?;
+            println!("OK");
+        }
+        CommandParser::Flushall { lazy } => {
+            client.flushall(lazy).await?;
+            println!("OK");
+        }
+        CommandParser::Hmset { key, pairs } => {
+            if pairs.len() % 2 != 0 {
+                println!("(error) ERR wrong number of arguments for 'hmset' command");
+            } else {
+                let fields = pairs
+                    .chunks(2)
+                    .map(|pair| (Bytes::from(pair[0].clone()), Bytes::from(pair[1].clone())))
+                    .collect();
+                client.hmset(&key, fields).await?;
+                println!("OK");
+            }
+        }
+        CommandParser::Hsetnx { key, field, value } => {
+            let created = client.hsetnx(&key, field, value).await?;
+            println!("(integer) {}", created as i64);
+        }
+        CommandParser::Hrandfield { key, count: None, .. } => match client.hrandfield(&key).await? {
+            Some(field) => println!("\"{}\"", bytes_text(&field)),
+            None => println!("(nil)"),
+        },
+        CommandParser::Hrandfield { key, count: Some(count), withvalues: false } => {
+            print_array(&client.hrandfield_count(&key, count).await?);
+        }
+        CommandParser::Hrandfield { key, count: Some(count), withvalues: true } => {
+            let values: Vec<Bytes> = client
+                .hrandfield_with_values(&key, count)
+                .await?
+                .into_iter()
+                .flat_map(|(field, value)| [field, value])
+                .collect();
+            print_array(&values);
+        }
+        CommandParser::Hmget { key, fields } => {
+            let fields = fields.into_iter().map(Bytes::from).collect();
+            for (i, value) in client.hmget(&key, fields).await?.into_iter().enumerate() {
+                match value {
+                    Some(value) => println!("{}) \"{}\"", i + 1, bytes_text(&value)),
+                    None => println!("{}) (nil)", i + 1),
                 }
+            }
+        }
+        CommandParser::Append { key, value } => {
+            let len = client.append(&key, value).await?;
+            println!("(integer) {}", len);
+        }
+        CommandParser::Zadd { key, members } => {
+            if members.len() % 2 != 0 {
+                return Err("zadd needs score member pairs".into());
+            }
+            let members = members
+                .chunks(2)
+                .map(|pair| Ok((bytes_to_score(pair[0].as_bytes())?, Bytes::from(pair[1].clone()))))
+                .collect::<nano_redis::Result<Vec<_>>>()?;
+            let added = client.zadd(&key, members).await?;
+            println!("(integer) {}", added);
+        }
+        CommandParser::Zincrby { key, increment, member } => {
+            let score = client.zincrby(&key, bytes_to_score(increment.as_bytes())?, Bytes::from(member)).await?;
+            println!("\"{}\"", fmt_score(score));
+        }
+        CommandParser::Zrem { key, members } => {
+            let removed = client.zrem(&key, members.into_iter().map(Bytes::from).collect()).await?;
+            println!("(integer) {}", removed);
+        }
+        CommandParser::Zcard { key } => {
+            let len = client.zcard(&key).await?;
+            println!("(integer) {}", len);
+        }
+        CommandParser::Zrange { key, start, stop, rev, withscores: false } => {
+            print_array(&client.zrange(&key, start, stop, rev).await?);
+        }
+        CommandParser::Zrange { key, start, stop, rev, withscores: true } => {
+            let values: Vec<Bytes> = client
+                .zrange_with_scores(&key, start, stop, rev)
+                .await?
+                .into_iter()
+                .flat_map(|(member, score)| [member, Bytes::from(fmt_score(score))])
+                .collect();
+            print_array(&values);
+        }
+        CommandParser::Zrangebyscore { key, min, max, withscores, limit } => {
+            let limit = limit.map(|limit| (limit[0], limit[1]));
+            if withscores {
+                let values: Vec<Bytes> = client
+                    .zrangebyscore_with_scores(&key, &min, &max, limit)
+                    .await?
+                    .into_iter()
+                    .flat_map(|(member, score)| [member, Bytes::from(fmt_score(score))])
+                    .collect();
+                print_array(&values);
             } else {
-                println!("(nil)");
+                print_array(&client.zrangebyscore(&key, &min, &max, limit).await?);
+            }
+        }
+        CommandParser::Cas { key, expected, new } => {
+            let swapped = client.cas(&key, expected, new).await?;
+            println!("(integer) {}", swapped as i64);
+        }
+        CommandParser::Publish { channel, message } => {
+            let receivers = client.publish(&channel, message).await?;
+            println!("(integer) {}", receivers);
+        }
+        CommandParser::Subscribe { channels } => {
+            let mut subscriber = client.subscribe(channels).await?;
+            while let Some(message) = subscriber.next_message().await {
+                println!("{}: \"{}\"", message.channel, bytes_text(&message.content));
+            }
+        }
+        CommandParser::Wait { numreplicas, timeout } => {
+            let replicas = client.wait(numreplicas, Duration::from_millis(timeout)).await?;
+            println!("(integer) {}", replicas);
+        }
+        CommandParser::Replicaof { host, port } => {
+            if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+                client.replicaof(None).await?;
+            } else {
+                let port = port.parse::<u16>().map_err(|_| "invalid port")?;
+                client.replicaof(Some((&host, port))).await?;
+            }
+            println!("OK");
+        }
+        CommandParser::Client { subcommand, name } => {
+            match (subcommand.to_lowercase().as_str(), name) {
+                ("id", None) => println!("(integer) {}", client.client_id().await?),
+                ("getname", None) => match client.client_getname().await? {
+                    Some(name) => println!("\"{}\"", bytes_text(&name)),
+                    None => println!("(nil)"),
+                },
+                ("setname", Some(name)) => {
+                    client.client_setname(&name).await?;
+                    println!("OK");
+                }
+                _ => println!("(error) ERR Unknown subcommand '{}'", subcommand),
+            }
+        }
+        CommandParser::Debug { subcommand, arg } => match subcommand.to_lowercase().as_str() {
+            "sleep" => match arg.parse::<f64>() {
+                Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+                    client.debug_sleep(Duration::from_secs_f64(seconds)).await?;
+                    println!("OK");
+                }
+                _ => println!("(error) ERR value is not a valid float"),
+            },
+            "object" => println!("{}", client.debug_object(&arg).await?),
+            "set-active-expire" => match arg.as_str() {
+                "0" | "1" => {
+                    client.debug_set_active_expire(arg == "1").await?;
+                    println!("OK");
+                }
+                _ => println!("(error) ERR syntax error"),
+            },
+            _ => println!("(error) ERR Unknown subcommand '{}'", subcommand),
+        },
+        CommandParser::Config { subcommand, args } => match (subcommand.to_lowercase().as_str(), &args[..]) {
+            ("get", [pattern]) => {
+                for (name, value) in client.config_get(pattern).await? {
+                    println!("{}\n{}", name, value);
+                }
+            }
+            ("set", [parameter, value]) => {
+                client.config_set(parameter, value).await?;
+                println!("OK");
             }
+            _ => println!("(error) ERR Unknown subcommand or wrong number of arguments for '{}'", subcommand),
+        },
+        CommandParser::Raw { args } => {
+            let reply = client.execute(args.into_iter().map(Bytes::from).collect()).await?;
+            for line in frame_lines(&reply) {
+                println!("{}", line);
+            }
+        }
+        CommandParser::Bench { .. } => unreachable!("bench is handled before connecting"),
+    }
+
+    Ok(())
+}
+
+// 按命令行参数建立连接，并选择数据库
+async fn connect(cli: &Cli) -> nano_redis::Result<Client> {
+    let mut client = match &cli.unixsocket {
+        Some(path) => Client::connect_unix(path).await?,
+        #[cfg(feature = "tls")]
+        None if cli.tls => {
+            let addr = format!("{}:{}", cli.host, cli.port);
+            Client::connect_tls(&addr, &cli.host).await?
+        }
+        None => {
+            // 获取要连接的远程地址
+            let addr = format!("{}:{}", cli.host, cli.port);
+            Client::connect(&addr).await?
         }
+    };
+
+    if cli.db != 0 {
+        client.select(cli.db).await?;
+    }
+    Ok(client)
+}
+
+// 先建立全部连接，再让每个连接在各自的任务中循环发送命令直到时间用完，最后汇总所有请求的延迟
+async fn bench(
+    cli: &Cli,
+    command: BenchCommand,
+    clients: usize,
+    duration: Duration,
+    data_size: usize,
+    keyspace: u64,
+) -> nano_redis::Result<()> {
+    let mut connections = Vec::with_capacity(clients);
+    for _ in 0..clients {
+        connections.push(connect(cli).await?);
+    }
+
+    let value = Bytes::from(vec![b'x'; data_size]);
+    let keyspace = keyspace.max(1);
+    let start = Instant::now();
+    let deadline = start + duration;
+    let tasks: Vec<_> = connections
+        .into_iter()
+        .enumerate()
+        .map(|(id, mut client)| {
+            let value = value.clone();
+            tokio::spawn(async move {
+                let (mut latencies, mut errors) = (vec![], 0usize);
+                // 不同连接从不同的 key 开始，避免所有连接同时访问同一个 key
+                let mut next = id as u64 * 7919;
+                while Instant::now() < deadline {
+                    let key = format!("key:{}", next % keyspace);
+                    next += 1;
+                    let sent = Instant::now();
+                    let res = match command {
+                        BenchCommand::Set => client.set(&key, value.clone(), None).await,
+                        BenchCommand::Get => client.get(&key).await.map(|_| ()),
+                        BenchCommand::Ping => client.ping(None).await.map(|_| ()),
+                    };
+                    match res {
+                        Ok(()) => latencies.push(sent.elapsed()),
+                        Err(_) => errors += 1,
+                    }
+                }
+                (latencies, errors)
+            })
+        })
+        .collect();
+
+    let (mut latencies, mut errors) = (vec![], 0);
+    for task in tasks {
+        let (task_latencies, task_errors) = task.await?;
+        latencies.extend(task_latencies);
+        errors += task_errors;
     }
+    let elapsed = start.elapsed();
+    latencies.sort();
 
+    println!(
+        "{:?}: {} requests in {:.2} s, {:.2} requests per second, {} errors",
+        command,
+        latencies.len(),
+        elapsed.as_secs_f64(),
+        latencies.len() as f64 / elapsed.as_secs_f64(),
+        errors
+    );
+    if !latencies.is_empty() {
+        let ms = |latency: Duration| latency.as_secs_f64() * 1000.0;
+        println!(
+            "latency (ms): p50 {:.3}, p95 {:.3}, p99 {:.3}, max {:.3}",
+            ms(percentile(&latencies, 50.0)),
+            ms(percentile(&latencies, 95.0)),
+            ms(percentile(&latencies, 99.0)),
+            ms(latencies[latencies.len() - 1])
+        );
+    }
     Ok(())
 }
 
+// 已排序的延迟中的第 p 百分位数
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// 按 redis-cli 的格式把回复帧转换成若干行：bulk 字符串加引号，嵌套数组的元素按上一层的序号缩进
+fn frame_lines(frame: &Frame) -> Vec<String> {
+    match frame {
+        Frame::Simple(value) => vec![value.clone()],
+        Frame::Error(msg) => vec![format!("(error) {}", msg)],
+        Frame::Integer(value) => vec![format!("(integer) {}", value)],
+        Frame::USize(value) => vec![format!("(integer) {}", value)],
+        Frame::Bulk(value) => vec![format!("\"{}\"", bytes_text(value))],
+        Frame::Null | Frame::NullArray => vec!["(nil)".to_string()],
+        Frame::Array(parts) if parts.is_empty() => vec!["(empty array)".to_string()],
+        Frame::Array(parts) => {
+            let mut lines = vec![];
+            for (i, part) in parts.iter().enumerate() {
+                let prefix = format!("{}) ", i + 1);
+                for (j, line) in frame_lines(part).into_iter().enumerate() {
+                    if j == 0 {
+                        lines.push(format!("{}{}", prefix, line));
+                    } else {
+                        lines.push(format!("{}{}", " ".repeat(prefix.len()), line));
+                    }
+                }
+            }
+            lines
+        }
+    }
+}
+
+// 按 redis-cli 的格式逐行打印数组回复
+fn print_array(values: &[Bytes]) {
+    if values.is_empty() {
+        println!("(empty array)");
+    }
+    for (i, value) in values.iter().enumerate() {
+        println!("{}) \"{}\"", i + 1, bytes_text(value));
+    }
+}
+
+// 按 redis-cli 的格式打印 SCAN、HSCAN 与 SSCAN 的回复：下一次的游标与这一次的元素
+fn print_scan_page(next: u64, items: &[Bytes]) {
+    println!("1) \"{}\"", next);
+    if items.is_empty() {
+        println!("2) (empty array)");
+    }
+    for (i, item) in items.iter().enumerate() {
+        let prefix = if i == 0 { "2) " } else { "   " };
+        println!("{}{}) \"{}\"", prefix, i + 1, bytes_text(item));
+    }
+}
+
+// 打印一个值，格式见 bytes_text
+fn print_bytes(value: &Bytes) {
+    println!("{}", bytes_text(value));
+}
+
+// 把值转换为可打印的文本：合法的 UTF-8 原样保留，不能解码的字节与 redis-cli 一样转义为 \xab
+fn bytes_text(value: &[u8]) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut rest = value;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return text;
+            }
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).unwrap());
+                // error_len 为 None 表示末尾是不完整的字符，剩下的字节全部转义
+                let len = err.error_len().unwrap_or(invalid.len());
+                for byte in &invalid[..len] {
+                    text.push_str(&format!("\\x{:02x}", byte));
+                }
+                rest = &invalid[len..];
+            }
+        }
+    }
+}
+
 fn duration_from_ms_str(src: &str) -> Result<Duration, ParseIntError> {
     let ms = src.parse::<u64>()?;
     Ok(Duration::from_millis(ms))
@@ -307,11 +1247,6 @@ fn bytes_from_str(src: &str) -> Result<Bytes, Infallible> {
     Ok(Bytes::from(src.to_string()))
 }
 
-fn i32_from_str(src: &str) -> Result<i32, ParseIntError> {
-    // Ok(Bytes::from(src.to_string()));
-    src.parse::<i32>()
-}
-
 fn i64_from_str(src: &str) -> Result<i64, ParseIntError> {
     // Ok(Bytes::from(src.to_string()));
     src.parse::<i64>()
@@ -6,6 +6,7 @@
 use nano_redis::{ DEFAULT_PORT};
 
 use clap::Parser;
+use std::path::PathBuf;
 use tokio::net::TcpListener;
 use tokio::signal;
 
@@ -21,21 +22,60 @@ use opentelemetry_aws::trace::XrayPropagator;
 #[cfg(feature = "otel")]
 // The `Ext` traits are to allow the Registry to accept the
 // OpenTelemetry-specific types (such as `OpenTelemetryLayer`)
-use tracing_subscriber::{
-    fmt, layer::SubscriberExt, util::SubscriberInitExt, util::TryInitError, EnvFilter,
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use nano_redis::connect::server_start::{
+    self, DATABASES, MAXMEMORY_SAMPLES, MAX_CONNECTIONS, PROTO_MAX_BULK_LEN, PROTO_MAX_MULTIBULK_LEN, SLOWLOG_LOG_SLOWER_THAN, SLOWLOG_MAX_LEN,
 };
-use nano_redis::connect::server_start;
+use nano_redis::connect::ServerConfig;
+use nano_redis::entity::{KeyspaceEvents, MaxmemoryPolicy, SlowClientPolicy};
+#[cfg(feature = "tls")]
+use nano_redis::connect::TlsConfig;
 
 #[tokio::main]
 pub async fn main() -> nano_redis::Result<()> {
-    set_up_logging()?;
-
     let cli = Cli::parse();
-    let port = cli.port.unwrap_or(DEFAULT_PORT);
 
-    let listener = TcpListener::bind(&format!("127.0.0.1:{}", port)).await?;
+    set_up_logging(&cli.log_level)?;
+
+    // 绑定监听地址
+    let listener = TcpListener::bind(&format!("{}:{}", cli.host, cli.port)).await?;
+
+    // 指定了 unix 套接字路径时同时监听
+    let unix_listener = match &cli.unixsocket {
+        Some(path) => Some(server_start::bind_unix(path).await?),
+        None => None,
+    };
+
+    #[allow(unused_mut)]
+    let mut config = ServerConfig {
+        max_connections: cli.max_connections,
+        databases: cli.databases,
+        slowlog_log_slower_than: cli.slowlog_log_slower_than,
+        slowlog_max_len: cli.slowlog_max_len,
+        maxmemory: cli.maxmemory,
+        maxmemory_policy: cli.maxmemory_policy,
+        maxmemory_samples: cli.maxmemory_samples,
+        enable_debug: cli.enable_debug,
+        notify_keyspace_events: cli.notify_keyspace_events,
+        timeout: cli.timeout,
+        tcp_nodelay: cli.tcp_nodelay,
+        tcp_keepalive: cli.tcp_keepalive,
+        pubsub_slow_client_policy: cli.pubsub_slow_client_policy,
+        pubsub_max_subscriptions: cli.pubsub_max_subscriptions,
+        proto_max_multibulk_len: cli.proto_max_multibulk_len,
+        proto_max_bulk_len: cli.proto_max_bulk_len,
+        ..ServerConfig::default()
+    };
+
+    // 同时指定证书与私钥时开启 TLS
+    #[cfg(feature = "tls")]
+    match (&cli.tls_cert, &cli.tls_key) {
+        (Some(cert), Some(key)) => config.tls = Some(TlsConfig::from_pem_files(cert, key)?),
+        (None, None) => {}
+        _ => return Err("--tls-cert and --tls-key must be given together".into()),
+    }
 
-    server_start::run(listener, signal::ctrl_c()).await;
+    server_start::run(listener, unix_listener, config, signal::ctrl_c()).await;
 
     Ok(())
 }
@@ -43,18 +83,113 @@ pub async fn main() -> nano_redis::Result<()> {
 #[derive(Parser, Debug)]
 #[clap(name = "mini-redis-server", version, author, about = "A Redis server")]
 struct Cli {
+    #[clap(long, default_value = "127.0.0.1")]
+    host: String,
+
+    #[clap(long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// 最大连接数
+    #[clap(long, default_value_t = MAX_CONNECTIONS, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    max_connections: usize,
+
+    /// 逻辑数据库个数
+    #[clap(long, default_value_t = DATABASES, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    databases: usize,
+
+    /// 执行时间超过该值（微秒）的命令记入慢查询日志，负数表示关闭
+    #[clap(long, default_value_t = SLOWLOG_LOG_SLOWER_THAN, allow_negative_numbers = true)]
+    slowlog_log_slower_than: i64,
+
+    /// 慢查询日志最多保留的条数
+    #[clap(long, default_value_t = SLOWLOG_MAX_LEN)]
+    slowlog_max_len: usize,
+
+    /// 数据占用的内存上限（字节），0 表示不限制
+    #[clap(long, default_value_t = 0)]
+    maxmemory: usize,
+
+    /// 超过内存上限时的淘汰策略：noeviction、allkeys-random、allkeys-lru、allkeys-lfu
+    #[clap(long, default_value_t = MaxmemoryPolicy::NoEviction)]
+    maxmemory_policy: MaxmemoryPolicy,
+
+    /// LRU/LFU 淘汰时每个数据库随机抽样的 key 数
+    #[clap(long, default_value_t = MAXMEMORY_SAMPLES, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    maxmemory_samples: usize,
+
+    /// 允许 DEBUG 命令（例如 DEBUG SLEEP），只用于测试
+    #[clap(long)]
+    enable_debug: bool,
+
+    /// 发布的键空间通知，格式与 redis 相同，例如 KEA；默认为空，不发布
+    #[clap(long, default_value_t = KeyspaceEvents::default())]
+    notify_keyspace_events: KeyspaceEvents,
+
+    /// 连接空闲超过该秒数后关闭，0 表示不关闭
+    #[clap(long, default_value_t = 0)]
+    timeout: u64,
+
+    /// 在 TCP 连接上设置 TCP_NODELAY，--tcp-nodelay false 关闭
+    #[clap(long, default_value_t = true, action = clap::ArgAction::Set)]
+    tcp_nodelay: bool,
+
+    /// TCP keepalive 的空闲时间（秒），0 表示不开启
+    #[clap(long, default_value_t = 0)]
+    tcp_keepalive: u64,
+
+    /// 订阅者跟不上频道的发布速度时的处理方式：skip 跳过丢失的消息，disconnect 回复错误后断开连接
+    #[clap(long, default_value_t = SlowClientPolicy::Skip)]
+    pubsub_slow_client_policy: SlowClientPolicy,
+
+    /// 每个连接最多订阅的频道数，0 表示不限制
+    #[clap(long, default_value_t = 0)]
+    pubsub_max_subscriptions: usize,
+
+    /// 请求中一个数组最多的元素个数，超过时回复协议错误并关闭连接
+    #[clap(long, default_value_t = PROTO_MAX_MULTIBULK_LEN)]
+    proto_max_multibulk_len: u64,
+
+    /// 请求中一个 bulk 字符串最大的字节数，超过时回复协议错误并关闭连接
+    #[clap(long, default_value_t = PROTO_MAX_BULK_LEN)]
+    proto_max_bulk_len: u64,
+
+    /// 同时监听的 unix 套接字路径
     #[clap(long)]
-    port: Option<u16>,
+    unixsocket: Option<PathBuf>,
+
+    /// 日志级别，也可以是 RUST_LOG 格式的过滤指令（例如 nano_redis=debug）。设置了 RUST_LOG 时以 RUST_LOG 为准
+    #[clap(long, default_value = "info")]
+    log_level: String,
+
+    /// TLS 证书链文件（PEM）
+    #[cfg(feature = "tls")]
+    #[clap(long)]
+    tls_cert: Option<PathBuf>,
+
+    /// TLS 私钥文件（PEM）
+    #[cfg(feature = "tls")]
+    #[clap(long)]
+    tls_key: Option<PathBuf>,
+}
+
+// 日志过滤：优先使用 RUST_LOG 环境变量，没有设置时使用 --log-level
+fn log_filter(log_level: &str) -> nano_redis::Result<tracing_subscriber::EnvFilter> {
+    match tracing_subscriber::EnvFilter::try_from_default_env() {
+        Ok(filter) => Ok(filter),
+        Err(_) => Ok(tracing_subscriber::EnvFilter::try_new(log_level)?),
+    }
 }
 
 #[cfg(not(feature = "otel"))]
-fn set_up_logging() -> nano_redis::Result<()> {
+fn set_up_logging(log_level: &str) -> nano_redis::Result<()> {
     // See https://docs.rs/tracing for more info
-    tracing_subscriber::fmt::try_init()
+    tracing_subscriber::fmt()
+        .with_env_filter(log_filter(log_level)?)
+        .try_init()
 }
 
 #[cfg(feature = "otel")]
-fn set_up_logging() -> Result<(), TryInitError> {
+fn set_up_logging(log_level: &str) -> nano_redis::Result<()> {
     // Set the global propagator to X-Ray propagator
     // Note: If you need to pass the x-amzn-trace-id across services in the same trace,
     // you will need this line. However, this requires additional code not pictured here.
@@ -78,8 +213,8 @@ fn set_up_logging() -> Result<(), TryInitError> {
     let opentelemetry = tracing_opentelemetry::layer().with_tracer(tracer);
 
     // Parse an `EnvFilter` configuration from the `RUST_LOG`
-    // environment variable.
-    let filter = EnvFilter::from_default_env();
+    // environment variable, falling back to `--log-level`.
+    let filter = log_filter(log_level)?;
 
     // Use the tracing subscriber `Registry`, or any other subscriber
     // that impls `LookupSpan`
@@ -87,5 +222,6 @@ fn set_up_logging() -> Result<(), TryInitError> {
         .with(opentelemetry)
         .with(filter)
         .with(fmt::Layer::default())
-        .try_init()
+        .try_init()?;
+    Ok(())
 }
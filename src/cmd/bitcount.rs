@@ -0,0 +1,56 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// BITCOUNT key [start end]
+// 统计字符串中为 1 的位数。start、end 是字节下标，包含两端，负数从末尾开始计数
+#[derive(Debug)]
+pub struct Bitcount {
+    key: String,
+
+    range: Option<(i64, i64)>,
+}
+
+impl Bitcount {
+    pub fn new(key: impl ToString, range: Option<(i64, i64)>) -> Bitcount {
+        Bitcount {
+            key: key.to_string(),
+            range,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Bitcount> {
+        let key = parse.next_string()?;
+        let range = match parse.remaining() {
+            0 => None,
+            2 => Some((parse.next_i64()?, parse.next_i64()?)),
+            _ => return Err("ERR syntax error".into()),
+        };
+        Ok(Bitcount { key, range })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.bitcount(&self.key, self.range) {
+            Ok(count) => Frame::Integer(count as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("bitcount".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some((start, end)) = self.range {
+            frame.push_i64(start);
+            frame.push_i64(end);
+        }
+        frame
+    }
+}
@@ -0,0 +1,74 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// BITPOS key bit [start [end]]
+// 返回第一个值为 bit 的位的位置，start、end 是字节下标，包含两端，负数从末尾开始计数
+#[derive(Debug)]
+pub struct Bitpos {
+    key: String,
+
+    bit: bool,
+
+    start: Option<i64>,
+
+    // 显式给出 end 时，查找 0 找不到会返回 -1 而不是字符串末尾之后的位置
+    end: Option<i64>,
+}
+
+impl Bitpos {
+    pub fn new(key: impl ToString, bit: bool, start: Option<i64>, end: Option<i64>) -> Bitpos {
+        // 协议上 end 必须跟在 start 后面，只给 end 时从第 0 个字节开始
+        let start = if end.is_some() { Some(start.unwrap_or(0)) } else { start };
+        Bitpos {
+            key: key.to_string(),
+            bit,
+            start,
+            end,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Bitpos> {
+        let key = parse.next_string()?;
+        let bit = match parse.next_i64()? {
+            0 => false,
+            1 => true,
+            _ => return Err("ERR The bit argument must be 1 or 0.".into()),
+        };
+        let (start, end) = match parse.remaining() {
+            0 => (None, None),
+            1 => (Some(parse.next_i64()?), None),
+            2 => (Some(parse.next_i64()?), Some(parse.next_i64()?)),
+            _ => return Err("ERR syntax error".into()),
+        };
+        Ok(Bitpos { key, bit, start, end })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.bitpos(&self.key, self.bit, self.start, self.end) {
+            Ok(position) => Frame::Integer(position),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("bitpos".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_i64(self.bit as i64);
+        if let Some(start) = self.start {
+            frame.push_i64(start);
+        }
+        if let Some(end) = self.end {
+            frame.push_i64(end);
+        }
+        frame
+    }
+}
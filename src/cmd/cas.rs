@@ -0,0 +1,54 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// CAS key expected new
+// 当前值等于 expected 时把 key 设为 new，比较与设置在同一次加锁中完成，成功返回 1，否则返回 0
+#[derive(Debug, Clone)]
+pub struct Cas {
+    key: String,
+
+    expected: Bytes,
+
+    new: Bytes,
+}
+
+impl Cas {
+    pub fn new(key: impl ToString, expected: Bytes, new: Bytes) -> Cas {
+        Cas {
+            key: key.to_string(),
+            expected,
+            new,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Cas> {
+        let key = parse.next_string()?;
+        let expected = parse.next_bytes()?;
+        let new = parse.next_bytes()?;
+        Ok(Cas { key, expected, new })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.cas(&self.key, self.expected, self.new) {
+            Ok(swapped) => Frame::Integer(swapped as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("cas".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.expected);
+        frame.push_bulk(self.new);
+        frame
+    }
+}
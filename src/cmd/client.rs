@@ -0,0 +1,80 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::{help_reply, unknown_subcommand};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse, ParseError};
+
+// CLIENT HELP 列出的子命令
+const HELP: &[&str] = &[
+    "GETNAME",
+    "    Return the name of the current connection.",
+    "ID",
+    "    Return the ID of the current connection.",
+    "SETNAME <name>",
+    "    Assign the name <name> to the current connection. An empty name clears it.",
+];
+
+// CLIENT ID | CLIENT SETNAME name | CLIENT GETNAME，查询与设置当前连接的信息
+#[derive(Debug)]
+pub struct ClientCommand {
+    // 子命令（小写）
+    subcommand: String,
+
+    // SETNAME 的连接名
+    name: Option<String>,
+}
+
+impl ClientCommand {
+    pub fn new(subcommand: impl ToString, name: Option<String>) -> ClientCommand {
+        ClientCommand {
+            subcommand: subcommand.to_string().to_lowercase(),
+            name,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ClientCommand> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let name = match parse.next_string() {
+            Ok(name) => Some(name),
+            Err(ParseError::EndOfStream) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ClientCommand { subcommand, name })
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.subcommand.as_str(), self.name) {
+            ("id", None) => Frame::Integer(session.id as i64),
+            ("getname", None) => match &session.name {
+                Some(name) => Frame::Bulk(Bytes::from(name.clone())),
+                None => Frame::Null,
+            },
+            // 连接名中不能有空格和不可见字符，空字符串表示清除连接名
+            ("setname", Some(name)) if name.chars().any(|c| c <= ' ' || c > '~') => Frame::Error(
+                "ERR Client names cannot contain spaces, newlines or special characters.".to_string(),
+            ),
+            ("setname", Some(name)) => {
+                session.name = if name.is_empty() { None } else { Some(name) };
+                Frame::Simple("OK".to_string())
+            }
+            ("help", None) => help_reply("client", HELP),
+            (subcommand, _) => unknown_subcommand("client", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("client".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        if let Some(name) = self.name {
+            frame.push_bulk(Bytes::from(name.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,87 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::{unknown_subcommand, COMMANDS};
+use crate::connect::Connection;
+use crate::entity::{Frame, Parse, ParseError};
+
+// COMMAND [COUNT | LIST | DOCS]，查询服务器支持的命令
+#[derive(Debug)]
+pub struct CommandInfo {
+    // 子命令（小写），没有子命令时返回每个命令的名称与参数个数
+    subcommand: Option<String>,
+
+    // 子命令后面的参数，目前的子命令都不接受参数
+    args: Vec<Bytes>,
+}
+
+impl CommandInfo {
+    pub fn new(subcommand: Option<String>) -> CommandInfo {
+        CommandInfo {
+            subcommand,
+            args: vec![],
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<CommandInfo> {
+        let subcommand = match parse.next_string() {
+            Ok(subcommand) => subcommand.to_lowercase(),
+            Err(ParseError::EndOfStream) => return Ok(CommandInfo::new(None)),
+            Err(e) => return Err(e.into()),
+        };
+
+        // 多余的参数留到 apply 时统一回复错误
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_bytes()?);
+        }
+
+        Ok(CommandInfo {
+            subcommand: Some(subcommand),
+            args,
+        })
+    }
+
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.subcommand.as_deref(), self.args.len()) {
+            // 每个命令返回 [名称, 参数个数]
+            (None, _) => Frame::Array(
+                COMMANDS
+                    .iter()
+                    .map(|spec| {
+                        Frame::Array(vec![
+                            Frame::Bulk(Bytes::from(spec.name)),
+                            Frame::Integer(spec.arity),
+                        ])
+                    })
+                    .collect(),
+            ),
+            (Some("count"), 0) => Frame::Integer(COMMANDS.len() as i64),
+            (Some("list"), 0) | (Some("docs"), 0) => {
+                let mut response = Frame::array();
+                for spec in COMMANDS {
+                    response.push_bulk(Bytes::from(spec.name));
+                }
+                response
+            }
+            (Some(subcommand), _) => unknown_subcommand("command", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("command".as_bytes()));
+        if let Some(subcommand) = self.subcommand {
+            frame.push_bulk(Bytes::from(subcommand.into_bytes()));
+        }
+        for arg in self.args {
+            frame.push_bulk(arg);
+        }
+        frame
+    }
+}
@@ -0,0 +1,112 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::{glob_match, help_reply, unknown_subcommand};
+use crate::connect::{Connection, ServerConfig};
+use crate::entity::{Databases, Frame, Parse};
+
+// CONFIG HELP 列出的子命令
+const HELP: &[&str] = &[
+    "GET <pattern> [<pattern> ...]",
+    "    Return parameters matching the glob-like <pattern> and their values.",
+    "SET <directive> <value> [<directive> <value> ...]",
+    "    Set the configuration <directive> to <value>. Either all directives are",
+    "    applied or none is. maxclients, databases and enable-debug cannot be changed.",
+];
+
+// CONFIG GET pattern [pattern ...] | CONFIG SET parameter value [parameter value ...]
+// 查看与修改服务器配置，修改对之后执行的命令生效
+#[derive(Debug)]
+pub struct ConfigCommand {
+    // 子命令（小写）
+    subcommand: String,
+
+    // GET 的模式，或 SET 的参数名与值
+    args: Vec<String>,
+}
+
+impl ConfigCommand {
+    pub fn get(pattern: impl ToString) -> ConfigCommand {
+        ConfigCommand {
+            subcommand: "get".to_string(),
+            args: vec![pattern.to_string()],
+        }
+    }
+
+    pub fn set(parameter: impl ToString, value: impl ToString) -> ConfigCommand {
+        ConfigCommand {
+            subcommand: "set".to_string(),
+            args: vec![parameter.to_string(), value.to_string()],
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ConfigCommand> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_string()?);
+        }
+        Ok(ConfigCommand { subcommand, args })
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.subcommand.as_str(), self.args.len()) {
+            ("get", n) if n > 0 => get(&databases.config().read().unwrap(), &self.args),
+            ("set", n) if n > 0 && n % 2 == 0 => set(&mut databases.config().write().unwrap(), &self.args),
+            ("help", 0) => help_reply("config", HELP),
+            (subcommand, _) => unknown_subcommand("config", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("config".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        for arg in self.args {
+            frame.push_bulk(Bytes::from(arg.into_bytes()));
+        }
+        frame
+    }
+}
+
+// 返回与任一模式匹配的参数及其值：[参数, 值, 参数, 值, ...]，没有匹配时返回空数组
+fn get(config: &ServerConfig, patterns: &[String]) -> Frame {
+    let mut response = Frame::array();
+    for name in ServerConfig::parameters() {
+        let pattern_matches = patterns
+            .iter()
+            .any(|pattern| glob_match(pattern.to_lowercase().as_bytes(), name.as_bytes()));
+        if let (true, Some(value)) = (pattern_matches, config.get(name)) {
+            response.push_bulk(Bytes::from(*name));
+            response.push_bulk(Bytes::from(value));
+        }
+    }
+    response
+}
+
+// 先在副本上修改所有参数，全部成功后才替换配置，任一参数出错时配置保持不变
+fn set(config: &mut ServerConfig, args: &[String]) -> Frame {
+    let mut updated = config.clone();
+    for pair in args.chunks(2) {
+        let name = pair[0].to_lowercase();
+        if updated.get(&name).is_none() {
+            return Frame::Error(format!(
+                "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                pair[0]
+            ));
+        }
+        if let Err(reason) = updated.set(&name, &pair[1]) {
+            return Frame::Error(format!(
+                "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+                name, reason
+            ));
+        }
+    }
+    *config = updated;
+    Frame::Simple("OK".to_string())
+}
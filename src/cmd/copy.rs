@@ -0,0 +1,65 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// COPY source destination [REPLACE]
+// 复制 source 的值与剩余过期时间到 destination，复制成功返回 1，否则返回 0
+#[derive(Debug, Clone)]
+pub struct Copy {
+    source: String,
+
+    destination: String,
+
+    // destination 已存在时是否覆盖
+    replace: bool,
+}
+
+impl Copy {
+    pub fn new(source: impl ToString, destination: impl ToString, replace: bool) -> Copy {
+        Copy {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            replace,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Copy> {
+        let source = parse.next_string()?;
+        let destination = parse.next_string()?;
+
+        let mut replace = false;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "REPLACE" => replace = true,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Copy { source, destination, replace })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.copy(&self.source, &self.destination, self.replace) {
+            Ok(copied) => Frame::Integer(copied as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("copy".as_bytes()));
+        frame.push_bulk(Bytes::from(self.source.into_bytes()));
+        frame.push_bulk(Bytes::from(self.destination.into_bytes()));
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace".as_bytes()));
+        }
+        frame
+    }
+}
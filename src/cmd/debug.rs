@@ -0,0 +1,117 @@
+use bytes::Bytes;
+use tokio::time::{self, Duration};
+use tracing::{debug, instrument};
+use crate::cmd::{help_reply, unknown_subcommand};
+use crate::connect::{Connection, ServerConfig};
+use crate::entity::{Databases, Frame, Parse};
+
+// DEBUG HELP 列出的子命令
+const HELP: &[&str] = &[
+    "OBJECT <key>",
+    "    Show low level info about the <key> and associated value.",
+    "SET-ACTIVE-EXPIRE <0|1>",
+    "    Setting it to 0 disables expiring keys in background when they are not accessed.",
+    "    Setting it to 1 reenables back the default.",
+    "SLEEP <seconds>",
+    "    Delay the reply on this connection by <seconds>. Decimals allowed.",
+    "Subcommands other than HELP require the server to be started with --enable-debug.",
+];
+
+// 调试命令，只有服务器以 --enable-debug 启动时才可用（HELP 除外）：
+// DEBUG SLEEP seconds，让处理该连接的任务睡眠后再回复，用于测试超时、流水线与连接数限制；
+// DEBUG OBJECT key，返回值的内部信息（编码、序列化长度、剩余过期时间）；
+// DEBUG SET-ACTIVE-EXPIRE 0|1，关闭或打开后台任务的主动过期，关闭后过期的 key 只在被读取时删除
+#[derive(Debug)]
+pub struct DebugCommand {
+    // 子命令（小写）
+    subcommand: String,
+
+    // 子命令的参数
+    args: Vec<String>,
+}
+
+impl DebugCommand {
+    pub fn new(subcommand: impl ToString, seconds: Option<f64>) -> DebugCommand {
+        DebugCommand {
+            subcommand: subcommand.to_string().to_lowercase(),
+            args: seconds.map(|seconds| seconds.to_string()).into_iter().collect(),
+        }
+    }
+
+    // DEBUG OBJECT key
+    pub fn object(key: impl ToString) -> DebugCommand {
+        DebugCommand {
+            subcommand: "object".to_string(),
+            args: vec![key.to_string()],
+        }
+    }
+
+    // DEBUG SET-ACTIVE-EXPIRE 0|1
+    pub fn set_active_expire(enabled: bool) -> DebugCommand {
+        DebugCommand {
+            subcommand: "set-active-expire".to_string(),
+            args: vec![(enabled as u8).to_string()],
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<DebugCommand> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_string()?);
+        }
+        Ok(DebugCommand { subcommand, args })
+    }
+
+    #[instrument(skip(self, databases, config, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        db_index: usize,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let response = if self.subcommand == "help" && self.args.is_empty() {
+            help_reply("debug", HELP)
+        } else if !config.enable_debug {
+            Frame::Error("ERR DEBUG command not allowed, start the server with --enable-debug".to_string())
+        } else {
+            match (self.subcommand.as_str(), self.args.as_slice()) {
+                ("sleep", [seconds]) => match seconds.parse::<f64>() {
+                    Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+                        time::sleep(Duration::from_secs_f64(seconds)).await;
+                        Frame::Simple("OK".to_string())
+                    }
+                    _ => Frame::Error("ERR value is not a valid float".to_string()),
+                },
+                ("object", [key]) => match databases.db(db_index).debug_object(key) {
+                    Some(info) => Frame::Simple(info),
+                    None => Frame::Error("ERR no such key".to_string()),
+                },
+                ("set-active-expire", [enabled]) => match enabled.as_str() {
+                    "0" | "1" => {
+                        databases.set_active_expire(enabled == "1");
+                        Frame::Simple("OK".to_string())
+                    }
+                    _ => Frame::Error("ERR syntax error".to_string()),
+                },
+                (subcommand, _) => unknown_subcommand("debug", subcommand),
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("debug".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        for arg in self.args {
+            frame.push_bulk(Bytes::from(arg.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,42 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse};
+
+// DISCARD，丢弃事务中排队的命令并退出事务
+#[derive(Debug, Default)]
+pub struct Discard;
+
+impl Discard {
+    pub fn new() -> Discard {
+        Discard
+    }
+
+    // DISCARD 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Discard> {
+        Ok(Discard)
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        let response = match session.queued.take() {
+            Some(_) => {
+                session.multi_error = false;
+                session.watched.clear();
+                Frame::Simple("OK".to_string())
+            }
+            None => Frame::Error("ERR DISCARD without MULTI".to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("discard".as_bytes()));
+        frame
+    }
+}
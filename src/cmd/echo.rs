@@ -0,0 +1,38 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Frame, Parse};
+
+// ECHO message，原样返回 message，空字符串返回空的 bulk 而不是 nil
+#[derive(Debug)]
+pub struct Echo {
+    message: Bytes,
+}
+
+impl Echo {
+    pub fn new(message: Bytes) -> Echo {
+        Echo { message }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Echo> {
+        let message = parse.next_bytes()?;
+        Ok(Echo { message })
+    }
+
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = Frame::Bulk(self.message);
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("echo".as_bytes()));
+        frame.push_bulk(self.message);
+        frame
+    }
+}
@@ -0,0 +1,86 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, ServerConfig, Session};
+use crate::cmd::Multi;
+use crate::entity::{Databases, Frame, Parse};
+
+// EXEC，按顺序执行事务中排队的命令，以数组返回每条命令的回复
+#[derive(Debug, Default)]
+pub struct Exec;
+
+impl Exec {
+    pub fn new() -> Exec {
+        Exec
+    }
+
+    // EXEC 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Exec> {
+        Ok(Exec)
+    }
+
+    // 排队时出过错的事务整个放弃；WATCH 的 key 被修改过时不执行并回复空数组；
+    // 执行时某条命令出错只影响它自己的回复，其余命令照常执行
+    #[instrument(skip(self, databases, session, config, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let queued = match session.queued.take() {
+            Some(queued) => queued,
+            None => {
+                let response = Frame::Error("ERR EXEC without MULTI".to_string());
+                debug!(?response);
+                dst.write_frame(&response).await?;
+                return Ok(());
+            }
+        };
+        // 无论事务是否执行，EXEC 之后都不再 WATCH
+        let watched = std::mem::take(&mut session.watched);
+
+        let response = if std::mem::take(&mut session.multi_error) {
+            Frame::Error("EXECABORT Transaction discarded because of previous errors.".to_string())
+        } else {
+            // 持有写锁直到所有命令执行完，版本号的比较也在锁内进行
+            let _guard = databases.exec_guard().await;
+            let modified = watched
+                .iter()
+                .any(|(index, key, version)| databases.db(*index).version(key) != *version);
+            if modified {
+                Frame::NullArray
+            } else {
+                dst.start_capture();
+                session.exec_propagation = Some(vec![]);
+                for cmd in queued {
+                    if let Err(err) = cmd.execute(databases, session, config, dst).await {
+                        dst.write_frame(&Frame::Error(err.to_string())).await?;
+                    }
+                }
+                // 副本也在 MULTI/EXEC 中执行事务成功的写命令，不会看到执行了一半的事务
+                let batch = session.exec_propagation.take().unwrap_or_default();
+                if let (Some((first, _)), Some((last, _))) = (batch.first(), batch.last()) {
+                    let (first, last) = (*first, *last);
+                    databases.propagate(first, Multi::new().into_frame());
+                    for (index, frame) in batch {
+                        databases.propagate(index, frame);
+                    }
+                    databases.propagate(last, Exec::new().into_frame());
+                }
+                Frame::Array(dst.finish_capture())
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("exec".as_bytes()));
+        frame
+    }
+}
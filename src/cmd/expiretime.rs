@@ -0,0 +1,62 @@
+use bytes::Bytes;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// EXPIRETIME key / PEXPIRETIME key，回复 key 过期时刻的 Unix 时间戳（秒或毫秒），
+// key 没有过期时间时回复 -1，key 不存在时回复 -2
+#[derive(Debug)]
+pub struct Expiretime {
+    key: String,
+
+    // PEXPIRETIME 以毫秒为单位
+    millis: bool,
+}
+
+impl Expiretime {
+    pub fn new(key: impl ToString, millis: bool) -> Expiretime {
+        Expiretime {
+            key: key.to_string(),
+            millis,
+        }
+    }
+
+    pub fn millis(&self) -> bool {
+        self.millis
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse, millis: bool) -> crate::Result<Expiretime> {
+        let key = parse.next_string()?;
+        Ok(Expiretime { key, millis })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.expiretime(&self.key) {
+            None => Frame::Integer(-2),
+            Some(None) => Frame::Integer(-1),
+            Some(Some(when)) => {
+                let since_epoch = when.duration_since(UNIX_EPOCH).unwrap_or_default();
+                if self.millis {
+                    Frame::Integer(since_epoch.as_millis() as i64)
+                } else {
+                    Frame::Integer(since_epoch.as_secs() as i64)
+                }
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        let name = if self.millis { "pexpiretime" } else { "expiretime" };
+        frame.push_bulk(Bytes::from(name.as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
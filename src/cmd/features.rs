@@ -0,0 +1,52 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, ServerConfig};
+use crate::entity::{Frame, Parse};
+
+// 非标准命令，返回服务器开启的可选功能，方便客户端和测试根据功能调整行为
+#[derive(Debug, Default)]
+pub struct Features;
+
+impl Features {
+    pub fn new() -> Features {
+        Features
+    }
+
+    // FEATURES 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Features> {
+        Ok(Features)
+    }
+
+    // 由编译时开启的 feature 和运行时配置共同决定
+    pub(crate) fn enabled(config: &ServerConfig) -> Vec<&'static str> {
+        let mut features = vec!["pubsub"];
+        if cfg!(feature = "tls") && config.tls_enabled() {
+            features.push("tls");
+        }
+        if config.enable_debug {
+            features.push("debug");
+        }
+        if config.notify_keyspace_events.is_enabled() {
+            features.push("keyspace-events");
+        }
+        features
+    }
+
+    #[instrument(skip(self, config, dst))]
+    pub(crate) async fn apply(self, config: &ServerConfig, dst: &mut Connection) -> crate::Result<()> {
+        let mut response = Frame::array();
+        for feature in Features::enabled(config) {
+            response.push_bulk(Bytes::from(feature));
+        }
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("features".as_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,63 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse};
+
+// FLUSHDB [ASYNC | SYNC] / FLUSHALL [ASYNC | SYNC]，删除当前数据库（或所有数据库）中的所有 key。
+// ASYNC 时只在锁内换上空表就回复 OK，旧的条目在阻塞线程池中释放
+#[derive(Debug, Clone)]
+pub struct Flush {
+    // FLUSHALL 清空所有数据库
+    all: bool,
+
+    // ASYNC：在后台释放旧的条目
+    lazy: bool,
+}
+
+impl Flush {
+    pub fn new(all: bool, lazy: bool) -> Flush {
+        Flush { all, lazy }
+    }
+
+    pub fn all(&self) -> bool {
+        self.all
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse, all: bool) -> crate::Result<Flush> {
+        let lazy = match parse.remaining() {
+            0 => false,
+            1 => match &parse.next_string()?.to_uppercase()[..] {
+                "ASYNC" => true,
+                "SYNC" => false,
+                _ => return Err("ERR syntax error".into()),
+            },
+            _ => return Err("ERR syntax error".into()),
+        };
+        Ok(Flush { all, lazy })
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, db_index: usize, dst: &mut Connection) -> crate::Result<()> {
+        if self.all {
+            databases.clear(self.lazy);
+        } else {
+            databases.db(db_index).clear(self.lazy);
+        }
+        let response = Frame::Simple("OK".to_string());
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        let name = if self.all { "flushall" } else { "flushdb" };
+        frame.push_bulk(Bytes::from(name.as_bytes()));
+        if self.lazy {
+            frame.push_bulk(Bytes::from("async".as_bytes()));
+        }
+        frame
+    }
+}
@@ -37,12 +37,12 @@ impl Get {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.get(&self.key) {
+        let response = match db.get(&self.key) {
             // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
+            Ok(Some(value)) => Frame::Bulk(value),
             // 没有找到命令
-            Frame::Null
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
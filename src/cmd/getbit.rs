@@ -0,0 +1,50 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::bit_offset;
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// GETBIT key offset
+// 返回字符串第 offset 位的值，超出字符串长度或 key 不存在时为 0
+#[derive(Debug)]
+pub struct Getbit {
+    key: String,
+
+    offset: i64,
+}
+
+impl Getbit {
+    pub fn new(key: impl ToString, offset: u64) -> Getbit {
+        Getbit {
+            key: key.to_string(),
+            offset: offset as i64,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Getbit> {
+        let key = parse.next_string()?;
+        let offset = parse.next_i64()?;
+        Ok(Getbit { key, offset })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match bit_offset(self.offset).and_then(|offset| db.getbit(&self.key, offset)) {
+            Ok(bit) => Frame::Integer(bit as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getbit".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_i64(self.offset);
+        frame
+    }
+}
@@ -0,0 +1,71 @@
+use bytes::Bytes;
+use std::time::Duration;
+use tracing::{debug, instrument};
+use crate::cmd::positive_expire;
+use crate::connect::Connection;
+use crate::entity::{Db, Expiry, Frame, Parse};
+
+/// GETEX key [EX seconds | PX milliseconds | PERSIST]
+/// 与 GET 一样返回字符串的值，同时可以设置新的过期时间（EX/PX）或删除过期时间（PERSIST）。
+#[derive(Debug, Clone)]
+pub struct Getex {
+    key: String,
+    expiry: Expiry,
+}
+
+impl Getex {
+    pub fn new(key: impl ToString, expiry: Expiry) -> Getex {
+        Getex {
+            key: key.to_string(),
+            expiry,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Getex> {
+        let key = parse.next_string()?;
+
+        // 不带选项时不修改过期时间，选项只能有一个
+        let mut expiry = Expiry::Keep;
+        if parse.remaining() > 0 {
+            expiry = match &parse.next_string()?.to_uppercase()[..] {
+                "EX" => Expiry::After(Duration::from_secs(positive_expire(parse.next_i64()?, "getex")?)),
+                "PX" => Expiry::After(Duration::from_millis(positive_expire(parse.next_i64()?, "getex")?)),
+                "PERSIST" => Expiry::Persist,
+                _ => return Err("ERR syntax error".into()),
+            };
+        }
+        if parse.remaining() > 0 {
+            return Err("ERR syntax error".into());
+        }
+
+        Ok(Getex { key, expiry })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.getex(&self.key, self.expiry) {
+            Ok(Some(value)) => Frame::Bulk(value),
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("getex".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        match self.expiry {
+            Expiry::Keep => {}
+            Expiry::After(duration) => {
+                frame.push_bulk(Bytes::from("px".as_bytes()));
+                frame.push_u64(duration.as_millis() as u64);
+            }
+            Expiry::Persist => frame.push_bulk(Bytes::from("persist".as_bytes())),
+        }
+        frame
+    }
+}
@@ -0,0 +1,68 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Frame, Parse};
+
+// 目前只支持 RESP2
+const PROTOCOL_VERSION: i64 = 2;
+
+// HELLO [protover]，握手并返回服务器信息（键值对交替排列的数组）
+#[derive(Debug, Default)]
+pub struct Hello {
+    // 客户端请求的协议版本
+    protover: Option<i64>,
+}
+
+impl Hello {
+    pub fn new(protover: Option<i64>) -> Hello {
+        Hello { protover }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hello> {
+        if parse.remaining() == 0 {
+            return Ok(Hello::default());
+        }
+        let protover = parse
+            .next_string()?
+            .parse::<i64>()
+            .map_err(|_| "ERR Protocol version is not an integer or out of range")?;
+        Ok(Hello::new(Some(protover)))
+    }
+
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.protover {
+            Some(protover) if protover != PROTOCOL_VERSION => {
+                Frame::Error("NOPROTO unsupported protocol version".to_string())
+            }
+            _ => {
+                Frame::Array(vec![
+                    Frame::Bulk(Bytes::from("server")),
+                    Frame::Bulk(Bytes::from("nano-redis")),
+                    Frame::Bulk(Bytes::from("version")),
+                    Frame::Bulk(Bytes::from(env!("CARGO_PKG_VERSION"))),
+                    Frame::Bulk(Bytes::from("proto")),
+                    Frame::Integer(PROTOCOL_VERSION),
+                    Frame::Bulk(Bytes::from("mode")),
+                    Frame::Bulk(Bytes::from("standalone")),
+                    Frame::Bulk(Bytes::from("role")),
+                    Frame::Bulk(Bytes::from("master")),
+                ])
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hello".as_bytes()));
+        if let Some(protover) = self.protover {
+            frame.push_bulk(Bytes::from(protover.to_string()));
+        }
+        frame
+    }
+}
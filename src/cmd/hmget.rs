@@ -0,0 +1,59 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// HMGET key field [field ...]
+// 按顺序返回每个字段的值，字段或 key 不存在时对应位置为 nil
+#[derive(Debug)]
+pub struct Hmget {
+    key: String,
+
+    fields: Vec<Bytes>,
+}
+
+impl Hmget {
+    pub fn new(key: impl ToString, fields: Vec<Bytes>) -> Hmget {
+        Hmget {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hmget> {
+        let key = parse.next_string()?;
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            fields.push(parse.next_bytes()?);
+        }
+        Ok(Hmget { key, fields })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hmget(&self.key, &self.fields) {
+            Ok(values) => Frame::Array(
+                values
+                    .into_iter()
+                    .map(|value| value.map_or(Frame::Null, Frame::Bulk))
+                    .collect(),
+            ),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hmget".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for field in self.fields {
+            frame.push_bulk(field);
+        }
+        frame
+    }
+}
@@ -0,0 +1,55 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// HMSET key field value [field value ...]
+// 一次设置哈希的多个字段，key 不存在时创建，回复 OK。字段与值的个数不成对时在解析命令时回复参数个数错误
+#[derive(Debug, Clone)]
+pub struct Hmset {
+    key: String,
+
+    fields: Vec<(Bytes, Bytes)>,
+}
+
+impl Hmset {
+    pub fn new(key: impl ToString, fields: Vec<(Bytes, Bytes)>) -> Hmset {
+        Hmset {
+            key: key.to_string(),
+            fields,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hmset> {
+        let key = parse.next_string()?;
+        let mut fields = vec![];
+        while parse.remaining() > 0 {
+            fields.push((parse.next_bytes()?, parse.next_bytes()?));
+        }
+        Ok(Hmset { key, fields })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hmset(&self.key, self.fields) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hmset".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (field, value) in self.fields {
+            frame.push_bulk(field);
+            frame.push_bulk(value);
+        }
+        frame
+    }
+}
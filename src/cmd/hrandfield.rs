@@ -0,0 +1,90 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// count 为负数时最多回复的字段个数。回复要在持有数据库锁时整个构建出来，
+// 不限制时一个很大的 -count 会在锁内分配失败并让服务器 panic
+const MAX_REPEATED_COUNT: i64 = 1024 * 1024;
+
+// HRANDFIELD key [count [WITHVALUES]]
+// 没有 count 时回复一个随机字段，key 不存在时回复 nil。count 为正数时回复不重复的字段，
+// 为负数时字段可能重复；key 不存在时回复空数组。WITHVALUES 时字段与值交替出现
+#[derive(Debug)]
+pub struct Hrandfield {
+    key: String,
+
+    count: Option<i64>,
+
+    with_values: bool,
+}
+
+impl Hrandfield {
+    pub fn new(key: impl ToString, count: Option<i64>, with_values: bool) -> Hrandfield {
+        Hrandfield {
+            key: key.to_string(),
+            count,
+            with_values,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hrandfield> {
+        let key = parse.next_string()?;
+        let mut hrandfield = Hrandfield::new(key, None, false);
+        if parse.remaining() > 0 {
+            let count = parse
+                .next_string()?
+                .parse::<i64>()
+                .map_err(|_| "ERR value is not an integer or out of range")?;
+            hrandfield.count = Some(count);
+        }
+        match parse.remaining() {
+            0 => {}
+            1 if parse.next_string()?.eq_ignore_ascii_case("withvalues") => hrandfield.with_values = true,
+            _ => return Err("ERR syntax error".into()),
+        }
+        Ok(hrandfield)
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.count {
+            // 允许重复时回复的元素个数是 -count（WITHVALUES 时加倍），太大时无法回复
+            Some(count) if count < -MAX_REPEATED_COUNT => Frame::Error("ERR value is out of range".to_string()),
+            count => match db.hrandfield(&self.key, count) {
+                Ok(None) if count.is_none() => Frame::Null,
+                Ok(None) => Frame::Array(vec![]),
+                Ok(Some(mut fields)) if count.is_none() => Frame::Bulk(fields.remove(0).0),
+                Ok(Some(fields)) => {
+                    let mut response = Frame::array();
+                    for (field, value) in fields {
+                        response.push_bulk(field);
+                        if self.with_values {
+                            response.push_bulk(value);
+                        }
+                    }
+                    response
+                }
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hrandfield".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_i64(count);
+            if self.with_values {
+                frame.push_bulk(Bytes::from("withvalues".as_bytes()));
+            }
+        }
+        frame
+    }
+}
@@ -0,0 +1,85 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::glob_match;
+use crate::cmd::scan::{parse_count, parse_cursor, DEFAULT_COUNT};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// HSCAN key cursor [MATCH pattern] [COUNT count]
+// 游标式遍历哈希的字段，回复下一次的游标与这一次的字段和值（交替出现），游标为 0 时遍历结束。
+// 游标是字段名的哈希值而不是位置，遍历期间增删字段不会让其他字段被跳过；MATCH 在遍历之后按字段名过滤
+#[derive(Debug)]
+pub struct Hscan {
+    key: String,
+
+    cursor: u64,
+
+    pattern: Option<String>,
+
+    count: Option<u64>,
+}
+
+impl Hscan {
+    pub fn new(key: impl ToString, cursor: u64, pattern: Option<String>, count: Option<u64>) -> Hscan {
+        Hscan {
+            key: key.to_string(),
+            cursor,
+            pattern,
+            count,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hscan> {
+        let key = parse.next_string()?;
+        let cursor = parse_cursor(parse)?;
+
+        let mut hscan = Hscan::new(key, cursor, None, None);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "MATCH" if parse.remaining() > 0 => hscan.pattern = Some(parse.next_string()?),
+                "COUNT" if parse.remaining() > 0 => hscan.count = Some(parse_count(parse)?),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+        Ok(hscan)
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let count = self.count.unwrap_or(DEFAULT_COUNT) as usize;
+        let response = match db.hscan(&self.key, self.cursor, count) {
+            Ok((next, fields)) => {
+                let mut page = Frame::array();
+                for (field, value) in fields {
+                    if self.pattern.as_ref().map_or(true, |pattern| glob_match(pattern.as_bytes(), &field)) {
+                        page.push_bulk(field);
+                        page.push_bulk(value);
+                    }
+                }
+                Frame::Array(vec![Frame::Bulk(Bytes::from(next.to_string())), page])
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hscan".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.cursor.to_string()));
+        if let Some(pattern) = self.pattern {
+            frame.push_bulk(Bytes::from("match".as_bytes()));
+            frame.push_bulk(Bytes::from(pattern.into_bytes()));
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,54 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// HSETNX key field value
+// 只在字段不存在时设置哈希的字段，key 不存在时创建。设置了回复 1，字段已存在回复 0
+#[derive(Debug, Clone)]
+pub struct Hsetnx {
+    key: String,
+
+    field: Bytes,
+
+    value: Bytes,
+}
+
+impl Hsetnx {
+    pub fn new(key: impl ToString, field: Bytes, value: Bytes) -> Hsetnx {
+        Hsetnx {
+            key: key.to_string(),
+            field,
+            value,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Hsetnx> {
+        let key = parse.next_string()?;
+        let field = parse.next_bytes()?;
+        let value = parse.next_bytes()?;
+        Ok(Hsetnx { key, field, value })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.hsetnx(&self.key, self.field, self.value) {
+            Ok(created) => Frame::Integer(created as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("hsetnx".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.field);
+        frame.push_bulk(self.value);
+        frame
+    }
+}
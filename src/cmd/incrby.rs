@@ -3,9 +3,8 @@ use bytes::Bytes;
 use std::time::Duration;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
-use crate::utils::serialization::{bytes_to_i64, i64_to_bytes};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Incrby {
     key: String,
     value: i64,
@@ -30,19 +29,16 @@ impl Incrby {
         // 获取 value
         let value = parse.next_i64()?;
 
-        // println!("{:?}", &i as &[u8]);
-
-
-        // let value = bytes_to_i64(i).unwrap();
-
         Ok(Incrby { key, value })
     }
 
     // 应用相关命令
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        db.incrby(self.key, self.value);
-        let response = Frame::Simple("OK".to_string());
+        let response = match db.incrby(self.key, self.value) {
+            Ok(_) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
         Ok(())
@@ -0,0 +1,86 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse, ParseError};
+
+// INFO [section]，返回服务器的统计信息，目前有 memory、replication 与 commandstats
+#[derive(Debug, Default)]
+pub struct Info {
+    // 要查询的部分（小写），为空时返回全部
+    section: Option<String>,
+}
+
+impl Info {
+    pub fn new(section: Option<String>) -> Info {
+        Info { section }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Info> {
+        match parse.next_string() {
+            Ok(section) => Ok(Info::new(Some(section.to_lowercase()))),
+            Err(ParseError::EndOfStream) => Ok(Info::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, dst: &mut Connection) -> crate::Result<()> {
+        let mut info = String::new();
+
+        let all = matches!(self.section.as_deref(), None | Some("all") | Some("everything"));
+        if all || self.section.as_deref() == Some("memory") {
+            let (used_memory, maxmemory, maxmemory_policy) = databases.memory_info();
+            info.push_str("# Memory\r\n");
+            info.push_str(&format!("used_memory:{}\r\n", used_memory));
+            info.push_str(&format!("maxmemory:{}\r\n", maxmemory));
+            info.push_str(&format!("maxmemory_policy:{}\r\n", maxmemory_policy));
+            if all {
+                info.push_str("\r\n");
+            }
+        }
+        if all || self.section.as_deref() == Some("replication") {
+            info.push_str("# Replication\r\n");
+            match databases.primary() {
+                Some((host, port)) => {
+                    info.push_str("role:slave\r\n");
+                    info.push_str(&format!("master_host:{}\r\n", host));
+                    info.push_str(&format!("master_port:{}\r\n", port));
+                }
+                None => info.push_str("role:master\r\n"),
+            }
+            info.push_str(&format!("connected_slaves:{}\r\n", databases.replica_count()));
+            if all {
+                info.push_str("\r\n");
+            }
+        }
+        if all || self.section.as_deref() == Some("commandstats") {
+            info.push_str("# Commandstats\r\n");
+            for (name, stats) in databases.command_stats() {
+                info.push_str(&format!(
+                    "cmdstat_{}:calls={},usec={},usec_per_call={:.2},usec_max={}\r\n",
+                    name,
+                    stats.calls,
+                    stats.usec,
+                    stats.usec as f64 / stats.calls as f64,
+                    stats.usec_max,
+                ));
+            }
+        }
+
+        // 未知的部分返回空字符串
+        let response = Frame::Bulk(Bytes::from(info));
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("info".as_bytes()));
+        if let Some(section) = self.section {
+            frame.push_bulk(Bytes::from(section.into_bytes()));
+        }
+        frame
+    }
+}
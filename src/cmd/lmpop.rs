@@ -0,0 +1,93 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// LMPOP numkeys key [key ...] LEFT|RIGHT [COUNT count]
+// 从第一个非空列表的头部或尾部弹出最多 count 个元素（默认 1 个），回复 [key, [元素...]]，所有列表都为空时回复 nil
+#[derive(Debug, Clone)]
+pub struct Lmpop {
+    keys: Vec<String>,
+
+    right: bool,
+
+    count: Option<i64>,
+}
+
+impl Lmpop {
+    pub fn new(keys: Vec<String>, right: bool, count: Option<u64>) -> Lmpop {
+        Lmpop {
+            keys,
+            right,
+            count: count.map(|count| count as i64),
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Lmpop> {
+        let numkeys = parse.next_i64()?;
+        if numkeys <= 0 {
+            return Err("ERR numkeys should be greater than 0".into());
+        }
+        // key 后面至少还要有方向
+        if numkeys as usize >= parse.remaining() {
+            return Err("ERR syntax error".into());
+        }
+
+        let mut keys = Vec::with_capacity(numkeys as usize);
+        for _ in 0..numkeys {
+            keys.push(parse.next_string()?);
+        }
+
+        let right = match &parse.next_string()?.to_uppercase()[..] {
+            "LEFT" => false,
+            "RIGHT" => true,
+            _ => return Err("ERR syntax error".into()),
+        };
+
+        let mut count = None;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "COUNT" if count.is_none() => count = Some(parse.next_i64()?),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Lmpop { keys, right, count })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match self.count {
+            Some(count) if count <= 0 => Frame::Error("ERR count should be greater than 0".to_string()),
+            count => match db.lmpop(&self.keys, self.right, count.unwrap_or(1) as usize) {
+                Ok(Some((key, values))) => Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(key)),
+                    Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+                ]),
+                Ok(None) => Frame::NullArray,
+                Err(err) => Frame::Error(err.to_string()),
+            },
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lmpop".as_bytes()));
+        frame.push_u64(self.keys.len() as u64);
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key));
+        }
+        let direction = if self.right { "right" } else { "left" };
+        frame.push_bulk(Bytes::from(direction.as_bytes()));
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_i64(count);
+        }
+        frame
+    }
+}
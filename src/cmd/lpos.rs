@@ -0,0 +1,90 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// LPOS key element [RANK rank] [COUNT num]
+// 返回列表中等于 element 的元素下标。RANK 指定从第几个匹配开始（负数从尾部向前查找），
+// 没有 COUNT 时返回一个整数或 nil，有 COUNT 时返回最多 num 个下标的数组，COUNT 0 表示全部
+#[derive(Debug)]
+pub struct Lpos {
+    key: String,
+
+    element: Bytes,
+
+    rank: Option<i64>,
+
+    count: Option<i64>,
+}
+
+impl Lpos {
+    pub fn new(key: impl ToString, element: Bytes, rank: Option<i64>, count: Option<u64>) -> Lpos {
+        Lpos {
+            key: key.to_string(),
+            element,
+            rank,
+            count: count.map(|count| count as i64),
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Lpos> {
+        let key = parse.next_string()?;
+        let element = parse.next_bytes()?;
+
+        let (mut rank, mut count) = (None, None);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "RANK" => rank = Some(parse.next_i64()?),
+                "COUNT" => count = Some(parse.next_i64()?),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Lpos { key, element, rank, count })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.rank, self.count) {
+            (Some(0), _) => Frame::Error(
+                "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... \
+                 or use negative to start from the end of the list"
+                    .to_string(),
+            ),
+            (_, Some(count)) if count < 0 => Frame::Error("ERR COUNT can't be negative".to_string()),
+            (rank, count) => {
+                let max = count.map_or(1, |count| count as usize);
+                match (db.lpos(&self.key, &self.element, rank.unwrap_or(1), max), count) {
+                    (Ok(positions), Some(_)) => Frame::Array(
+                        positions.into_iter().map(|index| Frame::Integer(index as i64)).collect(),
+                    ),
+                    (Ok(positions), None) => positions
+                        .first()
+                        .map_or(Frame::Null, |index| Frame::Integer(*index as i64)),
+                    (Err(err), _) => Frame::Error(err.to_string()),
+                }
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("lpos".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(self.element);
+        if let Some(rank) = self.rank {
+            frame.push_bulk(Bytes::from("rank".as_bytes()));
+            frame.push_i64(rank);
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_i64(count);
+        }
+        frame
+    }
+}
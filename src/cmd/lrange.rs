@@ -42,12 +42,12 @@ impl Lrange {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.lrange(&self.key,self.start,self.end) {
+        let response = match db.lrange(&self.key,self.start,self.end) {
             // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
+            Ok(Some(value)) => Frame::Bulk(value),
             // 没有找到命令
-            Frame::Null
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
@@ -0,0 +1,71 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::unknown_subcommand;
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// MEMORY USAGE key [SAMPLES count]，估算 key 与值占用的内存（字节），key 不存在时返回 nil。
+// 估算总是遍历所有元素，SAMPLES 只为兼容而接受
+#[derive(Debug)]
+pub struct Memory {
+    // 子命令（小写）
+    subcommand: String,
+
+    // 子命令的参数
+    args: Vec<String>,
+}
+
+impl Memory {
+    pub fn new(subcommand: impl ToString, key: impl ToString) -> Memory {
+        Memory {
+            subcommand: subcommand.to_string().to_lowercase(),
+            args: vec![key.to_string()],
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Memory> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_string()?);
+        }
+        Ok(Memory { subcommand, args })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.subcommand.as_str(), self.args.as_slice()) {
+            ("usage", [key]) => usage(db, key),
+            ("usage", [key, samples, count]) if samples.eq_ignore_ascii_case("samples") => {
+                match count.parse::<i64>() {
+                    Ok(count) if count >= 0 => usage(db, key),
+                    _ => Frame::Error("ERR value is not an integer or out of range".to_string()),
+                }
+            }
+            ("usage", [_, ..]) => Frame::Error("ERR syntax error".to_string()),
+            (subcommand, _) => unknown_subcommand("memory", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("memory".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        for arg in self.args {
+            frame.push_bulk(Bytes::from(arg.into_bytes()));
+        }
+        frame
+    }
+}
+
+fn usage(db: &Db, key: &str) -> Frame {
+    match db.memory_usage(key) {
+        Some(bytes) => Frame::Integer(bytes as i64),
+        None => Frame::Null,
+    }
+}
@@ -49,15 +49,19 @@ pub mod sismember;
 pub use sismember::Sismember;
 
 
-pub mod sismembers;
+pub mod smembers;
 
-pub use sismembers::Sismembers;
+pub use smembers::Smembers;
 
 
 pub mod sinter;
 
 pub use sinter::Sinter;
 
+pub mod sintercard;
+
+pub use sintercard::Sintercard;
+
 
 pub mod sdiff;
 
@@ -68,9 +72,390 @@ pub mod sunion;
 pub use sunion::Sunion;
 
 
+pub mod features;
+
+pub use features::Features;
+
+pub mod command_info;
+
+pub use command_info::CommandInfo;
+
+pub mod info;
+
+pub use info::Info;
+
+pub mod slowlog;
+
+pub use slowlog::Slowlog;
+
+pub mod object;
+
+pub use object::Object;
+
+pub mod copy;
+
+pub use copy::Copy;
+
+pub mod touch;
+
+pub use touch::Touch;
+
+pub mod getdel;
+
+pub use getdel::Getdel;
+
+pub mod getex;
+
+pub use getex::Getex;
+
+pub mod hello;
+
+pub use hello::Hello;
+
+pub mod select;
+
+pub use select::Select;
+
+pub mod debug;
+
+pub use debug::DebugCommand;
+
+pub mod client;
+
+pub use client::ClientCommand;
+
+pub mod multi;
+
+pub use multi::Multi;
+
+pub mod exec;
+
+pub use exec::Exec;
+
+pub mod discard;
+
+pub use discard::Discard;
+
+pub mod watch;
+
+pub use watch::Watch;
+
+pub mod unwatch;
+
+pub use unwatch::Unwatch;
+
+pub mod cas;
+
+pub use cas::Cas;
+
+pub mod publish;
+
+pub use publish::Publish;
+
+pub mod subscribe;
+
+pub use subscribe::Subscribe;
+
+pub mod unsubscribe;
+
+pub use unsubscribe::Unsubscribe;
+
+pub mod wait;
+
+pub use wait::Wait;
+
+pub mod sync;
+
+pub use sync::SyncCommand;
+
+pub mod replicaof;
+
+pub use replicaof::ReplicaOf;
+
+pub mod dump;
+
+pub use dump::Dump;
+
+pub mod restore;
+
+pub use restore::Restore;
+
+pub mod memory;
+
+pub use memory::Memory;
+
+pub mod setbit;
+
+pub use setbit::Setbit;
+
+pub mod getbit;
+
+pub use getbit::Getbit;
+
+pub mod bitcount;
+
+pub use bitcount::Bitcount;
+
+pub mod hmset;
+
+pub use hmset::Hmset;
+
+pub mod hmget;
+
+pub use hmget::Hmget;
+
+pub mod reset;
+
+pub use reset::Reset;
+
+pub mod echo;
+
+pub use echo::Echo;
+
+pub mod lpos;
+
+pub use lpos::Lpos;
+
+pub mod config;
+
+pub use config::ConfigCommand;
+
+pub mod lmpop;
+
+pub use lmpop::Lmpop;
+
+pub mod smismember;
+
+pub use smismember::Smismember;
+
+pub mod ttl;
+
+pub use ttl::Ttl;
+
+pub mod sort;
+
+pub use sort::Sort;
+
+pub mod expiretime;
+
+pub use expiretime::Expiretime;
+
+pub mod time;
+
+pub use time::Time;
+
+pub mod hsetnx;
+
+pub use hsetnx::Hsetnx;
+
+pub mod bitpos;
+
+pub use bitpos::Bitpos;
+
+pub mod flush;
+
+pub use flush::Flush;
+
+pub mod scan;
+
+pub use scan::Scan;
+
+pub mod hrandfield;
+
+pub use hrandfield::Hrandfield;
+
+pub mod pushx;
+
+pub use pushx::Pushx;
+
+pub mod move_key;
+
+pub use move_key::Move;
+
+pub mod append;
+
+pub use append::Append;
+
+pub mod zadd;
+
+pub use zadd::Zadd;
+
+pub mod zrange;
+
+pub use zrange::Zrange;
+
+pub mod zincrby;
+
+pub use zincrby::Zincrby;
+
+pub mod zrem;
+
+pub use zrem::Zrem;
+
+pub mod zcard;
+
+pub use zcard::Zcard;
+
+pub mod zrangebyscore;
+
+pub use zrangebyscore::Zrangebyscore;
+
+pub mod hscan;
+
+pub use hscan::Hscan;
+
+pub mod sscan;
+
+pub use sscan::Sscan;
+
+pub mod quit;
+
+pub use quit::Quit;
+
 pub use unknown::Unknown;
-use crate::entity::{Frame, Parse, Db};
-use crate::connect::{Connection};
+use bytes::Bytes;
+use crate::entity::{Databases, Frame, Parse};
+use crate::connect::{Connection, ServerConfig, Session};
+use tokio::time::Instant;
+
+// 命令的名称与参数个数（包含命令名本身，负数表示至少需要的参数个数）
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub arity: i64,
+}
+
+impl CommandSpec {
+    // 参数个数（包含命令名）是否满足要求
+    fn accepts(&self, argc: usize) -> bool {
+        if self.arity >= 0 {
+            argc as i64 == self.arity
+        } else {
+            argc as i64 >= -self.arity
+        }
+    }
+}
+
+// 服务器支持的所有命令，新增命令时需要同时在这里登记
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec { name: "get", arity: 2 },
+    CommandSpec { name: "set", arity: -3 },
+    CommandSpec { name: "ping", arity: -1 },
+    CommandSpec { name: "incrby", arity: 3 },
+    CommandSpec { name: "lrange", arity: 4 },
+    CommandSpec { name: "push", arity: -4 },
+    CommandSpec { name: "pop", arity: -3 },
+    CommandSpec { name: "sadd", arity: -3 },
+    CommandSpec { name: "srem", arity: -3 },
+    CommandSpec { name: "scard", arity: 2 },
+    CommandSpec { name: "sismember", arity: 3 },
+    CommandSpec { name: "smembers", arity: 2 },
+    CommandSpec { name: "sismembers", arity: 2 },
+    CommandSpec { name: "sinter", arity: -2 },
+    CommandSpec { name: "sintercard", arity: -3 },
+    CommandSpec { name: "sdiff", arity: -2 },
+    CommandSpec { name: "sunion", arity: -2 },
+    CommandSpec { name: "features", arity: 1 },
+    CommandSpec { name: "command", arity: -1 },
+    CommandSpec { name: "info", arity: -1 },
+    CommandSpec { name: "slowlog", arity: -2 },
+    CommandSpec { name: "object", arity: -2 },
+    CommandSpec { name: "copy", arity: -3 },
+    CommandSpec { name: "touch", arity: -2 },
+    CommandSpec { name: "getdel", arity: 2 },
+    CommandSpec { name: "getex", arity: -2 },
+    CommandSpec { name: "hello", arity: -1 },
+    CommandSpec { name: "select", arity: 2 },
+    CommandSpec { name: "debug", arity: -2 },
+    CommandSpec { name: "client", arity: -2 },
+    CommandSpec { name: "multi", arity: 1 },
+    CommandSpec { name: "exec", arity: 1 },
+    CommandSpec { name: "discard", arity: 1 },
+    CommandSpec { name: "watch", arity: -2 },
+    CommandSpec { name: "unwatch", arity: 1 },
+    CommandSpec { name: "cas", arity: 4 },
+    CommandSpec { name: "publish", arity: 3 },
+    CommandSpec { name: "subscribe", arity: -2 },
+    CommandSpec { name: "unsubscribe", arity: -1 },
+    CommandSpec { name: "wait", arity: 3 },
+    CommandSpec { name: "sync", arity: 1 },
+    CommandSpec { name: "replicaof", arity: 3 },
+    CommandSpec { name: "slaveof", arity: 3 },
+    CommandSpec { name: "dump", arity: 2 },
+    CommandSpec { name: "restore", arity: -4 },
+    CommandSpec { name: "memory", arity: -2 },
+    CommandSpec { name: "setbit", arity: 4 },
+    CommandSpec { name: "getbit", arity: 3 },
+    CommandSpec { name: "bitcount", arity: -2 },
+    CommandSpec { name: "hmset", arity: -4 },
+    CommandSpec { name: "hmget", arity: -3 },
+    CommandSpec { name: "reset", arity: 1 },
+    CommandSpec { name: "echo", arity: 2 },
+    CommandSpec { name: "lpos", arity: -3 },
+    CommandSpec { name: "config", arity: -2 },
+    CommandSpec { name: "lmpop", arity: -4 },
+    CommandSpec { name: "smismember", arity: -3 },
+    CommandSpec { name: "ttl", arity: 2 },
+    CommandSpec { name: "sort", arity: -2 },
+    CommandSpec { name: "expiretime", arity: 2 },
+    CommandSpec { name: "pexpiretime", arity: 2 },
+    CommandSpec { name: "time", arity: 1 },
+    CommandSpec { name: "hsetnx", arity: 4 },
+    CommandSpec { name: "bitpos", arity: -3 },
+    CommandSpec { name: "flushdb", arity: -1 },
+    CommandSpec { name: "flushall", arity: -1 },
+    CommandSpec { name: "scan", arity: -2 },
+    CommandSpec { name: "hrandfield", arity: -2 },
+    CommandSpec { name: "lpushx", arity: -3 },
+    CommandSpec { name: "rpushx", arity: -3 },
+    CommandSpec { name: "move", arity: 3 },
+    CommandSpec { name: "append", arity: 3 },
+    CommandSpec { name: "zadd", arity: -4 },
+    CommandSpec { name: "zrange", arity: -4 },
+    CommandSpec { name: "zincrby", arity: 4 },
+    CommandSpec { name: "zrem", arity: -3 },
+    CommandSpec { name: "zcard", arity: 2 },
+    CommandSpec { name: "zrangebyscore", arity: -4 },
+    CommandSpec { name: "hscan", arity: -3 },
+    CommandSpec { name: "sscan", arity: -3 },
+    CommandSpec { name: "quit", arity: -1 },
+];
+
+// 容器命令的子命令，参数个数包含命令名和子命令本身。
+// 在这里登记过子命令的命令，分发前会先按 (命令, 子命令) 检查，未登记的子命令直接回复错误
+pub const SUBCOMMANDS: &[(&str, CommandSpec)] = &[
+    ("client", CommandSpec { name: "id", arity: 2 }),
+    ("client", CommandSpec { name: "getname", arity: 2 }),
+    ("client", CommandSpec { name: "setname", arity: 3 }),
+    ("client", CommandSpec { name: "help", arity: 2 }),
+    ("command", CommandSpec { name: "count", arity: 2 }),
+    ("command", CommandSpec { name: "list", arity: 2 }),
+    ("command", CommandSpec { name: "docs", arity: 2 }),
+    ("config", CommandSpec { name: "get", arity: -3 }),
+    ("config", CommandSpec { name: "set", arity: -4 }),
+    ("config", CommandSpec { name: "help", arity: 2 }),
+    ("debug", CommandSpec { name: "sleep", arity: 3 }),
+    ("debug", CommandSpec { name: "object", arity: 3 }),
+    ("debug", CommandSpec { name: "set-active-expire", arity: 3 }),
+    ("debug", CommandSpec { name: "help", arity: 2 }),
+    ("memory", CommandSpec { name: "usage", arity: -3 }),
+    ("object", CommandSpec { name: "encoding", arity: 3 }),
+    ("object", CommandSpec { name: "idletime", arity: 3 }),
+    ("object", CommandSpec { name: "freq", arity: 3 }),
+    ("object", CommandSpec { name: "refcount", arity: 3 }),
+    ("object", CommandSpec { name: "help", arity: 2 }),
+    ("slowlog", CommandSpec { name: "get", arity: -2 }),
+    ("slowlog", CommandSpec { name: "len", arity: 2 }),
+    ("slowlog", CommandSpec { name: "reset", arity: 2 }),
+];
+
+// 查找容器命令的子命令
+fn find_subcommand(command: &str, subcommand: &str) -> Option<&'static CommandSpec> {
+    SUBCOMMANDS
+        .iter()
+        .find(|(name, spec)| *name == command && spec.name == subcommand)
+        .map(|(_, spec)| spec)
+}
 
 //共能接受 7 种命令，（最后一种为错误）
 #[derive(Debug)]
@@ -87,24 +472,226 @@ pub enum Command {
     Srem(Srem),
     Scard(Scard),
     Sismember(Sismember),
-    Sismembers(Sismembers),
+    Smembers(Smembers),
     Sinter(Sinter),
+    Sintercard(Sintercard),
     Sdiff(Sdiff),
     Sunion(Sunion),
+    Features(Features),
+    CommandInfo(CommandInfo),
+    Info(Info),
+    Slowlog(Slowlog),
+    Object(Object),
+    Copy(Copy),
+    Touch(Touch),
+    Getdel(Getdel),
+    Getex(Getex),
+    Hello(Hello),
+    Select(Select),
+    DebugCommand(DebugCommand),
+    ClientCommand(ClientCommand),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
+    Cas(Cas),
+    Publish(Publish),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    Wait(Wait),
+    SyncCommand(SyncCommand),
+    ReplicaOf(ReplicaOf),
+    Dump(Dump),
+    Restore(Restore),
+    Memory(Memory),
+    Setbit(Setbit),
+    Getbit(Getbit),
+    Bitcount(Bitcount),
+    Hmset(Hmset),
+    Hmget(Hmget),
+    Reset(Reset),
+    Echo(Echo),
+    Lpos(Lpos),
+    ConfigCommand(ConfigCommand),
+    Lmpop(Lmpop),
+    Smismember(Smismember),
+    Ttl(Ttl),
+    Sort(Sort),
+    Expiretime(Expiretime),
+    Time(Time),
+    Hsetnx(Hsetnx),
+    Bitpos(Bitpos),
+    Flush(Flush),
+    Scan(Scan),
+    Hrandfield(Hrandfield),
+    Pushx(Pushx),
+    Move(Move),
+    Append(Append),
+    Zadd(Zadd),
+    Zrange(Zrange),
+    Zincrby(Zincrby),
+    Zrem(Zrem),
+    Zcard(Zcard),
+    Zrangebyscore(Zrangebyscore),
+    Hscan(Hscan),
+    Sscan(Sscan),
+    Quit(Quit),
+}
+
+// 带子命令的容器命令（COMMAND、CONFIG、CLIENT 等）在子命令未知或参数个数不对时的统一回复
+pub(crate) fn unknown_subcommand(command: &str, subcommand: &str) -> Frame {
+    Frame::Error(unknown_subcommand_message(command, subcommand))
+}
+
+pub(crate) fn unknown_subcommand_message(command: &str, subcommand: &str) -> String {
+    format!(
+        "ERR Unknown subcommand or wrong number of arguments for '{}'. Try {} HELP.",
+        subcommand,
+        command.to_uppercase()
+    )
+}
+
+// 容器命令 HELP 子命令的回复：先是一行用法，然后是每个子命令的用法与说明，最后是 HELP 本身
+pub(crate) fn help_reply(command: &str, lines: &[&str]) -> Frame {
+    let mut response = Frame::array();
+    response.push_bulk(Bytes::from(format!(
+        "{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+        command.to_uppercase()
+    )));
+    for line in lines.iter().chain(&["HELP", "    Print this help."]) {
+        response.push_bulk(Bytes::from(line.to_string()));
+    }
+    response
+}
+
+// 过期时间必须为正数，0 或负数返回错误
+pub(crate) fn positive_expire(value: i64, command: &str) -> crate::Result<u64> {
+    if value <= 0 {
+        return Err(format!("ERR invalid expire time in '{}' command", command).into());
+    }
+    Ok(value as u64)
+}
+
+// 位偏移量的上限，与 redis 一样把位图限制在 512MB 以内，避免一条 SETBIT 分配过多内存
+const MAX_BIT_OFFSET: i64 = 512 * 1024 * 1024 * 8 - 1;
+
+// 检查 SETBIT/GETBIT 的位偏移量
+pub(crate) fn bit_offset(value: i64) -> crate::Result<usize> {
+    if !(0..=MAX_BIT_OFFSET).contains(&value) {
+        return Err("ERR bit offset is not an integer or out of range".into());
+    }
+    Ok(value as usize)
+}
+
+// glob 风格的匹配：* 匹配任意个字符，? 匹配一个字符，[abc]、[a-z]、[^a] 匹配字符集合，\\ 转义下一个字符
+pub(crate) fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => string.is_empty(),
+        Some((b'*', rest)) => (0..=string.len()).any(|i| glob_match(rest, &string[i..])),
+        Some((b'?', rest)) => !string.is_empty() && glob_match(rest, &string[1..]),
+        Some((b'[', rest)) => {
+            let (c, string) = match string.split_first() {
+                Some((&c, string)) => (c, string),
+                None => return false,
+            };
+            let (negate, mut class) = match rest.split_first() {
+                Some((b'^', class)) => (true, class),
+                _ => (false, rest),
+            };
+            let mut matched = false;
+            loop {
+                match class {
+                    // 没有闭合的 [ 把剩下的模式都当作字符集合
+                    [] => break,
+                    [b']', tail @ ..] => {
+                        class = tail;
+                        break;
+                    }
+                    [b'\\', escaped, tail @ ..] => {
+                        matched |= *escaped == c;
+                        class = tail;
+                    }
+                    [start, b'-', end, tail @ ..] if *end != b']' => {
+                        let (low, high) = if start <= end { (*start, *end) } else { (*end, *start) };
+                        matched |= (low..=high).contains(&c);
+                        class = tail;
+                    }
+                    [other, tail @ ..] => {
+                        matched |= *other == c;
+                        class = tail;
+                    }
+                }
+            }
+            matched != negate && glob_match(class, string)
+        }
+        Some((b'\\', [escaped, rest @ ..])) => {
+            string.first() == Some(escaped) && glob_match(rest, &string[1..])
+        }
+        Some((c, rest)) => string.first() == Some(c) && glob_match(rest, &string[1..]),
+    }
 }
 
 impl Command {
+    // 最常见的 GET key 与不带选项的 SET key value 直接构造命令，不经过 Parse、命令表查找与子命令检查。
+    // 其他形式（包括参数不是 bulk、key 不是 UTF-8）返回 None，由 from_frame 按一般流程解析并回复错误
+    fn from_hot_frame(frame: &Frame) -> Option<Command> {
+        let parts = match frame {
+            Frame::Array(parts) => parts,
+            _ => return None,
+        };
+        let bulk = |index: usize| match parts.get(index) {
+            Some(Frame::Bulk(data)) => Some(data),
+            _ => None,
+        };
+        let name = bulk(0)?;
+        let key = || std::str::from_utf8(bulk(1)?).ok();
+        match parts.len() {
+            2 if name.eq_ignore_ascii_case(b"get") => Some(Command::Get(Get::new(key()?))),
+            3 if name.eq_ignore_ascii_case(b"set") => Some(Command::Set(Set::new(key()?, bulk(2)?.clone(), None))),
+            _ => None,
+        }
+    }
+
     /// 从接收到的帧中解析命令。并返回
     pub fn from_frame(frame: Frame) -> crate::Result<Command> {
+        if let Some(command) = Command::from_hot_frame(&frame) {
+            return Ok(command);
+        }
+
         // 先转换帧为 parse
         let mut parse = Parse::new(frame)?;
 
-        // 命令转小写
-        let command_name = parse.next_string()?.to_lowercase();
-        println!("---------------{:?}",command_name);
+        // 命令名不区分大小写地在命令表中查找，之后使用表中的小写名字，每个请求不再为命令名分配字符串。
+        // 命令表里没有的一定是未知命令，只有这时才转小写生成错误信息
+        let name = parse.next_bytes()?;
+        let spec = match COMMANDS.iter().find(|spec| spec.name.as_bytes().eq_ignore_ascii_case(&name)) {
+            Some(spec) => spec,
+            None => return Ok(Command::Unknown(Unknown::new(String::from_utf8_lossy(&name).to_lowercase()))),
+        };
+        let command_name = spec.name;
+
+        // 先按命令表检查参数个数，不符时直接回复错误，不再解析参数
+        let argc = parse.remaining() + 1;
+        if !spec.accepts(argc) {
+            return Ok(Command::Unknown(Unknown::wrong_arity(command_name)));
+        }
+        // 容器命令按 (命令, 子命令) 检查，只把子命令转小写，后面的 key 和值保持原样
+        if SUBCOMMANDS.iter().any(|(name, _)| *name == command_name) {
+            if let Some(subcommand) = parse.peek_string().map(|subcommand| subcommand.to_lowercase()) {
+                match find_subcommand(command_name, &subcommand) {
+                    Some(spec) if spec.accepts(argc) => {}
+                    _ => return Ok(Command::Unknown(Unknown::subcommand(command_name, &subcommand))),
+                }
+            }
+        }
+        // HMSET 的字段与值必须成对出现，命令表只能表示参数个数的下限
+        if command_name == "hmset" && argc % 2 != 0 {
+            return Ok(Command::Unknown(Unknown::wrong_arity(command_name)));
+        }
 
         // 匹配命令
-        let command = match &command_name[..] {
+        let command = match command_name {
             "get" => Command::Get(Get::parse_frames(&mut parse)?),
             "pop" => Command::Pop(Pop::parse_frames(&mut parse)?),
             "set" => Command::Set(Set::parse_frames(&mut parse)?),
@@ -116,12 +703,78 @@ impl Command {
             "srem" => Command::Srem(Srem::parse_frames(&mut parse)?),
             "scard" => Command::Scard(Scard::parse_frames(&mut parse)?),
             "sismember" =>  Command::Sismember(Sismember::parse_frames(&mut parse)?) ,
-            "sismembers" => Command::Sismembers(Sismembers::parse_frames(&mut parse)?),
+            // sismembers 是 smembers 的旧名字
+            "smembers" | "sismembers" => Command::Smembers(Smembers::parse_frames(&mut parse)?),
             "sinter" => Command::Sinter(Sinter::parse_frames(&mut parse)?),
+            "sintercard" => Command::Sintercard(Sintercard::parse_frames(&mut parse)?),
             "sdiff" => Command::Sdiff(Sdiff::parse_frames(&mut parse)?),
             "sunion" => Command::Sunion(Sunion::parse_frames(&mut parse)?),
+            "features" => Command::Features(Features::parse_frames(&mut parse)?),
+            "command" => Command::CommandInfo(CommandInfo::parse_frames(&mut parse)?),
+            "info" => Command::Info(Info::parse_frames(&mut parse)?),
+            "slowlog" => Command::Slowlog(Slowlog::parse_frames(&mut parse)?),
+            "object" => Command::Object(Object::parse_frames(&mut parse)?),
+            "copy" => Command::Copy(Copy::parse_frames(&mut parse)?),
+            "touch" => Command::Touch(Touch::parse_frames(&mut parse)?),
+            "getdel" => Command::Getdel(Getdel::parse_frames(&mut parse)?),
+            "getex" => Command::Getex(Getex::parse_frames(&mut parse)?),
+            "hello" => Command::Hello(Hello::parse_frames(&mut parse)?),
+            "select" => Command::Select(Select::parse_frames(&mut parse)?),
+            "debug" => Command::DebugCommand(DebugCommand::parse_frames(&mut parse)?),
+            "client" => Command::ClientCommand(ClientCommand::parse_frames(&mut parse)?),
+            "multi" => Command::Multi(Multi::parse_frames(&mut parse)?),
+            "exec" => Command::Exec(Exec::parse_frames(&mut parse)?),
+            "discard" => Command::Discard(Discard::parse_frames(&mut parse)?),
+            "watch" => Command::Watch(Watch::parse_frames(&mut parse)?),
+            "unwatch" => Command::Unwatch(Unwatch::parse_frames(&mut parse)?),
+            "cas" => Command::Cas(Cas::parse_frames(&mut parse)?),
+            "publish" => Command::Publish(Publish::parse_frames(&mut parse)?),
+            "subscribe" => Command::Subscribe(Subscribe::parse_frames(&mut parse)?),
+            "unsubscribe" => Command::Unsubscribe(Unsubscribe::parse_frames(&mut parse)?),
+            "wait" => Command::Wait(Wait::parse_frames(&mut parse)?),
+            "sync" => Command::SyncCommand(SyncCommand::parse_frames(&mut parse)?),
+            // slaveof 是 replicaof 的旧名字
+            "replicaof" | "slaveof" => Command::ReplicaOf(ReplicaOf::parse_frames(&mut parse)?),
+            "dump" => Command::Dump(Dump::parse_frames(&mut parse)?),
+            "restore" => Command::Restore(Restore::parse_frames(&mut parse)?),
+            "memory" => Command::Memory(Memory::parse_frames(&mut parse)?),
+            "config" => Command::ConfigCommand(ConfigCommand::parse_frames(&mut parse)?),
+            "lmpop" => Command::Lmpop(Lmpop::parse_frames(&mut parse)?),
+            "smismember" => Command::Smismember(Smismember::parse_frames(&mut parse)?),
+            "ttl" => Command::Ttl(Ttl::parse_frames(&mut parse)?),
+            "sort" => Command::Sort(Sort::parse_frames(&mut parse)?),
+            "expiretime" => Command::Expiretime(Expiretime::parse_frames(&mut parse, false)?),
+            "pexpiretime" => Command::Expiretime(Expiretime::parse_frames(&mut parse, true)?),
+            "time" => Command::Time(Time::parse_frames(&mut parse)?),
+            "hsetnx" => Command::Hsetnx(Hsetnx::parse_frames(&mut parse)?),
+            "bitpos" => Command::Bitpos(Bitpos::parse_frames(&mut parse)?),
+            "flushdb" => Command::Flush(Flush::parse_frames(&mut parse, false)?),
+            "flushall" => Command::Flush(Flush::parse_frames(&mut parse, true)?),
+            "scan" => Command::Scan(Scan::parse_frames(&mut parse)?),
+            "hrandfield" => Command::Hrandfield(Hrandfield::parse_frames(&mut parse)?),
+            "lpushx" => Command::Pushx(Pushx::parse_frames(&mut parse, false)?),
+            "rpushx" => Command::Pushx(Pushx::parse_frames(&mut parse, true)?),
+            "move" => Command::Move(Move::parse_frames(&mut parse)?),
+            "append" => Command::Append(Append::parse_frames(&mut parse)?),
+            "zadd" => Command::Zadd(Zadd::parse_frames(&mut parse)?),
+            "zrange" => Command::Zrange(Zrange::parse_frames(&mut parse)?),
+            "zincrby" => Command::Zincrby(Zincrby::parse_frames(&mut parse)?),
+            "zrem" => Command::Zrem(Zrem::parse_frames(&mut parse)?),
+            "zcard" => Command::Zcard(Zcard::parse_frames(&mut parse)?),
+            "zrangebyscore" => Command::Zrangebyscore(Zrangebyscore::parse_frames(&mut parse)?),
+            "hscan" => Command::Hscan(Hscan::parse_frames(&mut parse)?),
+            "sscan" => Command::Sscan(Sscan::parse_frames(&mut parse)?),
+            "quit" => Command::Quit(Quit::parse_frames(&mut parse)?),
+            "setbit" => Command::Setbit(Setbit::parse_frames(&mut parse)?),
+            "getbit" => Command::Getbit(Getbit::parse_frames(&mut parse)?),
+            "bitcount" => Command::Bitcount(Bitcount::parse_frames(&mut parse)?),
+            "hmset" => Command::Hmset(Hmset::parse_frames(&mut parse)?),
+            "hmget" => Command::Hmget(Hmget::parse_frames(&mut parse)?),
+            "reset" => Command::Reset(Reset::parse_frames(&mut parse)?),
+            "echo" => Command::Echo(Echo::parse_frames(&mut parse)?),
+            "lpos" => Command::Lpos(Lpos::parse_frames(&mut parse)?),
             _ => {
-                // 匹配到未知命令
+                // 命令表中登记了但没有实现的命令也按未知命令处理
                 return Ok(Command::Unknown(Unknown::new(command_name)));
             }
         };
@@ -133,24 +786,382 @@ impl Command {
         Ok(command)
     }
 
-    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+    // 命令名称，用于统计等不需要分配内存的场景
+    pub(crate) fn name(&self) -> &'static str {
         match self {
+            Command::Get(_) => "get",
+            Command::Set(_) => "set",
+            Command::Ping(_) => "ping",
+            Command::Incrby(_) => "incrby",
+            Command::Push(_) => "push",
+            Command::Lrange(_) => "lrange",
+            Command::Pop(_) => "pop",
+            Command::Unknown(_) => "unknown",
+            Command::Sadd(_) => "sadd",
+            Command::Srem(_) => "srem",
+            Command::Scard(_) => "scard",
+            Command::Sismember(_) => "sismember",
+            Command::Smembers(_) => "smembers",
+            Command::Sinter(_) => "sinter",
+            Command::Sintercard(_) => "sintercard",
+            Command::Sdiff(_) => "sdiff",
+            Command::Sunion(_) => "sunion",
+            Command::Features(_) => "features",
+            Command::CommandInfo(_) => "command",
+            Command::Info(_) => "info",
+            Command::Slowlog(_) => "slowlog",
+            Command::Object(_) => "object",
+            Command::Copy(_) => "copy",
+            Command::Touch(_) => "touch",
+            Command::Getdel(_) => "getdel",
+            Command::Getex(_) => "getex",
+            Command::Hello(_) => "hello",
+            Command::Select(_) => "select",
+            Command::DebugCommand(_) => "debug",
+            Command::ClientCommand(_) => "client",
+            Command::Multi(_) => "multi",
+            Command::Exec(_) => "exec",
+            Command::Discard(_) => "discard",
+            Command::Watch(_) => "watch",
+            Command::Unwatch(_) => "unwatch",
+            Command::Cas(_) => "cas",
+            Command::Publish(_) => "publish",
+            Command::Subscribe(_) => "subscribe",
+            Command::Unsubscribe(_) => "unsubscribe",
+            Command::Wait(_) => "wait",
+            Command::SyncCommand(_) => "sync",
+            Command::ReplicaOf(_) => "replicaof",
+            Command::Dump(_) => "dump",
+            Command::Restore(_) => "restore",
+            Command::Memory(_) => "memory",
+            Command::ConfigCommand(_) => "config",
+            Command::Lmpop(_) => "lmpop",
+            Command::Smismember(_) => "smismember",
+            Command::Ttl(_) => "ttl",
+            Command::Sort(_) => "sort",
+            Command::Expiretime(cmd) if cmd.millis() => "pexpiretime",
+            Command::Expiretime(_) => "expiretime",
+            Command::Time(_) => "time",
+            Command::Hsetnx(_) => "hsetnx",
+            Command::Bitpos(_) => "bitpos",
+            Command::Flush(cmd) if cmd.all() => "flushall",
+            Command::Flush(_) => "flushdb",
+            Command::Scan(_) => "scan",
+            Command::Hrandfield(_) => "hrandfield",
+            Command::Pushx(cmd) if cmd.right() => "rpushx",
+            Command::Pushx(_) => "lpushx",
+            Command::Move(_) => "move",
+            Command::Append(_) => "append",
+            Command::Zadd(_) => "zadd",
+            Command::Zrange(_) => "zrange",
+            Command::Zincrby(_) => "zincrby",
+            Command::Zrem(_) => "zrem",
+            Command::Zcard(_) => "zcard",
+            Command::Zrangebyscore(_) => "zrangebyscore",
+            Command::Hscan(_) => "hscan",
+            Command::Sscan(_) => "sscan",
+            Command::Quit(_) => "quit",
+            Command::Setbit(_) => "setbit",
+            Command::Getbit(_) => "getbit",
+            Command::Bitcount(_) => "bitcount",
+            Command::Hmset(_) => "hmset",
+            Command::Hmget(_) => "hmget",
+            Command::Reset(_) => "reset",
+            Command::Echo(_) => "echo",
+            Command::Lpos(_) => "lpos",
+        }
+    }
+
+    // 可能增加内存占用的写命令，设置了 maxmemory 时执行前需要先释放内存
+    fn may_use_memory(&self) -> bool {
+        matches!(
+            self,
+            Command::Set(_)
+                | Command::Incrby(_)
+                | Command::Push(_)
+                | Command::Sadd(_)
+                | Command::Copy(_)
+                | Command::Cas(_)
+                | Command::Restore(_)
+                | Command::Setbit(_)
+                | Command::Hmset(_)
+                | Command::Hsetnx(_)
+                | Command::Pushx(_)
+                | Command::Append(_)
+                | Command::Zadd(_)
+                | Command::Zincrby(_)
+        )
+    }
+
+    // 订阅模式下（至少订阅了一个频道）允许执行的命令，其他命令（包括未知命令）回复错误
+    fn allowed_when_subscribed(&self) -> bool {
+        matches!(
+            self,
+            Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::Ping(_)
+                | Command::Quit(_)
+                | Command::Reset(_)
+        )
+    }
+
+    // 修改数据的命令：副本拒绝普通客户端执行，主节点执行后传播给副本
+    fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Command::Set(_)
+                | Command::Incrby(_)
+                | Command::Push(_)
+                | Command::Pop(_)
+                | Command::Lmpop(_)
+                | Command::Sadd(_)
+                | Command::Srem(_)
+                | Command::Copy(_)
+                | Command::Getdel(_)
+                | Command::Getex(_)
+                | Command::Cas(_)
+                | Command::Restore(_)
+                | Command::Setbit(_)
+                | Command::Hmset(_)
+                | Command::Hsetnx(_)
+                | Command::Flush(_)
+                | Command::Pushx(_)
+                | Command::Move(_)
+                | Command::Append(_)
+                | Command::Zadd(_)
+                | Command::Zincrby(_)
+                | Command::Zrem(_)
+        )
+    }
+
+    // 传播给副本的命令帧，只有写命令有
+    fn propagation_frame(&self) -> Option<Frame> {
+        let frame = match self {
+            Command::Set(cmd) => cmd.clone().into_frame(),
+            Command::Incrby(cmd) => cmd.clone().into_frame(),
+            Command::Push(cmd) => cmd.clone().into_frame(),
+            Command::Pop(cmd) => cmd.clone().into_frame(),
+            Command::Lmpop(cmd) => cmd.clone().into_frame(),
+            Command::Sadd(cmd) => cmd.clone().into_frame(),
+            Command::Srem(cmd) => cmd.clone().into_frame(),
+            Command::Copy(cmd) => cmd.clone().into_frame(),
+            Command::Getdel(cmd) => cmd.clone().into_frame(),
+            Command::Getex(cmd) => cmd.clone().into_frame(),
+            Command::Cas(cmd) => cmd.clone().into_frame(),
+            Command::Restore(cmd) => cmd.clone().into_frame(),
+            Command::Setbit(cmd) => cmd.clone().into_frame(),
+            Command::Hmset(cmd) => cmd.clone().into_frame(),
+            Command::Hsetnx(cmd) => cmd.clone().into_frame(),
+            Command::Flush(cmd) => cmd.clone().into_frame(),
+            Command::Pushx(cmd) => cmd.clone().into_frame(),
+            Command::Move(cmd) => cmd.clone().into_frame(),
+            Command::Append(cmd) => cmd.clone().into_frame(),
+            Command::Zadd(cmd) => cmd.clone().into_frame(),
+            Command::Zincrby(cmd) => cmd.clone().into_frame(),
+            Command::Zrem(cmd) => cmd.clone().into_frame(),
+            _ => return None,
+        };
+        Some(frame)
+    }
+
+    // 执行连接收到的命令：MULTI/EXEC/DISCARD/WATCH 控制事务，RESET 清除连接状态，QUIT 关闭连接，事务中的其他命令只排队，
+    // 否则与其他连接的命令并发执行（EXEC 执行期间除外）。WAIT 可能长时间等待，不加锁执行。
+    // 调用者（`Handler::run`）在执行期间暂存 dst 的回复，释放所有锁并记录耗时之后再写入套接字：
+    // 读得慢的客户端不会阻塞 EXEC，写回复的时间也不计入命令的耗时
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let name = self.name();
+        let start = Instant::now();
+
+        let res = match self {
+            Command::Multi(cmd) => cmd.apply(session, dst).await,
+            Command::Exec(cmd) => cmd.apply(databases, session, config, dst).await,
+            Command::Discard(cmd) => cmd.apply(session, dst).await,
+            Command::Watch(cmd) => cmd.apply(databases, session, dst).await,
+            // RESET 在事务中也立即执行，放弃事务
+            Command::Reset(cmd) => cmd.apply(session, dst).await,
+            // QUIT 在事务中也立即执行，连接随后关闭
+            Command::Quit(cmd) => cmd.apply(session, dst).await,
+            cmd if session.subscription_count() > 0 && !cmd.allowed_when_subscribed() => {
+                let command_name = match &cmd {
+                    Command::Unknown(unknown) => unknown.command_name(),
+                    _ => name,
+                };
+                let response = Frame::Error(format!(
+                    "ERR Can't execute '{}': only SUBSCRIBE / UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+                    command_name
+                ));
+                dst.write_frame(&response).await?;
+                Ok(())
+            }
+            // 未知命令或参数个数错误在排队时就回复错误，并使 EXEC 放弃整个事务
+            Command::Unknown(cmd) if session.in_multi() => {
+                session.multi_error = true;
+                return cmd.apply(dst).await;
+            }
+            cmd if session.in_multi() => {
+                session.queued.get_or_insert_with(Vec::new).push(cmd);
+                dst.write_frame(&Frame::Simple("QUEUED".to_string())).await?;
+                return Ok(());
+            }
+            // WAIT 可能等待很久，不持有 command_guard
+            Command::Wait(cmd) => cmd.apply(databases, true, dst).await,
+            // SYNC 自己获取 exec_guard 生成快照
+            Command::SyncCommand(cmd) => cmd.apply(databases, session, dst).await,
+            cmd => {
+                let _guard = databases.command_guard().await;
+                return cmd.execute(databases, session, config, dst).await;
+            }
+        };
+
+        databases.record_command(name, start.elapsed());
+        res
+    }
+
+    // 在连接当前选择的数据库（session.db_index）上执行命令，并记录每种命令的调用次数与耗时（未知命令不记录）。
+    // 调用者在执行期间暂存 dst 的回复，执行中持有的锁都在写入套接字之前释放
+    pub(crate) async fn execute(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let db = databases.db(session.db_index);
+        let name = self.name();
+        let unknown = matches!(self, Command::Unknown(_));
+        let start = Instant::now();
+
+        if self.is_write() && databases.is_replica() && !session.master {
+            dst.write_frame(&Frame::Error("READONLY You can't write against a read only replica.".to_string())).await?;
+            databases.record_command(name, start.elapsed());
+            return Ok(());
+        }
+
+        // 有副本时，写命令在执行前生成传播的帧，并只在执行与传播期间持有 propagation_guard。
+        // EXEC 中的命令不单独传播，由 EXEC 收集后包在 MULTI/EXEC 中一起传播，它持有的 exec_guard 已经排除了其他命令
+        let propagation = if self.is_write() && databases.replica_count() > 0 {
+            self.propagation_frame().map(|frame| (session.db_index, frame))
+        } else {
+            None
+        };
+        let propagation_guard = match (&propagation, &session.exec_propagation) {
+            (Some(_), None) => Some(databases.propagation_guard().await),
+            _ => None,
+        };
+        let replied = dst.captured().len();
+
+        if self.may_use_memory() {
+            if let Err(err) = databases.free_memory() {
+                dst.write_frame(&Frame::Error(err.to_string())).await?;
+                databases.record_command(name, start.elapsed());
+                return Ok(());
+            }
+        }
+
+        let res = match self {
             Command::Get(cmd) => cmd.apply(db, dst).await,
             Command::Lrange(cmd) => cmd.apply(db, dst).await,
             Command::Pop(cmd) => cmd.apply(db, dst).await,
             Command::Set(cmd) => cmd.apply(db, dst).await,
             Command::Push(cmd) => cmd.apply(db, dst).await,
-            Command::Ping(cmd) => cmd.apply(dst).await,
+            Command::Ping(cmd) => cmd.apply(session.subscription_count() > 0, dst).await,
             Command::Unknown(cmd) => cmd.apply(dst).await,
             Command::Incrby(cmd) => cmd.apply(db, dst).await,
             Command::Sadd(cmd) => cmd.apply(db, dst).await,
             Command::Srem(cmd) => cmd.apply(db, dst).await,
             Command::Scard(cmd) => cmd.apply(db, dst).await,
-            Command::Sismember(cmd) => {println!("xxx"); cmd.apply(db, dst).await },
-            Command::Sismembers(cmd) => cmd.apply(db, dst).await,
+            Command::Sismember(cmd) => cmd.apply(db, dst).await,
+            Command::Smembers(cmd) => cmd.apply(db, dst).await,
             Command::Sinter(cmd) => cmd.apply(db, dst).await,
+            Command::Sintercard(cmd) => cmd.apply(db, dst).await,
             Command::Sdiff(cmd) => cmd.apply(db, dst).await,
             Command::Sunion(cmd) => cmd.apply(db, dst).await,
+            Command::Features(cmd) => cmd.apply(config, dst).await,
+            Command::CommandInfo(cmd) => cmd.apply(dst).await,
+            Command::Info(cmd) => cmd.apply(databases, dst).await,
+            Command::Slowlog(cmd) => cmd.apply(databases, dst).await,
+            Command::Object(cmd) => cmd.apply(db, config, dst).await,
+            Command::Copy(cmd) => cmd.apply(db, dst).await,
+            Command::Touch(cmd) => cmd.apply(db, dst).await,
+            Command::Getdel(cmd) => cmd.apply(db, dst).await,
+            Command::Getex(cmd) => cmd.apply(db, dst).await,
+            Command::Hello(cmd) => cmd.apply(dst).await,
+            Command::Select(cmd) => cmd.apply(databases, &mut session.db_index, dst).await,
+            Command::DebugCommand(cmd) => cmd.apply(databases, session.db_index, config, dst).await,
+            Command::ClientCommand(cmd) => cmd.apply(session, dst).await,
+            Command::Unwatch(cmd) => cmd.apply(session, dst).await,
+            Command::Cas(cmd) => cmd.apply(db, dst).await,
+            Command::Publish(cmd) => cmd.apply(databases, dst).await,
+            Command::Subscribe(cmd) => cmd.apply(databases, session, config, dst).await,
+            Command::Unsubscribe(cmd) => cmd.apply(session, dst).await,
+            Command::Wait(cmd) => cmd.apply(databases, false, dst).await,
+            Command::ReplicaOf(cmd) => cmd.apply(databases, config, dst).await,
+            Command::Dump(cmd) => cmd.apply(db, dst).await,
+            Command::Restore(cmd) => cmd.apply(db, dst).await,
+            Command::Memory(cmd) => cmd.apply(db, dst).await,
+            Command::ConfigCommand(cmd) => cmd.apply(databases, dst).await,
+            Command::Lmpop(cmd) => cmd.apply(db, dst).await,
+            Command::Smismember(cmd) => cmd.apply(db, dst).await,
+            Command::Ttl(cmd) => cmd.apply(db, dst).await,
+            Command::Sort(cmd) => cmd.apply(db, dst).await,
+            Command::Expiretime(cmd) => cmd.apply(db, dst).await,
+            Command::Time(cmd) => cmd.apply(dst).await,
+            Command::Hsetnx(cmd) => cmd.apply(db, dst).await,
+            Command::Bitpos(cmd) => cmd.apply(db, dst).await,
+            Command::Flush(cmd) => cmd.apply(databases, session.db_index, dst).await,
+            Command::Scan(cmd) => cmd.apply(db, dst).await,
+            Command::Hrandfield(cmd) => cmd.apply(db, dst).await,
+            Command::Pushx(cmd) => cmd.apply(db, dst).await,
+            Command::Move(cmd) => cmd.apply(databases, session.db_index, dst).await,
+            Command::Append(cmd) => cmd.apply(db, dst).await,
+            Command::Zadd(cmd) => cmd.apply(db, dst).await,
+            Command::Zrange(cmd) => cmd.apply(db, dst).await,
+            Command::Zincrby(cmd) => cmd.apply(db, dst).await,
+            Command::Zrem(cmd) => cmd.apply(db, dst).await,
+            Command::Zcard(cmd) => cmd.apply(db, dst).await,
+            Command::Zrangebyscore(cmd) => cmd.apply(db, dst).await,
+            Command::Hscan(cmd) => cmd.apply(db, dst).await,
+            Command::Sscan(cmd) => cmd.apply(db, dst).await,
+            Command::Setbit(cmd) => cmd.apply(db, dst).await,
+            Command::Getbit(cmd) => cmd.apply(db, dst).await,
+            Command::Bitcount(cmd) => cmd.apply(db, dst).await,
+            Command::Hmset(cmd) => cmd.apply(db, dst).await,
+            Command::Hmget(cmd) => cmd.apply(db, dst).await,
+            Command::Echo(cmd) => cmd.apply(dst).await,
+            Command::Lpos(cmd) => cmd.apply(db, dst).await,
+            // 事务命令、RESET 与 QUIT 由 apply 处理，SYNC 不能在事务中执行
+            Command::Multi(_)
+            | Command::Exec(_)
+            | Command::Discard(_)
+            | Command::Watch(_)
+            | Command::Reset(_)
+            | Command::Quit(_)
+            | Command::SyncCommand(_) => {
+                dst.write_frame(&Frame::Error(format!("ERR {} is not allowed here", name.to_uppercase()))).await?;
+                Ok(())
+            }
+        };
+
+        // 只传播执行成功的写命令，回复错误的命令没有修改数据
+        if let Some((index, frame)) = propagation {
+            let failed = res.is_err() || dst.captured()[replied..].iter().any(|reply| matches!(reply, Frame::Error(_)));
+            match &mut session.exec_propagation {
+                _ if failed => {}
+                Some(batch) => batch.push((index, frame)),
+                None => databases.propagate(index, frame),
+            }
+        }
+        drop(propagation_guard);
+
+        if !unknown {
+            databases.record_command(name, start.elapsed());
         }
+        res
     }
 }
@@ -0,0 +1,58 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse};
+
+// MOVE key db，把 key 从当前数据库移动到下标为 db 的数据库，保留剩余的过期时间。
+// 移动成功回复 1；key 不存在或目标数据库中已有同名 key 时回复 0
+#[derive(Debug, Clone)]
+pub struct Move {
+    key: String,
+
+    db: i64,
+}
+
+impl Move {
+    pub fn new(key: impl ToString, db: i64) -> Move {
+        Move {
+            key: key.to_string(),
+            db,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Move> {
+        let key = parse.next_string()?;
+        let db = parse
+            .next_string()?
+            .parse::<i64>()
+            .map_err(|_| "ERR value is not an integer or out of range")?;
+        Ok(Move { key, db })
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, db_index: usize, dst: &mut Connection) -> crate::Result<()> {
+        let response = match databases.checked_index(self.db) {
+            Err(err) => Frame::Error(err.to_string()),
+            Ok(index) if index == db_index => {
+                Frame::Error("ERR source and destination objects are the same".to_string())
+            }
+            Ok(index) => {
+                let moved = databases.db(db_index).move_to(&self.key, databases.db(index));
+                Frame::Integer(moved as i64)
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("move".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.db.to_string()));
+        frame
+    }
+}
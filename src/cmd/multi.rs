@@ -0,0 +1,41 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse};
+
+// MULTI，开启事务，之后的命令只排队，直到 EXEC 或 DISCARD
+#[derive(Debug, Default)]
+pub struct Multi;
+
+impl Multi {
+    pub fn new() -> Multi {
+        Multi
+    }
+
+    // MULTI 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Multi> {
+        Ok(Multi)
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        let response = if session.in_multi() {
+            Frame::Error("ERR MULTI calls can not be nested".to_string())
+        } else {
+            session.queued = Some(vec![]);
+            session.multi_error = false;
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("multi".as_bytes()));
+        frame
+    }
+}
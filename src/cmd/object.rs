@@ -0,0 +1,105 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::{help_reply, unknown_subcommand};
+use crate::connect::{Connection, ServerConfig};
+use crate::entity::{Db, Frame, MaxmemoryPolicy, Parse};
+
+// 切换淘汰策略后 LRU 与 LFU 的数据需要一段时间才能反映实际的访问情况
+const POLICY_SWITCH_NOTE: &str =
+    "Please note that when switching between policies at runtime LRU and LFU data will take some time to adjust.";
+
+// OBJECT HELP 列出的子命令
+const HELP: &[&str] = &[
+    "ENCODING <key>",
+    "    Return the kind of internal representation used in order to store the value",
+    "    associated with a <key>.",
+    "FREQ <key>",
+    "    Return the access frequency index of the <key>. The returned integer is",
+    "    proportional to the logarithm of the recent access frequency of the key.",
+    "    Only available when maxmemory-policy is allkeys-lfu.",
+    "REFCOUNT <key>",
+    "    Return the number of references of the value associated with the specified",
+    "    <key>.",
+    "IDLETIME <key>",
+    "    Return the idle time of the <key>, that is the approximated number of",
+    "    seconds elapsed since the last access to the key.",
+    "    Not available when maxmemory-policy is allkeys-lfu.",
+];
+
+// OBJECT <subcommand> key，查看 key 的内部信息：
+// ENCODING 返回内部表示，IDLETIME 返回空闲秒数（LFU 策略下不可用），FREQ 返回访问计数（只在 LFU 策略下可用），
+// REFCOUNT 返回值的引用计数（共享的小整数为 i32::MAX）
+#[derive(Debug)]
+pub struct Object {
+    // 子命令（小写）
+    subcommand: String,
+
+    // 子命令的参数
+    args: Vec<String>,
+}
+
+impl Object {
+    pub fn new(subcommand: impl ToString, key: impl ToString) -> Object {
+        Object {
+            subcommand: subcommand.to_string().to_lowercase(),
+            args: vec![key.to_string()],
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Object> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let mut args = vec![];
+        while parse.remaining() > 0 {
+            args.push(parse.next_string()?);
+        }
+        Ok(Object { subcommand, args })
+    }
+
+    #[instrument(skip(self, db, config, dst))]
+    pub(crate) async fn apply(self, db: &Db, config: &ServerConfig, dst: &mut Connection) -> crate::Result<()> {
+        let lfu = config.maxmemory_policy == MaxmemoryPolicy::AllkeysLfu;
+        let response = match (self.subcommand.as_str(), self.args.as_slice()) {
+            ("encoding", [key]) => match db.encoding(key) {
+                Some(encoding) => Frame::Bulk(Bytes::from(encoding)),
+                None => Frame::Error("ERR no such key".to_string()),
+            },
+            ("idletime", [_]) if lfu => Frame::Error(format!(
+                "ERR An LFU maxmemory policy is selected, idle time not tracked. {}",
+                POLICY_SWITCH_NOTE
+            )),
+            ("idletime", [key]) => match db.object_idletime(key) {
+                Some(seconds) => Frame::Integer(seconds as i64),
+                None => Frame::Error("ERR no such key".to_string()),
+            },
+            ("freq", [_]) if !lfu => Frame::Error(format!(
+                "ERR An LFU maxmemory policy is not selected, access frequency not tracked. {}",
+                POLICY_SWITCH_NOTE
+            )),
+            ("freq", [key]) => match db.object_freq(key) {
+                Some(frequency) => Frame::Integer(frequency as i64),
+                None => Frame::Error("ERR no such key".to_string()),
+            },
+            ("refcount", [key]) => match db.refcount(key) {
+                Some(refcount) => Frame::Integer(refcount),
+                None => Frame::Error("ERR no such key".to_string()),
+            },
+            ("help", []) => help_reply("object", HELP),
+            (subcommand, _) => unknown_subcommand("object", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("object".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        for arg in self.args {
+            frame.push_bulk(Bytes::from(arg.into_bytes()));
+        }
+        frame
+    }
+}
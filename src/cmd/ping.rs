@@ -3,7 +3,8 @@ use crate::entity::{Frame, Parse, ParseError};
 use bytes::Bytes;
 use tracing::{debug, instrument};
 
-// 没有 ping 参数则返回 PONG，有参数则返回参数
+// 没有 ping 参数则返回 PONG，有参数则返回参数。
+// 订阅模式下回复与推送的消息一样是数组：["pong", 参数]，没有参数时为空字符串
 #[derive(Debug, Default)]
 pub struct Ping {
     // ping 的信息
@@ -24,10 +25,14 @@ impl Ping {
         }
     }
 
-    //
+    // subscribed 为连接是否处于订阅模式
     #[instrument(skip(self, dst))]
-    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+    pub(crate) async fn apply(self, subscribed: bool, dst: &mut Connection) -> crate::Result<()> {
         let response = match self.msg {
+            msg if subscribed => Frame::Array(vec![
+                Frame::Bulk(Bytes::from("pong")),
+                Frame::Bulk(msg.unwrap_or_default()),
+            ]),
             None => Frame::Simple("PONG".to_string()),
             Some(msg) => Frame::Bulk(msg),
         };
@@ -1,24 +1,27 @@
 use bytes::Bytes;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
-use crate::entity::{Frame, Db, Parse};
+use crate::entity::{Frame, Db, Parse, ParseError};
 
-/// 获取key的值。
+/// 从列表头部或尾部弹出元素。
 /// 如果键不存在，则返回特殊值nil。
-/// 如果key中存储的值不是字符串，则返回一个错误，因为GET只处理字符串值。
-#[derive(Debug)]
+/// 列表中的元素被弹空后 key 会被删除。
+#[derive(Debug, Clone)]
 pub struct Pop {
     /// 要获取的 key
     key: String,
     right: bool,
+    /// 要弹出的元素个数，指定时以数组返回
+    count: Option<u64>,
 }
 
 impl Pop {
     // 利用 key 创建一个新的`Pop`命令
-    pub fn new(key: impl ToString,right:bool) -> Pop {
+    pub fn new(key: impl ToString, right: bool, count: Option<u64>) -> Pop {
         Pop {
             key: key.to_string(),
             right,
+            count,
         }
     }
 
@@ -34,18 +37,28 @@ impl Pop {
         let key = parse.next_string()?;
         let u = parse.next_u64()?;
         let right = if u == 0 { false } else { true };
-        Ok(Pop { key, right })
+        // 可选的 count
+        let count = match parse.next_i64() {
+            Ok(count) if count < 0 => return Err("ERR value is out of range, must be positive".into()),
+            Ok(count) => Some(count as u64),
+            Err(ParseError::EndOfStream) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Pop { key, right, count })
     }
     // 将命令用于 db 数据中
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.pop(&self.key, self.right) {
-            // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
-            // 没有找到命令
-            Frame::Null
+        let count = self.count.unwrap_or(1) as usize;
+        let response = match (db.pop(&self.key, self.right, count), self.count) {
+            // 没有指定 count 时返回单个元素
+            (Ok(Some(mut values)), None) => values.pop().map(Frame::Bulk).unwrap_or(Frame::Null),
+            (Ok(Some(values)), Some(_)) => Frame::Array(values.into_iter().map(Frame::Bulk).collect()),
+            // key 不存在
+            (Ok(None), None) => Frame::Null,
+            (Ok(None), Some(_)) => Frame::NullArray,
+            (Err(err), _) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
@@ -60,6 +73,9 @@ impl Pop {
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
         let u = if self.right { 1 } else { 0 };
         frame.push_u64(u);
+        if let Some(count) = self.count {
+            frame.push_u64(count);
+        }
         frame
     }
 }
@@ -0,0 +1,46 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse};
+
+// PUBLISH channel message，向频道发布消息，返回收到消息的订阅者个数
+#[derive(Debug)]
+pub struct Publish {
+    channel: String,
+
+    message: Bytes,
+}
+
+impl Publish {
+    pub fn new(channel: impl ToString, message: Bytes) -> Publish {
+        Publish {
+            channel: channel.to_string(),
+            message,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Publish> {
+        let channel = parse.next_string()?;
+        let message = parse.next_bytes()?;
+        Ok(Publish { channel, message })
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, dst: &mut Connection) -> crate::Result<()> {
+        let receivers = databases.pubsub().publish(&self.channel, self.message);
+
+        let response = Frame::Integer(receivers as i64);
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("publish".as_bytes()));
+        frame.push_bulk(Bytes::from(self.channel.into_bytes()));
+        frame.push_bulk(self.message);
+        frame
+    }
+}
@@ -4,7 +4,7 @@ use std::time::Duration;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Push {
     key: String,
     value: Vec<String>,
@@ -37,13 +37,15 @@ impl Push {
         // 获取 key
         let key = parse.next_string()?;
 
-        // 获取 value
-        let lens = parse.next_i64()?;
-        let (len, right) = if lens > 0 {
-            (lens as u64, true)
-        } else {
-            ((-lens) as u64, false)
+        // 获取方向，L 插入头部，R 插入尾部
+        let right = match parse.next_string()?.to_uppercase().as_str() {
+            "L" => false,
+            "R" => true,
+            _ => return Err("ERR syntax error".into()),
         };
+
+        // 获取 value
+        let len = parse.next_u64()?;
         let mut value = vec![];
         for _ in 0..len {
             value.push(parse.next_string()?);
@@ -54,8 +56,11 @@ impl Push {
     // 应用相关命令
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        db.push(self.key, self.value, self.right);
-        let response = Frame::Simple("OK".to_string());
+        let response = match db.push(self.key, self.value, self.right) {
+            // 返回插入后列表的长度
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
         Ok(())
@@ -66,11 +71,8 @@ impl Push {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("push".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
-        let mut len: i64 = self.value.len() as i64;
-        if !self.right {
-            len = -len;
-        }
-        frame.push_i64(len);
+        frame.push_bulk(Bytes::from(if self.right { "R" } else { "L" }));
+        frame.push_u64(self.value.len() as u64);
         for v in self.value {
             frame.push_bulk(Bytes::from(v));
         }
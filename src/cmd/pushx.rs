@@ -0,0 +1,63 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// LPUSHX key value [value ...] / RPUSHX key value [value ...]
+// 只在 key 已经是列表时插入头部或尾部，回复插入后列表的长度；key 不存在时什么也不做，回复 0
+#[derive(Debug, Clone)]
+pub struct Pushx {
+    key: String,
+
+    values: Vec<String>,
+
+    // RPUSHX 插入尾部
+    right: bool,
+}
+
+impl Pushx {
+    pub fn new(key: impl ToString, values: Vec<String>, right: bool) -> Pushx {
+        Pushx {
+            key: key.to_string(),
+            values,
+            right,
+        }
+    }
+
+    pub fn right(&self) -> bool {
+        self.right
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse, right: bool) -> crate::Result<Pushx> {
+        let key = parse.next_string()?;
+        let mut values = vec![];
+        while parse.remaining() > 0 {
+            values.push(parse.next_string()?);
+        }
+        Ok(Pushx { key, values, right })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.pushx(self.key, self.values, self.right) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        let name = if self.right { "rpushx" } else { "lpushx" };
+        frame.push_bulk(Bytes::from(name.as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for value in self.values {
+            frame.push_bulk(Bytes::from(value));
+        }
+        frame
+    }
+}
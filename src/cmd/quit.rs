@@ -0,0 +1,37 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse};
+
+// QUIT，回复 OK 后关闭连接。apply 只在 session 上做标记，Handler 执行完命令后看到标记就结束循环，
+// 回复已经写入并刷新，所以客户端总能先收到 OK 再看到连接关闭。事务中与订阅模式下也立即执行
+#[derive(Debug, Default)]
+pub struct Quit;
+
+impl Quit {
+    pub fn new() -> Quit {
+        Quit
+    }
+
+    // QUIT 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Quit> {
+        Ok(Quit)
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        session.close_after_reply = true;
+        let response = Frame::Simple("OK".to_string());
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("quit".as_bytes()));
+        frame
+    }
+}
@@ -0,0 +1,72 @@
+use bytes::Bytes;
+use tracing::{debug, info, instrument};
+use crate::connect::{replication, Connection, ServerConfig};
+use crate::entity::{Databases, Frame, Parse};
+
+// REPLICAOF host port，成为 host:port 上主节点的副本：清空本地数据，接收主节点的快照与之后的写命令。
+// REPLICAOF NO ONE 停止复制并重新接受写命令，已经复制的数据保留
+#[derive(Debug)]
+pub struct ReplicaOf {
+    // None 表示 NO ONE
+    primary: Option<(String, u16)>,
+}
+
+impl ReplicaOf {
+    pub fn new(primary: Option<(String, u16)>) -> ReplicaOf {
+        ReplicaOf { primary }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<ReplicaOf> {
+        let host = parse.next_string()?;
+        let port = parse.next_string()?;
+        if host.eq_ignore_ascii_case("no") && port.eq_ignore_ascii_case("one") {
+            return Ok(ReplicaOf { primary: None });
+        }
+        let port = port
+            .parse::<u16>()
+            .map_err(|_| "ERR Invalid master port")?;
+        Ok(ReplicaOf { primary: Some((host, port)) })
+    }
+
+    #[instrument(skip(self, databases, config, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        match self.primary {
+            Some((host, port)) => {
+                info!(%host, port, "replicating primary");
+                let task = replication::spawn(databases.clone(), config.clone(), host.clone(), port);
+                databases.set_primary(Some((host, port, task)));
+            }
+            None => {
+                info!("replication stopped");
+                databases.set_primary(None);
+            }
+        }
+
+        let response = Frame::Simple("OK".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("replicaof".as_bytes()));
+        match self.primary {
+            Some((host, port)) => {
+                frame.push_bulk(Bytes::from(host.into_bytes()));
+                frame.push_bulk(Bytes::from(port.to_string()));
+            }
+            None => {
+                frame.push_bulk(Bytes::from("no".as_bytes()));
+                frame.push_bulk(Bytes::from("one".as_bytes()));
+            }
+        }
+        frame
+    }
+}
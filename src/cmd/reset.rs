@@ -0,0 +1,37 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse};
+
+// RESET，把连接恢复到刚建立时的状态：放弃事务与 WATCH、退订所有频道、选择 0 号数据库、清除连接名。
+// 连接池回收连接时使用，事务中也立即执行而不排队
+#[derive(Debug, Default)]
+pub struct Reset;
+
+impl Reset {
+    pub fn new() -> Reset {
+        Reset
+    }
+
+    // RESET 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Reset> {
+        Ok(Reset)
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        session.reset();
+        let response = Frame::Simple("RESET".to_string());
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("reset".as_bytes()));
+        frame
+    }
+}
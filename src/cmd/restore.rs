@@ -0,0 +1,76 @@
+use bytes::Bytes;
+use tokio::time::Duration;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// RESTORE key ttl serialized-value [REPLACE]
+// 用 DUMP 的结果重建 key，ttl 为毫秒，0 表示不过期。key 已存在且没有指定 REPLACE 时返回 BUSYKEY 错误
+#[derive(Debug, Clone)]
+pub struct Restore {
+    key: String,
+
+    ttl: i64,
+
+    payload: Bytes,
+
+    // key 已存在时是否覆盖
+    replace: bool,
+}
+
+impl Restore {
+    pub fn new(key: impl ToString, ttl: Option<Duration>, payload: Bytes, replace: bool) -> Restore {
+        Restore {
+            key: key.to_string(),
+            ttl: ttl.map_or(0, |ttl| ttl.as_millis() as i64),
+            payload,
+            replace,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Restore> {
+        let key = parse.next_string()?;
+        let ttl = parse.next_i64()?;
+        let payload = parse.next_bytes()?;
+
+        let mut replace = false;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "REPLACE" => replace = true,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Restore { key, ttl, payload, replace })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.ttl < 0 {
+            Frame::Error("ERR Invalid TTL value, must be >= 0".to_string())
+        } else {
+            let ttl = (self.ttl > 0).then(|| Duration::from_millis(self.ttl as u64));
+            match db.restore(&self.key, ttl, &self.payload, self.replace) {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("restore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.ttl.to_string()));
+        frame.push_bulk(self.payload);
+        if self.replace {
+            frame.push_bulk(Bytes::from("replace".as_bytes()));
+        }
+        frame
+    }
+}
@@ -4,7 +4,7 @@ use std::time::Duration;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Sadd {
     key: String,
     datas: Vec<String>,
@@ -27,11 +27,9 @@ impl Sadd {
         // 获取 key
         let key = parse.next_string()?;
 
-        // 获取 value
-        let len = parse.next_u64()?;
-
+        // 获取 value，key 之后的参数都是成员
         let mut datas = vec![];
-        for _ in 0..len {
+        while parse.remaining() > 0 {
             datas.push(parse.next_string()?);
         }
         Ok(Sadd { key, datas })
@@ -40,8 +38,10 @@ impl Sadd {
     // 应用相关命令
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        db.sadd(self.key, self.datas);
-        let response = Frame::Simple("OK".to_string());
+        let response = match db.sadd(self.key, self.datas) {
+            Ok(_) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
         Ok(())
@@ -52,8 +52,6 @@ impl Sadd {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("sadd".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
-        let len = self.datas.len() as u64;
-        frame.push_u64(len);
         for v in self.datas {
             frame.push_bulk(Bytes::from(v));
         }
@@ -0,0 +1,106 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::glob_match;
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// 没有指定 COUNT 时每次遍历的 key 个数，HSCAN 与 SSCAN 也使用
+pub(crate) const DEFAULT_COUNT: u64 = 10;
+
+// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+// 游标式遍历当前数据库的 key，回复下一次的游标与这一次的 key，游标为 0 时遍历结束。
+// COUNT 是每次遍历的 key 个数，MATCH 与 TYPE 在遍历之后过滤，所以返回的 key 可能更少
+#[derive(Debug)]
+pub struct Scan {
+    cursor: u64,
+
+    pattern: Option<String>,
+
+    count: Option<u64>,
+
+    // 只返回这种类型的 key：string、list、set 或 hash
+    type_name: Option<String>,
+}
+
+impl Scan {
+    pub fn new(cursor: u64, pattern: Option<String>, count: Option<u64>, type_name: Option<String>) -> Scan {
+        Scan {
+            cursor,
+            pattern,
+            count,
+            type_name,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Scan> {
+        let cursor = parse_cursor(parse)?;
+
+        let mut scan = Scan::new(cursor, None, None, None);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "MATCH" if parse.remaining() > 0 => scan.pattern = Some(parse.next_string()?),
+                "COUNT" if parse.remaining() > 0 => scan.count = Some(parse_count(parse)?),
+                "TYPE" if parse.remaining() > 0 => scan.type_name = Some(parse.next_string()?),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+        Ok(scan)
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let count = self.count.unwrap_or(DEFAULT_COUNT) as usize;
+        let (next, keys) = db.scan(self.cursor, count, self.type_name.as_deref());
+
+        let keys = keys
+            .into_iter()
+            .filter(|key| self.pattern.as_ref().map_or(true, |pattern| glob_match(pattern.as_bytes(), key.as_bytes())))
+            .map(|key| Frame::Bulk(Bytes::from(key.into_bytes())))
+            .collect();
+        let response = Frame::Array(vec![Frame::Bulk(Bytes::from(next.to_string())), Frame::Array(keys)]);
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("scan".as_bytes()));
+        frame.push_bulk(Bytes::from(self.cursor.to_string()));
+        if let Some(pattern) = self.pattern {
+            frame.push_bulk(Bytes::from("match".as_bytes()));
+            frame.push_bulk(Bytes::from(pattern.into_bytes()));
+        }
+        if let Some(count) = self.count {
+            frame.push_bulk(Bytes::from("count".as_bytes()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        if let Some(type_name) = self.type_name {
+            frame.push_bulk(Bytes::from("type".as_bytes()));
+            frame.push_bulk(Bytes::from(type_name.into_bytes()));
+        }
+        frame
+    }
+}
+
+// SCAN、HSCAN 与 SSCAN 的游标
+pub(crate) fn parse_cursor(parse: &mut Parse) -> crate::Result<u64> {
+    Ok(parse
+        .next_string()?
+        .parse::<u64>()
+        .map_err(|_| "ERR invalid cursor")?)
+}
+
+// COUNT 选项的值，必须是正整数
+pub(crate) fn parse_count(parse: &mut Parse) -> crate::Result<u64> {
+    let count = parse
+        .next_string()?
+        .parse::<i64>()
+        .map_err(|_| "ERR value is not an integer or out of range")?;
+    if count < 1 {
+        return Err("ERR syntax error".into());
+    }
+    Ok(count as u64)
+}
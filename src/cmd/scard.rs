@@ -28,12 +28,10 @@ impl Scard {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.scard(self.key) {
-            // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
-            // 没有找到命令
-            Frame::Null
+        let response = match db.scard(&self.key) {
+            // 返回元素个数，key 不存在时为 0
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
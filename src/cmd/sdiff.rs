@@ -23,9 +23,8 @@ impl Sdiff {
     // 将命令后面的参数转换为命令对象
     pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Sdiff> {
         // 获取 key
-        let len = parse.next_u64()?;
         let mut keys = Vec::new();
-        for _ in 0..len {
+        while parse.remaining() > 0 {
             keys.push(parse.next_string()?);
         }
         Ok(Sdiff { keys })
@@ -35,12 +34,12 @@ impl Sdiff {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.sdiff(self.keys) {
+        let response = match db.sdiff(self.keys) {
             // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
+            Ok(Some(value)) => Frame::Bulk(value),
             // 没有找到命令
-            Frame::Null
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
@@ -52,7 +51,6 @@ impl Sdiff {
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("sdiff".as_bytes()));
-        frame.push_u64(self.keys.len() as u64);
         for key in self.keys {
             frame.push_bulk(Bytes::from(key));
         }
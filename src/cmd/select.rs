@@ -0,0 +1,53 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse};
+
+// SELECT index，切换当前连接使用的数据库
+#[derive(Debug)]
+pub struct Select {
+    index: i64,
+}
+
+impl Select {
+    pub fn new(index: i64) -> Select {
+        Select { index }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Select> {
+        let index = parse
+            .next_string()?
+            .parse::<i64>()
+            .map_err(|_| "ERR value is not an integer or out of range")?;
+        Ok(Select { index })
+    }
+
+    // 下标越界时回复错误，并保持原来选择的数据库
+    #[instrument(skip(self, databases, db_index, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        db_index: &mut usize,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let response = match databases.checked_index(self.index) {
+            Ok(index) => {
+                *db_index = index;
+                Frame::Simple("OK".to_string())
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("select".as_bytes()));
+        frame.push_bulk(Bytes::from(self.index.to_string()));
+        frame
+    }
+}
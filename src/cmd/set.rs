@@ -1,22 +1,27 @@
-use crate::entity::{Db, Frame, Parse, ParseError};
+use crate::cmd::positive_expire;
+use crate::entity::{Db, Frame, Parse, SetCondition, SetOptions};
 use bytes::Bytes;
 use std::time::Duration;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Set {
     key: String,
     value: Bytes,
-    expire: Option<Duration>,
+    options: SetOptions,
 }
 
 impl Set {
     pub fn new(key: impl ToString, value: Bytes, expire: Option<Duration>) -> Set {
+        Set::with_options(key, value, SetOptions { expire, ..SetOptions::default() })
+    }
+
+    pub fn with_options(key: impl ToString, value: Bytes, options: SetOptions) -> Set {
         Set {
             key: key.to_string(),
             value,
-            expire,
+            options,
         }
     }
 
@@ -29,45 +34,79 @@ impl Set {
     }
 
     pub fn expire(&self) -> Option<Duration> {
-        self.expire
+        self.options.expire
     }
 
-    // 将命令后面的参数转换为命令对象
-    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Set> {
-        use ParseError::EndOfStream;
+    pub fn options(&self) -> &SetOptions {
+        &self.options
+    }
 
+    // 将命令后面的参数转换为命令对象：SET key value [NX|XX] [EX s|PX ms|KEEPTTL] [GET]
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Set> {
         // 获取 key
         let key = parse.next_string()?;
 
         // 获取 value
         let value = parse.next_bytes()?;
 
-        // 获取时间
-        let mut expire = None;
-        match parse.next_string() {
-            Ok(s) if s.to_uppercase() == "EX" => {
+        // 选项顺序任意，NX 与 XX、EX/PX 与 KEEPTTL 互斥，同一选项不能重复
+        let mut options = SetOptions::default();
+        let mut has_condition = false;
+        let mut has_expire = false;
+        while parse.remaining() > 0 {
+            let option = parse.next_string()?;
+            match &option.to_uppercase()[..] {
+                "NX" if !has_condition => {
+                    options.condition = SetCondition::IfAbsent;
+                    has_condition = true;
+                }
+                "XX" if !has_condition => {
+                    options.condition = SetCondition::IfPresent;
+                    has_condition = true;
+                }
                 // 过期时间为秒
-                let secs = parse.next_u64()?;
-                expire = Some(Duration::from_secs(secs));
-            }
-            Ok(s) if s.to_uppercase() == "PX" => {
+                "EX" if !has_expire => {
+                    let secs = positive_expire(parse.next_i64()?, "set")?;
+                    options.expire = Some(Duration::from_secs(secs));
+                    has_expire = true;
+                }
                 // 过期时间为毫秒
-                let ms: u64 = parse.next_u64()?;
-                expire = Some(Duration::from_millis(ms));
+                "PX" if !has_expire => {
+                    let ms = positive_expire(parse.next_i64()?, "set")?;
+                    options.expire = Some(Duration::from_millis(ms));
+                    has_expire = true;
+                }
+                "KEEPTTL" if !has_expire => {
+                    options.keep_ttl = true;
+                    has_expire = true;
+                }
+                "GET" if !options.get => options.get = true,
+                // 直接跟一个毫秒数（客户端命令行的写法）
+                _ if !has_expire => match option.parse::<i64>() {
+                    Ok(ms) => {
+                        options.expire = Some(Duration::from_millis(positive_expire(ms, "set")?));
+                        has_expire = true;
+                    }
+                    Err(_) => return Err("ERR syntax error".into()),
+                },
+                _ => return Err("ERR syntax error".into()),
             }
-            Ok(_) => return Err("currently `SET` only supports the expiration option".into()),
-            Err(EndOfStream) => {}
-            Err(err) => return Err(err.into()),
         }
 
-        Ok(Set { key, value, expire })
+        Ok(Set { key, value, options })
     }
 
-    // 应用相关命令
+    // 应用相关命令。带 GET 时回复原来的值，否则写入时回复 OK，条件不满足时回复 nil
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        db.set(self.key, self.value, self.expire);
-        let response = Frame::Simple("OK".to_string());
+        let get = self.options.get;
+        let response = match db.set(self.key, self.value, self.options) {
+            Ok((_, Some(prev))) if get => Frame::Bulk(prev),
+            Ok((_, None)) if get => Frame::Null,
+            Ok((true, _)) => Frame::Simple("OK".to_string()),
+            Ok((false, _)) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
+        };
         debug!(?response);
         dst.write_frame(&response).await?;
 
@@ -80,10 +119,20 @@ impl Set {
         frame.push_bulk(Bytes::from("set".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
         frame.push_bulk(self.value);
-        if let Some(ms) = self.expire {
+        match self.options.condition {
+            SetCondition::Always => {}
+            SetCondition::IfAbsent => frame.push_bulk(Bytes::from("nx".as_bytes())),
+            SetCondition::IfPresent => frame.push_bulk(Bytes::from("xx".as_bytes())),
+        }
+        if self.options.keep_ttl {
+            frame.push_bulk(Bytes::from("keepttl".as_bytes()));
+        } else if let Some(ms) = self.options.expire {
             frame.push_bulk(Bytes::from("px".as_bytes()));
             frame.push_u64(ms.as_millis() as u64);
         }
+        if self.options.get {
+            frame.push_bulk(Bytes::from("get".as_bytes()));
+        }
         frame
     }
 }
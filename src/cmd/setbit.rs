@@ -0,0 +1,59 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::bit_offset;
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// SETBIT key offset value
+// 把字符串当作位数组设置第 offset 位，字符串不够长时在末尾补 0，返回该位原来的值
+#[derive(Debug, Clone)]
+pub struct Setbit {
+    key: String,
+
+    offset: i64,
+
+    value: i64,
+}
+
+impl Setbit {
+    pub fn new(key: impl ToString, offset: u64, value: bool) -> Setbit {
+        Setbit {
+            key: key.to_string(),
+            offset: offset as i64,
+            value: value as i64,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Setbit> {
+        let key = parse.next_string()?;
+        let offset = parse.next_i64()?;
+        let value = parse.next_i64()?;
+        Ok(Setbit { key, offset, value })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (bit_offset(self.offset), self.value) {
+            (Err(err), _) => Frame::Error(err.to_string()),
+            (Ok(offset), 0 | 1) => match db.setbit(&self.key, offset, self.value == 1) {
+                Ok(old) => Frame::Integer(old as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            },
+            _ => Frame::Error("ERR bit is not an integer or out of range".to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("setbit".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_i64(self.offset);
+        frame.push_i64(self.value);
+        frame
+    }
+}
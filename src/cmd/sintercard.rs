@@ -0,0 +1,79 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// SINTERCARD numkeys key [key ...] [LIMIT limit]
+// 返回多个集合交集的成员个数，LIMIT 指定数到多少个就停止，LIMIT 0 表示不限制
+#[derive(Debug)]
+pub struct Sintercard {
+    keys: Vec<String>,
+
+    limit: i64,
+}
+
+impl Sintercard {
+    pub fn new(keys: Vec<String>, limit: Option<u64>) -> Sintercard {
+        Sintercard {
+            keys,
+            limit: limit.map_or(0, |limit| limit as i64),
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Sintercard> {
+        let numkeys = parse.next_i64()?;
+        if numkeys <= 0 {
+            return Err("ERR numkeys should be greater than 0".into());
+        }
+        if numkeys as usize > parse.remaining() {
+            return Err("ERR Number of keys can't be greater than number of args".into());
+        }
+
+        let mut keys = Vec::with_capacity(numkeys as usize);
+        for _ in 0..numkeys {
+            keys.push(parse.next_string()?);
+        }
+
+        let mut limit = 0;
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "LIMIT" => limit = parse.next_i64()?,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Sintercard { keys, limit })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.limit < 0 {
+            Frame::Error("ERR LIMIT can't be negative".to_string())
+        } else {
+            let limit = Some(self.limit as usize).filter(|limit| *limit > 0);
+            match db.sintercard(self.keys, limit) {
+                Ok(count) => Frame::Integer(count as i64),
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sintercard".as_bytes()));
+        frame.push_u64(self.keys.len() as u64);
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key));
+        }
+        if self.limit != 0 {
+            frame.push_bulk(Bytes::from("limit".as_bytes()));
+            frame.push_i64(self.limit);
+        }
+        frame
+    }
+}
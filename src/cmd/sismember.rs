@@ -30,13 +30,10 @@ impl Sismember {
     // 应用相关命令
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        // 获取值
-        let response = if let Some(value) = db.sismember(self.key.clone(),self.value.clone()) {
-            // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
-            // 没有找到命令
-            Frame::Null
+        // 是元素返回 1，否则返回 0
+        let response = match db.sismember(&self.key, &self.value) {
+            Ok(is_member) => Frame::Integer(is_member as i64),
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
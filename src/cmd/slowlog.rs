@@ -0,0 +1,81 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::cmd::unknown_subcommand;
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse, ParseError};
+
+// SLOWLOG GET 默认返回的条数
+const DEFAULT_GET_COUNT: u64 = 10;
+
+// SLOWLOG GET [count] | SLOWLOG LEN | SLOWLOG RESET
+#[derive(Debug)]
+pub struct Slowlog {
+    // 子命令（小写）
+    subcommand: String,
+
+    // GET 的条数
+    count: Option<u64>,
+}
+
+impl Slowlog {
+    pub fn new(subcommand: impl ToString, count: Option<u64>) -> Slowlog {
+        Slowlog {
+            subcommand: subcommand.to_string().to_lowercase(),
+            count,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Slowlog> {
+        let subcommand = parse.next_string()?.to_lowercase();
+        let count = match parse.next_u64() {
+            Ok(count) => Some(count),
+            Err(ParseError::EndOfStream) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Slowlog { subcommand, count })
+    }
+
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, dst: &mut Connection) -> crate::Result<()> {
+        let response = match (self.subcommand.as_str(), self.count) {
+            ("get", count) => {
+                // 每条记录为 [id, 时间戳, 耗时（微秒）, [命令及参数]]
+                let count = count.unwrap_or(DEFAULT_GET_COUNT) as usize;
+                Frame::Array(
+                    databases.slowlog_get(count)
+                        .into_iter()
+                        .map(|entry| {
+                            Frame::Array(vec![
+                                Frame::Integer(entry.id as i64),
+                                Frame::Integer(entry.timestamp as i64),
+                                Frame::Integer(entry.usec as i64),
+                                Frame::Array(entry.args.into_iter().map(Frame::Bulk).collect()),
+                            ])
+                        })
+                        .collect(),
+                )
+            }
+            ("len", None) => Frame::Integer(databases.slowlog_len() as i64),
+            ("reset", None) => {
+                databases.slowlog_reset();
+                Frame::Simple("OK".to_string())
+            }
+            (subcommand, _) => unknown_subcommand("slowlog", subcommand),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("slowlog".as_bytes()));
+        frame.push_bulk(Bytes::from(self.subcommand.into_bytes()));
+        if let Some(count) = self.count {
+            frame.push_u64(count);
+        }
+        frame
+    }
+}
@@ -1,17 +1,17 @@
-use crate::entity::{Db, Frame, Parse, ParseError};
+use crate::entity::{Db, Frame, Parse};
 use bytes::Bytes;
-use std::time::Duration;
 use tracing::{debug, instrument};
 use crate::connect::Connection;
 
+/// SMEMBERS key，以数组返回集合中的所有元素，key 不存在时返回空数组
 #[derive(Debug)]
-pub struct Sismembers {
+pub struct Smembers {
     key: String,
 }
 
-impl Sismembers {
-    pub fn new(key: impl ToString) -> Sismembers {
-        Sismembers {
+impl Smembers {
+    pub fn new(key: impl ToString) -> Smembers {
+        Smembers {
             key: key.to_string(),
         }
     }
@@ -20,29 +20,22 @@ impl Sismembers {
         &self.key
     }
 
-
-
     // 将命令后面的参数转换为命令对象
-    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Sismembers> {
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Smembers> {
         // 获取 key
         let key = parse.next_string()?;
 
-        Ok(Sismembers { key })
+        Ok(Smembers { key })
     }
 
     // 应用相关命令
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
-        // 获取值
-        let response = if let Some(value) = db.sismembers(self.key) {
-            // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
-            // 没有找到命令
-            Frame::Null
+        let response = match db.smembers(&self.key) {
+            Ok(members) => Frame::Array(members.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
-        // 将找到的值返回
         dst.write_frame(&response).await?;
         Ok(())
     }
@@ -50,7 +43,7 @@ impl Sismembers {
     // 命令封装成帧
     pub(crate) fn into_frame(self) -> Frame {
         let mut frame = Frame::array();
-        frame.push_bulk(Bytes::from("sismembers".as_bytes()));
+        frame.push_bulk(Bytes::from("smembers".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
         frame
     }
@@ -0,0 +1,53 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// SMISMEMBER key member [member ...]，按顺序回复每个 member 是否在集合中（1 或 0），key 不存在时全部为 0
+#[derive(Debug)]
+pub struct Smismember {
+    key: String,
+
+    members: Vec<Bytes>,
+}
+
+impl Smismember {
+    pub fn new(key: impl ToString, members: Vec<Bytes>) -> Smismember {
+        Smismember {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Smismember> {
+        let key = parse.next_string()?;
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            members.push(parse.next_bytes()?);
+        }
+        Ok(Smismember { key, members })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.smismember(&self.key, &self.members) {
+            Ok(flags) => Frame::Array(flags.into_iter().map(|is_member| Frame::Integer(is_member as i64)).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("smismember".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for member in self.members {
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
@@ -0,0 +1,76 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// SORT key [ALPHA] [ASC|DESC] [LIMIT offset count]
+// 回复列表或集合排序后的元素，默认按数值排序，ALPHA 按字节序排序，LIMIT 只返回其中的一段
+#[derive(Debug)]
+pub struct Sort {
+    key: String,
+
+    alpha: bool,
+
+    desc: bool,
+
+    limit: Option<(i64, i64)>,
+}
+
+impl Sort {
+    pub fn new(key: impl ToString, alpha: bool, desc: bool, limit: Option<(i64, i64)>) -> Sort {
+        Sort {
+            key: key.to_string(),
+            alpha,
+            desc,
+            limit,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Sort> {
+        let key = parse.next_string()?;
+
+        let (mut alpha, mut desc, mut limit) = (false, false, None);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "ALPHA" => alpha = true,
+                "ASC" => desc = false,
+                "DESC" => desc = true,
+                "LIMIT" => limit = Some((parse.next_i64()?, parse.next_i64()?)),
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+
+        Ok(Sort { key, alpha, desc, limit })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.sort(&self.key, self.alpha, self.desc, self.limit) {
+            Ok(elements) => Frame::Array(elements.into_iter().map(Frame::Bulk).collect()),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sort".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        if self.alpha {
+            frame.push_bulk(Bytes::from("alpha".as_bytes()));
+        }
+        if self.desc {
+            frame.push_bulk(Bytes::from("desc".as_bytes()));
+        }
+        if let Some((offset, count)) = self.limit {
+            frame.push_bulk(Bytes::from("limit".as_bytes()));
+            frame.push_bulk(Bytes::from(offset.to_string()));
+            frame.push_bulk(Bytes::from(count.to_string()));
+        }
+        frame
+    }
+}
@@ -5,7 +5,7 @@ use tracing::{debug, instrument};
 use crate::connect::Connection;
 
 // 移除set中的指定元素
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Srem {
     key: String,
     datas: Vec<String>,
@@ -28,11 +28,9 @@ impl Srem {
         // 获取 key
         let key = parse.next_string()?;
 
-        // 获取 value
-        let len = parse.next_u64()?;
-
+        // 获取 value，key 之后的参数都是成员
         let mut datas = vec![];
-        for _ in 0..len {
+        while parse.remaining() > 0 {
             datas.push(parse.next_string()?);
         }
         Ok(Srem { key, datas })
@@ -42,12 +40,12 @@ impl Srem {
     #[instrument(skip(self, db, dst))]
     pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
         // 获取值
-        let response = if let Some(value) = db.srem(&self.key, self.datas) {
+        let response = match db.srem(&self.key, self.datas) {
             // 找到命令，返回Bulk
-            Frame::Bulk(value)
-        } else {
+            Ok(Some(value)) => Frame::Bulk(value),
             // 没有找到命令
-            Frame::Null
+            Ok(None) => Frame::Null,
+            Err(err) => Frame::Error(err.to_string()),
         };
         debug!(?response);
         // 将找到的值返回
@@ -60,8 +58,6 @@ impl Srem {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("srem".as_bytes()));
         frame.push_bulk(Bytes::from(self.key.into_bytes()));
-        let len = self.datas.len() as u64;
-        frame.push_u64(len);
         for v in self.datas {
             frame.push_bulk(Bytes::from(v));
         }
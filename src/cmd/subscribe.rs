@@ -0,0 +1,73 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, ServerConfig, Session};
+use crate::entity::{Databases, Frame, Parse, ParseError};
+
+// SUBSCRIBE channel [channel ...]，订阅频道。每个频道回复一次 ["subscribe", 频道, 已订阅的频道数]，
+// 之后频道的消息以 ["message", 频道, 消息] 推送给连接。订阅的频道数达到 pubsub-max-subscriptions 后，
+// 对新频道回复错误，不再订阅剩下的频道
+#[derive(Debug)]
+pub struct Subscribe {
+    channels: Vec<String>,
+}
+
+impl Subscribe {
+    pub fn new(channels: Vec<String>) -> Subscribe {
+        Subscribe { channels }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Subscribe> {
+        let mut channels = vec![parse.next_string()?];
+        loop {
+            match parse.next_string() {
+                Ok(channel) => channels.push(channel),
+                Err(ParseError::EndOfStream) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Subscribe { channels })
+    }
+
+    #[instrument(skip(self, databases, session, config, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        config: &ServerConfig,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let limit = config.pubsub_max_subscriptions;
+        for channel in self.channels {
+            if limit > 0 && session.subscription_count() >= limit && !session.is_subscribed(&channel) {
+                let response = Frame::Error(format!(
+                    "ERR max number of subscriptions per client reached ({})",
+                    limit
+                ));
+                debug!(?response);
+                dst.write_frame(&response).await?;
+                break;
+            }
+            let receiver = databases.pubsub().subscribe(&channel);
+            session.subscribe(channel.clone(), receiver);
+
+            let response = Frame::Array(vec![
+                Frame::Bulk(Bytes::from("subscribe")),
+                Frame::Bulk(Bytes::from(channel)),
+                Frame::Integer(session.subscription_count() as i64),
+            ]);
+            debug!(?response);
+            dst.write_frame(&response).await?;
+        }
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("subscribe".as_bytes()));
+        for channel in self.channels {
+            frame.push_bulk(Bytes::from(channel.into_bytes()));
+        }
+        frame
+    }
+}
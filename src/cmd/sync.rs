@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use tracing::{debug, info, instrument};
+use crate::cmd::Select;
+use crate::connect::{Connection, Session};
+use crate::entity::{Databases, Frame, Parse};
+
+// SYNC，副本连接主节点后发送。主节点先回复 FULLRESYNC，然后发送重建所有 key 的写命令作为快照，
+// 之后把执行的每条写命令转发给副本。没有部分重同步，副本断线重连后总是重新全量同步
+#[derive(Debug, Default)]
+pub struct SyncCommand;
+
+impl SyncCommand {
+    pub fn new() -> SyncCommand {
+        SyncCommand
+    }
+
+    // SYNC 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<SyncCommand> {
+        Ok(SyncCommand)
+    }
+
+    #[instrument(skip(self, databases, session, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        if session.is_replica() {
+            dst.write_frame(&Frame::Error("ERR already syncing".to_string())).await?;
+            return Ok(());
+        }
+
+        // 持有 exec_guard 时没有命令在执行，快照与之后转发的命令之间不会有遗漏或重复
+        let (receiver, snapshot) = {
+            let _guard = databases.exec_guard().await;
+            (databases.subscribe_replication(), databases.snapshot())
+        };
+        info!(client = session.id, "replica connected, starting full resync");
+
+        let response = Frame::Simple("FULLRESYNC".to_string());
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        for (index, frames) in snapshot {
+            dst.write_frame(&Select::new(index as i64).into_frame()).await?;
+            for frame in frames {
+                dst.write_frame(&frame).await?;
+            }
+        }
+
+        session.start_replica_feed(receiver);
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("sync".as_bytes()));
+        frame
+    }
+}
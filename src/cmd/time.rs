@@ -0,0 +1,39 @@
+use bytes::Bytes;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Frame, Parse};
+
+// TIME，回复服务器当前的 Unix 时间：[秒, 微秒]，客户端用它检查时钟偏差
+#[derive(Debug, Default)]
+pub struct Time;
+
+impl Time {
+    pub fn new() -> Time {
+        Time
+    }
+
+    // TIME 没有参数
+    pub(crate) fn parse_frames(_parse: &mut Parse) -> crate::Result<Time> {
+        Ok(Time)
+    }
+
+    #[instrument(skip(self, dst))]
+    pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut response = Frame::array();
+        response.push_bulk(Bytes::from(now.as_secs().to_string()));
+        response.push_bulk(Bytes::from(now.subsec_micros().to_string()));
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("time".as_bytes()));
+        frame
+    }
+}
@@ -1,23 +1,50 @@
 use tracing::{debug, instrument};
+use crate::cmd::unknown_subcommand_message;
 use crate::connect::Connection;
 use crate::entity::Frame;
 
-// “未知”（错误）命令
+// “未知”（错误）命令：未知的命令名、子命令，或者参数个数不对的命令
 #[derive(Debug)]
 pub struct Unknown {
+    // 客户端发送的命令名（小写）
     command_name: String,
+    // 回复给客户端的错误信息
+    message: String,
 }
 
 impl Unknown {
     pub(crate) fn new(key: impl ToString) -> Unknown {
+        let command_name = key.to_string();
         Unknown {
-            command_name: key.to_string(),
+            message: format!("ERR unknown command '{}'", command_name),
+            command_name,
         }
     }
 
+    // 参数个数与命令表中登记的不符
+    pub(crate) fn wrong_arity(key: impl ToString) -> Unknown {
+        let command_name = key.to_string();
+        Unknown {
+            message: format!("ERR wrong number of arguments for '{}' command", command_name),
+            command_name,
+        }
+    }
+
+    // 容器命令的子命令未知，或者参数个数与子命令表中登记的不符
+    pub(crate) fn subcommand(command: &str, subcommand: &str) -> Unknown {
+        Unknown {
+            command_name: command.to_string(),
+            message: unknown_subcommand_message(command, subcommand),
+        }
+    }
+
+    pub(crate) fn command_name(&self) -> &str {
+        &self.command_name
+    }
+
     #[instrument(skip(self, dst))]
     pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
-        let response = Frame::Error(format!("ERR unknown command '{}'", self.command_name));
+        let response = Frame::Error(self.message);
 
         debug!(?response);
 
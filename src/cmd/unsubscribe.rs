@@ -0,0 +1,73 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Frame, Parse, ParseError};
+
+// UNSUBSCRIBE [channel ...]，退订频道，没有参数时退订所有频道。
+// 每个频道回复一次 ["unsubscribe", 频道, 剩余订阅的频道数]
+#[derive(Debug)]
+pub struct Unsubscribe {
+    channels: Vec<String>,
+}
+
+impl Unsubscribe {
+    pub fn new(channels: Vec<String>) -> Unsubscribe {
+        Unsubscribe { channels }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Unsubscribe> {
+        let mut channels = vec![];
+        loop {
+            match parse.next_string() {
+                Ok(channel) => channels.push(channel),
+                Err(ParseError::EndOfStream) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Unsubscribe { channels })
+    }
+
+    #[instrument(skip(self, session, dst))]
+    pub(crate) async fn apply(self, session: &mut Session, dst: &mut Connection) -> crate::Result<()> {
+        let channels = if self.channels.is_empty() {
+            session.channels()
+        } else {
+            self.channels
+        };
+
+        // 没有订阅任何频道时也回复一次，频道为空
+        if channels.is_empty() {
+            let response = Frame::Array(vec![
+                Frame::Bulk(Bytes::from("unsubscribe")),
+                Frame::Null,
+                Frame::Integer(0),
+            ]);
+            debug!(?response);
+            dst.write_frame(&response).await?;
+            return Ok(());
+        }
+
+        for channel in channels {
+            session.unsubscribe(&channel);
+
+            let response = Frame::Array(vec![
+                Frame::Bulk(Bytes::from("unsubscribe")),
+                Frame::Bulk(Bytes::from(channel)),
+                Frame::Integer(session.subscription_count() as i64),
+            ]);
+            debug!(?response);
+            dst.write_frame(&response).await?;
+        }
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("unsubscribe".as_bytes()));
+        for channel in self.channels {
+            frame.push_bulk(Bytes::from(channel.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,59 @@
+use bytes::Bytes;
+use std::future;
+use tokio::time::{self, Duration};
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Databases, Frame, Parse};
+
+// WAIT numreplicas timeout，等待至少 numreplicas 个副本确认之前的写命令，最多等待 timeout 毫秒（0 表示一直等待），
+// 返回确认的副本个数。副本还没有确认机制，已连接的副本视为已确认，个数已经足够时立即返回
+#[derive(Debug)]
+pub struct Wait {
+    numreplicas: i64,
+
+    timeout: i64,
+}
+
+impl Wait {
+    pub fn new(numreplicas: i64, timeout: i64) -> Wait {
+        Wait { numreplicas, timeout }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Wait> {
+        let numreplicas = parse.next_i64()?;
+        let timeout = parse.next_i64()?;
+        Ok(Wait { numreplicas, timeout })
+    }
+
+    // block 为 false 时（EXEC 中）不等待，直接返回当前的副本个数。
+    // 等待期间不持有 command_guard，不会阻塞其他连接的 EXEC
+    #[instrument(skip(self, databases, dst))]
+    pub(crate) async fn apply(self, databases: &Databases, block: bool, dst: &mut Connection) -> crate::Result<()> {
+        let response = if self.timeout < 0 {
+            Frame::Error("ERR timeout is negative".to_string())
+        } else {
+            let replicas = databases.replica_count();
+            if block && self.numreplicas > replicas as i64 {
+                // 副本还不会回复确认，只能等到超时后返回已连接的副本个数
+                match self.timeout {
+                    0 => future::pending::<()>().await,
+                    timeout => time::sleep(Duration::from_millis(timeout as u64)).await,
+                }
+            }
+            Frame::Integer(databases.replica_count() as i64)
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("wait".as_bytes()));
+        frame.push_bulk(Bytes::from(self.numreplicas.to_string()));
+        frame.push_bulk(Bytes::from(self.timeout.to_string()));
+        frame
+    }
+}
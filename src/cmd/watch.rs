@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::{Connection, Session};
+use crate::entity::{Databases, Frame, Parse, ParseError};
+
+// WATCH key [key ...]，记录 key 当前的版本号，EXEC 时任何一个 key 被修改过则放弃事务
+#[derive(Debug)]
+pub struct Watch {
+    keys: Vec<String>,
+}
+
+impl Watch {
+    pub fn new(keys: Vec<String>) -> Watch {
+        Watch { keys }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Watch> {
+        let mut keys = vec![parse.next_string()?];
+        loop {
+            match parse.next_string() {
+                Ok(key) => keys.push(key),
+                Err(ParseError::EndOfStream) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Watch { keys })
+    }
+
+    #[instrument(skip(self, databases, session, dst))]
+    pub(crate) async fn apply(
+        self,
+        databases: &Databases,
+        session: &mut Session,
+        dst: &mut Connection,
+    ) -> crate::Result<()> {
+        let response = if session.in_multi() {
+            Frame::Error("ERR WATCH inside MULTI is not allowed".to_string())
+        } else {
+            let db = databases.db(session.db_index);
+            for key in self.keys {
+                let version = db.version(&key);
+                session.watched.push((session.db_index, key, version));
+            }
+            Frame::Simple("OK".to_string())
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("watch".as_bytes()));
+        for key in self.keys {
+            frame.push_bulk(Bytes::from(key.into_bytes()));
+        }
+        frame
+    }
+}
@@ -0,0 +1,60 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+use crate::utils::serialization::{bytes_to_score, fmt_score};
+
+// ZADD key score member [score member ...]
+// 设置有序集合成员的分数，已有的成员更新分数，key 不存在时创建，回复新加入的成员个数
+#[derive(Debug, Clone)]
+pub struct Zadd {
+    key: String,
+
+    members: Vec<(f64, Bytes)>,
+}
+
+impl Zadd {
+    pub fn new(key: impl ToString, members: Vec<(f64, Bytes)>) -> Zadd {
+        Zadd {
+            key: key.to_string(),
+            members,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Zadd> {
+        let key = parse.next_string()?;
+        if parse.remaining() % 2 != 0 {
+            return Err("ERR syntax error".into());
+        }
+        let mut members = vec![];
+        while parse.remaining() > 0 {
+            let score = bytes_to_score(&parse.next_bytes()?)?;
+            members.push((score, parse.next_bytes()?));
+        }
+        Ok(Zadd { key, members })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zadd(&self.key, self.members) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zadd".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        for (score, member) in self.members {
+            frame.push_bulk(Bytes::from(fmt_score(score)));
+            frame.push_bulk(member);
+        }
+        frame
+    }
+}
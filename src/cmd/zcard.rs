@@ -0,0 +1,41 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+
+// ZCARD key，回复有序集合的成员个数，key 不存在时为 0
+#[derive(Debug)]
+pub struct Zcard {
+    key: String,
+}
+
+impl Zcard {
+    pub fn new(key: impl ToString) -> Zcard {
+        Zcard { key: key.to_string() }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Zcard> {
+        let key = parse.next_string()?;
+        Ok(Zcard { key })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zcard(&self.key) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zcard".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame
+    }
+}
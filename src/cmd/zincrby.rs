@@ -0,0 +1,55 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+use crate::utils::serialization::{bytes_to_score, fmt_score};
+
+// ZINCRBY key increment member
+// 把成员的分数加上 increment，成员不存在时以 increment 为分数加入，回复新的分数
+#[derive(Debug, Clone)]
+pub struct Zincrby {
+    key: String,
+
+    increment: f64,
+
+    member: Bytes,
+}
+
+impl Zincrby {
+    pub fn new(key: impl ToString, increment: f64, member: Bytes) -> Zincrby {
+        Zincrby {
+            key: key.to_string(),
+            increment,
+            member,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Zincrby> {
+        let key = parse.next_string()?;
+        let increment = bytes_to_score(&parse.next_bytes()?)?;
+        let member = parse.next_bytes()?;
+        Ok(Zincrby { key, increment, member })
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zincrby(&self.key, self.increment, self.member) {
+            Ok(score) => Frame::Bulk(Bytes::from(fmt_score(score))),
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zincrby".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(fmt_score(self.increment)));
+        frame.push_bulk(self.member);
+        frame
+    }
+}
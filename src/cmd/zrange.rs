@@ -0,0 +1,91 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse};
+use crate::utils::serialization::fmt_score;
+
+// ZRANGE key start stop [REV] [WITHSCORES]
+// 按下标返回有序集合的成员，下标的含义与 LRANGE 相同：负数从末尾开始计数，超出范围的部分被截断。
+// REV 时按分数从高到低计数，WITHSCORES 时每个成员后面跟着它的分数
+#[derive(Debug)]
+pub struct Zrange {
+    key: String,
+
+    start: i64,
+
+    stop: i64,
+
+    rev: bool,
+
+    with_scores: bool,
+}
+
+impl Zrange {
+    pub fn new(key: impl ToString, start: i64, stop: i64, rev: bool, with_scores: bool) -> Zrange {
+        Zrange {
+            key: key.to_string(),
+            start,
+            stop,
+            rev,
+            with_scores,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Zrange> {
+        let key = parse.next_string()?;
+        let mut index = || -> crate::Result<i64> {
+            parse
+                .next_string()?
+                .parse::<i64>()
+                .map_err(|_| "ERR value is not an integer or out of range".into())
+        };
+        let (start, stop) = (index()?, index()?);
+
+        let mut zrange = Zrange::new(key, start, stop, false, false);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "REV" => zrange.rev = true,
+                "WITHSCORES" => zrange.with_scores = true,
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+        Ok(zrange)
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let response = match db.zrange(&self.key, self.start, self.stop, self.rev) {
+            Ok(members) => {
+                let mut response = Frame::array();
+                for (member, score) in members {
+                    response.push_bulk(member);
+                    if self.with_scores {
+                        response.push_bulk(Bytes::from(fmt_score(score)));
+                    }
+                }
+                response
+            }
+            Err(err) => Frame::Error(err.to_string()),
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrange".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_i64(self.start);
+        frame.push_i64(self.stop);
+        if self.rev {
+            frame.push_bulk(Bytes::from("rev".as_bytes()));
+        }
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        frame
+    }
+}
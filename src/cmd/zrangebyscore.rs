@@ -0,0 +1,126 @@
+use bytes::Bytes;
+use tracing::{debug, instrument};
+use crate::connect::Connection;
+use crate::entity::{Db, Frame, Parse, ScoreBound};
+use crate::utils::serialization::{bytes_to_score, fmt_score};
+
+// ZRANGEBYSCORE key min max [WITHSCORES] [LIMIT offset count]
+// 按分数从低到高返回分数在 min 与 max 之间的成员。min 与 max 可以是 -inf、+inf，
+// 前面加 "(" 表示不包含这个分数，例如 (5 表示分数大于 5。LIMIT 跳过前 offset 个成员后最多返回 count 个，
+// offset 为负数时返回空，count 为负数时返回剩下的所有成员
+#[derive(Debug)]
+pub struct Zrangebyscore {
+    key: String,
+
+    min: String,
+
+    max: String,
+
+    with_scores: bool,
+
+    limit: Option<(i64, i64)>,
+}
+
+impl Zrangebyscore {
+    pub fn new(
+        key: impl ToString,
+        min: impl ToString,
+        max: impl ToString,
+        with_scores: bool,
+        limit: Option<(i64, i64)>,
+    ) -> Zrangebyscore {
+        Zrangebyscore {
+            key: key.to_string(),
+            min: min.to_string(),
+            max: max.to_string(),
+            with_scores,
+            limit,
+        }
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<Zrangebyscore> {
+        let key = parse.next_string()?;
+        let min = parse.next_string()?;
+        let max = parse.next_string()?;
+        // 区间在这里先检查一次，格式错误时不执行命令
+        parse_bound(&min)?;
+        parse_bound(&max)?;
+
+        let mut zrangebyscore = Zrangebyscore::new(key, min, max, false, None);
+        while parse.remaining() > 0 {
+            match &parse.next_string()?.to_uppercase()[..] {
+                "WITHSCORES" => zrangebyscore.with_scores = true,
+                "LIMIT" if parse.remaining() >= 2 => {
+                    let mut number = || -> crate::Result<i64> {
+                        parse
+                            .next_string()?
+                            .parse::<i64>()
+                            .map_err(|_| "ERR value is not an integer or out of range".into())
+                    };
+                    zrangebyscore.limit = Some((number()?, number()?));
+                }
+                _ => return Err("ERR syntax error".into()),
+            }
+        }
+        Ok(zrangebyscore)
+    }
+
+    #[instrument(skip(self, db, dst))]
+    pub(crate) async fn apply(self, db: &Db, dst: &mut Connection) -> crate::Result<()> {
+        let (min, max) = (parse_bound(&self.min)?, parse_bound(&self.max)?);
+        let (offset, count) = match self.limit {
+            None => (0, None),
+            Some((offset, count)) => (offset, usize::try_from(count).ok()),
+        };
+
+        let response = if offset < 0 {
+            Frame::array()
+        } else {
+            match db.zrangebyscore(&self.key, min, max, offset as usize, count) {
+                Ok(members) => {
+                    let mut response = Frame::array();
+                    for (member, score) in members {
+                        response.push_bulk(member);
+                        if self.with_scores {
+                            response.push_bulk(Bytes::from(fmt_score(score)));
+                        }
+                    }
+                    response
+                }
+                Err(err) => Frame::Error(err.to_string()),
+            }
+        };
+
+        debug!(?response);
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+
+    // 命令封装成帧
+    pub(crate) fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("zrangebyscore".as_bytes()));
+        frame.push_bulk(Bytes::from(self.key.into_bytes()));
+        frame.push_bulk(Bytes::from(self.min.into_bytes()));
+        frame.push_bulk(Bytes::from(self.max.into_bytes()));
+        if self.with_scores {
+            frame.push_bulk(Bytes::from("withscores".as_bytes()));
+        }
+        if let Some((offset, count)) = self.limit {
+            frame.push_bulk(Bytes::from("limit".as_bytes()));
+            frame.push_i64(offset);
+            frame.push_i64(count);
+        }
+        frame
+    }
+}
+
+// 解析区间的一端：分数前面的 "(" 表示不包含这个分数
+fn parse_bound(bound: &str) -> crate::Result<ScoreBound> {
+    let (score, exclusive) = match bound.strip_prefix('(') {
+        Some(score) => (score, true),
+        None => (bound, false),
+    };
+    let score = bytes_to_score(score.as_bytes()).map_err(|_| "ERR min or max is not a float")?;
+    Ok(ScoreBound { score, exclusive })
+}
@@ -2,14 +2,23 @@
 
 use crate::cmd::{*};
 use bytes::{Bytes, BytesMut};
+use std::collections::VecDeque;
 use std::io::{Error, ErrorKind};
 use std::time::Duration;
 use clap::value_parser;
-use tokio::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use tokio::net::{TcpStream, ToSocketAddrs, UnixStream};
 use tracing::{debug, instrument};
 use crate::connect::{Connection};
-use crate::entity::Frame;
+#[cfg(feature = "tls")]
+use crate::connect::tls;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+#[cfg(feature = "tls")]
+use tokio_rustls::rustls::ClientConfig;
+use crate::entity::{Expiry, Frame, SetOptions};
 use crate::entity::Frame::Error as FrameError;
+use crate::utils::serialization::bytes_to_score;
 
 // 与Redis服务器建立连接。
 // 由单个"TcpStream"支持，"Client"提供了基本的网络客户端功能（无池化、重试等）。
@@ -36,6 +45,67 @@ impl Client {
         Ok(Client { connection })
     }
 
+    /// 建立 TLS 连接，server_name 用于校验服务端证书，使用内置的根证书
+    #[cfg(feature = "tls")]
+    pub async fn connect_tls<T: ToSocketAddrs>(addr: T, server_name: &str) -> crate::Result<Client> {
+        Client::connect_tls_with_config(addr, server_name, tls::default_client_config()).await
+    }
+
+    /// 使用自定义的 rustls 客户端配置建立 TLS 连接（例如信任自签名证书）
+    #[cfg(feature = "tls")]
+    pub async fn connect_tls_with_config<T: ToSocketAddrs>(
+        addr: T,
+        server_name: &str,
+        config: Arc<ClientConfig>,
+    ) -> crate::Result<Client> {
+        let socket = TcpStream::connect(addr).await?;
+        let stream = tls::connect(socket, server_name, config).await?;
+        let connection = Connection::new(stream);
+
+        Ok(Client { connection })
+    }
+
+    /// 通过 unix 套接字连接同一台机器上的服务器
+    pub async fn connect_unix<P: AsRef<Path>>(path: P) -> crate::Result<Client> {
+        let socket = UnixStream::connect(path).await?;
+        let connection = Connection::new(socket);
+
+        Ok(Client { connection })
+    }
+
+    /// 服务器原样返回 message
+    #[instrument(skip(self))]
+    pub async fn echo(&mut self, message: Bytes) -> crate::Result<Bytes> {
+        let frame = Echo::new(message).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回服务器当前的 Unix 时间：(秒, 微秒)
+    #[instrument(skip(self))]
+    pub async fn time(&mut self) -> crate::Result<(u64, u64)> {
+        let frame = Time::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => match &frames[..] {
+                [Frame::Bulk(secs), Frame::Bulk(micros)] => {
+                    let parse = |value: &Bytes| std::str::from_utf8(value).ok()?.parse::<u64>().ok();
+                    match (parse(secs), parse(micros)) {
+                        (Some(secs), Some(micros)) => Ok((secs, micros)),
+                        _ => Err("protocol error; invalid TIME reply".into()),
+                    }
+                }
+                _ => Err("protocol error; invalid TIME reply".into()),
+            },
+            frame => Err(frame.to_error()),
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn ping(&mut self, msg: Option<Bytes>) -> crate::Result<Bytes> {
         // 将 ping 的内容传进来，创建对象，再封装成帧
@@ -72,6 +142,60 @@ impl Client {
         }
     }
 
+    /// 获取字符串的值并删除 key，key 不存在时返回 None
+    #[instrument(skip(self))]
+    pub async fn getdel(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = Getdel::new(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 获取字符串的值，同时按 expiry 调整过期时间
+    #[instrument(skip(self))]
+    pub async fn getex(&mut self, key: &str, expiry: Expiry) -> crate::Result<Option<Bytes>> {
+        let frame = Getex::new(key, expiry).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(Some(value.into())),
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 key 剩余的生存时间（秒），没有过期时间时返回 -1，key 不存在时返回 -2
+    #[instrument(skip(self))]
+    pub async fn ttl(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Ttl::new(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(ttl) => Ok(ttl),
+            Frame::USize(ttl) => Ok(ttl as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 key 过期时刻的 Unix 时间戳（millis 时为毫秒，否则为秒），没有过期时间时返回 -1，key 不存在时返回 -2
+    #[instrument(skip(self))]
+    pub async fn expiretime(&mut self, key: &str, millis: bool) -> crate::Result<i64> {
+        let frame = Expiretime::new(key, millis).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(timestamp) => Ok(timestamp),
+            Frame::USize(timestamp) => Ok(timestamp as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn lrange(&mut self, key: &str, start: u64, end: u64) -> crate::Result<Option<Bytes>> {
         // 将 key 封装成对象，再封装成帧
@@ -128,6 +252,33 @@ impl Client {
             frame => Err(frame.to_error()),
         }
     }
+
+    /// 按 options 写入字符串（忽略 `options.get`），返回是否写入；NX/XX 条件不满足时返回 false
+    #[instrument(skip(self))]
+    pub async fn set_with(&mut self, key: &str, value: Bytes, options: SetOptions) -> crate::Result<bool> {
+        let frame = Set::with_options(key, value, SetOptions { get: false, ..options }).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(true),
+            Frame::Null => Ok(false),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 按 options 写入字符串并返回 key 原来的值（SET ... GET），key 不存在时返回 None
+    #[instrument(skip(self))]
+    pub async fn set_get(&mut self, key: &str, value: Bytes, options: SetOptions) -> crate::Result<Option<Bytes>> {
+        let frame = Set::with_options(key, value, SetOptions { get: true, ..options }).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
     #[instrument(skip(self))]
     pub async fn mset(&mut self, datas: &Vec<String>) -> crate::Result<()> {
         let l = datas.len();
@@ -153,19 +304,32 @@ impl Client {
         }
     }
     #[instrument(skip(self))]
-    pub async fn push(&mut self, key: &str, value: Vec<String>, right: bool) -> crate::Result<()> {
+    pub async fn push(&mut self, key: &str, value: Vec<String>, right: bool) -> crate::Result<i64> {
         let cmd = Push::new(key, value, right);
         let frame = cmd.into_frame();
         debug!(request = ?frame);
         self.connection.write_frame(&frame).await?;
         match self.read_response().await? {
-            Frame::Simple(response) if response == "OK" => Ok(()),
+            Frame::Integer(len) => Ok(len),
+            Frame::USize(len) => Ok(len as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+    /// 只在 key 已经是列表时插入，返回插入后列表的长度；key 不存在时什么也不做，返回 0
+    #[instrument(skip(self))]
+    pub async fn pushx(&mut self, key: &str, value: Vec<String>, right: bool) -> crate::Result<i64> {
+        let frame = Pushx::new(key, value, right).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len),
+            Frame::USize(len) => Ok(len as i64),
             frame => Err(frame.to_error()),
         }
     }
     #[instrument(skip(self))]
     pub async fn pop(&mut self, key: &str, right: bool) -> crate::Result<Option<Bytes>> {
-        let cmd = Pop::new(key, right);
+        let cmd = Pop::new(key, right, None);
         let frame = cmd.into_frame();
         debug!(request = ?frame);
         self.connection.write_frame(&frame).await?;
@@ -177,6 +341,57 @@ impl Client {
         }
     }
 
+    /// 从第一个非空列表弹出最多 count 个元素（默认 1 个），返回列表的 key 与弹出的元素，所有列表都为空时返回 None
+    #[instrument(skip(self))]
+    pub async fn lmpop(
+        &mut self,
+        keys: Vec<String>,
+        right: bool,
+        count: Option<u64>,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        let frame = Lmpop::new(keys, right, count).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => match &frames[..] {
+                [Frame::Bulk(key), Frame::Array(values)] => {
+                    let values = values
+                        .iter()
+                        .map(|frame| match frame {
+                            Frame::Bulk(value) => Ok(value.clone()),
+                            frame => Err(frame.to_error()),
+                        })
+                        .collect::<crate::Result<_>>()?;
+                    Ok(Some((String::from_utf8_lossy(key).into_owned(), values)))
+                }
+                _ => Err(Frame::Array(frames).to_error()),
+            },
+            Frame::NullArray => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 弹出最多 count 个元素，key 不存在时返回 None
+    #[instrument(skip(self))]
+    pub async fn pop_count(&mut self, key: &str, right: bool, count: u64) -> crate::Result<Option<Vec<Bytes>>> {
+        let frame = Pop::new(key, right, Some(count)).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Simple(value) => Ok(value.into()),
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect::<crate::Result<_>>()
+                .map(Some),
+            Frame::NullArray => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
 
     #[instrument(skip(self))]
     pub async fn sadd(&mut self, key: &str, datas: Vec<String>) -> crate::Result<()> {
@@ -190,15 +405,14 @@ impl Client {
         }
     }
     #[instrument(skip(self))]
-    pub async fn scard(&mut self, key: String) -> crate::Result<Option<Bytes>> {
+    pub async fn scard(&mut self, key: String) -> crate::Result<i64> {
         let cmd = Scard::new(key.to_string());
         let frame = cmd.into_frame();
         debug!(request = ?frame);
         self.connection.write_frame(&frame).await?;
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(Some(value.into())),
-            Frame::Bulk(value) => Ok(Some(value)),
-            Frame::Null => Ok(None),
+            Frame::Integer(len) => Ok(len),
+            Frame::USize(len) => Ok(len as i64),
             frame => Err(frame.to_error()),
         }
     }
@@ -228,6 +442,18 @@ impl Client {
             frame => Err(frame.to_error()),
         }
     }
+    /// 返回多个集合交集的成员个数，limit 为 Some(n) 时最多数到 n
+    #[instrument(skip(self))]
+    pub async fn sintercard(&mut self, keys: Vec<String>, limit: Option<u64>) -> crate::Result<i64> {
+        let frame = Sintercard::new(keys, limit).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count),
+            Frame::USize(count) => Ok(count as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
     #[instrument(skip(self))]
     pub async fn sunion(&mut self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
         let cmd = Sunion::new(keys.clone());
@@ -255,47 +481,1205 @@ impl Client {
         }
     }
     #[instrument(skip(self))]
-    pub async fn sismember(&mut self, key: String, value: String) -> crate::Result<Option<Bytes>> {
+    pub async fn sismember(&mut self, key: String, value: String) -> crate::Result<bool> {
         let cmd = Sismember::new(key.clone(), value.clone());
         let frame = cmd.into_frame();
-        // println!("{:?},{:?}",key.to_string(),value.clone());
         debug!(request = ?frame);
         self.connection.write_frame(&frame).await?;
 
         match self.read_response().await? {
-            Frame::Simple(value) => {Ok(Some(value.into())) },
-            Frame::Bulk(value) => { Ok(Some(value)) },
-            Frame::Null => { Ok(None) },
-            frame => { Err(frame.to_error()) },
+            Frame::Integer(value) => Ok(value == 1),
+            Frame::USize(value) => Ok(value == 1),
+            frame => Err(frame.to_error()),
         }
     }
+    /// 按顺序返回每个 member 是否在集合中，key 不存在时全部为 false
     #[instrument(skip(self))]
-    pub async fn sismembers(&mut self, key: String) -> crate::Result<Option<Bytes>> {
-        let cmd = Sismembers::new(key.clone());
-        let frame = cmd.into_frame();
+    pub async fn smismember(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<Vec<bool>> {
+        let frame = Smismember::new(key, members).into_frame();
         debug!(request = ?frame);
         self.connection.write_frame(&frame).await?;
         match self.read_response().await? {
-            Frame::Simple(value) => Ok(Some(value.into())),
-            Frame::Bulk(value) => Ok(Some(value)),
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Integer(value) => Ok(value == 1),
+                    Frame::USize(value) => Ok(value == 1),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回集合的所有元素，key 不存在时返回空数组
+    /// 返回列表或集合排序后的元素，alpha 时按字节序排序，limit 为 (offset, count)
+    #[instrument(skip(self))]
+    pub async fn sort(
+        &mut self,
+        key: &str,
+        alpha: bool,
+        desc: bool,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<Bytes>> {
+        let frame = Sort::new(key, alpha, desc, limit).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn smembers(&mut self, key: String) -> crate::Result<Vec<Bytes>> {
+        let frame = Smembers::new(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Simple(value) => Ok(value.into()),
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    #[deprecated(note = "use `smembers`")]
+    pub async fn sismembers(&mut self, key: String) -> crate::Result<Vec<Bytes>> {
+        self.smembers(key).await
+    }
+
+    /// 切换当前连接使用的数据库
+    #[instrument(skip(self))]
+    pub async fn select(&mut self, index: usize) -> crate::Result<()> {
+        let frame = Select::new(index as i64).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 随机返回哈希的一个字段，key 不存在时返回 None
+    #[instrument(skip(self))]
+    pub async fn hrandfield(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = Hrandfield::new(key, None, false).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(field) => Ok(Some(field)),
             Frame::Null => Ok(None),
             frame => Err(frame.to_error()),
         }
     }
 
+    /// 随机返回哈希的多个字段：count 为正数时字段不重复，为负数时返回 -count 个字段且可能重复
+    #[instrument(skip(self))]
+    pub async fn hrandfield_count(&mut self, key: &str, count: i64) -> crate::Result<Vec<Bytes>> {
+        self.hrandfield_array(key, count, false).await
+    }
 
-    /// 读取响应帧
-    async fn read_response(&mut self) -> crate::Result<Frame> {
-        // 获取服务端的相应
-        let response = self.connection.read_frame().await?;
-        debug!(?response);
-        match response {
-            Some(Frame::Error(msg)) => Err(msg.into()),
-            Some(frame) => Ok(frame),
-            None => {
-                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
-                Err(err.into())
-            }
+    /// 与 hrandfield_count 相同，同时返回每个字段的值
+    #[instrument(skip(self))]
+    pub async fn hrandfield_with_values(&mut self, key: &str, count: i64) -> crate::Result<Vec<(Bytes, Bytes)>> {
+        let values = self.hrandfield_array(key, count, true).await?;
+        if values.len() % 2 != 0 {
+            return Err("protocol error; unexpected HRANDFIELD reply".into());
+        }
+        let mut values = values.into_iter();
+        let mut fields = vec![];
+        while let (Some(field), Some(value)) = (values.next(), values.next()) {
+            fields.push((field, value));
+        }
+        Ok(fields)
+    }
+
+    async fn hrandfield_array(&mut self, key: &str, count: i64, with_values: bool) -> crate::Result<Vec<Bytes>> {
+        let frame = Hrandfield::new(key, Some(count), with_values).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(parts) => parts
+                .into_iter()
+                .map(|part| match part {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置有序集合成员的分数，key 不存在时创建，返回新加入的成员个数
+    #[instrument(skip(self))]
+    pub async fn zadd(&mut self, key: &str, members: Vec<(f64, Bytes)>) -> crate::Result<i64> {
+        let frame = Zadd::new(key, members).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(added) => Ok(added),
+            Frame::USize(added) => Ok(added as i64),
+            frame => Err(frame.to_error()),
         }
     }
-}
\ No newline at end of file
+
+    /// 把成员的分数加上 increment，成员不存在时以 increment 为分数加入，返回新的分数
+    #[instrument(skip(self))]
+    pub async fn zincrby(&mut self, key: &str, increment: f64, member: Bytes) -> crate::Result<f64> {
+        let frame = Zincrby::new(key, increment, member).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(score) => bytes_to_score(&score),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 从有序集合中删除成员，返回删除的个数
+    #[instrument(skip(self))]
+    pub async fn zrem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<i64> {
+        let frame = Zrem::new(key, members).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(removed) => Ok(removed),
+            Frame::USize(removed) => Ok(removed as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回有序集合的成员个数，key 不存在时为 0
+    #[instrument(skip(self))]
+    pub async fn zcard(&mut self, key: &str) -> crate::Result<i64> {
+        let frame = Zcard::new(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len),
+            Frame::USize(len) => Ok(len as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 按下标返回有序集合的成员，负数下标从末尾开始计数，rev 时按分数从高到低
+    #[instrument(skip(self))]
+    pub async fn zrange(&mut self, key: &str, start: i64, stop: i64, rev: bool) -> crate::Result<Vec<Bytes>> {
+        self.zrange_array(Zrange::new(key, start, stop, rev, false).into_frame()).await
+    }
+
+    /// 与 zrange 相同，同时返回每个成员的分数
+    #[instrument(skip(self))]
+    pub async fn zrange_with_scores(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+        rev: bool,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let values = self.zrange_array(Zrange::new(key, start, stop, rev, true).into_frame()).await?;
+        with_scores(values)
+    }
+
+    /// 按分数从低到高返回分数在 min 与 max 之间的成员。min 与 max 可以是 -inf、+inf，
+    /// 前面加 "(" 时不包含这个分数；limit 为 (offset, count)，count 为负数时返回剩下的所有成员
+    #[instrument(skip(self))]
+    pub async fn zrangebyscore(
+        &mut self,
+        key: &str,
+        min: &str,
+        max: &str,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<Bytes>> {
+        self.zrange_array(Zrangebyscore::new(key, min, max, false, limit).into_frame()).await
+    }
+
+    /// 与 zrangebyscore 相同，同时返回每个成员的分数
+    #[instrument(skip(self))]
+    pub async fn zrangebyscore_with_scores(
+        &mut self,
+        key: &str,
+        min: &str,
+        max: &str,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let values = self.zrange_array(Zrangebyscore::new(key, min, max, true, limit).into_frame()).await?;
+        with_scores(values)
+    }
+
+    async fn zrange_array(&mut self, frame: Frame) -> crate::Result<Vec<Bytes>> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(parts) => parts
+                .into_iter()
+                .map(|part| match part {
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 游标式遍历当前数据库的 key，返回下一次的游标（为 0 时遍历结束）与这一次的 key。
+    /// pattern 与 type_name 在服务器遍历之后过滤，所以一次返回的 key 可能少于 count，甚至为空
+    #[instrument(skip(self))]
+    pub async fn scan(
+        &mut self,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<u64>,
+        type_name: Option<&str>,
+    ) -> crate::Result<(u64, Vec<Bytes>)> {
+        let frame = Scan::new(cursor, pattern.map(String::from), count, type_name.map(String::from)).into_frame();
+        self.scan_page(frame).await
+    }
+
+    /// 游标式遍历哈希的字段，返回下一次的游标（为 0 时遍历结束）与这一次的 (字段, 值)。
+    /// 游标按字段名的哈希值分页，遍历期间一直存在的字段一定会被返回；pattern 在服务器遍历之后过滤
+    #[instrument(skip(self))]
+    pub async fn hscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<u64>,
+    ) -> crate::Result<(u64, Vec<(Bytes, Bytes)>)> {
+        let frame = Hscan::new(key, cursor, pattern.map(String::from), count).into_frame();
+        let (next, values) = self.scan_page(frame).await?;
+        if values.len() % 2 != 0 {
+            return Err("protocol error; unexpected hscan reply".into());
+        }
+        let mut values = values.into_iter();
+        let mut fields = Vec::with_capacity(values.len() / 2);
+        while let (Some(field), Some(value)) = (values.next(), values.next()) {
+            fields.push((field, value));
+        }
+        Ok((next, fields))
+    }
+
+    /// 游标式遍历集合的元素，返回下一次的游标（为 0 时遍历结束）与这一次的元素，保证与 hscan 相同
+    #[instrument(skip(self))]
+    pub async fn sscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<u64>,
+    ) -> crate::Result<(u64, Vec<Bytes>)> {
+        let frame = Sscan::new(key, cursor, pattern.map(String::from), count).into_frame();
+        self.scan_page(frame).await
+    }
+
+    // SCAN、HSCAN 与 SSCAN 的回复：游标与这一次的元素
+    async fn scan_page(&mut self, frame: Frame) -> crate::Result<(u64, Vec<Bytes>)> {
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(parts) => match <[Frame; 2]>::try_from(parts) {
+                Ok([Frame::Bulk(next), Frame::Array(items)]) => {
+                    let next = std::str::from_utf8(&next)
+                        .ok()
+                        .and_then(|next| next.parse().ok())
+                        .ok_or("protocol error; invalid scan cursor")?;
+                    let items = items
+                        .into_iter()
+                        .map(|item| match item {
+                            Frame::Bulk(item) => Ok(item),
+                            frame => Err(frame.to_error()),
+                        })
+                        .collect::<crate::Result<_>>()?;
+                    Ok((next, items))
+                }
+                _ => Err("protocol error; unexpected scan reply".into()),
+            },
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 把 key 从当前数据库移动到下标为 db 的数据库，key 不存在或目标数据库中已有同名 key 时返回 false
+    #[instrument(skip(self))]
+    pub async fn move_key(&mut self, key: &str, db: usize) -> crate::Result<bool> {
+        let frame = Move::new(key, db as i64).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(moved) => Ok(moved == 1),
+            Frame::USize(moved) => Ok(moved == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 删除当前数据库中的所有 key，lazy 时服务器在后台释放旧的数据
+    #[instrument(skip(self))]
+    pub async fn flushdb(&mut self, lazy: bool) -> crate::Result<()> {
+        self.flush(false, lazy).await
+    }
+
+    /// 删除所有数据库中的所有 key，lazy 时服务器在后台释放旧的数据
+    #[instrument(skip(self))]
+    pub async fn flushall(&mut self, lazy: bool) -> crate::Result<()> {
+        self.flush(true, lazy).await
+    }
+
+    async fn flush(&mut self, all: bool, lazy: bool) -> crate::Result<()> {
+        let frame = Flush::new(all, lazy).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 握手，返回服务器信息的键值对
+    #[instrument(skip(self))]
+    pub async fn hello(&mut self, protover: Option<i64>) -> crate::Result<Vec<(Bytes, Frame)>> {
+        let frame = Hello::new(protover).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => {
+                let mut pairs = Vec::with_capacity(frames.len() / 2);
+                let mut frames = frames.into_iter();
+                while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+                    match key {
+                        Frame::Simple(key) => pairs.push((key.into(), value)),
+                        Frame::Bulk(key) => pairs.push((key, value)),
+                        frame => return Err(frame.to_error()),
+                    }
+                }
+                Ok(pairs)
+            }
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn features(&mut self) -> crate::Result<Vec<Bytes>> {
+        let frame = Features::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Simple(value) => Ok(value.into()),
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回服务器支持的命令个数
+    #[instrument(skip(self))]
+    pub async fn command_count(&mut self) -> crate::Result<i64> {
+        let frame = CommandInfo::new(Some("count".to_string())).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count),
+            Frame::USize(count) => Ok(count as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回服务器支持的命令名称
+    #[instrument(skip(self))]
+    pub async fn command_list(&mut self) -> crate::Result<Vec<Bytes>> {
+        let frame = CommandInfo::new(Some("list".to_string())).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Simple(value) => Ok(value.into()),
+                    Frame::Bulk(value) => Ok(value),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回服务器的统计信息，section 为空时返回全部
+    #[instrument(skip(self))]
+    pub async fn info(&mut self, section: Option<String>) -> crate::Result<Bytes> {
+        let frame = Info::new(section).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回最新的 count 条慢查询，每条为 [id, 时间戳, 耗时（微秒）, [命令及参数]]
+    #[instrument(skip(self))]
+    pub async fn slowlog_get(&mut self, count: Option<u64>) -> crate::Result<Vec<Frame>> {
+        let frame = Slowlog::new("get", count).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(entries) => Ok(entries),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn slowlog_len(&mut self) -> crate::Result<i64> {
+        let frame = Slowlog::new("len", None).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn slowlog_reset(&mut self) -> crate::Result<()> {
+        let frame = Slowlog::new("reset", None).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 key 对应值的内部表示（int、embstr、linkedlist 等）
+    #[instrument(skip(self))]
+    pub async fn object_encoding(&mut self, key: &str) -> crate::Result<Bytes> {
+        let frame = Object::new("encoding", key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(value) => Ok(value.into()),
+            Frame::Bulk(value) => Ok(value),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 key 距离上次被访问经过的秒数，淘汰策略为 LFU 时返回错误
+    #[instrument(skip(self))]
+    pub async fn object_idletime(&mut self, key: &str) -> crate::Result<i64> {
+        self.object_integer("idletime", key).await
+    }
+
+    /// 返回 key 的对数访问计数，只在淘汰策略为 LFU 时可用
+    #[instrument(skip(self))]
+    pub async fn object_freq(&mut self, key: &str) -> crate::Result<i64> {
+        self.object_integer("freq", key).await
+    }
+
+    /// 返回 key 对应值的引用计数，共享的小整数返回 i32::MAX
+    #[instrument(skip(self))]
+    pub async fn object_refcount(&mut self, key: &str) -> crate::Result<i64> {
+        self.object_integer("refcount", key).await
+    }
+
+    async fn object_integer(&mut self, subcommand: &str, key: &str) -> crate::Result<i64> {
+        let frame = Object::new(subcommand, key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            Frame::USize(value) => Ok(value as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 估算 key 与值占用的内存（字节），key 不存在时返回 None
+    #[instrument(skip(self))]
+    pub async fn memory_usage(&mut self, key: &str) -> crate::Result<Option<i64>> {
+        let frame = Memory::new("usage", key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(bytes) => Ok(Some(bytes)),
+            Frame::USize(bytes) => Ok(Some(bytes as i64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 复制 source 到 destination，destination 已存在且 replace 为 false 时返回 false
+    #[instrument(skip(self))]
+    pub async fn copy(&mut self, source: &str, destination: &str, replace: bool) -> crate::Result<bool> {
+        let frame = Copy::new(source, destination, replace).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value == 1),
+            Frame::USize(value) => Ok(value == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 keys 中存在的 key 个数，并刷新它们的访问时间
+    #[instrument(skip(self))]
+    pub async fn touch(&mut self, keys: Vec<String>) -> crate::Result<i64> {
+        let frame = Touch::new(keys).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            Frame::USize(value) => Ok(value as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回服务端为当前连接分配的 id
+    #[instrument(skip(self))]
+    pub async fn client_id(&mut self) -> crate::Result<i64> {
+        let frame = ClientCommand::new("id", None).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value),
+            Frame::USize(value) => Ok(value as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置当前连接的名字，空字符串表示清除
+    #[instrument(skip(self))]
+    pub async fn client_setname(&mut self, name: &str) -> crate::Result<()> {
+        let frame = ClientCommand::new("setname", Some(name.to_string())).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回当前连接的名字，没有设置时返回 None
+    #[instrument(skip(self))]
+    pub async fn client_getname(&mut self) -> crate::Result<Option<Bytes>> {
+        let frame = ClientCommand::new("getname", None).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(name) => Ok(Some(name)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 把 value 追加到字符串末尾，key 不存在时先创建，返回追加后的长度
+    #[instrument(skip(self))]
+    pub async fn append(&mut self, key: &str, value: Bytes) -> crate::Result<i64> {
+        let frame = Append::new(key, value).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(len) => Ok(len),
+            Frame::USize(len) => Ok(len as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 当前值等于 expected 时把 key 设为 new，返回是否设置成功
+    #[instrument(skip(self))]
+    pub async fn cas(&mut self, key: &str, expected: Bytes, new: Bytes) -> crate::Result<bool> {
+        let frame = Cas::new(key, expected, new).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value == 1),
+            Frame::USize(value) => Ok(value == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 设置字符串第 offset 位，返回该位原来的值
+    #[instrument(skip(self))]
+    pub async fn setbit(&mut self, key: &str, offset: u64, value: bool) -> crate::Result<bool> {
+        let frame = Setbit::new(key, offset, value).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value == 1),
+            Frame::USize(value) => Ok(value == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回字符串第 offset 位的值
+    #[instrument(skip(self))]
+    pub async fn getbit(&mut self, key: &str, offset: u64) -> crate::Result<bool> {
+        let frame = Getbit::new(key, offset).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(value) => Ok(value == 1),
+            Frame::USize(value) => Ok(value == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 统计字符串中为 1 的位数，range 为包含两端的字节下标
+    #[instrument(skip(self))]
+    pub async fn bitcount(&mut self, key: &str, range: Option<(i64, i64)>) -> crate::Result<i64> {
+        let frame = Bitcount::new(key, range).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(count) => Ok(count),
+            Frame::USize(count) => Ok(count as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回第一个值为 bit 的位的位置，start、end 为包含两端的字节下标，找不到时返回 -1
+    #[instrument(skip(self))]
+    pub async fn bitpos(&mut self, key: &str, bit: bool, start: Option<i64>, end: Option<i64>) -> crate::Result<i64> {
+        let frame = Bitpos::new(key, bit, start, end).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(position) => Ok(position),
+            Frame::USize(position) => Ok(position as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回列表中等于 element 的元素下标，rank 指定第几个匹配（负数从尾部查找），没有匹配时返回 None
+    #[instrument(skip(self))]
+    pub async fn lpos(&mut self, key: &str, element: Bytes, rank: Option<i64>) -> crate::Result<Option<i64>> {
+        let frame = Lpos::new(key, element, rank, None).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(index) => Ok(Some(index)),
+            Frame::USize(index) => Ok(Some(index as i64)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 与 `lpos` 相同，但返回最多 count 个匹配的下标，count 为 0 时返回全部
+    #[instrument(skip(self))]
+    pub async fn lpos_count(
+        &mut self,
+        key: &str,
+        element: Bytes,
+        rank: Option<i64>,
+        count: u64,
+    ) -> crate::Result<Vec<i64>> {
+        let frame = Lpos::new(key, element, rank, Some(count)).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Integer(index) => Ok(index),
+                    Frame::USize(index) => Ok(index as i64),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 一次设置哈希的多个字段
+    #[instrument(skip(self))]
+    pub async fn hmset(&mut self, key: &str, fields: Vec<(Bytes, Bytes)>) -> crate::Result<()> {
+        let frame = Hmset::new(key, fields).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 只在字段不存在时设置哈希的字段，返回是否设置
+    #[instrument(skip(self))]
+    pub async fn hsetnx(&mut self, key: &str, field: Bytes, value: Bytes) -> crate::Result<bool> {
+        let frame = Hsetnx::new(key, field, value).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(created) => Ok(created == 1),
+            Frame::USize(created) => Ok(created == 1),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 按顺序读取哈希的多个字段，不存在的字段为 None
+    #[instrument(skip(self))]
+    pub async fn hmget(&mut self, key: &str, fields: Vec<Bytes>) -> crate::Result<Vec<Option<Bytes>>> {
+        let frame = Hmget::new(key, fields).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    Frame::Bulk(value) => Ok(Some(value)),
+                    Frame::Null => Ok(None),
+                    frame => Err(frame.to_error()),
+                })
+                .collect(),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 向频道发布消息，返回收到消息的订阅者个数
+    #[instrument(skip(self))]
+    pub async fn publish(&mut self, channel: &str, message: Bytes) -> crate::Result<i64> {
+        let frame = Publish::new(channel, message).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(receivers) => Ok(receivers),
+            Frame::USize(receivers) => Ok(receivers as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 等待至少 `numreplicas` 个副本确认之前的写命令，最多等待 `timeout`（为 0 时一直等待），返回确认的副本个数
+    #[instrument(skip(self))]
+    pub async fn wait(&mut self, numreplicas: i64, timeout: Duration) -> crate::Result<i64> {
+        let frame = Wait::new(numreplicas, timeout.as_millis() as i64).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Integer(replicas) => Ok(replicas),
+            Frame::USize(replicas) => Ok(replicas as i64),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 让服务器成为 `primary`（主机名与端口）的副本，为 None 时停止复制
+    #[instrument(skip(self))]
+    pub async fn replicaof(&mut self, primary: Option<(&str, u16)>) -> crate::Result<()> {
+        let primary = primary.map(|(host, port)| (host.to_string(), port));
+        let frame = ReplicaOf::new(primary).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 序列化 key 的值，key 不存在时返回 None。结果可以传给 `restore`
+    #[instrument(skip(self))]
+    pub async fn dump(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        let frame = Dump::new(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(payload) => Ok(Some(payload)),
+            Frame::Null => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 用 `dump` 的结果重建 key，`ttl` 为 None 时不过期
+    #[instrument(skip(self, payload))]
+    pub async fn restore(&mut self, key: &str, ttl: Option<Duration>, payload: Bytes, replace: bool) -> crate::Result<()> {
+        let frame = Restore::new(key, ttl, payload, replace).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 开启事务，之后通过 `queue` 发送的命令在 `exec` 前只排队不执行
+    #[instrument(skip(self))]
+    pub async fn multi(&mut self) -> crate::Result<()> {
+        let frame = Multi::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 在事务中排队一条命令，args 为命令名及其参数
+    #[instrument(skip(self))]
+    pub async fn queue(&mut self, args: Vec<Bytes>) -> crate::Result<()> {
+        let frame = Frame::Array(args.into_iter().map(Frame::Bulk).collect());
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "QUEUED" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 执行事务，按排队顺序返回每条命令的回复，执行出错的命令对应错误帧。
+    /// WATCH 的 key 被修改过时事务不执行，返回 None
+    #[instrument(skip(self))]
+    pub async fn exec(&mut self) -> crate::Result<Option<Vec<Frame>>> {
+        let frame = Exec::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Array(replies) => Ok(Some(replies)),
+            Frame::NullArray => Ok(None),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 监视 keys，之后的 EXEC 在任何一个 key 被修改过时不执行事务
+    #[instrument(skip(self))]
+    pub async fn watch(&mut self, keys: Vec<String>) -> crate::Result<()> {
+        let frame = Watch::new(keys).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 取消监视所有 key
+    #[instrument(skip(self))]
+    pub async fn unwatch(&mut self) -> crate::Result<()> {
+        let frame = Unwatch::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 放弃事务中排队的命令
+    #[instrument(skip(self))]
+    pub async fn discard(&mut self) -> crate::Result<()> {
+        let frame = Discard::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 把连接恢复到刚建立时的状态：放弃事务、退订所有频道、选择 0 号数据库、清除连接名
+    #[instrument(skip(self))]
+    pub async fn reset(&mut self) -> crate::Result<()> {
+        let frame = Reset::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "RESET" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 结束会话：服务器回复 OK 后关闭连接，之后不能再使用这个客户端
+    #[instrument(skip(self))]
+    pub async fn quit(mut self) -> crate::Result<()> {
+        let frame = Quit::new().into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 让服务端处理该连接时先睡眠 duration 再回复，服务器需要以 --enable-debug 启动
+    #[instrument(skip(self))]
+    pub async fn debug_sleep(&mut self, duration: Duration) -> crate::Result<()> {
+        let frame = DebugCommand::new("sleep", Some(duration.as_secs_f64())).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回 key 的内部信息（编码、序列化长度、剩余过期毫秒数），key 不存在时返回错误，服务器需要以 --enable-debug 启动
+    #[instrument(skip(self))]
+    pub async fn debug_object(&mut self, key: &str) -> crate::Result<String> {
+        let frame = DebugCommand::object(key).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(info) => Ok(info),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 关闭或打开服务器后台的主动过期，关闭后过期的 key 只在被读取时删除，服务器需要以 --enable-debug 启动
+    #[instrument(skip(self))]
+    pub async fn debug_set_active_expire(&mut self, enabled: bool) -> crate::Result<()> {
+        let frame = DebugCommand::set_active_expire(enabled).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 返回与模式匹配的配置参数及其值
+    #[instrument(skip(self))]
+    pub async fn config_get(&mut self, pattern: &str) -> crate::Result<Vec<(String, String)>> {
+        let frame = ConfigCommand::get(pattern).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        let frames = match self.read_response().await? {
+            Frame::Array(frames) => frames,
+            frame => return Err(frame.to_error()),
+        };
+        let mut values = frames.into_iter().map(|frame| match frame {
+            Frame::Bulk(value) => Ok(String::from_utf8_lossy(&value).into_owned()),
+            frame => Err(frame.to_error()),
+        });
+        let mut pairs = vec![];
+        while let Some(name) = values.next() {
+            let value = values.next().ok_or("protocol error; missing config value")?;
+            pairs.push((name?, value?));
+        }
+        Ok(pairs)
+    }
+
+    /// 修改配置参数
+    #[instrument(skip(self))]
+    pub async fn config_set(&mut self, parameter: &str, value: &str) -> crate::Result<()> {
+        let frame = ConfigCommand::set(parameter, value).into_frame();
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(response) if response == "OK" => Ok(()),
+            frame => Err(frame.to_error()),
+        }
+    }
+
+    /// 发送任意命令，args 的第一个元素是命令名，返回服务器回复的原始帧。
+    /// 与其他方法不同，服务器回复的错误以 `Frame::Error` 返回，只有连接出错时才返回 Err
+    #[instrument(skip(self))]
+    pub async fn execute(&mut self, args: Vec<Bytes>) -> crate::Result<Frame> {
+        let mut frame = Frame::array();
+        for arg in args {
+            frame.push_bulk(arg);
+        }
+        debug!(request = ?frame);
+        self.connection.write_frame(&frame).await?;
+        let response = self.connection.read_frame().await?;
+        debug!(?response);
+        match response {
+            Some(frame) => Ok(frame),
+            None => {
+                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+                Err(err.into())
+            }
+        }
+    }
+
+
+    /// 读取响应帧
+    async fn read_response(&mut self) -> crate::Result<Frame> {
+        // 获取服务端的相应
+        let response = self.connection.read_frame().await?;
+        debug!(?response);
+        match response {
+            Some(Frame::Error(msg)) => Err(msg.into()),
+            Some(frame) => Ok(frame),
+            None => {
+                let err = Error::new(ErrorKind::ConnectionReset, "connection reset by server");
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// 订阅了频道的客户端，由 [`Client::subscribe`] 创建。
+/// 连接进入订阅状态后只用来接收消息和增减订阅，所以它持有原来的 `Client`
+pub struct Subscriber {
+    client: Client,
+
+    // 已订阅的频道
+    subscribed_channels: Vec<String>,
+
+    // 等待订阅、退订的回复时先收到的消息，next_message 按顺序返回
+    pending: VecDeque<Message>,
+}
+
+impl Client {
+    /// 订阅频道，之后通过返回的 `Subscriber` 接收消息
+    #[instrument(skip(self))]
+    pub async fn subscribe(self, channels: Vec<String>) -> crate::Result<Subscriber> {
+        let mut subscriber = Subscriber {
+            client: self,
+            subscribed_channels: vec![],
+            pending: VecDeque::new(),
+        };
+        subscriber.subscribe(&channels).await?;
+        Ok(subscriber)
+    }
+}
+
+impl Subscriber {
+    /// 当前订阅的频道
+    pub fn get_subscribed(&self) -> &[String] {
+        &self.subscribed_channels
+    }
+
+    /// 等待下一条消息。连接被关闭（包括在一帧的中间被关闭）或读取出错时返回 None
+    pub async fn next_message(&mut self) -> Option<Message> {
+        if let Some(message) = self.pending.pop_front() {
+            return Some(message);
+        }
+        loop {
+            let frame = match self.client.connection.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return None,
+                Err(err) => {
+                    debug!(cause = %err, "subscription closed");
+                    return None;
+                }
+            };
+            debug!(?frame);
+            match into_message(frame) {
+                Ok(message) => return Some(message),
+                // 不是消息的帧与订阅者无关
+                Err(frame) => debug!(?frame, "ignoring frame"),
+            }
+        }
+    }
+
+    /// 订阅更多频道
+    #[instrument(skip(self))]
+    pub async fn subscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = Subscribe::new(channels.to_vec()).into_frame();
+        debug!(request = ?frame);
+        self.client.connection.write_frame(&frame).await?;
+
+        // 每个频道回复一次 ["subscribe", 频道, 已订阅的频道数]
+        for _ in channels {
+            if let Some(channel) = self.read_reply("subscribe").await? {
+                if !self.subscribed_channels.contains(&channel) {
+                    self.subscribed_channels.push(channel);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 退订频道，channels 为空时退订所有频道
+    #[instrument(skip(self))]
+    pub async fn unsubscribe(&mut self, channels: &[String]) -> crate::Result<()> {
+        let frame = Unsubscribe::new(channels.to_vec()).into_frame();
+        debug!(request = ?frame);
+        self.client.connection.write_frame(&frame).await?;
+
+        // 退订所有频道时每个已订阅的频道回复一次，没有订阅任何频道时也回复一次
+        let replies = if channels.is_empty() {
+            self.subscribed_channels.len().max(1)
+        } else {
+            channels.len()
+        };
+        for _ in 0..replies {
+            if let Some(channel) = self.read_reply("unsubscribe").await? {
+                self.subscribed_channels.retain(|subscribed| *subscribed != channel);
+            }
+        }
+        Ok(())
+    }
+
+    /// 订阅期间检查连接，返回服务器原样回复的 msg（没有 msg 时为空）。之前到达的消息留给 next_message
+    #[instrument(skip(self))]
+    pub async fn ping(&mut self, msg: Option<Bytes>) -> crate::Result<Bytes> {
+        let frame = Ping::new(msg).into_frame();
+        debug!(request = ?frame);
+        self.client.connection.write_frame(&frame).await?;
+
+        // 订阅模式下的回复为 ["pong", msg]
+        loop {
+            match into_message(self.client.read_response().await?) {
+                Ok(message) => self.pending.push_back(message),
+                Err(Frame::Array(parts)) if parts.len() == 2 && parts[0] == "pong" => {
+                    return match <[Frame; 2]>::try_from(parts) {
+                        Ok([_, Frame::Bulk(msg)]) => Ok(msg),
+                        _ => Err("protocol error; unexpected pong reply".into()),
+                    };
+                }
+                Err(frame) => return Err(frame.to_error()),
+            }
+        }
+    }
+
+    // 读取一次订阅或退订的回复 [kind, 频道, 频道数]，返回其中的频道。之前到达的消息放入 pending
+    async fn read_reply(&mut self, kind: &str) -> crate::Result<Option<String>> {
+        loop {
+            let frame = self.client.read_response().await?;
+            let frame = match into_message(frame) {
+                Ok(message) => {
+                    self.pending.push_back(message);
+                    continue;
+                }
+                Err(frame) => frame,
+            };
+            match &frame {
+                Frame::Array(parts) if parts.len() == 3 && parts[0] == kind => {
+                    return match &parts[1] {
+                        Frame::Bulk(channel) => Ok(Some(String::from_utf8(channel.to_vec())?)),
+                        Frame::Null => Ok(None),
+                        _ => Err(frame.to_error()),
+                    };
+                }
+                _ => return Err(frame.to_error()),
+            }
+        }
+    }
+}
+
+// 把 WITHSCORES 的回复（成员与分数交替出现）转换为 (成员, 分数)
+fn with_scores(values: Vec<Bytes>) -> crate::Result<Vec<(Bytes, f64)>> {
+    if values.len() % 2 != 0 {
+        return Err("protocol error; unexpected WITHSCORES reply".into());
+    }
+    let mut values = values.into_iter();
+    let mut members = vec![];
+    while let (Some(member), Some(score)) = (values.next(), values.next()) {
+        members.push((member, bytes_to_score(&score)?));
+    }
+    Ok(members)
+}
+
+// 把 ["message", 频道, 消息] 转换为 Message，其他帧原样返回
+fn into_message(frame: Frame) -> Result<Message, Frame> {
+    if let Frame::Array(parts) = &frame {
+        if let [kind, Frame::Bulk(channel), Frame::Bulk(content)] = &parts[..] {
+            if *kind == "message" {
+                return Ok(Message {
+                    channel: String::from_utf8_lossy(channel).into_owned(),
+                    content: content.clone(),
+                });
+            }
+        }
+    }
+    Err(frame)
+}
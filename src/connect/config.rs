@@ -0,0 +1,225 @@
+use crate::connect::server_start::{
+    DATABASES, MAXMEMORY_SAMPLES, MAX_CONNECTIONS, PROTO_MAX_BULK_LEN, PROTO_MAX_MULTIBULK_LEN, SLOWLOG_LOG_SLOWER_THAN, SLOWLOG_MAX_LEN,
+};
+#[cfg(feature = "tls")]
+use crate::connect::TlsConfig;
+use crate::entity::{FrameLimits, KeyspaceEvents, MaxmemoryPolicy, SlowClientPolicy};
+use std::sync::{Arc, RwLock};
+
+/// 所有连接共享的服务器配置，CONFIG SET 修改后对之后执行的命令生效
+pub type SharedConfig = Arc<RwLock<ServerConfig>>;
+
+// CONFIG GET/SET 支持的参数，CONFIG GET * 按这个顺序返回
+const PARAMETERS: &[&str] = &[
+    "maxclients",
+    "databases",
+    "slowlog-log-slower-than",
+    "slowlog-max-len",
+    "maxmemory",
+    "maxmemory-policy",
+    "maxmemory-samples",
+    "enable-debug",
+    "notify-keyspace-events",
+    "timeout",
+    "tcp-nodelay",
+    "tcp-keepalive",
+    "pubsub-slow-client-policy",
+    "pubsub-max-subscriptions",
+    "proto-max-multibulk-len",
+    "proto-max-bulk-len",
+];
+
+// 只能在启动时设置的参数：连接数上限对应启动时创建的信号量，数据库个数对应启动时创建的数据库
+const IMMUTABLE_PARAMETERS: &[&str] = &["maxclients", "databases", "enable-debug"];
+
+/// 服务器配置，启动时传入 `run`
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    // 允许同时处理的最大连接数（信号量的许可数）
+    pub max_connections: usize,
+
+    // 逻辑数据库个数，SELECT 的下标范围为 0..databases
+    pub databases: usize,
+
+    // 执行时间超过该值（微秒）的命令记入慢查询日志，负数表示关闭
+    pub slowlog_log_slower_than: i64,
+
+    // 慢查询日志最多保留的条数
+    pub slowlog_max_len: usize,
+
+    // 数据占用的内存上限（字节），0 表示不限制
+    pub maxmemory: usize,
+
+    // 超过内存上限时的淘汰策略
+    pub maxmemory_policy: MaxmemoryPolicy,
+
+    // LRU/LFU 淘汰时每个数据库随机抽样的 key 数，越大越接近精确的 LRU/LFU，淘汰也越慢
+    pub maxmemory_samples: usize,
+
+    // 是否允许 DEBUG 命令，只应在测试时开启
+    pub enable_debug: bool,
+
+    // 需要发布的键空间通知，默认不发布
+    pub notify_keyspace_events: KeyspaceEvents,
+
+    // 连接空闲（没有发送命令）超过该秒数后关闭，0 表示不关闭
+    pub timeout: u64,
+
+    // 是否在 TCP 连接上设置 TCP_NODELAY，关闭 Nagle 算法以降低小请求的延迟
+    pub tcp_nodelay: bool,
+
+    // TCP keepalive 的空闲时间（秒），0 表示不开启
+    pub tcp_keepalive: u64,
+
+    // 订阅者跟不上频道的发布速度、丢失消息时的处理方式
+    pub pubsub_slow_client_policy: SlowClientPolicy,
+
+    // 每个连接最多订阅的频道数，0 表示不限制
+    pub pubsub_max_subscriptions: usize,
+
+    // 请求中一个数组最多的元素个数，超过时回复协议错误并关闭连接
+    pub proto_max_multibulk_len: u64,
+
+    // 请求中一个 bulk 字符串最大的字节数，超过时回复协议错误并关闭连接
+    pub proto_max_bulk_len: u64,
+
+    // 配置后在 TCP 连接上使用 TLS
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            max_connections: MAX_CONNECTIONS,
+            databases: DATABASES,
+            slowlog_log_slower_than: SLOWLOG_LOG_SLOWER_THAN,
+            slowlog_max_len: SLOWLOG_MAX_LEN,
+            maxmemory: 0,
+            maxmemory_policy: MaxmemoryPolicy::default(),
+            maxmemory_samples: MAXMEMORY_SAMPLES,
+            enable_debug: false,
+            notify_keyspace_events: KeyspaceEvents::default(),
+            timeout: 0,
+            tcp_nodelay: true,
+            tcp_keepalive: 0,
+            pubsub_slow_client_policy: SlowClientPolicy::default(),
+            pubsub_max_subscriptions: 0,
+            proto_max_multibulk_len: PROTO_MAX_MULTIBULK_LEN,
+            proto_max_bulk_len: PROTO_MAX_BULK_LEN,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    // 放入锁中，供所有连接共享
+    pub fn shared(self) -> SharedConfig {
+        Arc::new(RwLock::new(self))
+    }
+
+    // CONFIG GET/SET 支持的参数名
+    pub(crate) fn parameters() -> &'static [&'static str] {
+        PARAMETERS
+    }
+
+    // 参数的当前值，未知参数返回 None
+    pub(crate) fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "maxclients" => self.max_connections.to_string(),
+            "databases" => self.databases.to_string(),
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than.to_string(),
+            "slowlog-max-len" => self.slowlog_max_len.to_string(),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.to_string(),
+            "maxmemory-samples" => self.maxmemory_samples.to_string(),
+            "enable-debug" => yes_no(self.enable_debug),
+            "notify-keyspace-events" => self.notify_keyspace_events.to_string(),
+            "timeout" => self.timeout.to_string(),
+            "tcp-nodelay" => yes_no(self.tcp_nodelay),
+            "tcp-keepalive" => self.tcp_keepalive.to_string(),
+            "pubsub-slow-client-policy" => self.pubsub_slow_client_policy.to_string(),
+            "pubsub-max-subscriptions" => self.pubsub_max_subscriptions.to_string(),
+            "proto-max-multibulk-len" => self.proto_max_multibulk_len.to_string(),
+            "proto-max-bulk-len" => self.proto_max_bulk_len.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // 修改参数，参数未知、不能在运行时修改或值无法解析时返回错误原因
+    pub(crate) fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if IMMUTABLE_PARAMETERS.contains(&name) {
+            return Err("can't set immutable config".to_string());
+        }
+        match name {
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than = parse_number(value)?,
+            "slowlog-max-len" => self.slowlog_max_len = parse_number(value)?,
+            "maxmemory" => self.maxmemory = parse_number(value)?,
+            "maxmemory-policy" => self.maxmemory_policy = value.parse()?,
+            "maxmemory-samples" => self.maxmemory_samples = parse_at_least(value, 1)?,
+            "notify-keyspace-events" => self.notify_keyspace_events = value.parse()?,
+            "timeout" => self.timeout = parse_number(value)?,
+            "tcp-nodelay" => self.tcp_nodelay = parse_yes_no(value)?,
+            "tcp-keepalive" => self.tcp_keepalive = parse_number(value)?,
+            "pubsub-slow-client-policy" => self.pubsub_slow_client_policy = value.parse()?,
+            "pubsub-max-subscriptions" => self.pubsub_max_subscriptions = parse_number(value)?,
+            // 与 Redis 一样限制下限，上限太小时之后所有请求都会因协议错误被断开
+            "proto-max-multibulk-len" => self.proto_max_multibulk_len = parse_at_least(value, 1)?,
+            "proto-max-bulk-len" => self.proto_max_bulk_len = parse_at_least(value, 1024 * 1024)?,
+            _ => return Err("unknown option".to_string()),
+        }
+        Ok(())
+    }
+
+    // 检查只能在启动时设置的参数，`run` 拒绝启动时返回错误原因
+    pub fn validate(&self) -> Result<(), String> {
+        if self.databases == 0 {
+            return Err("databases must be at least 1".to_string());
+        }
+        if self.maxmemory_samples == 0 {
+            return Err("maxmemory-samples must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    // 读取请求帧时的大小上限
+    pub(crate) fn frame_limits(&self) -> FrameLimits {
+        FrameLimits {
+            max_multibulk_len: self.proto_max_multibulk_len,
+            max_bulk_len: self.proto_max_bulk_len,
+        }
+    }
+
+    // 是否在 TCP 连接上开启了 TLS
+    pub fn tls_enabled(&self) -> bool {
+        #[cfg(feature = "tls")]
+        return self.tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        return false;
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn parse_yes_no(value: &str) -> Result<bool, String> {
+    match &value.to_lowercase()[..] {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err("argument must be 'yes' or 'no'".to_string()),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| "argument couldn't be parsed into an integer".to_string())
+}
+
+fn parse_at_least<T: std::str::FromStr + PartialOrd + std::fmt::Display>(value: &str, min: T) -> Result<T, String> {
+    match parse_number(value)? {
+        value if value < min => Err(format!("argument must be at least {}", min)),
+        value => Ok(value),
+    }
+}
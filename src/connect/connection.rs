@@ -1,33 +1,79 @@
 use bytes::{Buf, BytesMut};
+use std::fmt::Debug;
 use std::io::{self, Cursor};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
-use tokio::net::TcpStream;
-use crate::entity::Frame;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::time;
+use crate::entity::{Frame, FrameLimits};
 use crate::entity::Error;
 
+// 连接底层的字节流。TcpStream、UnixStream 等可读写的流都可以用来创建连接
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> Stream for T {}
+
+/// `Connection::read_frame_timeout` 的结果
+#[derive(Debug)]
+pub enum ReadOutcome {
+    // 读到一个完整的帧
+    Frame(Frame),
+    // 对端在帧的边界处关闭了连接
+    Closed,
+    // 超时前没有读到完整的帧，已读到的部分留在缓冲区中，下一次读取时继续
+    TimedOut,
+}
+
 // 从远程对等端发送和接收`Frame`值。当实现网络协议时，该协议上的消息通常由几个称为帧的较小消息组成。
-// "Connection"的目的是在底层"TcpStream"上读写帧。
+// "Connection"的目的是在底层流（"TcpStream"、"UnixStream"等）上读写帧。
 // 为了读取帧，"Connection"使用一个内部缓冲区，该缓冲区被填满，直到有足够的字节创建一个完整的帧。
 // 一旦发生这种情况，`Connection`创建帧并将其返回给调用者。发送帧时，首先将帧编码到写入缓冲区。然后写入缓冲区的内容被写入套接字。
 #[derive(Debug)]
 pub struct Connection {
     // 可从中读写帧
-    stream: BufWriter<TcpStream>,
+    stream: BufWriter<Box<dyn Stream>>,
 
     // 缓冲区，可将 stream中的帧写入缓冲区
     buffer: BytesMut,
+
+    // 暂存的回复，可以嵌套（例如命令执行期间暂存回复时 EXEC 再暂存事务中各命令的回复），只有最内层接收写入的帧
+    captured: Vec<Vec<Frame>>,
+
+    // 读取的帧的大小上限，默认不限制，服务器按配置设置
+    limits: FrameLimits,
 }
 
 impl Connection {
-    // 通过TcpStream创建一个连接，连接包括写入流，和缓冲区
-    pub fn new(socket: TcpStream) -> Connection {
+    // 通过流创建一个连接，连接包括写入流，和缓冲区
+    pub fn new<S: Stream + 'static>(socket: S) -> Connection {
         Connection {
-            stream: BufWriter::new(socket),
+            stream: BufWriter::new(Box::new(socket)),
             // 默认为4KB读缓冲区。
             buffer: BytesMut::with_capacity(4 * 1024),
+            captured: Vec::new(),
+            limits: FrameLimits::UNLIMITED,
         }
     }
 
+    /// 设置之后读取的帧的大小上限，超过时 read_frame 返回协议错误
+    pub fn set_limits(&mut self, limits: FrameLimits) {
+        self.limits = limits;
+    }
+
+    // 开始暂存回复，之后 write_frame 写入的帧不再发送
+    pub(crate) fn start_capture(&mut self) {
+        self.captured.push(vec![]);
+    }
+
+    // 结束最近一次开始的暂存，按写入顺序返回期间的所有回复
+    pub(crate) fn finish_capture(&mut self) -> Vec<Frame> {
+        self.captured.pop().unwrap_or_default()
+    }
+
+    // 最内层暂存中已经写入的回复，没有在暂存时为空
+    pub(crate) fn captured(&self) -> &[Frame] {
+        self.captured.last().map_or(&[], |captured| &captured[..])
+    }
+
     /// 从stream中读取一个"Frame"值。
     /// 函数等待，直到检索到足够的数据来解析帧。在解析帧之后，读缓冲区中剩余的任何数据都将保留在那里，以备下次调用"read_frame"。
     ///
@@ -53,12 +99,24 @@ impl Connection {
         }
     }
 
+    /// 与 `read_frame` 相同，但最多等待 timeout。
+    /// 正常关闭与超时分别返回 `ReadOutcome::Closed` 与 `ReadOutcome::TimedOut`，帧的中间断开仍然返回错误
+    pub async fn read_frame_timeout(&mut self, timeout: Duration) -> crate::Result<ReadOutcome> {
+        // read_frame 只在读取流时等待，读到的数据先进入缓冲区，所以超时取消它不会丢失数据
+        match time::timeout(timeout, self.read_frame()).await {
+            Ok(Ok(Some(frame))) => Ok(ReadOutcome::Frame(frame)),
+            Ok(Ok(None)) => Ok(ReadOutcome::Closed),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Ok(ReadOutcome::TimedOut),
+        }
+    }
+
     // 将 buffer 中的数据转为帧
     fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
         let mut buf = Cursor::new(&self.buffer[..]);
 
         // 检查是否缓冲了足够的数据来解析单个帧。（能否有一行数据）
-        match Frame::check(&mut buf) {
+        match Frame::check_with_limits(&mut buf, &self.limits) {
             Ok(_) => {
                 // 获取帧长度
                 let len = buf.position() as usize;
@@ -87,6 +145,11 @@ impl Connection {
     /// 不建议直接在`TcpStream`上调用这些函数，因为这将导致大量的系统调用。
     /// 但是，在缓冲写流上调用这些函数是可以的。数据将被写入缓冲区。一旦缓冲区满了，它就会刷新到底层套接字。
     pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        if let Some(captured) = self.captured.last_mut() {
+            captured.push(frame.clone());
+            return Ok(());
+        }
+
         match frame {
             Frame::Array(val) => {
                 // 编码帧类型前缀。数组为'*'。
@@ -116,7 +179,7 @@ impl Connection {
                 self.stream.write_all(b"\r\n").await?;
             }
             Frame::Error(val) => {
-                self.stream.write_u8(b'#').await?;
+                self.stream.write_u8(b'-').await?;
                 self.stream.write_all(val.as_bytes()).await?;
                 self.stream.write_all(b"\r\n").await?;
             }
@@ -125,12 +188,15 @@ impl Connection {
                 self.write_decimal(*val).await?;
             }
             Frame::Integer(val) => {
-                self.stream.write_u8(b'=').await?;
+                self.stream.write_u8(b':').await?;
                 self.write_i64(*val).await?;
             }
             Frame::Null => {
                 self.stream.write_all(b"$-1\r\n").await?;
             }
+            Frame::NullArray => {
+                self.stream.write_all(b"*-1\r\n").await?;
+            }
             Frame::Bulk(val) => {
                 let len = val.len();
                 self.stream.write_u8(b'$').await?;
@@ -138,8 +204,14 @@ impl Connection {
                 self.stream.write_all(val).await?;
                 self.stream.write_all(b"\r\n").await?;
             }
-            // 不支持递归调用
-            Frame::Array(_val) => unreachable!(),
+            // 嵌套的数组递归写入（递归的 async 调用需要装箱）
+            Frame::Array(val) => {
+                self.stream.write_u8(b'*').await?;
+                self.write_decimal(val.len() as u64).await?;
+                for entry in val {
+                    Box::pin(self.write_value(entry)).await?;
+                }
+            }
         }
 
         Ok(())
@@ -1,6 +1,11 @@
 pub mod client_start;
 
-pub use client_start::{Client, Message};
+pub use client_start::{Client, Message, Subscriber};
+
+
+pub mod sharded_client;
+
+pub use sharded_client::{HashRing, ShardedClient};
 
 
 pub mod server_start;
@@ -8,10 +13,29 @@ pub mod server_start;
 pub use server_start::{Handler, Listener};
 
 
+pub mod config;
+
+pub use config::{ServerConfig, SharedConfig};
+
+
+pub mod session;
+
+pub use session::Session;
+
+pub(crate) mod replication;
+
+
 pub mod connection;
 
-pub use connection::{Connection};
+pub use connection::{Connection, ReadOutcome, Stream};
 
 pub mod shutdown;
 
 pub use shutdown::{Shutdown};
+
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
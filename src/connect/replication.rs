@@ -0,0 +1,62 @@
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration};
+use tracing::{info, warn};
+use crate::cmd::{Command, SyncCommand};
+use crate::connect::{Connection, ServerConfig, Session};
+use crate::entity::{Databases, Frame};
+
+// 与主节点的连接断开后，重连前等待的时间
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// 启动复制任务：连接主节点并执行它发来的命令，连接断开后重连并重新全量同步。
+// 任务只会被 REPLICAOF 或服务器关闭时终止
+pub(crate) fn spawn(databases: Databases, config: ServerConfig, host: String, port: u16) -> JoinHandle<()> {
+    tokio::spawn(replicate(databases, config, host, port))
+}
+
+async fn replicate(databases: Databases, config: ServerConfig, host: String, port: u16) {
+    loop {
+        if let Err(err) = sync_with_primary(&databases, &config, &host, port).await {
+            warn!(%host, port, cause = %err, "replication link broken, reconnecting");
+        }
+        time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+// 全量同步一次，然后一直执行主节点转发的写命令，直到连接断开
+async fn sync_with_primary(
+    databases: &Databases,
+    config: &ServerConfig,
+    host: &str,
+    port: u16,
+) -> crate::Result<()> {
+    let socket = TcpStream::connect((host, port)).await?;
+    let mut connection = Connection::new(socket);
+    connection.write_frame(&SyncCommand::new().into_frame()).await?;
+
+    match connection.read_frame().await? {
+        Some(Frame::Simple(response)) if response == "FULLRESYNC" => {}
+        Some(frame) => return Err(format!("unexpected reply to SYNC: {}", frame).into()),
+        None => return Err("primary closed the connection".into()),
+    }
+    // 快照会重建主节点的所有 key，先清空本地数据
+    databases.clear(false);
+    info!(%host, port, "full resync started");
+
+    let mut session = Session::new(0);
+    session.master = true;
+    loop {
+        let frame = match connection.read_frame().await? {
+            Some(frame) => frame,
+            None => return Err("primary closed the connection".into()),
+        };
+        let cmd = Command::from_frame(frame)?;
+
+        // 副本不回复主节点，命令的回复暂存后丢弃。与客户端的命令一样经过 apply，主节点传来的 MULTI/EXEC 在副本上也原子地执行
+        connection.start_capture();
+        let res = cmd.apply(databases, &mut session, config, &mut connection).await;
+        connection.finish_capture();
+        res?;
+    }
+}
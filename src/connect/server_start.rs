@@ -1,13 +1,20 @@
-use crate::entity::{Db, DbDropGuard};
-use crate::connect::{Connection, Shutdown};
+use crate::entity::{Databases, DbDropGuard};
+use crate::connect::{Connection, ReadOutcome, ServerConfig, Session, SharedConfig, Shutdown};
 use crate::cmd::{Command};
+use crate::entity::{Error as FrameError, Frame, ParseError};
 
 use std::future::Future;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
 use tokio::sync::{broadcast, mpsc, Semaphore};
-use tokio::time::{self, Duration};
-use tracing::{debug, error, info, instrument};
+use tokio::time::{self, Duration, Instant};
+use bytes::Bytes;
+use socket2::{SockRef, TcpKeepalive};
+use tracing::{debug, error, info, instrument, warn};
 
 /// 服务器侦听器状态。在“run”调用中创建。它包括一个"run"方法
 #[derive(Debug)]
@@ -18,9 +25,21 @@ pub struct Listener {
     // tcp 监听器
     listener: TcpListener,
 
+    // 可选的 unix 套接字监听器，同一台机器上的客户端可以不经过 TCP 协议栈
+    unix_listener: Option<UnixListener>,
+
     // 限制最大连接数（信号量机制）
     limit_connections: Arc<Semaphore>,
 
+    // 限制同时在回复“连接数已满”的任务数，超过时直接关闭新连接
+    limit_rejections: Arc<Semaphore>,
+
+    // 服务器配置，与数据库共享同一份
+    config: SharedConfig,
+
+    // 下一个连接的 id，CLIENT ID 返回
+    next_client_id: AtomicU64,
+
     // 向所有活动连接广播关闭信号。
     notify_shutdown: broadcast::Sender<()>,
     /// 用作正常关闭进程的一部分，以等待客户端连接完成处理。
@@ -37,12 +56,18 @@ pub struct Listener {
 // 每个连接处理程序。读取来自"connection"的请求并将命令应用到"db"。
 #[derive(Debug)]
 pub struct Handler {
-    // 数据库
-    db: Db,
+    // 所有逻辑数据库
+    databases: Databases,
+
+    // 连接独有的状态：id、当前选择的数据库、连接名等
+    session: Session,
 
     // 连接
     connection: Connection,
 
+    // 服务器配置，与数据库共享同一份
+    config: SharedConfig,
+
     // 关闭
     shutdown: Shutdown,
 
@@ -50,21 +75,111 @@ pub struct Handler {
     _shutdown_complete: mpsc::Sender<()>,
 }
 
-// 最大连接数
-const MAX_CONNECTIONS: usize = 250;
+// 监听器接收到的连接
+#[derive(Debug)]
+enum Socket {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Socket {
+    // 将接收到的流封装为连接，配置了 TLS 时先在 TCP 连接上完成握手
+    async fn into_connection(self, config: &ServerConfig) -> crate::Result<Connection> {
+        match self {
+            Socket::Tcp(socket) => {
+                configure_tcp(&socket, config)?;
+                #[cfg(feature = "tls")]
+                if let Some(tls) = &config.tls {
+                    return Ok(Connection::new(tls.accept(socket).await?));
+                }
+                Ok(Connection::new(socket))
+            }
+            Socket::Unix(socket) => Ok(Connection::new(socket)),
+        }
+    }
+}
+
+// accept 的错误是否是暂时性的，重试可能成功
+fn is_transient_accept_error(err: &io::Error) -> bool {
+    match err.kind() {
+        // 等待接收的连接在 accept 之前被对端中断
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::TimedOut
+        | io::ErrorKind::OutOfMemory => true,
+        // 进程或系统的文件描述符、内核缓冲区耗尽，连接关闭后会恢复
+        _ => matches!(
+            err.raw_os_error(),
+            Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM)
+        ),
+    }
+}
+
+// 按配置设置 TCP 连接的 TCP_NODELAY 与 SO_KEEPALIVE
+fn configure_tcp(socket: &TcpStream, config: &ServerConfig) -> crate::Result<()> {
+    socket.set_nodelay(config.tcp_nodelay)?;
+    if config.tcp_keepalive > 0 {
+        let keepalive = TcpKeepalive::new().with_time(Duration::from_secs(config.tcp_keepalive));
+        SockRef::from(socket).set_tcp_keepalive(&keepalive)?;
+    }
+    Ok(())
+}
+
+// 默认最大连接数
+pub const MAX_CONNECTIONS: usize = 250;
 
-// 运行
-pub async fn run(listener: TcpListener, shutdown: impl Future) {
+// 默认的逻辑数据库个数
+pub const DATABASES: usize = 16;
+
+// 默认的慢查询阈值（微秒）与日志长度
+pub const SLOWLOG_LOG_SLOWER_THAN: i64 = 10_000;
+pub const SLOWLOG_MAX_LEN: usize = 128;
+
+// 默认每次淘汰时抽样的 key 数
+pub const MAXMEMORY_SAMPLES: usize = 5;
+
+// 默认的请求帧大小上限：数组最多的元素个数与 bulk 字符串最大的字节数
+pub const PROTO_MAX_MULTIBULK_LEN: u64 = 1024 * 1024;
+pub const PROTO_MAX_BULK_LEN: u64 = 512 * 1024 * 1024;
+
+// 连接数已满时最多同时向多少个新连接回复错误，以及每次回复的时限
+const MAX_REJECTING: usize = 32;
+const REJECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// 慢查询日志中每条记录最多保留的参数个数与每个参数的最大长度
+const SLOWLOG_ENTRY_MAX_ARGC: usize = 32;
+const SLOWLOG_ENTRY_MAX_STRING: usize = 128;
+
+// 运行，config 中包含最大连接数等配置。传入 unix_listener 时同时在 unix 套接字上接收连接
+pub async fn run(
+    listener: TcpListener,
+    unix_listener: Option<UnixListener>,
+    config: ServerConfig,
+    shutdown: impl Future,
+) {
     // 广播一个关闭信息
     let (notify_shutdown, _) = broadcast::channel(1);
     // 多生产，单接收（客户端回复可以关闭）
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
 
+    if let Err(err) = config.validate() {
+        error!(cause = %err, "invalid server config");
+        return;
+    }
+
     // 初始化监听器
+    let limit_connections = Arc::new(Semaphore::new(config.max_connections));
+    let config = config.shared();
     let mut server = Listener {
         listener,
-        db_holder: DbDropGuard::new(),
-        limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
+        unix_listener,
+        db_holder: DbDropGuard::new(config.clone()),
+        limit_connections,
+        limit_rejections: Arc::new(Semaphore::new(MAX_REJECTING)),
+        config,
+        next_client_id: AtomicU64::new(1),
         notify_shutdown,
         shutdown_complete_tx,
     };
@@ -83,41 +198,105 @@ pub async fn run(listener: TcpListener, shutdown: impl Future) {
     let Listener {
         shutdown_complete_tx,
         notify_shutdown,
+        unix_listener,
         ..
     } = server;
 
+    // 停止监听后删除 unix 套接字文件，下次启动不会遇到残留的文件
+    let unix_path = unix_listener
+        .and_then(|listener| listener.local_addr().ok())
+        .and_then(|addr| addr.as_pathname().map(Path::to_path_buf));
+    if let Some(path) = unix_path {
+        if let Err(err) = std::fs::remove_file(&path) {
+            warn!(cause = %err, path = %path.display(), "failed to remove unix socket");
+        }
+    }
+
     drop(notify_shutdown);
     drop(shutdown_complete_tx);
     let _ = shutdown_complete_rx.recv().await;
 }
 
+/// 在 path 上绑定 unix 套接字。上次运行没有正常退出时残留的套接字文件，确认没有服务器在监听后先删除；
+/// path 上有服务器在监听或者不是套接字文件时返回错误，不会删除其他文件
+pub async fn bind_unix(path: &Path) -> crate::Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("{} exists and is not a unix socket", path.display()).into());
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(format!("another server is listening on {}", path.display()).into());
+        }
+        std::fs::remove_file(path)?;
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
 impl Listener {
     async fn run(&mut self) -> crate::Result<()> {
         info!("accepting inbound connections");
 
         loop {
-            // 检测能否连接，拷贝连接指针，获得所有权，
-            let permit = self
-                .limit_connections
-                .clone()
-                .acquire_owned()
-                .await
-                .unwrap();
-
-            // 获取 tcpstream
+            // 获取连接的流
             let socket = self.accept().await?;
+            // 新连接按接收时的配置设置 TCP 选项
+            let snapshot = self.config.read().unwrap().clone();
 
-            // 为每个连接创建一个 handler
-            let mut handler = Handler {
-                db: self.db_holder.db(),
-                connection: Connection::new(socket),
-                shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
-                // 一旦所有克隆被丢弃，通知接收器一半
-                _shutdown_complete: self.shutdown_complete_tx.clone(),
+            // 检测能否连接，拷贝连接指针，获得所有权。
+            // 没有可用的许可时说明连接数已满，回复错误后关闭这个连接，而不是让它一直等待。
+            // 回复在 REJECT_TIMEOUT 内完成，同时回复的连接过多时不回复直接关闭，被拒绝的连接不会无限占用任务
+            let permit = match self.limit_connections.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    warn!(
+                        max_connections = snapshot.max_connections,
+                        "server is at capacity, rejecting connection"
+                    );
+                    if let Ok(rejecting) = self.limit_rejections.clone().try_acquire_owned() {
+                        tokio::spawn(async move {
+                            let _ = time::timeout(REJECT_TIMEOUT, async {
+                                if let Ok(mut connection) = socket.into_connection(&snapshot).await {
+                                    let response =
+                                        Frame::Error("ERR max number of clients reached".to_string());
+                                    let _ = connection.write_frame(&response).await;
+                                }
+                            })
+                            .await;
+                            drop(rejecting);
+                        });
+                    }
+                    continue;
+                }
             };
 
+            let databases = self.db_holder.databases();
+            let shutdown = Shutdown::new(self.notify_shutdown.subscribe());
+            // 一旦所有克隆被丢弃，通知接收器一半
+            let shutdown_complete = self.shutdown_complete_tx.clone();
+            let config = self.config.clone();
+            let id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+
             // 生成一个新任务来处理连接
             tokio::spawn(async move {
+                // TLS 握手在任务中完成，握手失败只关闭这个连接，不会影响接收循环
+                let connection = match socket.into_connection(&snapshot).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        error!(cause = ?err, "failed to establish connection");
+                        return;
+                    }
+                };
+
+                // 为每个连接创建一个 handler
+                let mut handler = Handler {
+                    databases,
+                    session: Session::new(id),
+                    connection,
+                    config,
+                    shutdown,
+                    _shutdown_complete: shutdown_complete,
+                };
+
                 // 处理连接诶
                 if let Err(err) = handler.run().await {
                     error!(cause = ?err, "connection error");
@@ -128,19 +307,33 @@ impl Listener {
         }
     }
 
-    async fn accept(&mut self) -> crate::Result<TcpStream> {
+    // 接收一个连接。backoff 是每次调用的局部变量，成功接收后下一次调用重新从 1 秒开始。
+    // 暂时性的错误（对端在握手完成前断开、文件描述符或内存暂时耗尽等）按 1、2、4…64 秒退避后重试，
+    // 连续失败超过 64 秒仍未恢复时返回错误；其他错误（例如监听套接字本身已经失效）立即返回
+    async fn accept(&mut self) -> crate::Result<Socket> {
         let mut backoff = 1;
 
         // 一直循环获取
         loop {
+            // 同时监听 tcp 与 unix 套接字，先到的连接先处理
+            let res = match &self.unix_listener {
+                Some(unix_listener) => tokio::select! {
+                    res = self.listener.accept() => res.map(|(socket, _)| Socket::Tcp(socket)),
+                    res = unix_listener.accept() => res.map(|(socket, _)| Socket::Unix(socket)),
+                },
+                None => self.listener.accept().await.map(|(socket, _)| Socket::Tcp(socket)),
+            };
+
             // 如果获取到，则返回 stream
-            match self.listener.accept().await {
-                Ok((socket, _)) => return Ok(socket),
+            match res {
+                Ok(socket) => return Ok(socket),
+                Err(err) if !is_transient_accept_error(&err) => return Err(err.into()),
                 Err(err) => {
                     if backoff > 64 {
                         // Accept has failed too many times. Return the error.
                         return Err(err.into());
                     }
+                    warn!(cause = %err, backoff, "failed to accept, retrying");
                 }
             }
             // 暂停backoff秒，暂停时间随着循环翻倍
@@ -155,24 +348,70 @@ impl Handler {
     async fn run(&mut self) -> crate::Result<()> {
         // 只要没有收到关闭信号，则循环
         while !self.shutdown.is_shutdown() {
+            let idle_timeout = self.idle_timeout();
+            let (slow_client_policy, frame_limits) = {
+                let config = self.config.read().unwrap();
+                (config.pubsub_slow_client_policy, config.frame_limits())
+            };
+            self.connection.set_limits(frame_limits);
+
             // 读取请求帧和关闭信号，返回读取到的东西
-            let maybe_frame = tokio::select! {
-                res = self.connection.read_frame() => res?,
+            let frame = tokio::select! {
+                res = read_frame_within(&mut self.connection, idle_timeout) => match res {
+                    Ok(ReadOutcome::Frame(frame)) => frame,
+                    // 对端关闭了连接
+                    Ok(ReadOutcome::Closed) => return Ok(()),
+                    Ok(ReadOutcome::TimedOut) => {
+                        debug!(?idle_timeout, "closing idle connection");
+                        return Ok(());
+                    }
+                    // 协议错误后无法找到下一个请求的开头，与 redis 一样回复错误后关闭连接
+                    Err(err) if err.downcast_ref::<io::Error>().is_none() => {
+                        let _ = self.connection.write_frame(&error_reply(&err)).await;
+                        return Err(err);
+                    }
+                    Err(err) => return Err(err),
+                },
+                // 订阅频道的消息或转发给副本的写命令，没有订阅也不是副本时不会就绪
+                res = self.session.next_push(slow_client_policy) => {
+                    let (frames, disconnect) = res?;
+                    for frame in frames {
+                        self.connection.write_frame(&frame).await?;
+                    }
+                    if disconnect {
+                        return Ok(());
+                    }
+                    continue;
+                }
                 _ = self.shutdown.recv() => {
                     return Ok(());
                 }
             };
 
-            // 获取帧
-            let frame = match maybe_frame {
-                Some(frame) => frame,
-                None => return Ok(()),
+            // 与 redis 一样忽略空请求（*0\r\n），不回复也不关闭连接
+            if matches!(&frame, Frame::Array(parts) if parts.is_empty()) {
+                continue;
+            }
+
+            // 每条命令使用执行前的配置快照，CONFIG SET 从下一条命令开始生效
+            let config = self.config.read().unwrap().clone();
+
+            // 开启慢查询日志时，在帧被转换为命令之前保留一份参数（只拷贝 Bytes 的引用）
+            let slowlog_frame = if config.slowlog_log_slower_than >= 0 {
+                Some(frame.clone())
+            } else {
+                None
             };
-            println!("11111111111");
 
-            // 将帧封装成命令
-            let cmd = Command::from_frame(frame)?;
-            println!("xxxxxxxxxx");
+            // 将帧封装成命令，参数无法解析时回复错误，连接继续处理后面的请求
+            let cmd = match Command::from_frame(frame) {
+                Ok(cmd) => cmd,
+                Err(err) => {
+                    debug!(cause = %err, "failed to parse command");
+                    self.connection.write_frame(&error_reply(&err)).await?;
+                    continue;
+                }
+            };
             // ```
             // debug!(cmd = format!("{:?}", cmd));
             // ```记录日志
@@ -181,10 +420,100 @@ impl Handler {
             // 执行应用命令所需的工作。这可能会导致数据库状态发生变化。
             // 连接被传递到apply函数，允许命令将响应帧直接写入连接。
             // 在pub/sub的情况下，可以将多个帧发送回对等体。
-            // 服务端执行命令
-            cmd.apply(&self.db, &mut self.connection).await?;
+            // 服务端执行命令。这是唯一暂存回复的地方：回复在命令释放所有锁、计时结束之后才写出，
+            // 读得慢的客户端不会阻塞其他连接，慢查询与命令统计的耗时也不包括写回复的时间
+            let start = Instant::now();
+            self.connection.start_capture();
+            let res = cmd.apply(&self.databases, &mut self.session, &config, &mut self.connection).await;
+            let elapsed = start.elapsed();
+            for frame in self.connection.finish_capture() {
+                self.connection.write_frame(&frame).await?;
+            }
+
+            // 只有连接上的 I/O 错误才结束连接，其他错误回复给客户端
+            if let Err(err) = res {
+                if err.downcast_ref::<io::Error>().is_some() {
+                    return Err(err);
+                }
+                debug!(cause = %err, "command failed");
+                self.connection.write_frame(&error_reply(&err)).await?;
+            }
+
+            if let Some(frame) = slowlog_frame {
+                if elapsed.as_micros() as i64 >= config.slowlog_log_slower_than {
+                    self.databases.slowlog_push(slowlog_args(frame), elapsed, config.slowlog_max_len);
+                }
+            }
+
+            // QUIT 的回复已经写出，结束处理，连接随 Handler 一起关闭
+            if self.session.close_after_reply {
+                debug!("client quit");
+                return Ok(());
+            }
         }
 
         Ok(())
     }
+
+    // 空闲超时，未配置时为 None。与 redis 一样，订阅了频道的连接和副本的连接不会因为空闲被关闭
+    fn idle_timeout(&self) -> Option<Duration> {
+        let timeout = self.config.read().unwrap().timeout;
+        if timeout == 0 || self.session.subscription_count() > 0 || self.session.is_replica() {
+            return None;
+        }
+        Some(Duration::from_secs(timeout))
+    }
+}
+
+// 读取下一个请求帧，没有空闲超时时一直等待
+async fn read_frame_within(connection: &mut Connection, timeout: Option<Duration>) -> crate::Result<ReadOutcome> {
+    match timeout {
+        Some(timeout) => connection.read_frame_timeout(timeout).await,
+        None => Ok(connection.read_frame().await?.map_or(ReadOutcome::Closed, ReadOutcome::Frame)),
+    }
+}
+
+// 把错误转换为回复给客户端的错误帧。命令返回的错误本身就是回复的内容，带着错误类型前缀（ERR、WRONGTYPE 等）；
+// 读取帧与解析参数时的协议错误（entity::Error、ParseError）没有前缀，加上 ERR
+fn error_reply(err: &crate::Error) -> Frame {
+    if err.is::<FrameError>() || err.is::<ParseError>() {
+        Frame::Error(format!("ERR {}", err))
+    } else {
+        Frame::Error(err.to_string())
+    }
+}
+
+// 将请求帧转换为慢查询日志中的参数，参数过多或过长时截断
+fn slowlog_args(frame: Frame) -> Vec<Bytes> {
+    let parts = match frame {
+        Frame::Array(parts) => parts,
+        frame => vec![frame],
+    };
+    let argc = parts.len();
+
+    let mut args = Vec::with_capacity(argc.min(SLOWLOG_ENTRY_MAX_ARGC));
+    for (i, part) in parts.into_iter().enumerate() {
+        // 最后一个位置用来说明省略了多少参数
+        if i == SLOWLOG_ENTRY_MAX_ARGC - 1 && argc > SLOWLOG_ENTRY_MAX_ARGC {
+            args.push(Bytes::from(format!(
+                "... ({} more arguments)",
+                argc - SLOWLOG_ENTRY_MAX_ARGC + 1
+            )));
+            break;
+        }
+        let arg = match part {
+            Frame::Bulk(data) => data,
+            frame => Bytes::from(frame.to_string()),
+        };
+        if arg.len() > SLOWLOG_ENTRY_MAX_STRING {
+            let mut truncated = arg.slice(..SLOWLOG_ENTRY_MAX_STRING).to_vec();
+            truncated.extend_from_slice(
+                format!("... ({} more bytes)", arg.len() - SLOWLOG_ENTRY_MAX_STRING).as_bytes(),
+            );
+            args.push(Bytes::from(truncated));
+        } else {
+            args.push(arg);
+        }
+    }
+    args
 }
@@ -0,0 +1,237 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use crate::cmd::{Command, Select};
+use crate::entity::{Frame, SlowClientPolicy};
+
+// 转发到连接的频道消息最多缓冲的条数，连接写得太慢时转发任务会等待
+const MESSAGE_BUFFER: usize = 128;
+
+// 转发任务交给连接的内容
+#[derive(Debug)]
+enum Forwarded {
+    // 频道收到的消息
+    Message(String, Bytes),
+    // 连接读得太慢，频道中有 u64 条消息没有转发就被覆盖了
+    Lagged(String, u64),
+}
+
+// 每个连接独有的状态，由 Handler 持有并传给 Command::apply
+#[derive(Debug)]
+pub struct Session {
+    // 连接的唯一 id，由 Listener 按接收顺序分配
+    pub(crate) id: u64,
+
+    // 当前选择的数据库下标，新连接默认使用 0 号数据库
+    pub(crate) db_index: usize,
+
+    // CLIENT SETNAME 设置的连接名
+    pub(crate) name: Option<String>,
+
+    // MULTI 之后排队等待 EXEC 的命令，None 表示不在事务中
+    pub(crate) queued: Option<Vec<Command>>,
+
+    // 排队时有命令出错，EXEC 时放弃整个事务
+    pub(crate) multi_error: bool,
+
+    // WATCH 的 key：(数据库下标, key, WATCH 时的版本号)
+    pub(crate) watched: Vec<(usize, String, u64)>,
+
+    // EXEC 执行期间成功的写命令：(数据库下标, 命令帧)，EXEC 结束后包在 MULTI/EXEC 中传播给副本。None 表示不在 EXEC 中
+    pub(crate) exec_propagation: Option<Vec<(usize, Frame)>>,
+
+    // 订阅的频道，值为把该频道的消息转发到 messages 的任务
+    subscriptions: HashMap<String, JoinHandle<()>>,
+
+    // 转发任务的发送端，每个订阅克隆一份
+    message_tx: mpsc::Sender<Forwarded>,
+
+    // 所有订阅频道的消息，由 Handler 写给客户端
+    messages: mpsc::Receiver<Forwarded>,
+
+    // 复制任务执行主节点传来的命令时使用的会话，不受副本只读的限制
+    pub(crate) master: bool,
+
+    // 连接是副本（执行过 SYNC）时，需要转发给它的写命令
+    replica_feed: Option<ReplicaFeed>,
+
+    // QUIT 设置，Handler 写完这条命令的回复后关闭连接
+    pub(crate) close_after_reply: bool,
+}
+
+// 转发给副本的写命令流
+#[derive(Debug)]
+struct ReplicaFeed {
+    receiver: broadcast::Receiver<(usize, Frame)>,
+
+    // 副本当前选择的数据库，命令属于其他数据库时先发送 SELECT
+    db_index: Option<usize>,
+}
+
+impl Session {
+    pub(crate) fn new(id: u64) -> Session {
+        let (message_tx, messages) = mpsc::channel(MESSAGE_BUFFER);
+        Session {
+            id,
+            db_index: 0,
+            name: None,
+            queued: None,
+            multi_error: false,
+            watched: vec![],
+            exec_propagation: None,
+            subscriptions: HashMap::new(),
+            message_tx,
+            messages,
+            master: false,
+            replica_feed: None,
+            close_after_reply: false,
+        }
+    }
+
+    // RESET：恢复到连接刚建立时的状态。id 不变，副本的复制流也不受影响
+    pub(crate) fn reset(&mut self) {
+        self.db_index = 0;
+        self.name = None;
+        self.queued = None;
+        self.multi_error = false;
+        self.watched.clear();
+        for (_, task) in self.subscriptions.drain() {
+            task.abort();
+        }
+        // 换一个新的消息队列，旧的转发任务已经放入或正在放入的消息随旧队列丢弃
+        let (message_tx, messages) = mpsc::channel(MESSAGE_BUFFER);
+        self.message_tx = message_tx;
+        self.messages = messages;
+    }
+
+    // 是否处于 MULTI 开启的事务中
+    pub(crate) fn in_multi(&self) -> bool {
+        self.queued.is_some()
+    }
+
+    // 订阅频道的个数
+    pub(crate) fn subscription_count(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    // 是否已经订阅了频道
+    pub(crate) fn is_subscribed(&self, channel: &str) -> bool {
+        self.subscriptions.contains_key(channel)
+    }
+
+    // 订阅的所有频道
+    pub(crate) fn channels(&self) -> Vec<String> {
+        self.subscriptions.keys().cloned().collect()
+    }
+
+    // 开始把 receiver 收到的消息转发给连接，已经订阅过该频道时什么也不做
+    pub(crate) fn subscribe(&mut self, channel: String, mut receiver: broadcast::Receiver<Bytes>) {
+        if self.subscriptions.contains_key(&channel) {
+            return;
+        }
+        let tx = self.message_tx.clone();
+        let name = channel.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(message) => {
+                        // 连接已经关闭
+                        if tx.send(Forwarded::Message(name.clone(), message)).await.is_err() {
+                            break;
+                        }
+                    }
+                    // 连接读得太慢，广播队列中未读的消息被覆盖，由 Handler 按配置的策略处理
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        if tx.send(Forwarded::Lagged(name.clone(), skipped)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        self.subscriptions.insert(channel, task);
+    }
+
+    // 退订频道，返回之前是否订阅过
+    pub(crate) fn unsubscribe(&mut self, channel: &str) -> bool {
+        match self.subscriptions.remove(channel) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 连接成为副本，之后主节点执行的写命令从 receiver 转发给它
+    pub(crate) fn start_replica_feed(&mut self, receiver: broadcast::Receiver<(usize, Frame)>) {
+        self.replica_feed = Some(ReplicaFeed { receiver, db_index: None });
+    }
+
+    pub(crate) fn is_replica(&self) -> bool {
+        self.replica_feed.is_some()
+    }
+
+    // 等待下一批需要主动写给连接的帧：订阅频道的消息，或连接是副本时主节点执行的写命令。
+    // 返回的 bool 表示写完这些帧后是否断开连接：订阅者丢失了消息且策略为 disconnect 时，回复错误后断开。
+    // 副本落后太多时返回错误，由 Handler 断开连接，副本重连后重新全量同步
+    pub(crate) async fn next_push(&mut self, slow_client_policy: SlowClientPolicy) -> crate::Result<(Vec<Frame>, bool)> {
+        tokio::select! {
+            // 发送端由 session 持有，不会返回 None
+            Some(forwarded) = self.messages.recv() => match forwarded {
+                Forwarded::Message(channel, message) => Ok((vec![Frame::Array(vec![
+                    Frame::Bulk(Bytes::from("message")),
+                    Frame::Bulk(Bytes::from(channel)),
+                    Frame::Bulk(message),
+                ])], false)),
+                Forwarded::Lagged(channel, skipped) => {
+                    warn!(%channel, skipped, policy = %slow_client_policy, "subscriber lagged, messages dropped");
+                    match slow_client_policy {
+                        SlowClientPolicy::Skip => Ok((vec![], false)),
+                        SlowClientPolicy::Disconnect => Ok((vec![Frame::Error(format!(
+                            "ERR subscriber too slow, {} messages on channel '{}' were dropped",
+                            skipped, channel
+                        ))], true)),
+                    }
+                }
+            },
+            res = next_propagated(&mut self.replica_feed) => res.map(|frames| (frames, false)),
+        }
+    }
+}
+
+// 等待下一条需要转发给副本的写命令，连接不是副本时一直等待
+async fn next_propagated(replica_feed: &mut Option<ReplicaFeed>) -> crate::Result<Vec<Frame>> {
+    let feed = match replica_feed {
+        Some(feed) => feed,
+        None => return future::pending().await,
+    };
+    match feed.receiver.recv().await {
+        Ok((index, frame)) => {
+            let mut frames = vec![];
+            if feed.db_index != Some(index) {
+                feed.db_index = Some(index);
+                frames.push(Select::new(index as i64).into_frame());
+            }
+            frames.push(frame);
+            Ok(frames)
+        }
+        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+            Err(format!("replica fell {} commands behind", skipped).into())
+        }
+        Err(broadcast::error::RecvError::Closed) => Err("replication stream closed".into()),
+    }
+}
+
+impl Drop for Session {
+    // 连接关闭时停止所有转发任务，释放频道的订阅
+    fn drop(&mut self) {
+        for task in self.subscriptions.values() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! 客户端分片：将 key 通过一致性哈希路由到多个独立的服务器
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use bytes::{Bytes, BytesMut};
+use tracing::instrument;
+use crate::connect::Client;
+use crate::entity::SetOptions;
+use crate::utils::hash::crc32;
+
+// 每个服务器在哈希环上的虚拟节点数，虚拟节点越多，key 分布越均匀
+const VIRTUAL_NODES: u32 = 160;
+
+/// 一致性哈希环：每个节点按名字放置 VIRTUAL_NODES 个虚拟节点，key 落到顺时针方向的第一个虚拟节点。
+/// 虚拟节点的位置只取决于节点名，增删一个节点时只有属于它的 key 会改变归属
+pub struct HashRing {
+    // 虚拟节点的哈希值 -> 节点的下标
+    ring: BTreeMap<u32, usize>,
+}
+
+impl HashRing {
+    /// 按节点名（服务器地址）构建哈希环，节点的下标即在 nodes 中的位置
+    pub fn new<T: AsRef<str>>(nodes: &[T]) -> HashRing {
+        let mut ring = BTreeMap::new();
+        for (index, node) in nodes.iter().enumerate() {
+            for virtual_node in 0..VIRTUAL_NODES {
+                ring.insert(crc32(format!("{}#{}", node.as_ref(), virtual_node).as_bytes()), index);
+            }
+        }
+        HashRing { ring }
+    }
+
+    /// 返回 key 所在节点的下标，环为空时 panic
+    pub fn node(&self, key: &str) -> usize {
+        let hash = crc32(key.as_bytes());
+        // 顺时针找到第一个虚拟节点，超过环尾则回到环首
+        self.ring
+            .range(hash..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, index)| *index)
+            .unwrap()
+    }
+}
+
+// 持有多个服务器的连接，按 key 的哈希值选择服务器。
+// 单 key 命令直接转发到对应服务器；mget/mset 按 key 拆分；
+// 集合运算等多 key 命令要求所有 key 落在同一个服务器上，否则返回错误。
+pub struct ShardedClient {
+    // 各服务器的连接
+    clients: Vec<Client>,
+
+    // 以服务器地址构建的哈希环，节点下标即 clients 的下标
+    ring: HashRing,
+}
+
+impl ShardedClient {
+    /// 连接所有服务器并构建哈希环
+    pub async fn connect<T: AsRef<str>>(addrs: &[T]) -> crate::Result<ShardedClient> {
+        if addrs.is_empty() {
+            return Err("sharded client needs at least one address".into());
+        }
+
+        let mut clients = Vec::with_capacity(addrs.len());
+        for addr in addrs {
+            clients.push(Client::connect(addr.as_ref()).await?);
+        }
+
+        Ok(ShardedClient { clients, ring: HashRing::new(addrs) })
+    }
+
+    /// 返回 key 所在服务器的下标
+    pub fn shard_index(&self, key: &str) -> usize {
+        self.ring.node(key)
+    }
+
+    // 返回 key 所在服务器的连接
+    fn shard(&mut self, key: &str) -> &mut Client {
+        let index = self.shard_index(key);
+        &mut self.clients[index]
+    }
+
+    // 多 key 命令只能发往同一个服务器
+    fn same_shard(&mut self, keys: &[String]) -> crate::Result<&mut Client> {
+        let mut indexes = keys.iter().map(|key| self.shard_index(key));
+        let first = indexes.next().ok_or("wrong number of arguments")?;
+        if indexes.any(|index| index != first) {
+            return Err("CROSSSLOT keys in request don't hash to the same server".into());
+        }
+        Ok(&mut self.clients[first])
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get(&mut self, key: &str) -> crate::Result<Option<Bytes>> {
+        self.shard(key).get(key).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set(&mut self, key: &str, value: Bytes, expiration: Option<Duration>) -> crate::Result<()> {
+        self.shard(key).set(key, value, expiration).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_with(&mut self, key: &str, value: Bytes, options: SetOptions) -> crate::Result<bool> {
+        self.shard(key).set_with(key, value, options).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn set_get(&mut self, key: &str, value: Bytes, options: SetOptions) -> crate::Result<Option<Bytes>> {
+        self.shard(key).set_get(key, value, options).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn ttl(&mut self, key: &str) -> crate::Result<i64> {
+        self.shard(key).ttl(key).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn expiretime(&mut self, key: &str, millis: bool) -> crate::Result<i64> {
+        self.shard(key).expiretime(key, millis).await
+    }
+
+    // 按 key 拆分到各个服务器，结果格式与 `Client::mget` 相同
+    #[instrument(skip(self))]
+    pub async fn mget(&mut self, keys: &Vec<String>) -> crate::Result<Option<Bytes>> {
+        let mut res = BytesMut::new();
+        for key in keys {
+            match self.shard(key).get(key).await? {
+                Some(value) => res.extend(value),
+                None => res.extend(b"None"),
+            }
+            res.extend(b",");
+        }
+        Ok(Some(Bytes::from(res)))
+    }
+
+    // 按 key 拆分到各个服务器
+    #[instrument(skip(self))]
+    pub async fn mset(&mut self, datas: &Vec<String>) -> crate::Result<()> {
+        if datas.len() & 1 == 1 {
+            return Err("Wrong number of parameters".into());
+        }
+        for pair in datas.chunks(2) {
+            self.set(&pair[0], Bytes::from(pair[1].clone()), None).await?;
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn incrby(&mut self, key: &str, value: i64) -> crate::Result<()> {
+        self.shard(key).incrby(key, value).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn append(&mut self, key: &str, value: Bytes) -> crate::Result<i64> {
+        self.shard(key).append(key, value).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn push(&mut self, key: &str, value: Vec<String>, right: bool) -> crate::Result<i64> {
+        self.shard(key).push(key, value, right).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pushx(&mut self, key: &str, value: Vec<String>, right: bool) -> crate::Result<i64> {
+        self.shard(key).pushx(key, value, right).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pop(&mut self, key: &str, right: bool) -> crate::Result<Option<Bytes>> {
+        self.shard(key).pop(key, right).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn pop_count(&mut self, key: &str, right: bool, count: u64) -> crate::Result<Option<Vec<Bytes>>> {
+        self.shard(key).pop_count(key, right, count).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn lrange(&mut self, key: &str, start: u64, end: u64) -> crate::Result<Option<Bytes>> {
+        self.shard(key).lrange(key, start, end).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sadd(&mut self, key: &str, datas: Vec<String>) -> crate::Result<()> {
+        self.shard(key).sadd(key, datas).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn srem(&mut self, key: String, datas: Vec<String>) -> crate::Result<Option<Bytes>> {
+        self.shard(&key).srem(key, datas).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn scard(&mut self, key: String) -> crate::Result<i64> {
+        self.shard(&key).scard(key).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sismember(&mut self, key: String, value: String) -> crate::Result<bool> {
+        self.shard(&key).sismember(key, value).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn smismember(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<Vec<bool>> {
+        self.shard(key).smismember(key, members).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sort(
+        &mut self,
+        key: &str,
+        alpha: bool,
+        desc: bool,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<Bytes>> {
+        self.shard(key).sort(key, alpha, desc, limit).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn smembers(&mut self, key: String) -> crate::Result<Vec<Bytes>> {
+        self.shard(&key).smembers(key).await
+    }
+
+    #[deprecated(note = "use `smembers`")]
+    pub async fn sismembers(&mut self, key: String) -> crate::Result<Vec<Bytes>> {
+        self.smembers(key).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sscan(
+        &mut self,
+        key: &str,
+        cursor: u64,
+        pattern: Option<&str>,
+        count: Option<u64>,
+    ) -> crate::Result<(u64, Vec<Bytes>)> {
+        self.shard(key).sscan(key, cursor, pattern, count).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zadd(&mut self, key: &str, members: Vec<(f64, Bytes)>) -> crate::Result<i64> {
+        self.shard(key).zadd(key, members).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zincrby(&mut self, key: &str, increment: f64, member: Bytes) -> crate::Result<f64> {
+        self.shard(key).zincrby(key, increment, member).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zrem(&mut self, key: &str, members: Vec<Bytes>) -> crate::Result<i64> {
+        self.shard(key).zrem(key, members).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zcard(&mut self, key: &str) -> crate::Result<i64> {
+        self.shard(key).zcard(key).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zrangebyscore(
+        &mut self,
+        key: &str,
+        min: &str,
+        max: &str,
+        limit: Option<(i64, i64)>,
+    ) -> crate::Result<Vec<Bytes>> {
+        self.shard(key).zrangebyscore(key, min, max, limit).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zrange(&mut self, key: &str, start: i64, stop: i64, rev: bool) -> crate::Result<Vec<Bytes>> {
+        self.shard(key).zrange(key, start, stop, rev).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn zrange_with_scores(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+        rev: bool,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        self.shard(key).zrange_with_scores(key, start, stop, rev).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sinter(&mut self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        self.same_shard(&keys)?.sinter(keys).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sintercard(&mut self, keys: Vec<String>, limit: Option<u64>) -> crate::Result<i64> {
+        self.same_shard(&keys)?.sintercard(keys, limit).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sdiff(&mut self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        self.same_shard(&keys)?.sdiff(keys).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn sunion(&mut self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        self.same_shard(&keys)?.sunion(keys).await
+    }
+}
@@ -0,0 +1,76 @@
+//! TLS 支持（需要开启 `tls` feature）
+
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::{client, server, TlsAcceptor, TlsConnector};
+
+// 服务端完成 TLS 握手的时限，连上后不发送握手数据的客户端不能一直占用连接
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 服务端 TLS 配置，用于在接收到的 TcpStream 上完成握手
+#[derive(Clone)]
+pub struct TlsConfig {
+    acceptor: TlsAcceptor,
+}
+
+impl TlsConfig {
+    // 从 PEM 格式的证书链和私钥文件加载配置
+    pub fn from_pem_files(cert: &Path, key: &Path) -> crate::Result<TlsConfig> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert)?))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(key)?))?
+            .ok_or("no private key found in tls key file")?;
+
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+
+        Ok(TlsConfig {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+        })
+    }
+
+    // 在 socket 上完成服务端握手，超过 HANDSHAKE_TIMEOUT 时返回 TimedOut 错误
+    pub(crate) async fn accept(&self, socket: TcpStream) -> std::io::Result<server::TlsStream<TcpStream>> {
+        match time::timeout(HANDSHAKE_TIMEOUT, self.acceptor.accept(socket)).await {
+            Ok(res) => res,
+            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "tls handshake timed out")),
+        }
+    }
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("TlsConfig").finish_non_exhaustive()
+    }
+}
+
+// 默认的客户端配置，使用 webpki 内置的根证书
+pub(crate) fn default_client_config() -> Arc<ClientConfig> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+// 在 socket 上完成客户端握手，server_name 用于校验服务端证书
+pub(crate) async fn connect(
+    socket: TcpStream,
+    server_name: &str,
+    config: Arc<ClientConfig>,
+) -> crate::Result<client::TlsStream<TcpStream>> {
+    let server_name = ServerName::try_from(server_name.to_string())?;
+    let stream = TlsConnector::from(config).connect(server_name, socket).await?;
+    Ok(stream)
+}
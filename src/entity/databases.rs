@@ -0,0 +1,411 @@
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use crate::entity::db::random_index;
+use crate::entity::keyspace::KeyspaceNotifier;
+use crate::connect::SharedConfig;
+use crate::entity::{Db, Frame, PubSub};
+
+// 向副本传播的写命令最多缓冲的条数，副本落后更多时断开连接，重连后重新全量同步
+const REPLICATION_BACKLOG: usize = 4096;
+
+// `Databases`的包装类。当这个结构被丢弃时，通知每个数据库的后台清除任务退出
+#[derive(Debug)]
+pub struct DbDropGuard {
+    /// 删除此"DbDropGuard"结构时将关闭的数据库。
+    databases: Databases,
+}
+
+/// 服务器的所有逻辑数据库（SELECT 按下标选择），以及与具体数据库无关的全局状态
+#[derive(Debug, Clone)]
+pub struct Databases {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    // 逻辑数据库，每个数据库有自己的后台清除任务
+    dbs: Vec<Db>,
+
+    // 发布/订阅的频道表，频道不属于任何一个数据库
+    pubsub: PubSub,
+
+    // 每种命令的调用统计，使用单独的锁，避免与数据访问争用
+    command_stats: Mutex<HashMap<&'static str, CommandStats>>,
+
+    // 慢查询日志
+    slowlog: Mutex<Slowlog>,
+
+    // 服务器配置，内存上限与淘汰策略每次淘汰时从这里读取，CONFIG SET 修改后立即生效
+    config: SharedConfig,
+
+    // 普通命令执行时持有读锁，EXEC 执行整个事务时持有写锁，
+    // 保证事务中的命令之间不会穿插其他连接的命令
+    exec_lock: RwLock<()>,
+
+    // 向副本传播的写命令：(数据库下标, 命令帧)，每个副本连接持有一个接收端
+    replication: broadcast::Sender<(usize, Frame)>,
+
+    // 有副本时写命令在执行与传播期间持有，保证副本收到命令的顺序与执行顺序一致
+    propagation_lock: tokio::sync::Mutex<()>,
+
+    // 作为副本时复制的主节点，None 表示本身是主节点
+    primary: Mutex<Option<PrimaryLink>>,
+}
+
+// 作为副本时与主节点的连接
+#[derive(Debug)]
+struct PrimaryLink {
+    host: String,
+    port: u16,
+
+    // 从主节点接收并执行命令的后台任务
+    task: JoinHandle<()>,
+}
+
+/// 超过 maxmemory 时的淘汰策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxmemoryPolicy {
+    // 不淘汰，写命令返回 OOM 错误
+    #[default]
+    NoEviction,
+    // 随机淘汰任意 key
+    AllkeysRandom,
+    // 淘汰最久没有被访问的 key
+    AllkeysLru,
+    // 淘汰访问频率最低的 key
+    AllkeysLfu,
+}
+
+impl FromStr for MaxmemoryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MaxmemoryPolicy, String> {
+        match &s.to_lowercase()[..] {
+            "noeviction" => Ok(MaxmemoryPolicy::NoEviction),
+            "allkeys-random" => Ok(MaxmemoryPolicy::AllkeysRandom),
+            "allkeys-lru" => Ok(MaxmemoryPolicy::AllkeysLru),
+            "allkeys-lfu" => Ok(MaxmemoryPolicy::AllkeysLfu),
+            _ => Err(format!("unknown maxmemory policy '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for MaxmemoryPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MaxmemoryPolicy::NoEviction => "noeviction",
+            MaxmemoryPolicy::AllkeysRandom => "allkeys-random",
+            MaxmemoryPolicy::AllkeysLru => "allkeys-lru",
+            MaxmemoryPolicy::AllkeysLfu => "allkeys-lfu",
+        };
+        fmt.write_str(name)
+    }
+}
+
+// 慢查询日志，最新的记录在最前面，超过长度上限时丢弃最旧的记录
+#[derive(Debug, Default)]
+struct Slowlog {
+    // 下一条记录的 id
+    next_id: u64,
+    entries: VecDeque<SlowlogEntry>,
+}
+
+// 一条慢查询记录
+#[derive(Debug, Clone)]
+pub(crate) struct SlowlogEntry {
+    pub(crate) id: u64,
+    // 记录时的 unix 时间戳（秒）
+    pub(crate) timestamp: u64,
+    // 执行耗时（微秒）
+    pub(crate) usec: u64,
+    // 命令及参数
+    pub(crate) args: Vec<Bytes>,
+}
+
+// 一种命令的调用次数与耗时（微秒）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandStats {
+    pub(crate) calls: u64,
+    pub(crate) usec: u64,
+    pub(crate) usec_max: u64,
+}
+
+impl DbDropGuard {
+    /// 按配置中的数据库个数新建数据库，不启动服务器也可以直接使用（例如在 `Db` 的列表上实现自己的阻塞弹出）。
+    /// guard 被 drop 时停止各数据库的后台任务
+    pub fn new(config: SharedConfig) -> DbDropGuard {
+        DbDropGuard {
+            databases: Databases::new(config),
+        }
+    }
+
+    /// 返回数据库的指针
+    pub fn databases(&self) -> Databases {
+        self.databases.clone()
+    }
+}
+
+impl Drop for DbDropGuard {
+    fn drop(&mut self) {
+        // 向每个'Db'实例发出信号以关闭
+        for db in &self.databases.shared.dbs {
+            db.shutdown_purge_task();
+        }
+        // 停止复制任务
+        self.databases.set_primary(None);
+    }
+}
+
+impl Databases {
+    pub(crate) fn new(config: SharedConfig) -> Databases {
+        let count = config.read().unwrap().databases;
+        let pubsub = PubSub::new();
+        // 每个数据库持有频道表的克隆来发布键空间通知，是否发布由配置决定，可以在运行时打开
+        let dbs = (0..count)
+            .map(|index| Db::new(KeyspaceNotifier::new(pubsub.clone(), config.clone(), index)))
+            .collect();
+        Databases {
+            shared: Arc::new(Shared {
+                dbs,
+                pubsub,
+                command_stats: Mutex::new(HashMap::new()),
+                slowlog: Mutex::new(Slowlog::default()),
+                config,
+                exec_lock: RwLock::new(()),
+                replication: broadcast::channel(REPLICATION_BACKLOG).0,
+                propagation_lock: tokio::sync::Mutex::new(()),
+                primary: Mutex::new(None),
+            }),
+        }
+    }
+
+    // 执行单条命令前获取，可以与其他连接的命令并发
+    pub(crate) async fn command_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.shared.exec_lock.read().await
+    }
+
+    // 执行事务前获取，等待正在执行的命令结束，并阻止其他命令开始
+    pub(crate) async fn exec_guard(&self) -> RwLockWriteGuard<'_, ()> {
+        self.shared.exec_lock.write().await
+    }
+
+    // 发布/订阅的频道表
+    pub(crate) fn pubsub(&self) -> &PubSub {
+        &self.shared.pubsub
+    }
+
+    // 已连接的副本个数
+    pub(crate) fn replica_count(&self) -> usize {
+        self.shared.replication.receiver_count()
+    }
+
+    // 新副本开始接收之后执行的写命令。调用时应持有 exec_guard，保证与快照之间没有遗漏的命令
+    pub(crate) fn subscribe_replication(&self) -> broadcast::Receiver<(usize, Frame)> {
+        self.shared.replication.subscribe()
+    }
+
+    // 有副本时，写命令执行前获取，执行结束后释放
+    pub(crate) async fn propagation_guard(&self) -> MutexGuard<'_, ()> {
+        self.shared.propagation_lock.lock().await
+    }
+
+    // 把在数据库 index 上执行的写命令传播给所有副本
+    pub(crate) fn propagate(&self, index: usize, frame: Frame) {
+        // 没有副本时发送失败，忽略
+        let _ = self.shared.replication.send((index, frame));
+    }
+
+    // 所有数据库的快照：(数据库下标, 重建其中所有 key 的写命令)，跳过空的数据库
+    pub(crate) fn snapshot(&self) -> Vec<(usize, Vec<Frame>)> {
+        self.shared
+            .dbs
+            .iter()
+            .enumerate()
+            .map(|(index, db)| (index, db.snapshot()))
+            .filter(|(_, frames)| !frames.is_empty())
+            .collect()
+    }
+
+    // 删除所有数据库中的所有 key，lazy 时在后台释放旧的条目
+    pub(crate) fn clear(&self, lazy: bool) {
+        for db in &self.shared.dbs {
+            db.clear(lazy);
+        }
+    }
+
+    // 打开或关闭所有数据库后台任务的主动过期（DEBUG SET-ACTIVE-EXPIRE）
+    pub(crate) fn set_active_expire(&self, enabled: bool) {
+        for db in &self.shared.dbs {
+            db.set_active_expire(enabled);
+        }
+    }
+
+    // 开始复制 host:port 上的主节点（task 为复制任务），None 表示不再复制。之前的复制任务会被停止
+    pub(crate) fn set_primary(&self, primary: Option<(String, u16, JoinHandle<()>)>) {
+        let link = primary.map(|(host, port, task)| PrimaryLink { host, port, task });
+        let prev = std::mem::replace(&mut *self.shared.primary.lock().unwrap(), link);
+        if let Some(prev) = prev {
+            prev.task.abort();
+        }
+    }
+
+    // 作为副本时主节点的地址
+    pub(crate) fn primary(&self) -> Option<(String, u16)> {
+        let primary = self.shared.primary.lock().unwrap();
+        primary.as_ref().map(|link| (link.host.clone(), link.port))
+    }
+
+    // 是否是副本，副本拒绝普通客户端的写命令
+    pub(crate) fn is_replica(&self) -> bool {
+        self.shared.primary.lock().unwrap().is_some()
+    }
+
+    // 数据库个数
+    pub(crate) fn len(&self) -> usize {
+        self.shared.dbs.len()
+    }
+
+    // SELECT、MOVE 等命令给出的数据库下标，不在 0..len 范围内时返回 DB index is out of range 错误
+    pub(crate) fn checked_index(&self, index: i64) -> Result<usize, &'static str> {
+        match usize::try_from(index) {
+            Ok(index) if index < self.len() => Ok(index),
+            _ => Err("ERR DB index is out of range"),
+        }
+    }
+
+    // 所有连接共享的服务器配置
+    pub(crate) fn config(&self) -> &SharedConfig {
+        &self.shared.config
+    }
+
+    /// 返回下标为 index 的数据库，下标越界时 panic（SELECT 时已经检查过）
+    pub fn db(&self, index: usize) -> &Db {
+        &self.shared.dbs[index]
+    }
+
+    // 写命令执行前调用：所有数据库的内存占用之和超过 maxmemory 时按策略淘汰 key，
+    // 策略为 noeviction 或没有可淘汰的 key 时返回 OOM 错误
+    pub(crate) fn free_memory(&self) -> crate::Result<()> {
+        let maxmemory = self.shared.config.read().unwrap().maxmemory;
+        if maxmemory == 0 {
+            return Ok(());
+        }
+
+        while self.used_memory() > maxmemory {
+            if !self.evict_one() {
+                return Err("OOM command not allowed when used memory > 'maxmemory'.".into());
+            }
+        }
+        Ok(())
+    }
+
+    // 按策略淘汰一个 key，没有可淘汰的 key 时返回 false
+    fn evict_one(&self) -> bool {
+        let dbs = &self.shared.dbs;
+        let (policy, samples) = {
+            let config = self.shared.config.read().unwrap();
+            (config.maxmemory_policy, config.maxmemory_samples)
+        };
+        let candidate = match policy {
+            MaxmemoryPolicy::NoEviction => None,
+            // 从随机的数据库开始，找到第一个有 key 的数据库
+            MaxmemoryPolicy::AllkeysRandom => {
+                let start = random_index(dbs.len());
+                (0..dbs.len())
+                    .map(|i| &dbs[(start + i) % dbs.len()])
+                    .find_map(|db| db.eviction_candidate(policy, samples).map(|(key, _, _)| (db, key)))
+            }
+            // 在每个数据库抽样选出的 key 中选最久没有被访问的
+            MaxmemoryPolicy::AllkeysLru => dbs
+                .iter()
+                .filter_map(|db| db.eviction_candidate(policy, samples).map(|(key, _, at)| (db, key, at)))
+                .min_by_key(|(_, _, accessed_at): &(&Db, String, Instant)| *accessed_at)
+                .map(|(db, key, _)| (db, key)),
+            // 在每个数据库抽样选出的 key 中选访问计数最小的，计数相同时选最久没有被访问的
+            MaxmemoryPolicy::AllkeysLfu => dbs
+                .iter()
+                .filter_map(|db| db.eviction_candidate(policy, samples).map(|(key, freq, at)| (db, key, (freq, at))))
+                .min_by_key(|(_, _, rank): &(&Db, String, (u8, Instant))| *rank)
+                .map(|(db, key, _)| (db, key)),
+        };
+
+        match candidate {
+            Some((db, key)) => {
+                db.evict(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 所有数据库估算的内存占用之和
+    pub(crate) fn used_memory(&self) -> usize {
+        self.shared.dbs.iter().map(|db| db.used_memory()).sum()
+    }
+
+    // 当前估算的内存占用、内存上限与淘汰策略
+    pub(crate) fn memory_info(&self) -> (usize, usize, MaxmemoryPolicy) {
+        let config = self.shared.config.read().unwrap();
+        (self.used_memory(), config.maxmemory, config.maxmemory_policy)
+    }
+
+    // 记录一次命令调用的耗时
+    pub(crate) fn record_command(&self, name: &'static str, elapsed: Duration) {
+        let usec = elapsed.as_micros() as u64;
+        let mut command_stats = self.shared.command_stats.lock().unwrap();
+        let stats = command_stats.entry(name).or_default();
+        stats.calls += 1;
+        stats.usec += usec;
+        stats.usec_max = stats.usec_max.max(usec);
+    }
+
+    // 返回按命令名排序的调用统计
+    pub(crate) fn command_stats(&self) -> Vec<(&'static str, CommandStats)> {
+        let command_stats = self.shared.command_stats.lock().unwrap();
+        let mut stats: Vec<_> = command_stats
+            .iter()
+            .map(|(name, stats)| (*name, stats.clone()))
+            .collect();
+        stats.sort_by_key(|(name, _)| *name);
+        stats
+    }
+
+    // 记录一条慢查询，日志最多保留 max_len 条
+    pub(crate) fn slowlog_push(&self, args: Vec<Bytes>, elapsed: Duration, max_len: usize) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut slowlog = self.shared.slowlog.lock().unwrap();
+        let id = slowlog.next_id;
+        slowlog.next_id += 1;
+        slowlog.entries.push_front(SlowlogEntry {
+            id,
+            timestamp,
+            usec: elapsed.as_micros() as u64,
+            args,
+        });
+        slowlog.entries.truncate(max_len);
+    }
+
+    // 返回最新的 count 条慢查询
+    pub(crate) fn slowlog_get(&self, count: usize) -> Vec<SlowlogEntry> {
+        let slowlog = self.shared.slowlog.lock().unwrap();
+        slowlog.entries.iter().take(count).cloned().collect()
+    }
+
+    pub(crate) fn slowlog_len(&self) -> usize {
+        self.shared.slowlog.lock().unwrap().entries.len()
+    }
+
+    pub(crate) fn slowlog_reset(&self) {
+        self.shared.slowlog.lock().unwrap().entries.clear();
+    }
+}
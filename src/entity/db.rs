@@ -2,20 +2,21 @@ use tokio::sync::{Notify};
 use tokio::time::{self, Duration, Instant};
 
 use bytes::{Bytes, BytesMut};
+use std::collections::hash_map::RandomState;
 use std::collections::{BTreeSet, HashMap, LinkedList};
+use std::hash::{BuildHasher, Hasher};
 use std::io::Read;
+use std::mem;
 // use std::str::Bytes;
 // use std::str::Bytes;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 use tracing::debug;
-use crate::utils::serialization::{btree_to_bytes, bytes_to_i64, i64_to_bytes, list_to_bytes, map_to_bytes};
-
-// `Db`的包装类。为了允许有序地清理"Db"，当这个结构被丢弃时，通过信号通知后台清除任务关闭系统
-#[derive(Debug)]
-pub struct DbDropGuard {
-    /// 删除此"DbHolder"结构时将关闭的"Db"实例。
-    db: Db,
-}
+use crate::entity::keyspace::{EventClass, KeyspaceNotifier};
+use crate::entity::{Frame, MaxmemoryPolicy, ScoreBound, SortedSet};
+use crate::utils::serialization::{self, btree_to_bytes, bytes_to_i64, fmt_score, i64_to_bytes};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct Db {
@@ -30,6 +31,17 @@ struct Shared {
 
     // 通知后台任务处理条目过期。后台任务等待通知，然后检查过期值或关机信号。
     background_task: Notify,
+
+    // 按 notify-keyspace-events 配置发布键空间通知。只持有发布用的句柄而不是 `Databases`，
+    // 所以写操作与过期清除任务都可以直接发布，而不需要反向引用数据库集合
+    notifier: KeyspaceNotifier,
+
+    // SCAN 游标使用的哈希函数，在数据库存在期间不变，同一个 key 在每次遍历中的位置相同
+    scan_hasher: RandomState,
+
+    // 后台任务是否主动删除过期的 key，DEBUG SET-ACTIVE-EXPIRE 0 时关闭，
+    // 过期的 key 只在被读取时删除，用于测试惰性删除
+    active_expire: AtomicBool,
 }
 
 #[derive(Debug)]
@@ -37,18 +49,70 @@ struct State {
     // 存储数据
     entries: HashMap<String, Entry>,
 
-    // pub 与 sub 的存储，可以不断进行订阅，广播
-    // pub_sub: HashMap<String, broadcast::Sender<Bytes>>,
+    // 所有 key 的数组，条目的 slot 是它在数组中的下标，淘汰时按下标随机抽样 key
+    keys: Vec<String>,
 
     /// 跟踪键的TTL（网络生存时间）。
     /// 这就允许后台任务对这个映射进行迭代，以找到下一个到期的值。
     /// 同一瞬间创建多个条目是可能的，因此，“Instant”对于key来说是不够的。一个唯一的键（`String`）用于打破这些束缚。
     expirations: BTreeSet<(Instant, String)>,
 
+    // 所有条目估算占用的内存（字节），随条目的增删改增量维护
+    used_memory: usize,
+
+    // 最近一次分配的版本号，每次修改条目时递增
+    version: u64,
+
+    // 最近一次删除条目时分配的版本号，作为所有不存在的 key 的版本号。
+    // key 被创建后又被删除时版本号因此不会回到原来的值
+    removed_version: u64,
+
+    // 等待列表 key 收到数据的任务，每个 key 一个 `Notify`。最后一个等待者离开时删除，避免表无限增长
+    list_waiters: HashMap<String, Arc<Notify>>,
+
+    // 读取时发现已过期而删除的 key。持有锁时不能发布通知，由后台任务下次运行时发布 expired 事件
+    lazily_expired: Vec<String>,
+
     // db关闭时为True。当所有的"Db"值都被 drop 时。将其设置为"true"，则向后台任务发出退出的信号。
     shutdown: bool,
 }
 
+/// GETEX 对过期时间的调整
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    // 不修改过期时间
+    Keep,
+    // 从现在起经过给定时长后过期
+    After(Duration),
+    // 删除过期时间，key 不再过期
+    Persist,
+}
+
+/// SET 写入的前提条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetCondition {
+    // 总是写入
+    #[default]
+    Always,
+    // NX：只在 key 不存在时写入
+    IfAbsent,
+    // XX：只在 key 已存在时写入
+    IfPresent,
+}
+
+/// SET 的可选参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetOptions {
+    // 写入的前提条件
+    pub condition: SetCondition,
+    // 过期时间，None 时 key 不过期（除非 keep_ttl）
+    pub expire: Option<Duration>,
+    // KEEPTTL：保留 key 原有的过期时间
+    pub keep_ttl: bool,
+    // GET：回复 key 原来的值
+    pub get: bool,
+}
+
 // 数据条目
 #[derive(Debug)]
 struct Entry {
@@ -57,47 +121,238 @@ struct Entry {
 
     // 条目过期时，应该从数据库中删除。
     expires_at: Option<Instant>,
+
+    // 最近一次被访问的时间，用于 LRU 淘汰
+    accessed_at: Instant,
+
+    // 对数访问计数器，用于 LFU 淘汰
+    frequency: u8,
+
+    // 最近一次修改时分配的版本号，WATCH 据此判断 key 是否被修改过
+    version: u64,
+
+    // key 在 `State::keys` 中的下标，插入时分配
+    slot: usize,
 }
 
-#[derive(Debug, Clone)]
+impl Entry {
+    // 新建条目，版本号与下标在插入时分配
+    fn new(data: DbData, expires_at: Option<Instant>) -> Entry {
+        Entry {
+            data,
+            expires_at,
+            accessed_at: Instant::now(),
+            frequency: LFU_INIT_VAL,
+            version: 0,
+            slot: 0,
+        }
+    }
+
+    // 按距离上次访问经过的时间衰减后的访问计数
+    fn frequency(&self) -> u8 {
+        let periods = self.accessed_at.elapsed().as_secs() / LFU_DECAY_SECS;
+        self.frequency.saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+
+    // 记录一次访问：刷新访问时间，访问计数先衰减，再以 1 / ((计数 - 初始值) * 因子 + 1) 的概率加 1，
+    // 所以计数与访问次数的对数成正比，255 可以表示上百万次访问
+    fn touch(&mut self) {
+        let mut frequency = self.frequency();
+        if frequency < u8::MAX {
+            let base = frequency.saturating_sub(LFU_INIT_VAL) as f64;
+            if random_fraction() < 1.0 / (base * LFU_LOG_FACTOR + 1.0) {
+                frequency += 1;
+            }
+        }
+        self.frequency = frequency;
+        self.accessed_at = Instant::now();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 enum DbData {
     String(Bytes),
     List(LinkedList<Bytes>),
     Set(BTreeSet<Bytes>),
     Hash(HashMap<Bytes, Bytes>),
+    ZSet(SortedSet),
 }
 
-// 新建和获取数据库指针
-impl DbDropGuard {
-    // 新建
-    pub(crate) fn new() -> DbDropGuard {
-        DbDropGuard { db: Db::new() }
+// 不超过该长度的字符串按 embstr 报告
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+// 元素都是整数且不超过该个数的集合按 intset 报告
+const INTSET_MAX_ENTRIES: usize = 512;
+
+// 每个 key 的固定开销：哈希表中的 key 与条目本身
+const ENTRY_OVERHEAD: usize = mem::size_of::<String>() + mem::size_of::<Entry>();
+
+// 列表每个元素的开销：链表节点的前后指针与 Bytes
+const LIST_NODE_OVERHEAD: usize = 2 * mem::size_of::<usize>() + mem::size_of::<Bytes>();
+
+// 集合每个元素的开销
+const SET_MEMBER_OVERHEAD: usize = mem::size_of::<Bytes>();
+
+// 哈希每个字段的开销：字段与值各一个 Bytes
+const HASH_FIELD_OVERHEAD: usize = 2 * mem::size_of::<Bytes>();
+
+// 有序集合每个成员的开销：成员与分数在两个索引中各保存一份
+const ZSET_MEMBER_OVERHEAD: usize = 2 * (mem::size_of::<Bytes>() + mem::size_of::<f64>());
+
+// LFU 访问计数的初始值，与 redis 一样让新 key 不会马上被淘汰
+const LFU_INIT_VAL: u8 = 5;
+
+// LFU 访问计数的对数因子，越大计数增长越慢
+const LFU_LOG_FACTOR: f64 = 10.0;
+
+// 每经过这么多秒没有访问，访问计数减 1
+const LFU_DECAY_SECS: u64 = 60;
+
+// 值的类型与命令要求的不符时回复的错误
+pub(crate) const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+
+// 共享整数池的大小：0 到 OBJ_SHARED_INTEGERS - 1 的整数字符串只保存一份
+const OBJ_SHARED_INTEGERS: i64 = 10000;
+
+// 共享值的引用计数，与 redis 一样报告为 i32::MAX
+const OBJ_SHARED_REFCOUNT: i64 = i32::MAX as i64;
+
+// 共享的小整数字符串。所有数字拼接后放在一块只分配一次、永不释放的内存里，池中每个值都是指向其中一段的静态 Bytes，
+// clone 既不分配内存也不修改原子引用计数。池在第一次使用时创建，常驻约 40KB 数字加上每个值一个 Bytes 句柄
+fn shared_integers() -> &'static [Bytes] {
+    static SHARED: OnceLock<Vec<Bytes>> = OnceLock::new();
+    SHARED.get_or_init(|| {
+        let mut digits = String::new();
+        let mut ends = Vec::with_capacity(OBJ_SHARED_INTEGERS as usize);
+        for n in 0..OBJ_SHARED_INTEGERS {
+            digits.push_str(&n.to_string());
+            ends.push(digits.len());
+        }
+        let digits: &'static [u8] = Box::leak(digits.into_boxed_str()).as_bytes();
+        let mut start = 0;
+        ends.into_iter()
+            .map(|end| {
+                let value = Bytes::from_static(&digits[start..end]);
+                start = end;
+                value
+            })
+            .collect()
+    })
+}
+
+// n 在共享整数池的范围内时返回池中的值
+fn shared_integer(n: i64) -> Option<Bytes> {
+    if (0..OBJ_SHARED_INTEGERS).contains(&n) {
+        Some(shared_integers()[n as usize].clone())
+    } else {
+        None
     }
+}
 
-    // 返回一个数据库的指针
-    pub(crate) fn db(&self) -> Db {
-        self.db.clone()
+// value 是池中整数的规范写法（没有前导 0 和正负号）时返回池中对应的值
+fn lookup_shared_integer(value: &[u8]) -> Option<Bytes> {
+    if value.len() > 4 {
+        return None;
     }
+    std::str::from_utf8(value)
+        .ok()?
+        .parse::<i64>()
+        .ok()
+        .and_then(shared_integer)
+        .filter(|shared| shared[..] == *value)
 }
 
-// 实现 drop 实例
-impl Drop for DbDropGuard {
-    fn drop(&mut self) {
-        // 向'Db'实例发出信号以关闭
-        self.db.shutdown_purge_task();
+// 能共享时用池中的值代替 value。这样写入的值不再各自持有内存（例如读取请求的缓冲区），
+// 代价是长度不超过 4 的值要多解析一次
+fn share_integer(value: Bytes) -> Bytes {
+    lookup_shared_integer(&value).unwrap_or(value)
+}
+
+impl DbData {
+    // 值的类型名称，与 redis 的 TYPE 命令一致
+    fn type_name(&self) -> &'static str {
+        match self {
+            DbData::String(_) => "string",
+            DbData::List(_) => "list",
+            DbData::Set(_) => "set",
+            DbData::Hash(_) => "hash",
+            DbData::ZSet(_) => "zset",
+        }
+    }
+
+    // 命令要求的类型为 expected，而值是其他类型时返回统一的 WRONGTYPE 错误
+    fn wrong_type(&self, expected: &'static str) -> crate::Error {
+        debug!(expected, actual = self.type_name(), "wrong type");
+        WRONGTYPE.into()
+    }
+
+    // 估算值占用的内存：数据本身的字节数加上每个元素的容器开销。
+    // maxmemory 的统计与 MEMORY USAGE 都使用它，原地修改集合时按同样的常量增减
+    fn estimated_size(&self) -> usize {
+        match self {
+            DbData::String(v) => v.len(),
+            DbData::List(v) => v.iter().map(|m| m.len() + LIST_NODE_OVERHEAD).sum(),
+            DbData::Set(v) => v.iter().map(|m| m.len() + SET_MEMBER_OVERHEAD).sum(),
+            DbData::Hash(v) => v.iter().map(|(f, m)| f.len() + m.len() + HASH_FIELD_OVERHEAD).sum(),
+            DbData::ZSet(v) => v.iter().map(|(m, _)| m.len() + ZSET_MEMBER_OVERHEAD).sum(),
+        }
+    }
+
+    // 值的引用计数：共享整数池中的字符串报告为 OBJ_SHARED_REFCOUNT，其他值只属于一个 key
+    fn refcount(&self) -> i64 {
+        match self {
+            DbData::String(v) if lookup_shared_integer(v).is_some_and(|shared| shared.as_ptr() == v.as_ptr()) => {
+                OBJ_SHARED_REFCOUNT
+            }
+            _ => 1,
+        }
+    }
+
+    // 值的内部表示，命名与 redis 的 OBJECT ENCODING 保持一致
+    fn encoding(&self) -> &'static str {
+        match self {
+            DbData::String(v) => {
+                if bytes_to_i64(v.clone()).is_ok() {
+                    "int"
+                } else if v.len() <= EMBSTR_SIZE_LIMIT {
+                    "embstr"
+                } else {
+                    "raw"
+                }
+            }
+            DbData::List(_) => "linkedlist",
+            DbData::Set(v) => {
+                if v.len() <= INTSET_MAX_ENTRIES && v.iter().all(|m| bytes_to_i64(m.clone()).is_ok()) {
+                    "intset"
+                } else {
+                    "hashtable"
+                }
+            }
+            DbData::Hash(_) => "hashtable",
+            DbData::ZSet(_) => "skiplist",
+        }
     }
 }
 
 impl Db {
     // 创建一个新的`Db`实例
-    pub(crate) fn new() -> Db {
+    pub(crate) fn new(notifier: KeyspaceNotifier) -> Db {
         let shared = Arc::new(Shared {
             state: Mutex::new(State {
                 entries: HashMap::new(),
+                keys: vec![],
                 expirations: BTreeSet::new(),
+                used_memory: 0,
+                version: 0,
+                removed_version: 0,
+                list_waiters: HashMap::new(),
+                lazily_expired: vec![],
                 shutdown: false,
             }),
             background_task: Notify::new(),
+            notifier,
+            scan_hasher: RandomState::new(),
+            active_expire: AtomicBool::new(true),
         });
 
         // 启动后台任务
@@ -106,63 +361,61 @@ impl Db {
         Db { shared }
     }
 
-    // 获取 key 的值
-    pub(crate) fn get(&self, key: &str) -> Option<Bytes> {
-        // 数据浅拷贝出去
-        let state = self.shared.state.lock().unwrap();
-        // let option = state.entries.get(key).map(|entry| entry.data.clone());
-        if let Some(option) = state.entries.get(key).map(|entry| entry.data.clone()) {
-            return match option {
-                DbData::String(v) => {
-                    drop(state);
-                    Some(v)
-                }
-                DbData::List(v) => {
-                    drop(state);
-                    Some(list_to_bytes(&v))
-                }
-                DbData::Set(v) => {
-                    drop(state);
-                    Some(btree_to_bytes(&v))
-                }
-                DbData::Hash(v) => {
-                    drop(state);
-                    Some(map_to_bytes(&v))
-                }
-            };
-        };
-        None
+    // 获取 key 的值，值不是字符串时返回 WRONGTYPE 错误
+    pub(crate) fn get(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(DbData::String(v)) => Ok(Some(v.clone())),
+            Some(data) => Err(data.wrong_type("string")),
+        }
     }
 
-    pub(crate) fn pop(&self, key: &String, right: bool) -> Option<Bytes> {
-        // 数据浅拷贝出去
+    /// 从列表头部（right 为 false）或尾部弹出最多 count 个元素，列表弹空时删除 key，key 不存在时返回 None
+    pub fn pop(&self, key: &str, right: bool, count: usize) -> crate::Result<Option<Vec<Bytes>>> {
         let mut state = self.shared.state.lock().unwrap();
-        let x = match state.entries.get_mut(key) {
-            None => { None }
-            Some(v) => {
-                match &mut v.data {
-                    DbData::List(v) => {
-                        if right {
-                            v.pop_back()
-                        } else {
-                            v.pop_front()
-                        }
-                    }
-                    _ => { None }
-                }
-            }
+        let (values, empty) = match state.pop_list(key, right, count)? {
+            Some(popped) => popped,
+            None => return Ok(None),
         };
         drop(state);
-        println!("{:?}", self.get(key));
-        x
+
+        self.notify_pop(key, right, empty);
+        Ok(Some(values))
     }
 
-    pub(crate) fn lrange(&self, key: &String, start: u64, end: u64) -> Option<Bytes> {
-        // 数据浅拷贝出去
-        let state = self.shared.state.lock().unwrap();
-        let option = state.entries.get(key).map(|entry| entry.data.clone()).unwrap();
-        match option {
-            DbData::List(list) => {
+    // 从第一个存在的列表弹出最多 count 个元素，返回它的 key 与弹出的元素，所有 key 都不存在时返回 None。
+    // 列表弹空时 key 被删除，所以存在的列表一定非空
+    pub(crate) fn lmpop(
+        &self,
+        keys: &[String],
+        right: bool,
+        count: usize,
+    ) -> crate::Result<Option<(String, Vec<Bytes>)>> {
+        let mut state = self.shared.state.lock().unwrap();
+        for key in keys {
+            if let Some((values, empty)) = state.pop_list(key, right, count)? {
+                drop(state);
+
+                self.notify_pop(key, right, empty);
+                return Ok(Some((key.clone(), values)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn notify_pop(&self, key: &str, right: bool, empty: bool) {
+        self.notify(EventClass::List, if right { "rpop" } else { "lpop" }, key);
+        if empty {
+            self.notify(EventClass::Generic, "del", key);
+        }
+    }
+
+    pub(crate) fn lrange(&self, key: &str, start: u64, end: u64) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(None),
+            Some(DbData::List(list)) => {
                 let mut bytes_mut = BytesMut::new();
                 bytes_mut.extend_from_slice(b"[");
 
@@ -182,51 +435,92 @@ impl Db {
                 }
                 bytes_mut.extend_from_slice(b"]");
                 // 将 BytesMut 转换为 Bytes
-                Some(bytes_mut.freeze())
-            }
-            _ => {
-                None
+                Ok(Some(bytes_mut.freeze()))
             }
+            Some(data) => Err(data.wrong_type("list")),
         }
     }
 
+    // 查找列表中等于 element 的元素，返回它们从头部开始计数的下标。跳过前 rank - 1 个匹配，
+    // rank 为负数时从尾部向前查找；max 为 0 时返回所有匹配。key 不存在时返回空
+    pub(crate) fn lpos(&self, key: &str, element: &[u8], rank: i64, max: usize) -> crate::Result<Vec<usize>> {
+        let mut state = self.shared.state.lock().unwrap();
+        let list = match state.lookup(key).map(|entry| &entry.data) {
+            None => return Ok(vec![]),
+            Some(DbData::List(list)) => list,
+            Some(data) => return Err(data.wrong_type("list")),
+        };
+        let len = list.len();
+        let skip = (rank.unsigned_abs() - 1) as usize;
+        let take = if max == 0 { usize::MAX } else { max };
+        let matches = |(_, value): &(usize, &Bytes)| &value[..] == element;
+        let positions = if rank > 0 {
+            list.iter().enumerate().filter(matches).skip(skip).take(take).map(|(i, _)| i).collect()
+        } else {
+            list.iter().rev().enumerate().filter(matches).skip(skip).take(take).map(|(i, _)| len - 1 - i).collect()
+        };
+        Ok(positions)
+    }
+
     // 设置键值，以及可选的过期持续时间。如果存在该键，则会先删除在插入。
-    pub(crate) fn set(&self, key: String, value: Bytes, expire: Option<Duration>) {
-        let mut state = self.shared.state.lock().unwrap();
-        // 如果这个`set`成为下一个过期的密钥**，则需要通知后台任务，以便它可以更新其状态。是否需要通知任务是在"set"例程期间计算的。
-        let mut notify = false;
-        // 获取到期时间
-        let expires_at = expire.map(|duration| {
-            // 设置到期时间
-            let when = Instant::now() + duration;
-            // 只有当新插入的expiration是下一个要删除的键时，才通知辅助任务。在这种情况下，需要唤醒worker以更新其状态。
-            // 查看树中的第一个结点（最小结点），是否大于当前节点的存在时间
-            notify = state
+    // 按 options 写入字符串，条件判断、读取旧值和写入在同一次加锁中完成。
+    // 返回是否写入以及 key 原来的值；指定 GET 而旧值不是字符串时返回错误且不写入
+    pub(crate) fn set(&self, key: String, value: Bytes, options: SetOptions) -> crate::Result<(bool, Option<Bytes>)> {
+        let mut state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
+        // 已过期但还没被后台任务删除的 key 视为不存在
+        let (exists, prev_value, prev_expires_at) = match state.lookup(&key) {
+            Some(entry) if entry.expires_at.map_or(true, |when| when > now) => {
+                let prev_value = match &entry.data {
+                    DbData::String(v) => Some(v.clone()),
+                    data if options.get => return Err(data.wrong_type("string")),
+                    _ => None,
+                };
+                (true, prev_value, entry.expires_at)
+            }
+            _ => (false, None, None),
+        };
+
+        let allowed = match options.condition {
+            SetCondition::Always => true,
+            SetCondition::IfAbsent => !exists,
+            SetCondition::IfPresent => exists,
+        };
+        if !allowed {
+            return Ok((false, prev_value));
+        }
+
+        // 新的过期时间：
+        // - KEEPTTL：沿用原来的过期时间（key 不存在时为不过期）
+        // - EX/PX：从现在起经过给定时长后过期
+        // - 都没有指定：清除原来的过期时间，key 不再过期（与 Redis 一致）
+        let expires_at = if options.keep_ttl {
+            prev_expires_at
+        } else {
+            options.expire.map(|duration| now + duration)
+        };
+        // 只有当新的过期时间是下一个要删除的键时，才需要唤醒后台任务以更新其状态。
+        // 查看树中的第一个结点（最小结点），是否大于新的过期时间
+        let notify = expires_at.is_some_and(|when| {
+            state
                 .next_expiration()
                 .map(|expiration| expiration > when)
-                .unwrap_or(true);
-
-            when
+                .unwrap_or(true)
         });
 
-        // 将值插入哈希表中
-        let prev = state.entries.insert(
+        // 将值插入哈希表中，新条目的过期时间总是 expires_at
+        let prev = state.insert(
             key.clone(),
-            Entry {
-                data: DbData::String(value),
-                expires_at,
-            },
+            Entry::new(DbData::String(share_integer(value)), expires_at),
         );
-        // 如果键已经存在。则删除
-        if let Some(prev) = prev {
-            if let Some(when) = prev.expires_at {
-                // 删除 expiration
-                state.expirations.remove(&(when, key.clone()));
-            }
+        // 删除旧条目在树中的过期时间，无论是清除、替换还是保留过期时间都先删除，
+        // 避免后台任务按旧的记录删除新的值
+        if let Some(when) = prev.and_then(|prev| prev.expires_at) {
+            state.expirations.remove(&(when, key.clone()));
         }
-        // 插入键值对到树中
+        // 插入新的过期时间到树中
         if let Some(when) = expires_at {
-            state.expirations.insert((when, key));
+            state.expirations.insert((when, key.clone()));
         }
         // 释放互斥锁
         drop(state);
@@ -235,264 +529,642 @@ impl Db {
             // 激活 notified(需要删除节点)
             self.shared.background_task.notify_one();
         }
+        self.notify(EventClass::String, "set", &key);
+        Ok((true, prev_value))
     }
 
-    pub(crate) fn incrby(&self, key: String, value: i64) -> Option<Bytes> {
+    // 在一次加锁中读取并修改字符串，保留原来的过期时间。key 不是字符串时返回 WRONGTYPE；
+    // key 不存在时，create 为 true 则把空字符串交给 f 并在成功后插入，否则返回 None。
+    // 修改后的内存占用与版本号在这里统一更新，f 返回错误时值不会被修改
+    fn with_string_mut<T>(
+        &self,
+        key: &str,
+        create: bool,
+        f: impl FnOnce(&mut Bytes) -> crate::Result<T>,
+    ) -> crate::Result<Option<T>> {
         let mut state = self.shared.state.lock().unwrap();
-        match state.entries.get_mut(&key) {
-            None => {}
-            Some(v) => {
-                match &mut v.data {
-                    DbData::String(serde_derive) => {
-                        let int = bytes_to_i64(serde_derive.clone()).unwrap();
-                        *serde_derive = Bytes::from((int + value).to_string());
-                    }
-                    DbData::List(_) => {}
-                    DbData::Set(_) => {}
-                    DbData::Hash(_) => {}
-                }
+        let (old_len, new_len, result) = match state.lookup(key).map(|entry| &mut entry.data) {
+            Some(DbData::String(value)) => {
+                let old_len = value.len();
+                let mut new = value.clone();
+                let result = f(&mut new)?;
+                *value = new;
+                (old_len, value.len(), result)
             }
-        }
-        let option = match state.entries.get_mut(&key).map(|entry| entry.data.clone()) {
-            None => {
-                Some(Bytes::from("error"))
-            }
-            Some(ref mut data) => {
-                *data = match data {
-                    DbData::String(serde_derive) => {
-                        let int = bytes_to_i64(serde_derive.clone()).unwrap();
-                        DbData::String(Bytes::from((int + value).to_string()))
-                    }
-                    _ => {
-                        DbData::String(Bytes::from("error".to_string()))
-                    }
-                };
-                Some(Bytes::from("OK"))
+            Some(data) => return Err(data.wrong_type("string")),
+            None if create => {
+                let mut value = Bytes::new();
+                let result = f(&mut value)?;
+                state.insert(key.to_string(), Entry::new(DbData::String(value), None));
+                return Ok(Some(result));
             }
+            None => return Ok(None),
         };
-        drop(state);
-        return option;
+        state.used_memory = state.used_memory - old_len + new_len;
+        state.modified(key);
+        Ok(Some(result))
     }
-    pub(crate) fn push(&self, key: String, value: Vec<String>, right: bool) {
-        let mut state = self.shared.state.lock().unwrap();
 
-        let option = match state.entries.get_mut(&key) {
-            None => {
-                // 将值插入哈希表中
-                let expire = None;
-                // 如果这个`set`成为下一个过期的密钥**，则需要通知后台任务，以便它可以更新其状态。是否需要通知任务是在"set"例程期间计算的。
-                let mut notify = false;
-                // 获取到期时间
-                let expires_at = expire.map(|duration| {
-                    // 设置到期时间
-                    let when = Instant::now() + duration;
-                    // 只有当新插入的expiration是下一个要删除的键时，才通知辅助任务。在这种情况下，需要唤醒worker以更新其状态。
-                    // 查看树中的第一个结点（最小结点），是否大于当前节点的存在时间
-                    notify = state
-                        .next_expiration()
-                        .map(|expiration| expiration > when)
-                        .unwrap_or(true);
+    // 将字符串表示的整数加上 value，返回新的值，key 不存在时返回 None
+    pub(crate) fn incrby(&self, key: String, value: i64) -> crate::Result<Option<Bytes>> {
+        let new = self.with_string_mut(&key, false, |current| {
+            let int = bytes_to_i64(current.clone())?
+                .checked_add(value)
+                .ok_or("ERR increment or decrement would overflow")?;
+            *current = shared_integer(int).unwrap_or_else(|| Bytes::from(int.to_string()));
+            Ok(current.clone())
+        })?;
 
-                    when
-                });
-                let linked_list: LinkedList<Bytes> = if right {
-                    value.into_iter().map(|str| Bytes::from(str)).collect()
-                } else {
-                    value.into_iter().map(|str| Bytes::from(str)).rev().collect()
-                };
+        if new.is_some() {
+            self.notify(EventClass::String, "incrby", &key);
+        }
+        Ok(new)
+    }
 
-                let prev = state.entries.insert(
-                    key.clone(),
-                    Entry {
-                        data: DbData::List(linked_list),
-                        expires_at,
-                    },
-                );
-                // // 如果键已经存在。则删除
-                if let Some(prev) = prev {
-                    if let Some(when) = prev.expires_at {
-                        // 删除 expiration
-                        state.expirations.remove(&(when, key.clone()));
-                    }
-                }
-                // 插入键值对到树中
-                if let Some(when) = expires_at {
-                    state.expirations.insert((when, key));
+    // 把 value 追加到字符串末尾，key 不存在时先创建为空字符串，返回追加后的长度。
+    // 读取、拼接与写回在同一次加锁中完成，并发的追加不会互相覆盖
+    pub(crate) fn append(&self, key: &str, value: &[u8]) -> crate::Result<usize> {
+        let len = self
+            .with_string_mut(key, true, |current| {
+                let mut appended = BytesMut::with_capacity(current.len() + value.len());
+                appended.extend_from_slice(current);
+                appended.extend_from_slice(value);
+                *current = appended.freeze();
+                Ok(current.len())
+            })?
+            .expect("append creates missing keys");
+
+        self.notify(EventClass::String, "append", key);
+        Ok(len)
+    }
+
+    // 字符串的当前值等于 expected 时替换为 new，保留原来的过期时间，返回是否替换。key 不存在时不设置
+    pub(crate) fn cas(&self, key: &str, expected: Bytes, new: Bytes) -> crate::Result<bool> {
+        let swapped = self
+            .with_string_mut(key, false, |value| {
+                if *value != expected {
+                    return Ok(false);
                 }
-                // 释放互斥锁
-                if notify {
-                    // 激活 notified(需要删除节点)
-                    self.shared.background_task.notify_one();
+                *value = new;
+                Ok(true)
+            })?
+            .unwrap_or(false);
+
+        if swapped {
+            self.notify(EventClass::String, "set", key);
+        }
+        Ok(swapped)
+    }
+
+    // 把字符串当作位数组（每个字节从高位开始计数），设置第 offset 位并返回原来的值。
+    // key 不存在时创建，字符串不够长时在末尾补 0，保留原来的过期时间
+    pub(crate) fn setbit(&self, key: &str, offset: usize, on: bool) -> crate::Result<bool> {
+        let old = self
+            .with_string_mut(key, true, |value| {
+                let (index, mask) = (offset / 8, 0x80 >> (offset % 8));
+                // 取出原值就地修改：没有其它引用时复用原来的缓冲区，只在下标越界时扩展
+                let mut bits = BytesMut::from(std::mem::take(value));
+                if bits.len() <= index {
+                    bits.resize(index + 1, 0);
                 }
-                Some(Bytes::from("error"));
-            }
-            Some(data) => {
-                let dbdata = &mut data.data;
-                if right {
-                    if let DbData::List(ref mut l) = dbdata {
-                        for v in value {
-                            l.push_back(Bytes::from(v));
-                        }
-                    }
+                let old = bits[index] & mask != 0;
+                if on {
+                    bits[index] |= mask;
                 } else {
-                    if let DbData::List(ref mut l) = dbdata {
-                        for v in value {
-                            l.push_front(Bytes::from(v));
-                        }
-                    }
+                    bits[index] &= !mask;
                 }
-                // let int = bytes_to_i64(bytes1.clone()).unwrap();
-                // let bytes = Bytes::from((int + value).to_string()).clone();
-                // data.data = DbData::String(bytes.clone());
-                // Some(bytes.clone())
+                *value = bits.freeze();
+                Ok(old)
+            })?
+            .expect("setbit creates missing keys");
+
+        self.notify(EventClass::String, "setbit", key);
+        Ok(old)
+    }
+
+    // 返回字符串第 offset 位的值，超出字符串长度或 key 不存在时为 false
+    pub(crate) fn getbit(&self, key: &str, offset: usize) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(false),
+            Some(DbData::String(value)) => Ok(value
+                .get(offset / 8)
+                .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)),
+            Some(data) => Err(data.wrong_type("string")),
+        }
+    }
+
+    // 统计字符串中为 1 的位数，range 为字节下标（包含两端，负数从末尾计数），key 不存在时为 0
+    pub(crate) fn bitcount(&self, key: &str, range: Option<(i64, i64)>) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        let value = match state.lookup(key).map(|entry| &entry.data) {
+            None => return Ok(0),
+            Some(DbData::String(value)) => value,
+            Some(data) => return Err(data.wrong_type("string")),
+        };
+        let len = value.len() as i64;
+        let (start, end) = match range {
+            None => (0, len - 1),
+            Some((start, end)) => {
+                let start = if start < 0 { (len + start).max(0) } else { start };
+                let end = if end < 0 { len + end } else { end.min(len - 1) };
+                (start, end)
             }
         };
-        drop(state);
+        if start > end {
+            return Ok(0);
+        }
+        Ok(value[start as usize..=end as usize]
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum())
     }
 
-    // 插入数据
-    pub(crate) fn sadd(&self, key: String, datas: Vec<String>) {
+    // 返回第一个值为 bit 的位的位置，找不到时返回 -1。
+    // 查找 0 且没有显式给出 end 时，字符串右侧视为无限个 0，所以全 1 的字符串返回末尾之后的位置；
+    // key 不存在时同理，查找 0 返回 0
+    pub(crate) fn bitpos(&self, key: &str, bit: bool, start: Option<i64>, end: Option<i64>) -> crate::Result<i64> {
         let mut state = self.shared.state.lock().unwrap();
+        let value = match state.lookup(key).map(|entry| &entry.data) {
+            None => return Ok(if bit { -1 } else { 0 }),
+            Some(DbData::String(value)) => value,
+            Some(data) => return Err(data.wrong_type("string")),
+        };
+        let len = value.len() as i64;
+        let start = match start.unwrap_or(0) {
+            start if start < 0 => (len + start).max(0),
+            start => start,
+        };
+        let end_given = end.is_some();
+        let end = match end.unwrap_or(-1) {
+            end if end < 0 => len + end,
+            end => end.min(len - 1),
+        };
+        if start > end {
+            return Ok(-1);
+        }
 
-        let option = match state.entries.get_mut(&key) {
-            None => {
-                // 将值插入哈希表中
-                let expire = None;
-                // 如果这个`set`成为下一个过期的密钥**，则需要通知后台任务，以便它可以更新其状态。是否需要通知任务是在"set"例程期间计算的。
-                let mut notify = false;
-                // 获取到期时间
-                let expires_at = expire.map(|duration| {
-                    // 设置到期时间
-                    let when = Instant::now() + duration;
-                    // 只有当新插入的expiration是下一个要删除的键时，才通知辅助任务。在这种情况下，需要唤醒worker以更新其状态。
-                    // 查看树中的第一个结点（最小结点），是否大于当前节点的存在时间
-                    notify = state
-                        .next_expiration()
-                        .map(|expiration| expiration > when)
-                        .unwrap_or(true);
+        // 查找 0 时把字节取反，两种情况都变成查找第一个 1
+        let found = value[start as usize..=end as usize]
+            .iter()
+            .map(|&byte| if bit { byte } else { !byte })
+            .enumerate()
+            .find(|(_, byte)| *byte != 0);
+        match found {
+            Some((i, byte)) => Ok((start + i as i64) * 8 + byte.leading_zeros() as i64),
+            None if !bit && !end_given => Ok((end + 1) * 8),
+            None => Ok(-1),
+        }
+    }
 
-                    when
-                });
-                let new_set: BTreeSet<Bytes> = datas.into_iter().map(|str| Bytes::from(str)).collect();
+    // 在一次加锁中设置哈希的多个字段，key 不存在时创建
+    pub(crate) fn hmset(&self, key: &str, fields: Vec<(Bytes, Bytes)>) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.lookup(key).is_none() {
+            state.insert(key.to_string(), Entry::new(DbData::Hash(HashMap::new()), None));
+        }
 
-                let prev = state.entries.insert(
-                    key.clone(),
-                    Entry {
-                        data: DbData::Set(new_set),
-                        expires_at,
-                    },
-                );
-                // // 如果键已经存在。则删除
-                if let Some(prev) = prev {
-                    if let Some(when) = prev.expires_at {
-                        // 删除 expiration
-                        state.expirations.remove(&(when, key.clone()));
-                    }
-                }
-                // 插入键值对到树中
-                if let Some(when) = expires_at {
-                    state.expirations.insert((when, key));
-                }
-                // 释放互斥锁
-                if notify {
-                    // 激活 notified(需要删除节点)
-                    self.shared.background_task.notify_one();
-                }
-                Some(Bytes::from("error"));
-            }
-            Some(data) => {
-                let dbdata = &mut data.data;
-                if let DbData::Set(ref mut l) = dbdata {
-                    for v in datas {
-                        l.insert(Bytes::from(v));
-                    }
-                }
-            }
+        let entry = state.lookup(key).expect("hash was just inserted");
+        let hash = match &mut entry.data {
+            DbData::Hash(hash) => hash,
+            data => return Err(data.wrong_type("hash")),
         };
+        let (mut added, mut removed) = (0, 0);
+        for (field, value) in fields {
+            let field_len = field.len();
+            added += value.len();
+            match hash.insert(field, value) {
+                Some(old) => removed += old.len(),
+                None => added += field_len + HASH_FIELD_OVERHEAD,
+            }
+        }
+
+        state.used_memory = state.used_memory + added - removed;
+        state.modified(key);
         drop(state);
+
+        self.notify(EventClass::Hash, "hset", key);
+        Ok(())
     }
-    //  返回set中元素的个数
-    pub(crate) fn scard(&self, key: String) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
-        let option = state.entries.get(&*key).map(|entry| entry.data.clone()).unwrap();
-        let option1 = match option {
-            DbData::Set(v) => {
-                Some(Bytes::from(v.len().to_string()))
+
+    // 只在字段不存在时设置哈希的字段，key 不存在时创建，返回是否设置。判断与写入在同一次加锁中完成，
+    // 并发设置同一个字段时只有一个会成功
+    pub(crate) fn hsetnx(&self, key: &str, field: Bytes, value: Bytes) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => {
+                state.insert(key.to_string(), Entry::new(DbData::Hash(HashMap::new()), None));
             }
-            _ => {
-                None
+            Some(DbData::Hash(hash)) if hash.contains_key(&field) => return Ok(false),
+            Some(DbData::Hash(_)) => {}
+            Some(data) => return Err(data.wrong_type("hash")),
+        }
+
+        let added = field.len() + value.len() + HASH_FIELD_OVERHEAD;
+        if let Some(DbData::Hash(hash)) = state.lookup(key).map(|entry| &mut entry.data) {
+            hash.insert(field, value);
+        }
+        state.used_memory += added;
+        state.modified(key);
+        drop(state);
+
+        self.notify(EventClass::Hash, "hset", key);
+        Ok(true)
+    }
+
+    // 在一次加锁中读取哈希的多个字段，字段或 key 不存在时对应位置为 None
+    pub(crate) fn hmget(&self, key: &str, fields: &[Bytes]) -> crate::Result<Vec<Option<Bytes>>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(vec![None; fields.len()]),
+            Some(DbData::Hash(hash)) => Ok(fields.iter().map(|field| hash.get(field).cloned()).collect()),
+            Some(data) => Err(data.wrong_type("hash")),
+        }
+    }
+
+    // 随机返回哈希的字段与值，key 不存在时返回 None。count 为 None 时返回一个字段；
+    // 为正数时返回不重复的 min(count, 字段数) 个字段；为负数时返回 -count 个字段，可能重复
+    pub(crate) fn hrandfield(&self, key: &str, count: Option<i64>) -> crate::Result<Option<Vec<(Bytes, Bytes)>>> {
+        let mut state = self.shared.state.lock().unwrap();
+        let hash = match state.lookup(key).map(|entry| &entry.data) {
+            // 空的哈希与不存在的 key 一样处理
+            Some(DbData::Hash(hash)) if hash.is_empty() => return Ok(None),
+            None => return Ok(None),
+            Some(DbData::Hash(hash)) => hash,
+            Some(data) => return Err(data.wrong_type("hash")),
+        };
+        let fields: Vec<(&Bytes, &Bytes)> = hash.iter().collect();
+        let picked = match count {
+            None => vec![fields[random_index(fields.len())]],
+            Some(count) if count < 0 => (0..count.unsigned_abs())
+                .map(|_| fields[random_index(fields.len())])
+                .collect(),
+            Some(count) if count as usize >= fields.len() => fields,
+            Some(count) => {
+                // 部分 Fisher-Yates 洗牌，只打乱前 count 个位置
+                let mut fields = fields;
+                let count = count as usize;
+                for i in 0..count {
+                    let j = i + random_index(fields.len() - i);
+                    fields.swap(i, j);
+                }
+                fields.truncate(count);
+                fields
+            }
+        };
+        Ok(Some(picked.into_iter().map(|(field, value)| (field.clone(), value.clone())).collect()))
+    }
+
+    // 在一次加锁中设置有序集合多个成员的分数，key 不存在时创建，返回新加入的成员个数
+    pub(crate) fn zadd(&self, key: &str, members: Vec<(f64, Bytes)>) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.lookup(key).is_none() {
+            state.insert(key.to_string(), Entry::new(DbData::ZSet(SortedSet::new()), None));
+        }
+
+        let entry = state.lookup(key).expect("sorted set was just inserted");
+        let zset = match &mut entry.data {
+            DbData::ZSet(zset) => zset,
+            data => return Err(data.wrong_type("zset")),
+        };
+        let (mut added, mut size) = (0, 0);
+        for (score, member) in members {
+            let member_len = member.len();
+            if zset.insert(member, score).is_none() {
+                added += 1;
+                size += member_len + ZSET_MEMBER_OVERHEAD;
             }
+        }
+
+        state.used_memory += size;
+        state.modified(key);
+        drop(state);
+
+        self.notify(EventClass::ZSet, "zadd", key);
+        Ok(added)
+    }
+
+    // 把成员的分数加上 increment，成员或 key 不存在时以 increment 为分数加入，返回新的分数
+    pub(crate) fn zincrby(&self, key: &str, increment: f64, member: Bytes) -> crate::Result<f64> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.lookup(key).is_none() {
+            state.insert(key.to_string(), Entry::new(DbData::ZSet(SortedSet::new()), None));
+        }
+
+        let entry = state.lookup(key).expect("sorted set was just inserted");
+        let zset = match &mut entry.data {
+            DbData::ZSet(zset) => zset,
+            data => return Err(data.wrong_type("zset")),
         };
+        let score = zset.score(&member).unwrap_or(0.0) + increment;
+        // inf 加上 -inf 的结果不是数字，此时不修改成员的分数。increment 不会是 NaN，
+        // 所以只有已存在的成员会出现这种情况，不会留下新建的空有序集合
+        if score.is_nan() {
+            return Err("ERR resulting score is not a number (NaN)".into());
+        }
+        let member_len = member.len();
+        if zset.insert(member, score).is_none() {
+            state.used_memory += member_len + ZSET_MEMBER_OVERHEAD;
+        }
+        state.modified(key);
         drop(state);
-        option1
+
+        self.notify(EventClass::ZSet, "zincr", key);
+        Ok(score)
     }
-    pub(crate) fn sdiff(&self, keys: Vec<String>) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
-        let mut sets: Vec<BTreeSet<Bytes>> = vec![];
-        for key in keys {
-            let optionx = state.entries.get(&*key).map(|entry| entry.data.clone());
-            if optionx.is_none() {
-                return None
+
+    // 从有序集合中删除成员，返回删除的个数，最后一个成员被删除时删除 key
+    pub(crate) fn zrem(&self, key: &str, members: &[Bytes]) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        let zset = match state.lookup(key).map(|entry| &mut entry.data) {
+            None => return Ok(0),
+            Some(DbData::ZSet(zset)) => zset,
+            Some(data) => return Err(data.wrong_type("zset")),
+        };
+        let (mut removed, mut size) = (0, 0);
+        for member in members {
+            if zset.remove(member).is_some() {
+                removed += 1;
+                size += member.len() + ZSET_MEMBER_OVERHEAD;
             }
-            let option = optionx.unwrap();
-            match option {
-                DbData::Set(v) => {
-                    sets.push(v.iter().cloned().collect());
-                }
-                _ => {}
-            };
         }
-        let mut iter = sets.into_iter();
-        let first_set = iter.next().unwrap(); // 获取第一个集合作为基准集合
+        let empty = zset.is_empty();
+        if removed == 0 {
+            return Ok(0);
+        }
+        state.used_memory -= size;
+        if empty {
+            state.remove(key);
+        } else {
+            state.modified(key);
+        }
+        drop(state);
 
-        let set1 = iter.fold(first_set, |acc, set| {
-            acc.difference(&set).cloned().collect() // 顺序计算差集
-        });
+        self.notify(EventClass::ZSet, "zrem", key);
+        if empty {
+            self.notify(EventClass::Generic, "del", key);
+        }
+        Ok(removed)
+    }
+
+    // 返回有序集合的成员个数，key 不存在时为 0
+    pub(crate) fn zcard(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(0),
+            Some(DbData::ZSet(zset)) => Ok(zset.len()),
+            Some(data) => Err(data.wrong_type("zset")),
+        }
+    }
+
+    // 按下标返回有序集合的成员与分数，下标的含义见 SortedSet::range_by_index。key 不存在时返回空
+    pub(crate) fn zrange(&self, key: &str, start: i64, stop: i64, rev: bool) -> crate::Result<Vec<(Bytes, f64)>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(vec![]),
+            Some(DbData::ZSet(zset)) => Ok(zset.range_by_index(start, stop, rev)),
+            Some(data) => Err(data.wrong_type("zset")),
+        }
+    }
+
+    // 按分数返回有序集合的成员与分数，参数的含义见 SortedSet::range_by_score。key 不存在时返回空
+    pub(crate) fn zrangebyscore(
+        &self,
+        key: &str,
+        min: ScoreBound,
+        max: ScoreBound,
+        offset: usize,
+        count: Option<usize>,
+    ) -> crate::Result<Vec<(Bytes, f64)>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(vec![]),
+            Some(DbData::ZSet(zset)) => Ok(zset.range_by_score(min, max, offset, count)),
+            Some(data) => Err(data.wrong_type("zset")),
+        }
+    }
+
+    /// 将 value 依次插入列表头部（right 为 false）或尾部，key 不存在时创建列表，返回插入后列表的长度
+    pub fn push(&self, key: String, value: Vec<String>, right: bool) -> crate::Result<usize> {
+        self.push_values(key, value, right, true)
+    }
+
+    // 只在 key 已存在时插入，key 不存在时什么也不做并返回 0，已存在但不是列表时返回 WRONGTYPE
+    pub(crate) fn pushx(&self, key: String, value: Vec<String>, right: bool) -> crate::Result<usize> {
+        self.push_values(key, value, right, false)
+    }
+
+    // PUSH 与 PUSHX 共用：key 不存在时 create 决定是否创建列表，检查与插入在同一次加锁中完成
+    fn push_values(&self, key: String, value: Vec<String>, right: bool, create: bool) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.lookup(&key).is_none() {
+            if !create || value.is_empty() {
+                return Ok(0);
+            }
+            state.insert(
+                key.clone(),
+                Entry::new(DbData::List(LinkedList::new()), None),
+            );
+        }
+
+        let entry = state.lookup(&key).expect("list was just inserted");
+        let list = match &mut entry.data {
+            DbData::List(list) => list,
+            data => return Err(data.wrong_type("list")),
+        };
+        let mut added = 0;
+        for v in value {
+            added += v.len() + LIST_NODE_OVERHEAD;
+            if right {
+                list.push_back(Bytes::from(v));
+            } else {
+                list.push_front(Bytes::from(v));
+            }
+        }
+        let len = list.len();
+        state.used_memory += added;
+        state.modified(&key);
         drop(state);
 
-        Some(btree_to_bytes(&set1))
+        self.notify(EventClass::List, if right { "rpush" } else { "lpush" }, &key);
+        self.notify_list_waiters(&key);
+        Ok(len)
     }
-    pub(crate) fn sinter(&self, keys: Vec<String>) -> Option<Bytes> {
 
+    /// 唤醒所有等待列表 key 收到数据的任务（见 `wait_for_list`），没有等待者时什么也不做。
+    /// PUSH、PUSHX、COPY、MOVE 与 RESTORE 写入 key 后会自动调用
+    pub fn notify_list_waiters(&self, key: &str) {
         let state = self.shared.state.lock().unwrap();
+        if let Some(notify) = state.list_waiters.get(key) {
+            notify.notify_waiters();
+        }
+    }
+
+    /// 是否有任务在 `wait_for_list` 中等待列表 key。最后一个等待者离开（被唤醒或被取消）后返回 false
+    pub fn has_list_waiters(&self, key: &str) -> bool {
+        self.shared.state.lock().unwrap().list_waiters.contains_key(key)
+    }
+
+    /// 等待列表 key 中有数据：key 已经是非空列表时立即返回，否则等到下一次 `notify_list_waiters`。
+    /// 检查与注册在同一次加锁中完成，所以“弹出失败后等待”的循环不会错过两者之间的 PUSH。
+    /// 被唤醒后数据可能已经被其他任务取走，调用者需要重新弹出并在失败时继续等待
+    pub async fn wait_for_list(&self, key: &str) {
+        let notify;
+        let notified;
+        // 锁只在这个块中持有，不能跨过 await
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            if let Some(DbData::List(list)) = state.entries.get(key).map(|entry| &entry.data) {
+                if !list.is_empty() {
+                    return;
+                }
+            }
+            notify = state
+                .list_waiters
+                .entry(key.to_string())
+                .or_insert_with(|| Arc::new(Notify::new()))
+                .clone();
+            // Notified 创建后就能收到 notify_waiters，不需要先被 poll
+            notified = notify.notified();
+        }
+
+        // 无论是被唤醒还是 future 被取消，离开时都检查是否还有其他等待者
+        let _waiter = ListWaiter { shared: &self.shared, key, notify: &notify };
+        notified.await;
+    }
+
+    // 插入数据
+    pub(crate) fn sadd(&self, key: String, datas: Vec<String>) -> crate::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let option = match state.lookup(&key) {
+            None => {
+                // 将值插入哈希表中
+                let expire = None;
+                // 如果这个`set`成为下一个过期的密钥**，则需要通知后台任务，以便它可以更新其状态。是否需要通知任务是在"set"例程期间计算的。
+                let mut notify = false;
+                // 获取到期时间
+                let expires_at = expire.map(|duration| {
+                    // 设置到期时间
+                    let when = Instant::now() + duration;
+                    // 只有当新插入的expiration是下一个要删除的键时，才通知辅助任务。在这种情况下，需要唤醒worker以更新其状态。
+                    // 查看树中的第一个结点（最小结点），是否大于当前节点的存在时间
+                    notify = state
+                        .next_expiration()
+                        .map(|expiration| expiration > when)
+                        .unwrap_or(true);
+
+                    when
+                });
+                let new_set: BTreeSet<Bytes> = datas.into_iter().map(|str| Bytes::from(str)).collect();
+
+                let prev = state.insert(
+                    key.clone(),
+                    Entry::new(DbData::Set(new_set), expires_at),
+                );
+                // // 如果键已经存在。则删除
+                if let Some(prev) = prev {
+                    if let Some(when) = prev.expires_at {
+                        // 删除 expiration
+                        state.expirations.remove(&(when, key.clone()));
+                    }
+                }
+                // 插入键值对到树中
+                if let Some(when) = expires_at {
+                    state.expirations.insert((when, key.clone()));
+                }
+                // 释放互斥锁
+                if notify {
+                    // 激活 notified(需要删除节点)
+                    self.shared.background_task.notify_one();
+                }
+                Some(Bytes::from("error"));
+            }
+            Some(data) => {
+                let l = match &mut data.data {
+                    DbData::Set(l) => l,
+                    data => return Err(data.wrong_type("set")),
+                };
+                let mut added = 0;
+                for v in datas {
+                    let len = v.len();
+                    if l.insert(Bytes::from(v)) {
+                        added += len + SET_MEMBER_OVERHEAD;
+                    }
+                }
+                state.used_memory += added;
+                state.modified(&key);
+            }
+        };
+        drop(state);
+
+        self.notify(EventClass::Set, "sadd", &key);
+        Ok(())
+    }
+    //  返回set中元素的个数，key 不存在时返回 0
+    pub(crate) fn scard(&self, key: &str) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(0),
+            Some(DbData::Set(v)) => Ok(v.len()),
+            Some(data) => Err(data.wrong_type("set")),
+        }
+    }
+    pub(crate) fn sdiff(&self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
         let mut sets: Vec<BTreeSet<Bytes>> = vec![];
-        for key in keys {
-            let optionx = state.entries.get(&*key).map(|entry| entry.data.clone());
+        for key in &keys {
+            let optionx = state.lookup(key).map(|entry| entry.data.clone());
             if optionx.is_none() {
-                return None
+                return Ok(None)
             }
             let option = optionx.unwrap();
             match option {
                 DbData::Set(v) => {
                     sets.push(v.iter().cloned().collect());
                 }
-                _ => {}
+                data => return Err(data.wrong_type("set")),
             };
         }
         let mut iter = sets.into_iter();
         let first_set = iter.next().unwrap(); // 获取第一个集合作为基准集合
 
         let set1 = iter.fold(first_set, |acc, set| {
-            acc.intersection(&set).cloned().collect() // 顺序计算差集
+            acc.difference(&set).cloned().collect() // 顺序计算差集
         });
         drop(state);
 
-        Some(btree_to_bytes(&set1))
+        Ok(Some(btree_to_bytes(&set1)))
     }
-    pub(crate) fn sunion(&self, keys: Vec<String>) -> Option<Bytes> {
-        let state = self.shared.state.lock().unwrap();
+    pub(crate) fn sinter(&self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        let members = state.intersection(&keys, None)?;
+        drop(state);
+
+        Ok(members.map(|members| btree_to_bytes(&members)))
+    }
+
+    // 只返回交集的成员个数，limit 为 Some(n) 时数到 n 个就停止，不必算出完整的交集
+    pub(crate) fn sintercard(&self, keys: Vec<String>, limit: Option<usize>) -> crate::Result<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        let members = state.intersection(&keys, limit)?;
+        Ok(members.map_or(0, |members| members.len()))
+    }
+
+    pub(crate) fn sunion(&self, keys: Vec<String>) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
         let mut sets: Vec<BTreeSet<Bytes>> = vec![];
-        for key in keys {
-            let optionx = state.entries.get(&*key).map(|entry| entry.data.clone());
+        for key in &keys {
+            let optionx = state.lookup(key).map(|entry| entry.data.clone());
             if optionx.is_none() {
-                return None
+                return Ok(None)
             }
             let option = optionx.unwrap();
             match option {
                 DbData::Set(v) => {
                     sets.push(v.iter().cloned().collect());
                 }
-                _ => {}
+                data => return Err(data.wrong_type("set")),
             };
         }
         let mut iter = sets.into_iter();
@@ -503,109 +1175,620 @@ impl Db {
         });
         drop(state);
 
-        Some(btree_to_bytes(&set1))
+        Ok(Some(btree_to_bytes(&set1)))
     }
-    pub(crate) fn srem(&self, key: &String, datas: Vec<String>) -> Option<Bytes> {
+    pub(crate) fn srem(&self, key: &str, datas: Vec<String>) -> crate::Result<Option<Bytes>> {
         let mut state = self.shared.state.lock().unwrap();
-        let x = match state.entries.get_mut(key) {
+        let mut removed = 0;
+        let x = match state.lookup(key) {
             None => { None }
             Some(v) => {
                 match &mut v.data {
                     DbData::Set(v) => {
                         for data in datas {
-                            v.remove(&*Bytes::from(data));
+                            if v.remove(&*Bytes::from(data.clone())) {
+                                removed += data.len() + SET_MEMBER_OVERHEAD;
+                            }
                         }
                         Some(Bytes::from("remove success"))
                     }
-                    _ => { None }
+                    data => return Err(data.wrong_type("set")),
                 }
             }
         };
+        state.used_memory -= removed;
+        state.modified(key);
         drop(state);
-        println!("{:?}", self.get(key));
-        x
+
+        if removed > 0 {
+            self.notify(EventClass::Set, "srem", key);
+        }
+        Ok(x)
+    }
+    // 判断 value 是否是集合中的元素，key 不存在时返回 false
+    pub(crate) fn sismember(&self, key: &str, value: &str) -> crate::Result<bool> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(false),
+            Some(DbData::Set(members)) => Ok(members.contains(value.as_bytes())),
+            Some(data) => Err(data.wrong_type("set")),
+        }
+    }
+    // 按顺序返回每个 member 是否在集合中，key 不存在时全部为 false
+    pub(crate) fn smismember(&self, key: &str, members: &[Bytes]) -> crate::Result<Vec<bool>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(vec![false; members.len()]),
+            Some(DbData::Set(set)) => Ok(members.iter().map(|member| set.contains(member)).collect()),
+            Some(data) => Err(data.wrong_type("set")),
+        }
     }
-    pub(crate) fn sismember(&self, key: String, value: String) -> Option<Bytes> {
-        // 数据浅拷贝出去
+
+    // 返回集合的所有元素，key 不存在时返回空集合
+    pub(crate) fn smembers(&self, key: &str) -> crate::Result<Vec<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok(vec![]),
+            Some(DbData::Set(members)) => Ok(members.iter().cloned().collect()),
+            Some(data) => Err(data.wrong_type("set")),
+        }
+    }
+
+    // 游标式遍历 key，返回下一次的游标（遍历结束时为 0）与这一次的 key。
+    // 每次取哈希值不小于 cursor 的 count 个 key，再只保留类型为 type_name 的，所以返回的 key 可能少于 count。
+    // 从开始到结束一直存在的 key 一定会被返回，遍历期间增删的 key 可能返回也可能不返回
+    pub(crate) fn scan(&self, cursor: u64, count: usize, type_name: Option<&str>) -> (u64, Vec<String>) {
         let state = self.shared.state.lock().unwrap();
-        let optionx = state.entries.get(&*key).map(|entry| entry.data.clone());
-        let x = match optionx {
-            None => {Some(Bytes::from("There is no such key.".to_string()))}
-            Some(option) => {
-                match option {
-                    DbData::Set(v) => {
-                        if v.contains(&Bytes::from(value)) {
-                            Some(Bytes::from("true".to_string()))
-                        } else {
-                            Some(Bytes::from("false".to_string()))
-                        }
-                    }
-                    _ => {
-                        Some(Bytes::from("false".to_string()))
-                    }
-                }
+        let now = Instant::now();
+        let live = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.expires_at.map_or(true, |when| when > now))
+            .map(|(key, entry)| (key.as_bytes(), (key, entry)));
+        let (next, page) = scan_page(&self.shared.scan_hasher, cursor, count, live);
+        let keys = page
+            .into_iter()
+            .filter(|(_, entry)| type_name.map_or(true, |name| name.eq_ignore_ascii_case(entry.data.type_name())))
+            .map(|(key, _)| key.clone())
+            .collect();
+        (next, keys)
+    }
+
+    // 游标式遍历哈希的字段，返回下一次的游标与这一次的 (字段, 值)，key 不存在时遍历立即结束。
+    // 与 SCAN 一样按字段名的哈希值排序分页：遍历期间一直存在的字段一定会被返回，期间增删的字段可能返回也可能不返回
+    pub(crate) fn hscan(&self, key: &str, cursor: u64, count: usize) -> crate::Result<(u64, Vec<(Bytes, Bytes)>)> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok((0, vec![])),
+            Some(DbData::Hash(hash)) => {
+                let fields = hash.iter().map(|(field, value)| (&field[..], (field, value)));
+                let (next, page) = scan_page(&self.shared.scan_hasher, cursor, count, fields);
+                Ok((next, page.into_iter().map(|(field, value)| (field.clone(), value.clone())).collect()))
             }
-        };
-        drop(state);
-        x
+            Some(data) => Err(data.wrong_type("hash")),
+        }
     }
-    pub(crate) fn sismembers(&self, key: String) -> Option<Bytes> {
-        // 数据浅拷贝出去
-        let state = self.shared.state.lock().unwrap();
-        let option = state.entries.get(&*key).map(|entry| entry.data.clone()).unwrap();
-        let option1 = match option {
-            DbData::Set(v) => {
-                Some(btree_to_bytes(&v))
+
+    // 游标式遍历集合的元素，返回下一次的游标与这一次的元素，保证与 hscan 相同
+    pub(crate) fn sscan(&self, key: &str, cursor: u64, count: usize) -> crate::Result<(u64, Vec<Bytes>)> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key).map(|entry| &entry.data) {
+            None => Ok((0, vec![])),
+            Some(DbData::Set(members)) => {
+                let members = members.iter().map(|member| (&member[..], member));
+                let (next, page) = scan_page(&self.shared.scan_hasher, cursor, count, members);
+                Ok((next, page.into_iter().cloned().collect()))
             }
-            _ => {
-                None
+            Some(data) => Err(data.wrong_type("set")),
+        }
+    }
+
+    // 返回列表或集合排序后的元素：默认按数值排序（数值相同时按字节序），alpha 时按字节序排序，desc 时降序。
+    // limit 为 (offset, count)，count 为负数时取到末尾。key 不存在时返回空，有元素不能转换为数值时返回错误
+    pub(crate) fn sort(&self, key: &str, alpha: bool, desc: bool, limit: Option<(i64, i64)>) -> crate::Result<Vec<Bytes>> {
+        // 只在复制元素时持有锁，排序在锁外进行
+        let mut elements: Vec<Bytes> = {
+            let mut state = self.shared.state.lock().unwrap();
+            match state.lookup(key).map(|entry| &entry.data) {
+                None => return Ok(vec![]),
+                Some(DbData::List(list)) => list.iter().cloned().collect(),
+                Some(DbData::Set(members)) => members.iter().cloned().collect(),
+                Some(data) => return Err(data.wrong_type("list")),
             }
         };
+
+        if alpha {
+            elements.sort();
+        } else {
+            let mut scored = elements
+                .into_iter()
+                .map(|element| {
+                    let score = std::str::from_utf8(&element)
+                        .ok()
+                        .and_then(|s| s.parse::<f64>().ok())
+                        .filter(|score| !score.is_nan())
+                        .ok_or("ERR One or more scores can't be converted into double")?;
+                    Ok((score, element))
+                })
+                .collect::<crate::Result<Vec<(f64, Bytes)>>>()?;
+            scored.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+            elements = scored.into_iter().map(|(_, element)| element).collect();
+        }
+        if desc {
+            elements.reverse();
+        }
+
+        let (offset, count) = match limit {
+            Some((offset, count)) => (offset.max(0) as usize, count),
+            None => (0, -1),
+        };
+        let elements = elements.into_iter().skip(offset);
+        Ok(match usize::try_from(count) {
+            Ok(count) => elements.take(count).collect(),
+            Err(_) => elements.collect(),
+        })
+    }
+
+    // 返回 keys 中存在的 key 个数（重复的 key 重复计数），并刷新它们的访问时间
+    pub(crate) fn touch(&self, keys: &[String]) -> usize {
+        let mut state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
+        keys.iter()
+            .filter(|key| match state.lookup(key) {
+                Some(entry) => entry.expires_at.map_or(true, |when| when > now),
+                None => false,
+            })
+            .count()
+    }
+
+    // 返回字符串的值并删除 key，两步在同一次加锁中完成。值不是字符串时返回错误且不删除
+    pub(crate) fn getdel(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.entries.get(key).map(|entry| &entry.data) {
+            None => return Ok(None),
+            Some(DbData::String(_)) => {}
+            Some(data) => return Err(data.wrong_type("string")),
+        }
+        let value = match state.remove(key).map(|entry| entry.data) {
+            Some(DbData::String(value)) => Some(value),
+            _ => None,
+        };
+        drop(state);
+
+        self.notify(EventClass::Generic, "del", key);
+        Ok(value)
+    }
+
+    // 返回字符串的值，并在同一次加锁中按 expiry 调整过期时间。值不是字符串时返回错误且不修改
+    pub(crate) fn getex(&self, key: &str, expiry: Expiry) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        let (value, prev_expires_at) = match state.lookup(key) {
+            None => return Ok(None),
+            Some(Entry { data: DbData::String(value), expires_at, .. }) => (value.clone(), *expires_at),
+            Some(entry) => return Err(entry.data.wrong_type("string")),
+        };
+
+        let expires_at = match expiry {
+            Expiry::Keep => return Ok(Some(value)),
+            Expiry::After(duration) => Some(Instant::now() + duration),
+            Expiry::Persist => None,
+        };
+        // 新的过期时间早于当前最早的过期时间时，需要唤醒后台任务
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+
+        if let Some(when) = prev_expires_at {
+            state.expirations.remove(&(when, key.to_string()));
+        }
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, key.to_string()));
+        }
+        if let Some(entry) = state.entries.get_mut(key) {
+            entry.expires_at = expires_at;
+        }
+        state.modified(key);
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        let event = if expires_at.is_some() { "expire" } else { "persist" };
+        self.notify(EventClass::Generic, event, key);
+        Ok(Some(value))
+    }
+
+    // 将 src 的值和剩余过期时间复制到 dst。dst 已存在且没有指定 replace 时不复制，返回 false
+    pub(crate) fn copy(&self, src: &str, dst: &str, replace: bool) -> crate::Result<bool> {
+        if src == dst {
+            return Err("ERR source and destination objects are the same".into());
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
+
+        // 源 key 不存在或已过期（后台任务还没来得及删除）
+        let (data, remaining) = match state.entries.get(src) {
+            Some(entry) if entry.expires_at.map_or(true, |when| when > now) => (
+                entry.data.clone(),
+                entry.expires_at.map(|when| when - now),
+            ),
+            _ => return Ok(false),
+        };
+
+        if !replace && state.entries.contains_key(dst) {
+            return Ok(false);
+        }
+
+        // 剩余时间以当前时刻为起点重新计算
+        let expires_at = remaining.map(|duration| Instant::now() + duration);
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+
+        let prev = state.insert(dst.to_string(), Entry::new(data, expires_at));
+        if let Some(when) = prev.and_then(|prev| prev.expires_at) {
+            state.expirations.remove(&(when, dst.to_string()));
+        }
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, dst.to_string()));
+        }
+        drop(state);
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        self.notify(EventClass::Generic, "copy_to", dst);
+        self.notify_list_waiters(dst);
+        Ok(true)
+    }
+
+    // 把 key 连同剩余过期时间移动到另一个数据库 dest。key 不存在（或已过期）、dest 中已有同名 key 时不移动，返回 false。
+    // 两个数据库的锁同时持有，按地址顺序获取，避免方向相反的两个 MOVE 互相等待
+    pub(crate) fn move_to(&self, key: &str, dest: &Db) -> bool {
+        if Arc::ptr_eq(&self.shared, &dest.shared) {
+            return false;
+        }
+        let (mut state, mut dest_state) = if Arc::as_ptr(&self.shared) < Arc::as_ptr(&dest.shared) {
+            let state = self.shared.state.lock().unwrap();
+            (state, dest.shared.state.lock().unwrap())
+        } else {
+            let dest_state = dest.shared.state.lock().unwrap();
+            (self.shared.state.lock().unwrap(), dest_state)
+        };
+        let now = Instant::now();
+        let live = |entry: &Entry| entry.expires_at.map_or(true, |when| when > now);
+
+        if !state.entries.get(key).is_some_and(live) {
+            return false;
+        }
+        match dest_state.entries.get(key) {
+            Some(entry) if live(entry) => return false,
+            // 已过期但后台任务还没来得及删除，直接覆盖
+            Some(_) => {
+                dest_state.remove(key);
+            }
+            None => {}
+        }
+
+        let mut entry = state.remove(key).expect("key was just checked");
+        // 剩余时间以当前时刻为起点重新计算
+        entry.expires_at = entry.expires_at.map(|when| Instant::now() + (when - now));
+        let notify = entry.expires_at.is_some_and(|when| {
+            dest_state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+        if let Some(when) = entry.expires_at {
+            dest_state.expirations.insert((when, key.to_string()));
+        }
+        dest_state.insert(key.to_string(), entry);
+        drop(state);
+        drop(dest_state);
+
+        if notify {
+            dest.shared.background_task.notify_one();
+        }
+        self.notify(EventClass::Generic, "move_from", key);
+        dest.notify(EventClass::Generic, "move_to", key);
+        dest.notify_list_waiters(key);
+        true
+    }
+
+    // 把 key 的值序列化为 DUMP 格式，不包含过期时间。key 不存在时返回 None
+    pub(crate) fn dump(&self, key: &str) -> crate::Result<Option<Bytes>> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.lookup(key) {
+            Some(entry) => Ok(Some(serialization::dump(&entry.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    // 用 DUMP 的结果重建 key，ttl 为 None 时不过期。key 已存在且没有指定 replace 时返回 BUSYKEY 错误
+    pub(crate) fn restore(&self, key: &str, ttl: Option<Duration>, payload: &[u8], replace: bool) -> crate::Result<()> {
+        // 先在锁外反序列化，数据损坏时不修改数据库
+        let data: DbData = serialization::restore(payload)?;
+
+        let mut state = self.shared.state.lock().unwrap();
+        if !replace && state.entries.contains_key(key) {
+            return Err("BUSYKEY Target key name already exists.".into());
+        }
+
+        let expires_at = ttl.map(|duration| Instant::now() + duration);
+        let notify = expires_at.is_some_and(|when| {
+            state
+                .next_expiration()
+                .map(|expiration| expiration > when)
+                .unwrap_or(true)
+        });
+
+        let prev = state.insert(key.to_string(), Entry::new(data, expires_at));
+        if let Some(when) = prev.and_then(|prev| prev.expires_at) {
+            state.expirations.remove(&(when, key.to_string()));
+        }
+        if let Some(when) = expires_at {
+            state.expirations.insert((when, key.to_string()));
+        }
         drop(state);
-        option1
+
+        if notify {
+            self.shared.background_task.notify_one();
+        }
+        self.notify(EventClass::Generic, "restore", key);
+        self.notify_list_waiters(key);
+        Ok(())
+    }
+
+    // 估算的内存占用（字节）
+    pub(crate) fn used_memory(&self) -> usize {
+        self.shared.state.lock().unwrap().used_memory
     }
 
+    // 按淘汰策略从 samples 个随机抽样的 key 中选出一个可以淘汰的，同时返回它的访问计数与最近一次被访问的时间
+    pub(crate) fn eviction_candidate(&self, policy: MaxmemoryPolicy, samples: usize) -> Option<(String, u8, Instant)> {
+        let state = self.shared.state.lock().unwrap();
+        let key = state.eviction_candidate(policy, samples)?;
+        let entry = &state.entries[key];
+        Some((key.clone(), entry.frequency(), entry.accessed_at))
+    }
+
+    // 淘汰 key，返回 key 是否存在
+    pub(crate) fn evict(&self, key: &str) -> bool {
+        debug!(key, "evicting key");
+        let evicted = self.shared.state.lock().unwrap().remove(key).is_some();
+        if evicted {
+            self.notify(EventClass::Evicted, "evicted", key);
+        }
+        evicted
+    }
+
+    // key 的版本号，key 不存在时为最近一次删除条目的版本号。key 被修改、删除或过期后版本号都会变化；
+    // 不存在的 key 在这个数据库删除任何条目后也会变化，这时 EXEC 可能多放弃一次，但不会漏掉修改
+    pub(crate) fn version(&self, key: &str) -> u64 {
+        let state = self.shared.state.lock().unwrap();
+        state.entries.get(key).map_or(state.removed_version, |entry| entry.version)
+    }
+
+    // 估算 key 与值占用的内存（字节），key 不存在时返回 None
+    pub(crate) fn memory_usage(&self, key: &str) -> Option<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        state
+            .lookup(key)
+            .map(|entry| key.len() + ENTRY_OVERHEAD + entry.data.estimated_size())
+    }
+
+    // 返回 key 对应值的内部表示，key 不存在时返回 None
+    pub(crate) fn encoding(&self, key: &str) -> Option<&'static str> {
+        let state = self.shared.state.lock().unwrap();
+        state.entries.get(key).map(|entry| entry.data.encoding())
+    }
+
+    // 返回 key 对应值的引用计数，key 不存在时返回 None。与 encoding 一样不更新访问时间
+    pub(crate) fn refcount(&self, key: &str) -> Option<i64> {
+        let state = self.shared.state.lock().unwrap();
+        state.entries.get(key).map(|entry| entry.data.refcount())
+    }
+
+    // 距离上次访问经过的秒数，不更新访问时间
+    pub(crate) fn object_idletime(&self, key: &str) -> Option<u64> {
+        let state = self.shared.state.lock().unwrap();
+        state.entries.get(key).map(|entry| entry.accessed_at.elapsed().as_secs())
+    }
+
+    // key 剩余的生存时间：key 不存在（或已过期）时返回 None，没有过期时间时返回 Some(None)。不更新访问时间
+    pub(crate) fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        let state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
+        match state.entries.get(key)?.expires_at {
+            None => Some(None),
+            Some(when) if when > now => Some(Some(when - now)),
+            Some(_) => None,
+        }
+    }
+
+    // key 过期的时刻（系统时间）：key 不存在（或已过期）时返回 None，没有过期时间时返回 Some(None)。
+    // 过期时间按单调时钟保存，这里用同一时刻读取的 Instant 与 SystemTime 之差换算成系统时间
+    pub(crate) fn expiretime(&self, key: &str) -> Option<Option<SystemTime>> {
+        let state = self.shared.state.lock().unwrap();
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        match state.entries.get(key)?.expires_at {
+            None => Some(None),
+            Some(when) if when > now => Some(Some(wall_now + (when - now))),
+            Some(_) => None,
+        }
+    }
+
+    // 衰减后的对数访问计数，不计入这次查询
+    pub(crate) fn object_freq(&self, key: &str) -> Option<u8> {
+        let state = self.shared.state.lock().unwrap();
+        state.entries.get(key).map(|entry| entry.frequency())
+    }
+
+    // DEBUG OBJECT 的回复：引用计数、编码、DUMP 序列化后的长度、剩余的过期时间（毫秒，不过期为 -1）。
+    // 与 encoding 一样不更新访问时间
+    pub(crate) fn debug_object(&self, key: &str) -> Option<String> {
+        let state = self.shared.state.lock().unwrap();
+        let entry = state.entries.get(key)?;
+        let serialized_length = serialization::dump(&entry.data).map_or(0, |payload| payload.len());
+        let ttl = entry.expires_at.map_or(-1, |when| {
+            when.saturating_duration_since(Instant::now()).as_millis() as i64
+        });
+        Some(format!(
+            "refcount:{} encoding:{} serializedlength:{} ttl_ms:{}",
+            entry.data.refcount(),
+            entry.data.encoding(),
+            serialized_length,
+            ttl
+        ))
+    }
+
+    // 删除所有 key。锁内只把条目与过期时间换成空表，旧表在释放锁之后再释放；
+    // lazy 时交给阻塞线程池释放，避免释放大量条目时阻塞事件循环
+    pub(crate) fn clear(&self, lazy: bool) {
+        let mut state = self.shared.state.lock().unwrap();
+        let entries = mem::take(&mut state.entries);
+        let keys = mem::take(&mut state.keys);
+        let expirations = mem::take(&mut state.expirations);
+        state.used_memory = 0;
+        state.version += 1;
+        state.removed_version = state.version;
+        drop(state);
+
+        if lazy {
+            tokio::task::spawn_blocking(move || drop((entries, keys, expirations)));
+        }
+    }
+
+    // 把所有 key 转换为重建它们的写命令，用于副本的全量同步。字符串带上剩余的过期时间
+    pub(crate) fn snapshot(&self) -> Vec<Frame> {
+        let state = self.shared.state.lock().unwrap();
+        let now = Instant::now();
+        let mut frames = Vec::with_capacity(state.entries.len());
+        for (key, entry) in &state.entries {
+            // 已过期但后台任务还没来得及删除
+            if entry.expires_at.is_some_and(|when| when <= now) {
+                continue;
+            }
+            let mut frame = Frame::array();
+            match &entry.data {
+                DbData::String(value) => {
+                    frame.push_bulk(Bytes::from("set"));
+                    frame.push_bulk(Bytes::from(key.clone()));
+                    frame.push_bulk(value.clone());
+                    if let Some(when) = entry.expires_at {
+                        frame.push_bulk(Bytes::from("px"));
+                        frame.push_u64(((when - now).as_millis() as u64).max(1));
+                    }
+                }
+                DbData::List(list) => {
+                    frame.push_bulk(Bytes::from("push"));
+                    frame.push_bulk(Bytes::from(key.clone()));
+                    frame.push_bulk(Bytes::from("R"));
+                    frame.push_u64(list.len() as u64);
+                    for value in list {
+                        frame.push_bulk(value.clone());
+                    }
+                }
+                DbData::Set(members) => {
+                    frame.push_bulk(Bytes::from("sadd"));
+                    frame.push_bulk(Bytes::from(key.clone()));
+                    for member in members {
+                        frame.push_bulk(member.clone());
+                    }
+                }
+                DbData::Hash(fields) => {
+                    frame.push_bulk(Bytes::from("hmset"));
+                    frame.push_bulk(Bytes::from(key.clone()));
+                    for (field, value) in fields {
+                        frame.push_bulk(field.clone());
+                        frame.push_bulk(value.clone());
+                    }
+                }
+                DbData::ZSet(members) => {
+                    frame.push_bulk(Bytes::from("zadd"));
+                    frame.push_bulk(Bytes::from(key.clone()));
+                    for (member, score) in members.iter() {
+                        frame.push_bulk(Bytes::from(fmt_score(score)));
+                        frame.push_bulk(member.clone());
+                    }
+                }
+            }
+            frames.push(frame);
+        }
+        frames
+    }
 
     // 关闭信号
-    fn shutdown_purge_task(&self) {
+    pub(crate) fn shutdown_purge_task(&self) {
         // 删除state，通知删除树，shotdown
         let mut state = self.shared.state.lock().unwrap();
         state.shutdown = true;
         drop(state);
         self.shared.background_task.notify_one();
     }
+
+    // 打开或关闭后台任务的主动过期。打开时唤醒后台任务，立即删除关闭期间过期的 key
+    pub(crate) fn set_active_expire(&self, enabled: bool) {
+        self.shared.active_expire.store(enabled, Ordering::Relaxed);
+        self.shared.background_task.notify_one();
+    }
+
+    // 发布 key 上发生的 event，没有开启键空间通知时什么也不做。调用前需要释放 state 的锁
+    fn notify(&self, class: EventClass, event: &str, key: &str) {
+        self.shared.notify(class, event, key);
+    }
 }
 
 impl Shared {
     // 取消所有过期的密钥，并返回下一个密钥将过期的"Instant"。后台任务将休眠，直到此时。返回 None 表示数据库为空
     fn purge_expired_keys(&self) -> Option<Instant> {
-        let mut state = self.state.lock().unwrap();
+        let mut guard = self.state.lock().unwrap();
 
-        if state.shutdown {
+        if guard.shutdown {
             // 数据库正在关闭。共享指针都已经删除。后台任务退出。
             return None;
         }
         // `lock（）`返回一个`MutexGuard`而不是`& mut State`。
         // 借用检查器无法"穿透"互斥保护，
         // 所以我们在循环外得到一个对`State`的"真正"可变引用。
-        let state = &mut *state;
+        let state = &mut *guard;
 
         // 查找此前计划过期的所有密钥。
 
         // 获取当前时间
         let now = Instant::now();
+        // 被删除的 key，释放锁之后再发布 expired 事件。先取出读取时惰性删除的 key
+        let mut expired = mem::take(&mut state.lazily_expired);
+        let mut next = None;
         // 遍历这个二叉树，当when>now时，返回，否则删除
         while let Some(&(when, ref key)) = state.expirations.iter().next() {
             if when > now {
                 // 由于二叉树有序，因此返回
-                return Some(when);
+                next = Some(when);
+                break;
             }
             // 删除数据库中的值，同时删除树中的值
-            state.entries.remove(key);
-            state.expirations.remove(&(when, key.clone()));
+            let key = key.clone();
+            state.remove(&key);
+            expired.push(key);
         }
+        drop(guard);
 
-        None
+        for key in &expired {
+            self.notify(EventClass::Expired, "expired", key);
+        }
+        next
+    }
+
+    fn notify(&self, class: EventClass, event: &str, key: &str) {
+        self.notifier.notify(class, event, key);
     }
 
     // 返回是否关闭
@@ -615,6 +1798,152 @@ impl Shared {
 }
 
 impl State {
+    // 插入条目并更新内存占用与版本号，返回被替换的旧条目（旧条目的过期时间由调用方处理）
+    fn insert(&mut self, key: String, mut entry: Entry) -> Option<Entry> {
+        self.version += 1;
+        entry.version = self.version;
+        // 替换已有的 key 时沿用它的下标，否则追加到 keys 末尾
+        match self.entries.get(&key) {
+            Some(prev) => entry.slot = prev.slot,
+            None => {
+                entry.slot = self.keys.len();
+                self.keys.push(key.clone());
+            }
+        }
+        let key_len = key.len();
+        self.used_memory += key_len + ENTRY_OVERHEAD + entry.data.estimated_size();
+        let prev = self.entries.insert(key, entry);
+        if let Some(prev) = &prev {
+            self.used_memory -= key_len + ENTRY_OVERHEAD + prev.data.estimated_size();
+        }
+        prev
+    }
+
+    // 原地修改条目后调用，为它分配新的版本号
+    fn modified(&mut self, key: &str) {
+        self.version += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.version = self.version;
+        }
+    }
+
+    // 删除条目及其过期时间，并更新内存占用与版本号
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.version += 1;
+        self.removed_version = self.version;
+        // 用最后一个 key 填补空出的下标
+        self.keys.swap_remove(entry.slot);
+        if let Some(moved) = self.keys.get(entry.slot) {
+            self.entries.get_mut(moved).expect("every key has an entry").slot = entry.slot;
+        }
+        self.used_memory -= key.len() + ENTRY_OVERHEAD + entry.data.estimated_size();
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, key.to_string()));
+        }
+        Some(entry)
+    }
+
+    // 按淘汰策略选出一个要删除的 key。与 Redis 一样是近似算法：随机抽样 samples 个 key，
+    // 从中选最久没有被访问（LRU）或访问计数最小（LFU）的，不遍历整个数据库
+    fn eviction_candidate(&self, policy: MaxmemoryPolicy, samples: usize) -> Option<&String> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let mut sampled = (0..samples.max(1)).map(|_| {
+            let key = &self.keys[random_index(self.keys.len())];
+            (key, &self.entries[key])
+        });
+        match policy {
+            MaxmemoryPolicy::NoEviction => None,
+            MaxmemoryPolicy::AllkeysRandom => sampled.next().map(|(key, _)| key),
+            MaxmemoryPolicy::AllkeysLru => sampled.min_by_key(|(_, entry)| entry.accessed_at).map(|(key, _)| key),
+            MaxmemoryPolicy::AllkeysLfu => sampled
+                .min_by_key(|(_, entry)| (entry.frequency(), entry.accessed_at))
+                .map(|(key, _)| key),
+        }
+    }
+
+    // 计算多个集合的交集，找到 limit 个成员后提前停止。任一 key 不存在时返回 None
+    fn intersection(
+        &mut self,
+        keys: &[String],
+        limit: Option<usize>,
+    ) -> crate::Result<Option<BTreeSet<Bytes>>> {
+        for key in keys {
+            match self.lookup(key).map(|entry| &entry.data) {
+                None => return Ok(None),
+                Some(DbData::Set(_)) => {}
+                Some(data) => return Err(data.wrong_type("set")),
+            }
+        }
+
+        let mut sets: Vec<&BTreeSet<Bytes>> = keys
+            .iter()
+            .filter_map(|key| match self.entries.get(key).map(|entry| &entry.data) {
+                Some(DbData::Set(set)) => Some(set),
+                _ => None,
+            })
+            .collect();
+        // 从最小的集合开始检查，其余集合只做查找
+        sets.sort_by_key(|set| set.len());
+        let (smallest, rest) = match sets.split_first() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
+
+        Ok(Some(
+            smallest
+                .iter()
+                .filter(|member| rest.iter().all(|set| set.contains(*member)))
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    // 从列表 key 弹出最多 count 个元素，列表被弹空时删除 key。
+    // 返回弹出的元素与列表是否被弹空，key 不存在时返回 None
+    fn pop_list(&mut self, key: &str, right: bool, count: usize) -> crate::Result<Option<(Vec<Bytes>, bool)>> {
+        let (values, empty) = match self.lookup(key) {
+            None => return Ok(None),
+            Some(entry) => match &mut entry.data {
+                DbData::List(list) => {
+                    let mut values = Vec::with_capacity(count.min(list.len()));
+                    while values.len() < count {
+                        let value = if right { list.pop_back() } else { list.pop_front() };
+                        match value {
+                            Some(value) => values.push(value),
+                            None => break,
+                        }
+                    }
+                    (values, list.is_empty())
+                }
+                data => return Err(data.wrong_type("list")),
+            },
+        };
+        self.used_memory -= values.iter().map(|value| value.len() + LIST_NODE_OVERHEAD).sum::<usize>();
+        if empty {
+            self.remove(key);
+        } else {
+            self.modified(key);
+        }
+        Ok(Some((values, empty)))
+    }
+
+    // 查找 key 并记录一次访问
+    // 查找 key 并更新访问信息。已过期但后台任务还没有删除的 key 在这里删除，当作不存在
+    fn lookup(&mut self, key: &str) -> Option<&mut Entry> {
+        if self.entries.get(key)?.expires_at.is_some_and(|when| when <= Instant::now()) {
+            self.remove(key);
+            self.lazily_expired.push(key.to_string());
+            return None;
+        }
+        let entry = self.entries.get_mut(key)?;
+        entry.touch();
+        Some(entry)
+    }
+
     fn next_expiration(&self) -> Option<Instant> {
         self.expirations
             .iter()
@@ -623,12 +1952,78 @@ impl State {
     }
 }
 
+// 游标式遍历的一页：按名字的哈希值排序，返回哈希值不小于 cursor 的前 count 项，以及下一页的游标，没有更多时为 0。
+// 0 同时表示开始与结束，所以哈希值至少为 1。哈希值相同的项可能跨页被返回两次，与 redis 的 SCAN 一样允许重复
+fn scan_page<'a, T>(
+    hasher: &RandomState,
+    cursor: u64,
+    count: usize,
+    items: impl Iterator<Item = (&'a [u8], T)>,
+) -> (u64, Vec<T>) {
+    let mut page: Vec<(u64, T)> = items
+        .map(|(name, item)| {
+            let mut hasher = hasher.build_hasher();
+            hasher.write(name);
+            (hasher.finish().max(1), item)
+        })
+        .filter(|(hash, _)| *hash >= cursor)
+        .collect();
+    // 只需要最小的 count 项，先用线性时间的选择把它们放到前面
+    let next = if page.len() > count {
+        page.select_nth_unstable_by_key(count, |(hash, _)| *hash);
+        let next = page[count].0;
+        page.truncate(count);
+        next
+    } else {
+        0
+    };
+    page.sort_unstable_by_key(|(hash, _)| *hash);
+    (next, page.into_iter().map(|(_, item)| item).collect())
+}
+
+// 返回 0..len 中的一个随机下标（len 必须大于 0）
+pub(crate) fn random_index(len: usize) -> usize {
+    // 每次新建的 RandomState 使用不同的随机种子
+    RandomState::new().build_hasher().finish() as usize % len
+}
+
+// 返回 [0, 1) 中的一个随机数
+fn random_fraction() -> f64 {
+    (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// 等待列表数据的任务，drop 时如果没有其他等待者就删除 key 对应的 `Notify`
+struct ListWaiter<'a> {
+    shared: &'a Shared,
+    key: &'a str,
+    notify: &'a Arc<Notify>,
+}
+
+impl Drop for ListWaiter<'_> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        // 表中一份、这里一份，说明没有其他等待者。新的等待者在持有锁时才会克隆，所以这里的计数是准确的
+        let last = match state.list_waiters.get(self.key) {
+            Some(notify) => Arc::ptr_eq(notify, self.notify) && Arc::strong_count(notify) == 2,
+            None => false,
+        };
+        if last {
+            state.list_waiters.remove(self.key);
+        }
+    }
+}
+
 /// 后台任务执行的过程
 ///
 /// 等待通知。收到通知后，从共享状态句柄中清除所有过期的密钥。如果设置了"shoot"，则终止任务。
 async fn purge_expired_tasks(shared: Arc<Shared>) {
     // 如果设置了关闭标志，则任务应退出。
     while !shared.is_shutdown() {
+        if !shared.active_expire.load(Ordering::Relaxed) {
+            // 主动过期已关闭，等待重新打开或关闭数据库
+            shared.background_task.notified().await;
+            continue;
+        }
         // 删除所有过期的密钥。该函数返回下一个密钥到期的时刻
         if let Some(when) = shared.purge_expired_keys() {
             // 等待直到下一个密钥过期或直到后台任务收到通知。
@@ -17,9 +17,30 @@ pub enum Frame {
     Integer(i64),
     Bulk(Bytes),
     Null,
+    // 空数组（*-1），用于阻塞命令超时、事务被取消等需要返回数组的场景
+    NullArray,
     Array(Vec<Frame>),
 }
 
+/// 请求帧的大小上限。长度前缀超过上限时在缓冲或分配之前就返回协议错误，
+/// 避免一个声称有上亿个元素或上 GB 数据的请求耗尽内存
+#[derive(Debug, Clone, Copy)]
+pub struct FrameLimits {
+    // 一个数组最多的元素个数
+    pub max_multibulk_len: u64,
+
+    // 一个 bulk 字符串最大的字节数
+    pub max_bulk_len: u64,
+}
+
+impl FrameLimits {
+    /// 不限制，用于读取服务器的回复
+    pub const UNLIMITED: FrameLimits = FrameLimits {
+        max_multibulk_len: u64::MAX,
+        max_bulk_len: u64::MAX,
+    };
+}
+
 #[derive(Debug)]
 pub enum Error {
     // 没有足够的信息转换
@@ -46,67 +67,72 @@ impl Frame {
         }
     }
 
-    // 如果这个self帧完成初始化，则在数组中 push 一个 int
+    // 如果这个self帧完成初始化，则在数组中 push 一个 int。
+    // 与 redis 客户端一样，命令中的数字参数以 bulk 字符串发送，由 Parse::next_u64 解析
     pub(crate) fn push_u64(&mut self, value: u64) {
-        match self {
-            Frame::Array(vec) => {
-                vec.push(Frame::USize(value));
-            }
-            _ => panic!("not an array frame"),
-        }
+        self.push_bulk(Bytes::from(value.to_string()));
     }
 
     pub(crate) fn push_i64(&mut self, value: i64) {
-        match self {
-            Frame::Array(vec) => {
-                vec.push(Frame::Integer(value));
-            }
-            _ => panic!("not an array frame"),
-        }
+        self.push_bulk(Bytes::from(value.to_string()));
     }
 
     // 检查是否可以从`src`解码整个消息（src 为一个光标指针）
     pub fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
+        Frame::check_with_limits(src, &FrameLimits::UNLIMITED)
+    }
+
+    // 与 check 相同，数组长度或 bulk 长度超过 limits 时返回协议错误，此时数据可能还没有读完
+    pub fn check_with_limits(src: &mut Cursor<&[u8]>, limits: &FrameLimits) -> Result<(), Error> {
         match get_u8(src)? {
             // + 获取下一行
-            // = 获取下一行
-            // : 获取下一行（数字）
+            // - 获取下一行
+            // : 获取下一行（有符号数字）
             // $ 如果下一个是 - 跳过 4 字节
             // $ 否则获取下一行（数字），然后跳过长度为：数字+2
-            // * 获取下一个数字n，然后循环 n 次 check
+            // * 如果下一个是 - 跳过 4 字节
+            // * 否则获取下一个数字n，然后循环 n 次 check
             b'+' => {
                 get_line(src)?;
                 Ok(())
             }
-            b'#' => {
+            b'-' => {
                 get_line(src)?;
                 Ok(())
             }
             b':' => {
-                let _ = get_decimal(src)?;
-                Ok(())
-            }
-            b'=' => {
                 let _ = get_i64(src)?;
                 Ok(())
             }
             b'$' => {
-                if b'#' == peek_u8(src)? {
+                if b'-' == peek_u8(src)? {
                     // Skip '-1\r\n'
                     skip(src, 4)
                 } else {
                     // Read the bulk string
-                    let len: usize = get_decimal(src)?.try_into()?;
+                    let len = get_decimal(src)?;
+                    if len > limits.max_bulk_len {
+                        return Err("protocol error; invalid bulk length".into());
+                    }
+                    let len: usize = len.try_into()?;
 
                     // skip that number of bytes + 2 (\r\n).
                     skip(src, len + 2)
                 }
             }
             b'*' => {
+                if b'-' == peek_u8(src)? {
+                    // Skip '-1\r\n'
+                    return skip(src, 4);
+                }
+
                 let len = get_decimal(src)?;
+                if len > limits.max_multibulk_len {
+                    return Err("protocol error; invalid multibulk length".into());
+                }
 
                 for _ in 0..len {
-                    Frame::check(src)?;
+                    Frame::check_with_limits(src, limits)?;
                 }
 
                 Ok(())
@@ -124,7 +150,7 @@ impl Frame {
                 let string = String::from_utf8(line)?;
                 Ok(Frame::Simple(string))
             }
-            b'#' => {
+            b'-' => {
                 // 获取下一行，转为 string ，封装成Error帧返回
                 let line = get_line(src)?.to_vec();
                 let string = String::from_utf8(line)?;
@@ -132,18 +158,13 @@ impl Frame {
                 Ok(Frame::Error(string))
             }
             b':' => {
-                // 获取下一行，转为 u64 ，封装成Integer帧返回
-                let len = get_decimal(src)?;
-                Ok(Frame::USize(len))
-            }
-            b'=' => {
-                // 获取下一行，转为 u64 ，封装成Integer帧返回
-                let len = get_i64(src)?;
-                Ok(Frame::Integer(len))
+                // 获取下一行，RESP 的整数是有符号的，转为 i64 ，封装成Integer帧返回
+                let value = get_i64(src)?;
+                Ok(Frame::Integer(value))
             }
             b'$' => {
                 // 如果下一个为 - 则获取下一行，如果获取到的下一行为-1 则错误，否则返回 null
-                if b'#' == peek_u8(src)? {
+                if b'-' == peek_u8(src)? {
                     let line = get_line(src)?;
                     if line != b"-1" {
                         return Err("protocol error; invalid frame format".into());
@@ -158,6 +179,11 @@ impl Frame {
                         return Err(Error::Incomplete);
                     }
 
+                    // 数据后面必须紧跟 \r\n，否则长度与实际数据不符
+                    if &src.chunk()[len..n] != b"\r\n" {
+                        return Err("protocol error; invalid frame format".into());
+                    }
+
                     let data = Bytes::copy_from_slice(&src.chunk()[..len]);
 
                     // 跳过 n 个字节 + 2 (\r\n).
@@ -167,9 +193,20 @@ impl Frame {
                 }
             }
             b'*' => {
+                // 如果下一个为 - 则获取下一行，为 -1 时返回空数组
+                if b'-' == peek_u8(src)? {
+                    let line = get_line(src)?;
+                    if line != b"-1" {
+                        return Err("protocol error; invalid frame format".into());
+                    }
+                    return Ok(Frame::NullArray);
+                }
+
                 // 获取数字，并 new 数组，并递归继续转换帧。
+                // 每个元素至少占 3 个字节（例如 "+\r\n"），按剩余的数据限制预分配的大小，
+                // 长度前缀再大也不会先分配一个巨大的数组
                 let len = get_decimal(src)?.try_into()?;
-                let mut out = Vec::with_capacity(len);
+                let mut out = Vec::with_capacity(std::cmp::min(len, src.remaining() / 3));
 
                 for _ in 0..len {
                     out.push(Frame::parse(src)?);
@@ -211,7 +248,7 @@ impl fmt::Display for Frame {
                 Ok(string) => string.fmt(fmt),
                 Err(_) => write!(fmt, "{:?}", msg),
             },
-            Frame::Null => "(nil)".fmt(fmt),
+            Frame::Null | Frame::NullArray => "(nil)".fmt(fmt),
             Frame::Array(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
@@ -267,7 +304,7 @@ fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
 fn get_i64(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
     use atoi::atoi;
     let line = get_line(src)?;
-    // 转为 u64
+    // 转为 i64
     atoi::<i64>(line).ok_or_else(|| "protocol error; invalid frame format".into())
 }
 
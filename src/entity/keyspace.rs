@@ -0,0 +1,159 @@
+use bytes::Bytes;
+use std::fmt;
+use std::str::FromStr;
+use crate::connect::SharedConfig;
+use crate::entity::PubSub;
+
+// 事件发布到 __keyspace@<db>__:<key>，消息为事件名
+const KEYSPACE: u16 = 1 << 0;
+// 事件发布到 __keyevent@<db>__:<event>，消息为 key
+const KEYEVENT: u16 = 1 << 1;
+
+/// 键空间事件的分类，对应 --notify-keyspace-events 中的字符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventClass {
+    // g：del、expire、persist、copy_to 等与类型无关的事件
+    Generic,
+    // $：字符串命令
+    String,
+    // l：列表命令
+    List,
+    // s：集合命令
+    Set,
+    // h：哈希命令
+    Hash,
+    // z：有序集合命令
+    ZSet,
+    // x：key 过期被删除
+    Expired,
+    // e：key 因 maxmemory 被淘汰
+    Evicted,
+}
+
+impl EventClass {
+    const ALL: [EventClass; 8] = [
+        EventClass::Generic,
+        EventClass::String,
+        EventClass::List,
+        EventClass::Set,
+        EventClass::Hash,
+        EventClass::ZSet,
+        EventClass::Expired,
+        EventClass::Evicted,
+    ];
+
+    fn flag(self) -> u16 {
+        match self {
+            EventClass::Generic => 1 << 2,
+            EventClass::String => 1 << 3,
+            EventClass::List => 1 << 4,
+            EventClass::Set => 1 << 5,
+            EventClass::Hash => 1 << 6,
+            EventClass::Expired => 1 << 7,
+            EventClass::Evicted => 1 << 8,
+            EventClass::ZSet => 1 << 9,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            EventClass::Generic => 'g',
+            EventClass::String => '$',
+            EventClass::List => 'l',
+            EventClass::Set => 's',
+            EventClass::Hash => 'h',
+            EventClass::ZSet => 'z',
+            EventClass::Expired => 'x',
+            EventClass::Evicted => 'e',
+        }
+    }
+}
+
+/// 需要发布的键空间事件，格式与 redis 的 notify-keyspace-events 相同，例如 "KEA"、"Ex"。
+/// 空字符串（默认）表示关闭
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyspaceEvents {
+    flags: u16,
+}
+
+impl KeyspaceEvents {
+    // 至少选择了一种频道和一类事件时才会发布
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.flags & (KEYSPACE | KEYEVENT) != 0 && self.flags & !(KEYSPACE | KEYEVENT) != 0
+    }
+
+    fn contains(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+impl FromStr for KeyspaceEvents {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<KeyspaceEvents, String> {
+        let mut flags = 0;
+        for c in s.chars() {
+            flags |= match c {
+                'K' => KEYSPACE,
+                'E' => KEYEVENT,
+                // A 是 g$lshzxe 的别名
+                'A' => EventClass::ALL.iter().map(|class| class.flag()).fold(0, |acc, flag| acc | flag),
+                c => EventClass::ALL
+                    .iter()
+                    .find(|class| class.symbol() == c)
+                    .map(|class| class.flag())
+                    .ok_or_else(|| format!("invalid keyspace events flag '{}'", c))?,
+            };
+        }
+        Ok(KeyspaceEvents { flags })
+    }
+}
+
+impl fmt::Display for KeyspaceEvents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = String::new();
+        if self.contains(KEYSPACE) {
+            s.push('K');
+        }
+        if self.contains(KEYEVENT) {
+            s.push('E');
+        }
+        for class in EventClass::ALL {
+            if self.contains(class.flag()) {
+                s.push(class.symbol());
+            }
+        }
+        f.write_str(&s)
+    }
+}
+
+/// 每个数据库发布键空间通知用的句柄，只持有 `PubSub` 与配置的克隆
+#[derive(Debug)]
+pub(crate) struct KeyspaceNotifier {
+    pubsub: PubSub,
+    config: SharedConfig,
+    db_index: usize,
+}
+
+impl KeyspaceNotifier {
+    pub(crate) fn new(pubsub: PubSub, config: SharedConfig, db_index: usize) -> KeyspaceNotifier {
+        KeyspaceNotifier { pubsub, config, db_index }
+    }
+
+    // 按配置把 key 上发生的 event 发布到键空间与键事件频道，每次发布时读取配置，
+    // 所以 CONFIG SET notify-keyspace-events 立即生效
+    pub(crate) fn notify(&self, class: EventClass, event: &str, key: &str) {
+        let events = self.config.read().unwrap().notify_keyspace_events;
+        if !events.contains(class.flag()) {
+            return;
+        }
+        if events.contains(KEYSPACE) {
+            let channel = format!("__keyspace@{}__:{}", self.db_index, key);
+            self.pubsub.publish(&channel, Bytes::from(event.to_string()));
+        }
+        if events.contains(KEYEVENT) {
+            let channel = format!("__keyevent@{}__:{}", self.db_index, event);
+            self.pubsub.publish(&channel, Bytes::from(key.to_string()));
+        }
+    }
+}
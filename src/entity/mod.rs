@@ -1,13 +1,28 @@
 pub mod frame;
 
-pub use frame::{Frame,Error};
+pub use frame::{Frame,Error,FrameLimits};
 
 pub mod db;
 
 pub use db::Db;
-pub use db::DbDropGuard;
+pub use db::{Expiry, SetCondition, SetOptions};
+
+pub mod databases;
+
+pub use databases::{Databases, DbDropGuard, MaxmemoryPolicy};
+
+pub mod pubsub;
+
+pub use pubsub::{PubSub, SlowClientPolicy};
+
+pub mod keyspace;
+
+pub use keyspace::KeyspaceEvents;
 
 pub mod parse;
 
 pub use parse::{Parse, ParseError};
 
+pub(crate) mod zset;
+
+pub(crate) use zset::{ScoreBound, SortedSet};
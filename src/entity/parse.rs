@@ -39,10 +39,12 @@ impl Parse {
         self.parts.next().ok_or(ParseError::EndOfStream)
     }
 
-    // 将下一个条目作为字符串返回。只能返回（Simple，Bulk）
+    // 将下一个条目作为字符串返回。只能返回（Simple，Bulk，整数）
     pub(crate) fn next_string(&mut self) -> Result<String, ParseError> {
         match self.next()? {
             Frame::Simple(s) => Ok(s),
+            Frame::USize(v) => Ok(v.to_string()),
+            Frame::Integer(v) => Ok(v.to_string()),
             Frame::Bulk(data) => str::from_utf8(&data[..])
                 .map(|s| s.to_string())
                 .map_err(|_| "protocol error; invalid string".into()),
@@ -53,6 +55,15 @@ impl Parse {
         }
     }
 
+    // 查看下一个条目的字符串形式但不消费它，用于按子命令分发
+    pub(crate) fn peek_string(&self) -> Option<String> {
+        match self.parts.as_slice().first()? {
+            Frame::Simple(s) => Some(s.clone()),
+            Frame::Bulk(data) => str::from_utf8(data).ok().map(|s| s.to_string()),
+            _ => None,
+        }
+    }
+
     /// 将下一个条目以 byte 形式返回。只能返回（Simple，Bulk）
     pub(crate) fn next_bytes(&mut self) -> Result<Bytes, ParseError> {
         match self.next()? {
@@ -74,6 +85,7 @@ impl Parse {
         match self.next()? {
             // An integer frame type is already stored as an integer.
             Frame::USize(v) => Ok(v),
+            Frame::Integer(v) => u64::try_from(v).map_err(|_| MSG.into()),
             // Simple and bulk frames must be parsed as integers. If the parsing
             // fails, an error is returned.
             Frame::Simple(data) => atoi::<u64>(data.as_bytes()).ok_or_else(|| MSG.into()),
@@ -90,6 +102,7 @@ impl Parse {
         match self.next()? {
             // An integer frame type is already stored as an integer.
             Frame::Integer(v) => Ok(v),
+            Frame::USize(v) => i64::try_from(v).map_err(|_| MSG.into()),
             // Simple and bulk frames must be parsed as integers. If the parsing
             // fails, an error is returned.
             Frame::Simple(data) => atoi::<i64>(data.as_bytes()).ok_or_else(|| MSG.into()),
@@ -97,6 +110,11 @@ impl Parse {
             frame => Err(format!("protocol error; expected int frame but got {:?}", frame).into()),
         }
     }
+    // 剩余未读取的帧个数
+    pub(crate) fn remaining(&self) -> usize {
+        self.parts.len()
+    }
+
     // 判断所有帧是否遍历完成
     pub(crate) fn finish(&mut self) -> Result<(), ParseError> {
         if self.parts.next().is_none() {
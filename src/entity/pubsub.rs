@@ -0,0 +1,83 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+// 每个频道的广播队列容量，订阅者落后超过该数量的消息时会丢失消息
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 订阅者读得太慢、广播队列中未读的消息被覆盖时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlowClientPolicy {
+    // 跳过丢失的消息并记录日志，连接继续接收之后的消息
+    #[default]
+    Skip,
+    // 回复错误后断开连接
+    Disconnect,
+}
+
+impl FromStr for SlowClientPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SlowClientPolicy, String> {
+        match &s.to_lowercase()[..] {
+            "skip" => Ok(SlowClientPolicy::Skip),
+            "disconnect" => Ok(SlowClientPolicy::Disconnect),
+            _ => Err(format!("unknown slow client policy '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for SlowClientPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SlowClientPolicy::Skip => "skip",
+            SlowClientPolicy::Disconnect => "disconnect",
+        };
+        fmt.write_str(name)
+    }
+}
+
+/// 发布/订阅的频道表，服务器内所有连接与数据库共享。
+/// 它不依赖 `Db`，所以 `Db` 可以持有它的克隆来发布键空间通知，而不会形成循环依赖
+#[derive(Debug, Clone, Default)]
+pub struct PubSub {
+    channels: Arc<Mutex<HashMap<String, broadcast::Sender<Bytes>>>>,
+}
+
+impl PubSub {
+    pub(crate) fn new() -> PubSub {
+        PubSub::default()
+    }
+
+    // 订阅频道，频道不存在时创建
+    pub(crate) fn subscribe(&self, channel: &str) -> broadcast::Receiver<Bytes> {
+        let mut channels = self.channels.lock().unwrap();
+        match channels.get(channel) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                channels.insert(channel.to_string(), sender);
+                receiver
+            }
+        }
+    }
+
+    // 向频道发布消息，返回收到消息的订阅者个数。已经没有订阅者的频道会被删除
+    pub(crate) fn publish(&self, channel: &str, message: Bytes) -> usize {
+        let mut channels = self.channels.lock().unwrap();
+        let sent = match channels.get(channel) {
+            Some(sender) => sender.send(message),
+            None => return 0,
+        };
+        match sent {
+            Ok(receivers) => receivers,
+            Err(_) => {
+                channels.remove(channel);
+                0
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+/// 可以排序的分数。有序集合中的分数不会是 NaN，按 f64::total_cmp 比较
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct OrderedScore(pub(crate) f64);
+
+impl PartialEq for OrderedScore {
+    fn eq(&self, other: &OrderedScore) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedScore {}
+
+impl PartialOrd for OrderedScore {
+    fn partial_cmp(&self, other: &OrderedScore) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedScore {
+    fn cmp(&self, other: &OrderedScore) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// 分数区间的一端，exclusive 时不包含 score 本身（ZRANGEBYSCORE 中的 "(5"）
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScoreBound {
+    pub(crate) score: f64,
+
+    pub(crate) exclusive: bool,
+}
+
+impl ScoreBound {
+    // score 是否在这个下界之上
+    fn above(&self, score: f64) -> bool {
+        if self.exclusive { score > self.score } else { score >= self.score }
+    }
+
+    // score 是否在这个上界之下
+    fn below(&self, score: f64) -> bool {
+        if self.exclusive { score < self.score } else { score <= self.score }
+    }
+}
+
+/// 有序集合：按成员查找分数的哈希表，以及按 (分数, 成员) 排序的集合。
+/// 两个索引只在这里修改，始终包含相同的成员；分数相同的成员按字节序排列，与 redis 一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct SortedSet {
+    scores: HashMap<Bytes, f64>,
+
+    ordered: BTreeSet<(OrderedScore, Bytes)>,
+}
+
+impl SortedSet {
+    pub(crate) fn new() -> SortedSet {
+        SortedSet::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // 设置成员的分数，返回原来的分数，成员原来不存在时返回 None
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> Option<f64> {
+        // -0 与 0 是同一个分数，统一保存为 0，避免 total_cmp 把它们排成两个位置
+        let score = if score == 0.0 { 0.0 } else { score };
+        let prev = self.scores.insert(member.clone(), score);
+        if let Some(prev) = prev {
+            self.ordered.remove(&(OrderedScore(prev), member.clone()));
+        }
+        self.ordered.insert((OrderedScore(score), member));
+        prev
+    }
+
+    // 删除成员，返回它的分数
+    pub(crate) fn remove(&mut self, member: &[u8]) -> Option<f64> {
+        let (member, score) = self.scores.remove_entry(member)?;
+        self.ordered.remove(&(OrderedScore(score), member));
+        Some(score)
+    }
+
+    // 按分数从低到高遍历成员与分数
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = (&Bytes, f64)> {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+
+    // 按分数从低到高返回分数在 min 与 max 之间的成员与分数，跳过前 offset 个，最多返回 count 个（None 时不限制）。
+    // 从有序索引中第一个分数不小于 min 的位置开始查找，不需要遍历分数更低的成员
+    pub(crate) fn range_by_score(
+        &self,
+        min: ScoreBound,
+        max: ScoreBound,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(Bytes, f64)> {
+        // 空成员在分数相同的成员中排在最前面，所以 (min, "") 是分数等于 min 的第一个位置
+        let start = (OrderedScore(min.score), Bytes::new());
+        self.ordered
+            .range(start..)
+            .map(|(score, member)| (member, score.0))
+            .skip_while(|&(_, score)| !min.above(score))
+            .take_while(|&(_, score)| max.below(score))
+            .skip(offset)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(member, score)| (member.clone(), score))
+            .collect()
+    }
+
+    // 返回下标在 [start, stop] 之间的成员与分数，下标的含义与 LRANGE 相同：负数从末尾开始计数，
+    // 超出范围的部分被截断。rev 时下标 0 是分数最高的成员
+    pub(crate) fn range_by_index(&self, start: i64, stop: i64, rev: bool) -> Vec<(Bytes, f64)> {
+        let len = self.len() as i64;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop || start >= len {
+            return vec![];
+        }
+
+        let (skip, take) = (start as usize, (stop - start + 1) as usize);
+        let members: Box<dyn Iterator<Item = (&Bytes, f64)>> = if rev {
+            Box::new(self.iter().rev())
+        } else {
+            Box::new(self.iter())
+        };
+        members.skip(skip).take(take).map(|(member, score)| (member.clone(), score)).collect()
+    }
+}
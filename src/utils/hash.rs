@@ -0,0 +1,13 @@
+// CRC32（IEEE 802.3 多项式），用于客户端分片时计算 key 的哈希值
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            // 最低位为 1 时与多项式异或
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -1 +1,3 @@
-pub mod serialization;
\ No newline at end of file
+pub mod serialization;
+
+pub mod hash;
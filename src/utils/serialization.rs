@@ -6,8 +6,14 @@ use std::str::{self, Utf8Error};
 use std::num::ParseIntError;
 use crate::entity::Frame::Error;
 
-pub fn string_to_bytes(s: &str) -> Bytes {
-    Bytes::from(bincode::serialize(s).unwrap())
+// DUMP 格式的版本，写在序列化数据的最后一个字节，格式变化时递增
+const DUMP_VERSION: u8 = 1;
+
+// 序列化数据损坏或版本不符时的错误
+const DUMP_PAYLOAD_ERROR: &str = "ERR DUMP payload version or checksum are wrong";
+
+pub fn string_to_bytes(s: &str) -> crate::Result<Bytes> {
+    Ok(Bytes::from(bincode::serialize(s)?))
 }
 
 // pub fn linked_list_to_bytes(list: &LinkedList<Bytes>) -> Bytes {
@@ -23,20 +29,35 @@ pub fn string_to_bytes(s: &str) -> Bytes {
 // }
 //
 
-pub fn bytes_to_string(bytes: &Bytes) -> String {
-    bincode::deserialize(bytes).unwrap()
+pub fn bytes_to_string(bytes: &Bytes) -> crate::Result<String> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+pub fn bytes_to_linked_list<T: DeserializeOwned>(bytes: &Bytes) -> crate::Result<LinkedList<T>> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+pub fn bytes_to_hash_set<T: DeserializeOwned + std::cmp::Eq + std::hash::Hash>(bytes: &Bytes) -> crate::Result<HashSet<T>> {
+    Ok(bincode::deserialize(bytes)?)
 }
 
-pub fn bytes_to_linked_list<T: DeserializeOwned>(bytes: &Bytes) -> LinkedList<T> {
-    bincode::deserialize(bytes).unwrap()
+pub fn bytes_to_hash_map<K: DeserializeOwned + std::cmp::Eq + std::hash::Hash, V: DeserializeOwned>(bytes: &Bytes) -> crate::Result<HashMap<K, V>> {
+    Ok(bincode::deserialize(bytes)?)
 }
 
-pub fn bytes_to_hash_set<T: DeserializeOwned + std::cmp::Eq + std::hash::Hash>(bytes: &Bytes) -> HashSet<T> {
-    bincode::deserialize(bytes).unwrap()
+// DUMP 的序列化格式：bincode 编码的值，最后加一个版本字节
+pub(crate) fn dump<T: Serialize>(value: &T) -> crate::Result<Bytes> {
+    let mut payload = bincode::serialize(value)?;
+    payload.push(DUMP_VERSION);
+    Ok(Bytes::from(payload))
 }
 
-pub fn bytes_to_hash_map<K: DeserializeOwned + std::cmp::Eq + std::hash::Hash, V: DeserializeOwned>(bytes: &Bytes) -> HashMap<K, V> {
-    bincode::deserialize(bytes).unwrap()
+// 反序列化 DUMP 的结果，版本不符或数据损坏时返回错误
+pub(crate) fn restore<T: DeserializeOwned>(payload: &[u8]) -> crate::Result<T> {
+    match payload.split_last() {
+        Some((&DUMP_VERSION, value)) => bincode::deserialize(value).map_err(|_| DUMP_PAYLOAD_ERROR.into()),
+        _ => Err(DUMP_PAYLOAD_ERROR.into()),
+    }
 }
 
 // 将 i64 转换为 Bytes
@@ -54,59 +75,63 @@ pub fn f64_to_bytes(value: f64) -> Bytes {
 
 use crate::entity::ParseError;
 
-// 将 Bytes 转换回 i64
-pub fn bytes_to_i64(bytes: Bytes) -> Result<i64, Box<dyn std::error::Error>> {
-    let num_str = str::from_utf8(&bytes)?;
-    let num = num_str.parse::<i64>()?;
-    Ok(num)
+// 将十进制字符串表示的 Bytes 转换回 i64，不是整数或超出范围时返回错误
+pub fn bytes_to_i64(bytes: Bytes) -> crate::Result<i64> {
+    str::from_utf8(&bytes)
+        .ok()
+        .and_then(|num| num.parse::<i64>().ok())
+        .ok_or_else(|| "ERR value is not an integer or out of range".into())
 }
 
 
-// 将 Bytes 转换回 f64
-pub fn bytes_to_f64(bytes: Bytes) -> f64 {
+// 将 f64_to_bytes 的结果转换回 f64，长度不是 8 字节时返回错误
+pub fn bytes_to_f64(bytes: Bytes) -> crate::Result<f64> {
+    if bytes.len() != 8 {
+        return Err("ERR value is not a valid float".into());
+    }
     let mut buf = bytes;
-    buf.get_f64()
+    Ok(buf.get_f64())
 }
 
-pub(crate) fn list_to_bytes(list: &LinkedList<Bytes>) -> Bytes {
-    let mut result = String::from("[");
-    for (i, bytes) in list.iter().enumerate() {
-        if i > 0 {
-            result.push(',');
-        }
-        if let Ok(s) = str::from_utf8(bytes) {
-            result.push_str(s);
-        }
-    }
-    result.push(']');
+// 解析有序集合的分数，接受 inf、+inf、-inf，不接受 NaN
+pub fn bytes_to_score(bytes: &[u8]) -> crate::Result<f64> {
+    str::from_utf8(bytes)
+        .ok()
+        .and_then(|num| num.parse::<f64>().ok())
+        .filter(|score| !score.is_nan())
+        .ok_or_else(|| "ERR value is not a valid float".into())
+}
 
-    Bytes::from(result)
+/// 按 redis 的规则把分数格式化为字符串：整数不带小数点（1 而不是 1.0），无穷大为 inf 与 -inf。
+/// 与 %.17g 一样，十进制指数在 [-4, 17) 之外时使用科学计数法（1e+20、1e-05），
+/// 有效数字取能精确还原这个 f64 的最短写法，所以格式化后再解析得到的是同一个分数
+pub fn fmt_score(score: f64) -> String {
+    if score.is_infinite() {
+        return if score > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    // {:e} 给出最短的有效数字与十进制指数，例如 1.5e-5
+    let scientific = format!("{:e}", score);
+    let (mantissa, exponent) = scientific.split_once('e').expect("{:e} always has an exponent");
+    let exponent: i32 = exponent.parse().expect("{:e} exponent is an integer");
+    if (-4..17).contains(&exponent) {
+        format!("{}", score)
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
 }
 
+// 集合格式化为 {a,b,c}，元素按原样输出，不要求是 UTF-8
 pub(crate) fn btree_to_bytes(collection: &BTreeSet<Bytes>) -> Bytes {
-    let mut result = String::from("{");
+    let mut result = BytesMut::from(&b"{"[..]);
     for (i, item) in collection.iter().enumerate() {
         if i > 0 {
-            result.push(',');
+            result.extend_from_slice(b",");
         }
-        result.push_str(str::from_utf8(item.as_ref()).unwrap());
+        result.extend_from_slice(item);
     }
-    result.push('}');
+    result.extend_from_slice(b"}");
 
-    Bytes::from(result)
+    result.freeze()
 }
 
-pub(crate) fn map_to_bytes(map: &HashMap<Bytes, Bytes>) -> Bytes {
-    let mut result = String::from("{");
-    for (i, (key, value)) in map.iter().enumerate() {
-        if i > 0 {
-            result.push(',');
-        }
-        result.push_str(str::from_utf8(key.as_ref()).unwrap());
-        result.push(':');
-        result.push_str(str::from_utf8(value.as_ref()).unwrap());
-    }
-    result.push('}');
-
-    Bytes::from(result)
-}
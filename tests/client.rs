@@ -0,0 +1,3022 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use bytes::Bytes;
+use nano_redis::cmd::COMMANDS;
+use nano_redis::connect::{server_start, Client, Connection, HashRing, ReadOutcome, ServerConfig, ShardedClient};
+use nano_redis::entity::{DbDropGuard, Expiry, Frame, SetCondition, SetOptions, SlowClientPolicy};
+use nano_redis::utils::hash::crc32;
+use nano_redis::utils::serialization::fmt_score;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{self, Instant};
+
+// 以给定配置在随机端口上启动服务器，返回它绑定的地址
+async fn start_server(config: ServerConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        server_start::run(listener, None, config, std::future::pending::<()>()).await
+    });
+
+    addr
+}
+
+async fn connect() -> Client {
+    let addr = start_server(ServerConfig::default()).await;
+    Client::connect(addr).await.unwrap()
+}
+
+// 直接收发帧的连接，用于 SUBSCRIBE 之后服务器主动推送的消息
+async fn raw_connect(addr: SocketAddr) -> Connection {
+    Connection::new(TcpStream::connect(addr).await.unwrap())
+}
+
+async fn send(connection: &mut Connection, args: &[&str]) {
+    let frame = Frame::Array(args.iter().map(|arg| Frame::Bulk(Bytes::from(arg.to_string()))).collect());
+    connection.write_frame(&frame).await.unwrap();
+}
+
+// 发送一条命令，断言服务器回复的原始字节与 expected 完全相同
+async fn assert_raw_reply(stream: &mut TcpStream, args: &[&str], expected: &[u8]) {
+    let mut request = format!("*{}\r\n", args.len());
+    for arg in args {
+        request.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = vec![0; expected.len()];
+    time::timeout(Duration::from_secs(1), stream.read_exact(&mut response)).await.unwrap().unwrap();
+    assert_eq!(String::from_utf8_lossy(expected), String::from_utf8_lossy(&response));
+}
+
+// 读取一个数组帧，元素转换为字符串，1 秒内没有收到时 panic
+async fn read_array(connection: &mut Connection) -> Vec<String> {
+    let frame = time::timeout(Duration::from_secs(1), connection.read_frame())
+        .await
+        .expect("timed out waiting for a frame")
+        .unwrap()
+        .unwrap();
+    match frame {
+        Frame::Array(parts) => parts
+            .into_iter()
+            .map(|part| match part {
+                Frame::Bulk(bytes) => String::from_utf8(bytes.to_vec()).unwrap(),
+                Frame::Integer(value) => value.to_string(),
+                Frame::Null => "(nil)".to_string(),
+                frame => panic!("unexpected element {:?}", frame),
+            })
+            .collect(),
+        frame => panic!("expected an array, got {:?}", frame),
+    }
+}
+
+#[tokio::test]
+async fn set_and_get() {
+    let mut client = connect().await;
+
+    client.set("hello", "world".into(), None).await.unwrap();
+    assert_eq!(Some(Bytes::from("world")), client.get("hello").await.unwrap());
+    assert_eq!(None, client.get("missing").await.unwrap());
+}
+
+#[tokio::test]
+async fn incrby() {
+    let mut client = connect().await;
+
+    client.set("counter", "10".into(), None).await.unwrap();
+    client.incrby("counter", 5).await.unwrap();
+    client.incrby("counter", -20).await.unwrap();
+    assert_eq!(Some(Bytes::from("-5")), client.get("counter").await.unwrap());
+
+    client.set("text", "abc".into(), None).await.unwrap();
+    assert!(client.incrby("text", 1).await.is_err());
+
+    client.set("max", i64::MAX.to_string().into(), None).await.unwrap();
+    assert!(client.incrby("max", 1).await.is_err());
+    assert_eq!(Some(Bytes::from(i64::MAX.to_string())), client.get("max").await.unwrap());
+}
+
+#[tokio::test]
+async fn push_returns_list_length() {
+    let mut client = connect().await;
+
+    assert_eq!(2, client.push("list", vec!["a".into(), "b".into()], true).await.unwrap());
+    assert_eq!(3, client.push("list", vec!["c".into()], true).await.unwrap());
+    assert_eq!(4, client.push("list", vec!["z".into()], false).await.unwrap());
+    assert_eq!(Some(Bytes::from("[z,a,b,c]")), client.lrange("list", 0, 10).await.unwrap());
+}
+
+#[tokio::test]
+async fn pushx_only_pushes_to_existing_lists() {
+    let mut client = connect().await;
+
+    // key 不存在时什么也不做
+    assert_eq!(0, client.pushx("list", vec!["a".into()], true).await.unwrap());
+    assert_eq!(0, client.pushx("list", vec!["a".into()], false).await.unwrap());
+    assert_eq!(None, client.lrange("list", 0, 10).await.unwrap());
+
+    client.push("list", vec!["b".into()], true).await.unwrap();
+    assert_eq!(3, client.pushx("list", vec!["c".into(), "d".into()], true).await.unwrap());
+    assert_eq!(5, client.pushx("list", vec!["a".into(), "z".into()], false).await.unwrap());
+    assert_eq!(Some(Bytes::from("[z,a,b,c,d]")), client.lrange("list", 0, 10).await.unwrap());
+
+    client.set("string", "value".into(), None).await.unwrap();
+    let err = client.pushx("string", vec!["a".into()], true).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+}
+
+#[tokio::test]
+async fn pop_from_both_ends() {
+    let mut client = connect().await;
+
+    client.push("list", vec!["a".into(), "b".into(), "c".into()], true).await.unwrap();
+    assert_eq!(Some(Bytes::from("a")), client.pop("list", false).await.unwrap());
+    assert_eq!(Some(Bytes::from("c")), client.pop("list", true).await.unwrap());
+    assert_eq!(None, client.pop("missing", false).await.unwrap());
+
+    client.push("many", vec!["1".into(), "2".into(), "3".into()], true).await.unwrap();
+    assert_eq!(
+        Some(vec![Bytes::from("1"), Bytes::from("2")]),
+        client.pop_count("many", false, 2).await.unwrap()
+    );
+    assert_eq!(None, client.pop_count("missing", false, 2).await.unwrap());
+}
+
+#[tokio::test]
+async fn pop_last_element_deletes_key() {
+    let mut client = connect().await;
+
+    client.push("list", vec!["only".into()], true).await.unwrap();
+    assert_eq!(Some(Bytes::from("only")), client.pop("list", true).await.unwrap());
+    assert_eq!(0, client.touch(vec!["list".into()]).await.unwrap());
+}
+
+#[tokio::test]
+async fn wait_for_list_wakes_on_push() {
+    let guard = DbDropGuard::new(ServerConfig::default().shared());
+    let databases = guard.databases();
+
+    // 等待者在列表为空时注册，PUSH 之后被唤醒并弹出数据
+    let waiter = {
+        let databases = databases.clone();
+        tokio::spawn(async move {
+            let db = databases.db(0);
+            loop {
+                if let Some(values) = db.pop("list", false, 1).unwrap() {
+                    return values;
+                }
+                db.wait_for_list("list").await;
+            }
+        })
+    };
+    let db = databases.db(0);
+    time::timeout(Duration::from_secs(1), async {
+        while !db.has_list_waiters("list") {
+            time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    db.push("list".to_string(), vec!["a".to_string()], true).unwrap();
+    let values = time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+    assert_eq!(vec![Bytes::from("a")], values);
+    // 最后一个等待者离开后删除 key 对应的表项
+    assert!(!db.has_list_waiters("list"));
+
+    // 列表非空时立即返回，不注册
+    db.push("list".to_string(), vec!["b".to_string()], true).unwrap();
+    time::timeout(Duration::from_millis(100), db.wait_for_list("list")).await.unwrap();
+    assert!(!db.has_list_waiters("list"));
+
+    // 被取消的等待者同样清理表项
+    assert!(time::timeout(Duration::from_millis(20), db.wait_for_list("other")).await.is_err());
+    assert!(!db.has_list_waiters("other"));
+}
+
+#[tokio::test]
+async fn set_operations() {
+    let mut client = connect().await;
+
+    client.sadd("s1", vec!["a".into(), "b".into(), "c".into()]).await.unwrap();
+    client.sadd("s2", vec!["b".into(), "c".into(), "d".into()]).await.unwrap();
+    assert_eq!(3, client.scard("s1".into()).await.unwrap());
+
+    assert_eq!(
+        Some(Bytes::from("{b,c}")),
+        client.sinter(vec!["s1".into(), "s2".into()]).await.unwrap()
+    );
+
+    client.srem("s1".into(), vec!["a".into()]).await.unwrap();
+    assert_eq!(2, client.scard("s1".into()).await.unwrap());
+    assert_eq!(0, client.scard("missing".into()).await.unwrap());
+    assert!(client.sismember("s1".into(), "b".into()).await.unwrap());
+    assert!(!client.sismember("s1".into(), "a".into()).await.unwrap());
+}
+
+#[tokio::test]
+async fn scard_replies_with_integer() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    assert_raw_reply(&mut stream, &["scard", "missing"], b":0\r\n").await;
+    assert_raw_reply(&mut stream, &["sadd", "set", "a", "b", "c"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["scard", "set"], b":3\r\n").await;
+    assert_raw_reply(&mut stream, &["set", "string", "value"], b"+OK\r\n").await;
+    assert_raw_reply(
+        &mut stream,
+        &["scard", "string"],
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+    )
+    .await;
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(3, client.scard("set".into()).await.unwrap());
+    assert!(client.scard("string".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn sismember_and_push_reply_with_integers() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    assert_raw_reply(&mut stream, &["sadd", "set", "a"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["sismember", "set", "a"], b":1\r\n").await;
+    assert_raw_reply(&mut stream, &["sismember", "set", "b"], b":0\r\n").await;
+    assert_raw_reply(&mut stream, &["sismember", "missing", "a"], b":0\r\n").await;
+
+    // PUSH 回复插入后列表的长度
+    assert_raw_reply(&mut stream, &["push", "list", "L", "1", "x"], b":1\r\n").await;
+    assert_raw_reply(&mut stream, &["push", "list", "R", "2", "y", "z"], b":3\r\n").await;
+}
+
+#[tokio::test]
+async fn wrong_type() {
+    let mut client = connect().await;
+
+    client.set("string", "value".into(), None).await.unwrap();
+    let err = client.push("string", vec!["a".into()], true).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"));
+    let err = client.sadd("string", vec!["a".into()]).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"));
+}
+
+#[tokio::test]
+async fn debug_sleep_requires_enable_debug() {
+    let mut client = connect().await;
+
+    assert!(client.debug_sleep(Duration::from_millis(10)).await.is_err());
+}
+
+#[tokio::test]
+async fn debug_sleep() {
+    let config = ServerConfig { enable_debug: true, ..ServerConfig::default() };
+    let mut client = Client::connect(start_server(config).await).await.unwrap();
+
+    let start = Instant::now();
+    client.debug_sleep(Duration::from_millis(100)).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn client_id_and_name() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut first = Client::connect(addr).await.unwrap();
+    let mut second = Client::connect(addr).await.unwrap();
+
+    assert_ne!(first.client_id().await.unwrap(), second.client_id().await.unwrap());
+
+    assert_eq!(None, first.client_getname().await.unwrap());
+    first.client_setname("worker-1").await.unwrap();
+    assert_eq!(Some(Bytes::from("worker-1")), first.client_getname().await.unwrap());
+    assert_eq!(None, second.client_getname().await.unwrap());
+
+    assert!(first.client_setname("has space").await.is_err());
+    first.client_setname("").await.unwrap();
+    assert_eq!(None, first.client_getname().await.unwrap());
+}
+
+#[tokio::test]
+async fn multi_exec() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    client.queue(vec!["set".into(), "b".into(), "2".into()]).await.unwrap();
+    client.queue(vec!["incrby".into(), "a".into(), "5".into()]).await.unwrap();
+    // EXEC 之前排队的命令不生效
+    assert_eq!(None, other.get("a").await.unwrap());
+
+    let replies = client.exec().await.unwrap().unwrap();
+    assert_eq!(3, replies.len());
+    assert_eq!(Some(Bytes::from("6")), other.get("a").await.unwrap());
+    assert_eq!(Some(Bytes::from("2")), other.get("b").await.unwrap());
+}
+
+#[tokio::test]
+async fn slow_reader_does_not_block_exec() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("big", Bytes::from(vec![b'x'; 1024 * 1024]), None).await.unwrap();
+
+    // 不读取回复的客户端：回复填满套接字缓冲区后，服务器写回复时会一直等待
+    let mut slow = TcpStream::connect(addr).await.unwrap();
+    let request = "*2\r\n$3\r\nget\r\n$3\r\nbig\r\n".repeat(64);
+    slow.write_all(request.as_bytes()).await.unwrap();
+    time::sleep(Duration::from_millis(200)).await;
+
+    time::timeout(Duration::from_secs(2), async {
+        client.multi().await.unwrap();
+        client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+        assert_eq!(1, client.exec().await.unwrap().unwrap().len());
+        assert_eq!(Some(Bytes::from("1")), client.get("a").await.unwrap());
+    })
+    .await
+    .expect("EXEC blocked by a client that does not read its replies");
+}
+
+// 流水线发送 count 个只包含 GET big 的事务，等待 delay 之后才读取回复，期间服务器写回复时会一直等待
+async fn exec_get_big_slowly(addr: SocketAddr, value_len: usize, count: usize, delay: Duration) {
+    let mut slow = TcpStream::connect(addr).await.unwrap();
+    let request = "*1\r\n$5\r\nmulti\r\n*2\r\n$3\r\nget\r\n$3\r\nbig\r\n*1\r\n$4\r\nexec\r\n".repeat(count);
+    slow.write_all(request.as_bytes()).await.unwrap();
+    time::sleep(delay).await;
+    let reply_len = "+OK\r\n+QUEUED\r\n*1\r\n".len() + format!("${}\r\n", value_len).len() + value_len + 2;
+    let mut replies = vec![0; reply_len * count];
+    time::timeout(Duration::from_secs(5), slow.read_exact(&mut replies)).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn command_stats_exclude_reply_write() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("big", Bytes::from(vec![b'x'; 1024 * 1024]), None).await.unwrap();
+
+    exec_get_big_slowly(addr, 1024 * 1024, 64, Duration::from_millis(300)).await;
+
+    let info = client.info(Some("commandstats".to_string())).await.unwrap();
+    let info = String::from_utf8_lossy(&info).to_string();
+    let line = info.lines().find(|line| line.starts_with("cmdstat_exec:")).unwrap();
+    let usec_max: u64 = line.rsplit("usec_max=").next().unwrap().parse().unwrap();
+    assert!(usec_max < 200_000, "{}", line);
+}
+
+#[tokio::test]
+async fn slowlog_excludes_reply_write() {
+    let config = ServerConfig {
+        slowlog_log_slower_than: 200_000,
+        ..ServerConfig::default()
+    };
+    let addr = start_server(config).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("big", Bytes::from(vec![b'x'; 1024 * 1024]), None).await.unwrap();
+
+    exec_get_big_slowly(addr, 1024 * 1024, 64, Duration::from_millis(300)).await;
+
+    assert_eq!(0, client.slowlog_len().await.unwrap());
+}
+
+#[tokio::test]
+async fn exec_reports_runtime_errors_per_command() {
+    let mut client = connect().await;
+
+    client.set("text", "abc".into(), None).await.unwrap();
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    client.queue(vec!["incrby".into(), "text".into(), "1".into()]).await.unwrap();
+
+    // 回复按排队顺序排列，出错的命令只影响自己的那一项
+    let replies = client.exec().await.unwrap().unwrap();
+    assert_eq!(2, replies.len());
+    assert!(matches!(&replies[0], Frame::Simple(ok) if ok == "OK"), "{:?}", replies);
+    assert!(matches!(&replies[1], Frame::Error(err) if err.contains("not an integer")), "{:?}", replies);
+    assert_eq!(Some(Bytes::from("1")), client.get("a").await.unwrap());
+
+    // 出错的命令在前时，后面的命令照常执行
+    client.multi().await.unwrap();
+    client.queue(vec!["incrby".into(), "text".into(), "1".into()]).await.unwrap();
+    client.queue(vec!["set".into(), "b".into(), "2".into()]).await.unwrap();
+    let replies = client.exec().await.unwrap().unwrap();
+    assert!(matches!(replies[0], Frame::Error(_)), "{:?}", replies);
+    assert!(matches!(&replies[1], Frame::Simple(ok) if ok == "OK"), "{:?}", replies);
+    assert_eq!(Some(Bytes::from("2")), client.get("b").await.unwrap());
+}
+
+#[tokio::test]
+async fn queue_error_aborts_exec() {
+    let mut client = connect().await;
+
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    assert!(client.queue(vec!["nosuchcommand".into()]).await.is_err());
+
+    let err = client.exec().await.unwrap_err();
+    assert!(err.to_string().starts_with("EXECABORT"));
+    assert_eq!(None, client.get("a").await.unwrap());
+}
+
+#[tokio::test]
+async fn discard() {
+    let mut client = connect().await;
+
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    client.discard().await.unwrap();
+
+    assert_eq!(None, client.get("a").await.unwrap());
+    assert!(client.exec().await.is_err());
+    assert!(client.discard().await.is_err());
+}
+
+#[tokio::test]
+async fn watched_key_modified_aborts_exec() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.set("balance", "10".into(), None).await.unwrap();
+    client.watch(vec!["balance".into()]).await.unwrap();
+    client.multi().await.unwrap();
+    client.queue(vec!["incrby".into(), "balance".into(), "5".into()]).await.unwrap();
+
+    // 另一个连接在 WATCH 与 EXEC 之间修改了 key
+    other.set("balance", "100".into(), None).await.unwrap();
+
+    assert!(client.exec().await.unwrap().is_none());
+    assert_eq!(Some(Bytes::from("100")), client.get("balance").await.unwrap());
+}
+
+#[tokio::test]
+async fn watched_key_untouched_runs_exec() {
+    let mut client = connect().await;
+
+    client.set("balance", "10".into(), None).await.unwrap();
+    client.watch(vec!["balance".into(), "missing".into()]).await.unwrap();
+    client.multi().await.unwrap();
+    client.queue(vec!["incrby".into(), "balance".into(), "5".into()]).await.unwrap();
+
+    assert!(client.exec().await.unwrap().is_some());
+    assert_eq!(Some(Bytes::from("15")), client.get("balance").await.unwrap());
+}
+
+#[tokio::test]
+async fn watched_missing_key_created_and_deleted_aborts_exec() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    // key 在 WATCH 时不存在，EXEC 时也不存在，但中间被创建又删除过
+    for delete in [&["getdel", "key"][..], &["flushdb"][..]] {
+        client.watch(vec!["key".into()]).await.unwrap();
+        other.set("key", "temporary".into(), None).await.unwrap();
+        other.execute(delete.iter().map(|arg| Bytes::from(arg.to_string())).collect()).await.unwrap();
+
+        client.multi().await.unwrap();
+        client.queue(vec!["set".into(), "key".into(), "mine".into()]).await.unwrap();
+        assert!(client.exec().await.unwrap().is_none(), "{:?}", delete);
+        assert_eq!(None, client.get("key").await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn unwatch_forgets_watched_keys() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    client.watch(vec!["key".into()]).await.unwrap();
+    other.set("key", "changed".into(), None).await.unwrap();
+    client.unwatch().await.unwrap();
+
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "key".into(), "mine".into()]).await.unwrap();
+    assert!(client.exec().await.unwrap().is_some());
+    assert_eq!(Some(Bytes::from("mine")), client.get("key").await.unwrap());
+}
+
+#[tokio::test]
+async fn cas() {
+    let mut client = connect().await;
+
+    assert!(!client.cas("missing", "a".into(), "b".into()).await.unwrap());
+
+    client.set("key", "a".into(), None).await.unwrap();
+    assert!(!client.cas("key", "x".into(), "b".into()).await.unwrap());
+    assert!(client.cas("key", "a".into(), "b".into()).await.unwrap());
+    assert_eq!(Some(Bytes::from("b")), client.get("key").await.unwrap());
+
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    assert!(client.cas("list", "a".into(), "b".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn concurrent_cas_has_single_winner() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("lock", "free".into(), None).await.unwrap();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            tokio::spawn(async move {
+                let mut client = Client::connect(addr).await.unwrap();
+                client.cas("lock", "free".into(), Bytes::from(format!("owner-{}", i))).await.unwrap()
+            })
+        })
+        .collect();
+
+    let mut winners = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            winners += 1;
+        }
+    }
+    assert_eq!(1, winners);
+}
+
+#[tokio::test]
+async fn append() {
+    let mut client = connect().await;
+
+    assert_eq!(5, client.append("key", "hello".into()).await.unwrap());
+    client.set("ttl", "a".into(), Some(Duration::from_secs(100))).await.unwrap();
+    assert_eq!(11, client.append("key", " world".into()).await.unwrap());
+    assert_eq!(Some(Bytes::from("hello world")), client.get("key").await.unwrap());
+    // 追加保留原来的过期时间
+    assert_eq!(2, client.append("ttl", "b".into()).await.unwrap());
+    let ttl = client.ttl("ttl").await.unwrap();
+    assert!((99..=100).contains(&ttl), "{}", ttl);
+
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    assert!(client.append("list", "b".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn concurrent_appends_do_not_interleave() {
+    let addr = start_server(ServerConfig::default()).await;
+
+    let tasks: Vec<_> = (0..8u8)
+        .map(|i| {
+            tokio::spawn(async move {
+                let mut client = Client::connect(addr).await.unwrap();
+                let chunk = Bytes::from(vec![b'a' + i; 16]);
+                for _ in 0..50 {
+                    client.append("key", chunk.clone()).await.unwrap();
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    // 每次追加都完整地出现在结果中，没有丢失也没有被其他追加打断
+    let mut client = Client::connect(addr).await.unwrap();
+    let value = client.get("key").await.unwrap().unwrap();
+    assert_eq!(8 * 50 * 16, value.len());
+    for chunk in value.chunks(16) {
+        assert!(chunk.iter().all(|&byte| byte == chunk[0]), "{:?}", chunk);
+    }
+}
+
+#[tokio::test]
+async fn publish_and_subscribe() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut subscriber = raw_connect(addr).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    send(&mut subscriber, &["subscribe", "news", "sports"]).await;
+    assert_eq!(vec!["subscribe", "news", "1"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["subscribe", "sports", "2"], read_array(&mut subscriber).await);
+
+    assert_eq!(1, client.publish("news", "hello".into()).await.unwrap());
+    assert_eq!(0, client.publish("weather", "sunny".into()).await.unwrap());
+    assert_eq!(vec!["message", "news", "hello"], read_array(&mut subscriber).await);
+
+    send(&mut subscriber, &["unsubscribe", "news"]).await;
+    assert_eq!(vec!["unsubscribe", "news", "1"], read_array(&mut subscriber).await);
+
+    client.publish("news", "ignored".into()).await.unwrap();
+    client.publish("sports", "goal".into()).await.unwrap();
+    assert_eq!(vec!["message", "sports", "goal"], read_array(&mut subscriber).await);
+
+    send(&mut subscriber, &["unsubscribe"]).await;
+    assert_eq!(vec!["unsubscribe", "sports", "0"], read_array(&mut subscriber).await);
+}
+
+#[tokio::test]
+async fn ping_reply_shape_depends_on_subscribe_mode() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    assert_raw_reply(&mut stream, &["ping"], b"+PONG\r\n").await;
+    assert_raw_reply(&mut stream, &["ping", "hi"], b"$2\r\nhi\r\n").await;
+
+    // 订阅模式下与推送的消息一样回复数组，客户端据此区分 PING 的回复与消息
+    assert_raw_reply(&mut stream, &["subscribe", "news"], b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n").await;
+    assert_raw_reply(&mut stream, &["ping"], b"*2\r\n$4\r\npong\r\n$0\r\n\r\n").await;
+    assert_raw_reply(&mut stream, &["ping", "hi"], b"*2\r\n$4\r\npong\r\n$2\r\nhi\r\n").await;
+
+    assert_raw_reply(&mut stream, &["unsubscribe"], b"*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:0\r\n").await;
+    assert_raw_reply(&mut stream, &["ping"], b"+PONG\r\n").await;
+}
+
+#[tokio::test]
+async fn subscribe_mode_allows_only_pubsub_commands() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut subscriber = raw_connect(addr).await;
+
+    send(&mut subscriber, &["subscribe", "news"]).await;
+    assert_eq!(vec!["subscribe", "news", "1"], read_array(&mut subscriber).await);
+
+    send(&mut subscriber, &["ping"]).await;
+    assert_eq!(vec!["pong", ""], read_array(&mut subscriber).await);
+    send(&mut subscriber, &["ping", "hello"]).await;
+    assert_eq!(vec!["pong", "hello"], read_array(&mut subscriber).await);
+
+    send(&mut subscriber, &["get", "key"]).await;
+    let reply = subscriber.read_frame().await.unwrap().unwrap();
+    assert!(
+        matches!(&reply, Frame::Error(err) if err.contains("Can't execute 'get'") && err.contains("SUBSCRIBE")),
+        "{:?}",
+        reply
+    );
+
+    // 未知命令同样回复订阅模式的错误
+    send(&mut subscriber, &["foo", "bar"]).await;
+    let reply = subscriber.read_frame().await.unwrap().unwrap();
+    assert!(
+        matches!(&reply, Frame::Error(err) if err.contains("Can't execute 'foo'") && err.contains("SUBSCRIBE")),
+        "{:?}",
+        reply
+    );
+
+    // 退订所有频道后回到普通模式
+    send(&mut subscriber, &["unsubscribe"]).await;
+    assert_eq!(vec!["unsubscribe", "news", "0"], read_array(&mut subscriber).await);
+    send(&mut subscriber, &["ping"]).await;
+    assert!(subscriber.read_frame().await.unwrap().unwrap() == "PONG");
+    send(&mut subscriber, &["get", "key"]).await;
+    assert!(matches!(subscriber.read_frame().await.unwrap().unwrap(), Frame::Null));
+}
+
+#[tokio::test]
+async fn keyspace_notifications() {
+    let config = ServerConfig {
+        notify_keyspace_events: "KEA".parse().unwrap(),
+        ..ServerConfig::default()
+    };
+    let addr = start_server(config).await;
+    let mut subscriber = raw_connect(addr).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    send(&mut subscriber, &["subscribe", "__keyspace@0__:mykey", "__keyevent@0__:expired"]).await;
+    read_array(&mut subscriber).await;
+    read_array(&mut subscriber).await;
+
+    client.set("mykey", "1".into(), None).await.unwrap();
+    assert_eq!(vec!["message", "__keyspace@0__:mykey", "set"], read_array(&mut subscriber).await);
+
+    client.push("mykey2", vec!["a".into()], false).await.unwrap();
+    client.incrby("mykey", 1).await.unwrap();
+    assert_eq!(vec!["message", "__keyspace@0__:mykey", "incrby"], read_array(&mut subscriber).await);
+
+    client.set("temp", "1".into(), Some(Duration::from_millis(20))).await.unwrap();
+    assert_eq!(vec!["message", "__keyevent@0__:expired", "temp"], read_array(&mut subscriber).await);
+}
+
+#[tokio::test]
+async fn keyspace_notifications_disabled_by_default() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut subscriber = raw_connect(addr).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    send(&mut subscriber, &["subscribe", "__keyspace@0__:mykey", "done"]).await;
+    read_array(&mut subscriber).await;
+    read_array(&mut subscriber).await;
+
+    client.set("mykey", "1".into(), None).await.unwrap();
+    client.publish("done", "1".into()).await.unwrap();
+    assert_eq!(vec!["message", "done", "1"], read_array(&mut subscriber).await);
+}
+
+#[tokio::test]
+async fn wait_without_replicas() {
+    let mut client = connect().await;
+
+    let start = Instant::now();
+    assert_eq!(0, client.wait(0, Duration::from_millis(500)).await.unwrap());
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    let start = Instant::now();
+    assert_eq!(0, client.wait(1, Duration::from_millis(100)).await.unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+// 轮询 key 直到等于 expected，1 秒内没有等到时 panic
+async fn wait_for_value(client: &mut Client, key: &str, expected: Option<Bytes>) {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    loop {
+        let value = client.get(key).await.unwrap();
+        if value == expected {
+            return;
+        }
+        assert!(Instant::now() < deadline, "{} is {:?}, expected {:?}", key, value, expected);
+        time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn replicaof_syncs_snapshot_and_stream() {
+    let primary_addr = start_server(ServerConfig::default()).await;
+    let replica_addr = start_server(ServerConfig::default()).await;
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+
+    primary.set("before", "1".into(), None).await.unwrap();
+    primary.push("list", vec!["a".into(), "b".into()], true).await.unwrap();
+    replica.set("stale", "1".into(), None).await.unwrap();
+
+    replica.replicaof(Some(("127.0.0.1", primary_addr.port()))).await.unwrap();
+    wait_for_value(&mut replica, "before", Some("1".into())).await;
+    assert_eq!(None, replica.get("stale").await.unwrap());
+    assert_eq!(Some(Bytes::from("[a,b]")), replica.lrange("list", 0, 10).await.unwrap());
+
+    primary.set("after", "2".into(), None).await.unwrap();
+    primary.incrby("before", 10).await.unwrap();
+    wait_for_value(&mut replica, "after", Some("2".into())).await;
+    wait_for_value(&mut replica, "before", Some("11".into())).await;
+    assert_eq!(1, primary.wait(1, Duration::from_millis(100)).await.unwrap());
+
+    let err = replica.set("local", "1".into(), None).await.unwrap_err();
+    assert!(err.to_string().starts_with("READONLY"), "{}", err);
+
+    replica.replicaof(None).await.unwrap();
+    replica.set("local", "1".into(), None).await.unwrap();
+    assert_eq!(Some(Bytes::from("1")), replica.get("local").await.unwrap());
+}
+
+#[tokio::test]
+async fn slow_reader_does_not_block_propagated_writes() {
+    let primary_addr = start_server(ServerConfig::default()).await;
+    let replica_addr = start_server(ServerConfig::default()).await;
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    primary.set("big", Bytes::from(vec![b'x'; 1024 * 1024]), None).await.unwrap();
+    replica.replicaof(Some(("127.0.0.1", primary_addr.port()))).await.unwrap();
+    wait_for_value(&mut replica, "big", Some(Bytes::from(vec![b'x'; 1024 * 1024]))).await;
+
+    // GETEX 是要传播的写命令，不读取回复的客户端不能让其他写命令一直等待传播的锁
+    let mut slow = TcpStream::connect(primary_addr).await.unwrap();
+    let request = "*2\r\n$5\r\ngetex\r\n$3\r\nbig\r\n".repeat(64);
+    slow.write_all(request.as_bytes()).await.unwrap();
+    time::sleep(Duration::from_millis(200)).await;
+
+    time::timeout(Duration::from_secs(2), primary.set("a", "1".into(), None))
+        .await
+        .expect("write blocked by a client that does not read its replies")
+        .unwrap();
+    wait_for_value(&mut replica, "a", Some("1".into())).await;
+}
+
+#[tokio::test]
+async fn only_successful_writes_are_propagated() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("text", "value".into(), None).await.unwrap();
+
+    // 直接发送 SYNC 的连接，读取主节点转发给副本的命令
+    let mut replica = raw_connect(addr).await;
+    send(&mut replica, &["sync"]).await;
+    let reply = replica.read_frame().await.unwrap().unwrap();
+    assert!(reply == "FULLRESYNC", "{:?}", reply);
+    assert_eq!(vec!["select", "0"], read_array(&mut replica).await);
+    assert_eq!("set text", read_array(&mut replica).await[..2].join(" "));
+
+    // 出错的写命令不传播
+    assert!(client.incrby("text", 1).await.is_err());
+    // 事务中成功的写命令包在 MULTI/EXEC 中传播，出错的命令跳过
+    client.multi().await.unwrap();
+    client.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    client.queue(vec!["incrby".into(), "text".into(), "1".into()]).await.unwrap();
+    client.queue(vec!["set".into(), "b".into(), "2".into()]).await.unwrap();
+    client.exec().await.unwrap().unwrap();
+    // 没有成功写命令的事务不传播
+    client.multi().await.unwrap();
+    client.queue(vec!["incrby".into(), "text".into(), "1".into()]).await.unwrap();
+    client.exec().await.unwrap().unwrap();
+    client.set("end", "1".into(), None).await.unwrap();
+
+    let mut propagated = vec![];
+    loop {
+        let parts = read_array(&mut replica).await;
+        let command = parts[..parts.len().min(2)].join(" ");
+        if command == "set end" {
+            break;
+        }
+        propagated.push(command);
+    }
+    // 转发的第一条命令之前总有 SELECT
+    assert_eq!(vec!["select 0", "multi", "set a", "set b", "exec"], propagated);
+}
+
+#[tokio::test]
+async fn replica_applies_transactions() {
+    let primary_addr = start_server(ServerConfig::default()).await;
+    let replica_addr = start_server(ServerConfig::default()).await;
+    let mut primary = Client::connect(primary_addr).await.unwrap();
+    let mut replica = Client::connect(replica_addr).await.unwrap();
+    replica.replicaof(Some(("127.0.0.1", primary_addr.port()))).await.unwrap();
+    primary.set("ready", "1".into(), None).await.unwrap();
+    wait_for_value(&mut replica, "ready", Some("1".into())).await;
+
+    primary.multi().await.unwrap();
+    primary.queue(vec!["set".into(), "a".into(), "1".into()]).await.unwrap();
+    primary.queue(vec!["set".into(), "b".into(), "2".into()]).await.unwrap();
+    primary.exec().await.unwrap().unwrap();
+
+    wait_for_value(&mut replica, "b", Some("2".into())).await;
+    assert_eq!(Some(Bytes::from("1")), replica.get("a").await.unwrap());
+}
+
+// tests/tls 中的自签名证书（CN 与 SAN 均为 localhost），以及只信任它的客户端配置
+#[cfg(feature = "tls")]
+fn self_signed_tls() -> (nano_redis::connect::TlsConfig, std::sync::Arc<tokio_rustls::rustls::ClientConfig>) {
+    use std::path::Path;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/tls");
+    let server = nano_redis::connect::TlsConfig::from_pem_files(&dir.join("cert.pem"), &dir.join("key.pem")).unwrap();
+
+    let mut roots = RootCertStore::empty();
+    let pem = std::fs::read(dir.join("cert.pem")).unwrap();
+    for cert in rustls_pemfile::certs(&mut &pem[..]) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let client = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+    (server, std::sync::Arc::new(client))
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn tls_round_trip() {
+    let (tls, client_config) = self_signed_tls();
+    let addr = start_server(ServerConfig { tls: Some(tls), ..ServerConfig::default() }).await;
+
+    let mut client = Client::connect_tls_with_config(addr, "localhost", client_config.clone()).await.unwrap();
+    client.set("key", "value".into(), None).await.unwrap();
+    assert_eq!(Some(Bytes::from("value")), client.get("key").await.unwrap());
+
+    // 证书与 server_name 不符时握手失败
+    assert!(Client::connect_tls_with_config(addr, "example.com", client_config).await.is_err());
+    // 内置根证书不信任自签名证书
+    assert!(Client::connect_tls(addr, "localhost").await.is_err());
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn plain_tcp_to_tls_port_is_dropped() {
+    let (tls, client_config) = self_signed_tls();
+    let addr = start_server(ServerConfig { tls: Some(tls), ..ServerConfig::default() }).await;
+
+    // 明文请求不是 TLS 握手：服务器最多回复一个 TLS 警报，不执行命令，随后关闭连接
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"*1\r\n$4\r\nPING\r\n").await.unwrap();
+    let mut response = vec![];
+    let _ = time::timeout(Duration::from_secs(2), stream.read_to_end(&mut response))
+        .await
+        .expect("connection was not closed after a failed handshake");
+    assert!(!response.windows(4).any(|window| window == b"PONG"), "{:?}", response);
+
+    // 握手失败只影响这个连接，接收循环继续工作
+    let mut client = Client::connect_tls_with_config(addr, "localhost", client_config).await.unwrap();
+    assert_eq!(Bytes::from("PONG"), client.ping(None).await.unwrap());
+}
+
+#[test]
+fn server_binary_flags() {
+    use std::io::{Read, Write};
+    use std::process::{Command, Stdio};
+
+    let server = env!("CARGO_BIN_EXE_server");
+
+    // 连接数上限至少为 1，与 --databases 一样在解析参数时拒绝
+    let output = Command::new(server).args(["--max-connections", "0"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-connections"));
+
+    // --host 与 --port 决定监听的地址
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let mut child = Command::new(server)
+        .args(["--host", "127.0.0.1", "--port", &port.to_string(), "--max-connections", "1"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    let mut stream = loop {
+        match std::net::TcpStream::connect(("127.0.0.1", port)) {
+            Ok(stream) => break stream,
+            Err(err) if std::time::Instant::now() >= deadline => panic!("server did not start: {}", err),
+            Err(_) => std::thread::sleep(Duration::from_millis(20)),
+        }
+    };
+    stream.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    stream.write_all(b"*1\r\n$4\r\nPING\r\n").unwrap();
+    let mut response = [0; 7];
+    stream.read_exact(&mut response).unwrap();
+    assert_eq!(b"+PONG\r\n", &response);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn hash_ring_maps_keys_stably() {
+    assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+
+    let nodes = ["10.0.0.1:6379", "10.0.0.2:6379", "10.0.0.3:6379"];
+    let ring = HashRing::new(&nodes);
+    // 节点的位置只取决于地址，key 的归属在不同进程与版本之间保持不变
+    for (key, node) in [("user:1", 1), ("user:2", 0), ("user:3", 2), ("order:42", 2), ("session:abc", 1), ("foo", 1)] {
+        assert_eq!(node, ring.node(key), "{}", key);
+    }
+
+    // 去掉一个节点后，只有原来属于它的 key 改变归属
+    let remaining = [nodes[0], nodes[2]];
+    let smaller = HashRing::new(&remaining);
+    let mut moved = 0;
+    for i in 0..3000 {
+        let key = format!("key:{}", i);
+        let before = nodes[ring.node(&key)];
+        let after = remaining[smaller.node(&key)];
+        if before == nodes[1] {
+            moved += 1;
+        } else {
+            assert_eq!(before, after, "{}", key);
+        }
+    }
+    assert_eq!(997, moved);
+}
+
+#[tokio::test]
+async fn sharded_client_routes_keys() {
+    let addrs = [
+        start_server(ServerConfig::default()).await.to_string(),
+        start_server(ServerConfig::default()).await.to_string(),
+    ];
+    let mut sharded = ShardedClient::connect(&addrs).await.unwrap();
+    let mut direct = [Client::connect(&addrs[0]).await.unwrap(), Client::connect(&addrs[1]).await.unwrap()];
+
+    let keys: Vec<String> = (0..200).map(|i| format!("key:{}", i)).collect();
+    for key in &keys {
+        sharded.set(key, key.clone().into(), None).await.unwrap();
+    }
+    let mut per_shard = [0; 2];
+    for key in &keys {
+        assert_eq!(Some(Bytes::from(key.clone())), sharded.get(key).await.unwrap());
+        // key 只存在于它所在的服务器上
+        let index = sharded.shard_index(key);
+        per_shard[index] += 1;
+        assert_eq!(Some(Bytes::from(key.clone())), direct[index].get(key).await.unwrap());
+        assert_eq!(None, direct[1 - index].get(key).await.unwrap());
+    }
+    assert!(per_shard.iter().all(|count| *count > 0), "{:?}", per_shard);
+}
+
+#[tokio::test]
+async fn dump_and_restore() {
+    let mut source = connect().await;
+    let mut target = connect().await;
+
+    source.set("string", "hello".into(), None).await.unwrap();
+    source.push("list", vec!["a".into(), "b".into()], true).await.unwrap();
+    source.sadd("set", vec!["x".into(), "y".into()]).await.unwrap();
+    assert_eq!(None, source.dump("missing").await.unwrap());
+
+    for key in ["string", "list", "set"] {
+        let payload = source.dump(key).await.unwrap().unwrap();
+        target.restore(key, None, payload, false).await.unwrap();
+    }
+    assert_eq!(Some(Bytes::from("hello")), target.get("string").await.unwrap());
+    assert_eq!(Some(Bytes::from("[a,b]")), target.lrange("list", 0, 10).await.unwrap());
+    assert_eq!(vec![Bytes::from("x"), Bytes::from("y")], target.smembers("set".into()).await.unwrap());
+
+    let payload = source.dump("string").await.unwrap().unwrap();
+    let err = target.restore("string", None, payload.clone(), false).await.unwrap_err();
+    assert!(err.to_string().starts_with("BUSYKEY"), "{}", err);
+    target.restore("list", Some(Duration::from_millis(20)), payload, true).await.unwrap();
+    assert_eq!(Some(Bytes::from("hello")), target.get("list").await.unwrap());
+    time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(None, target.get("list").await.unwrap());
+
+    let err = target.restore("corrupt", None, "not a dump".into(), false).await.unwrap_err();
+    assert!(err.to_string().contains("DUMP payload"), "{}", err);
+    target.restore("corrupt", None, Bytes::new(), false).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn copy_list_with_ttl() {
+    let mut client = connect().await;
+
+    // 带过期时间的列表：DUMP 后以 RESTORE 的 ttl 重建
+    client.push("src", vec!["a".into(), "b".into()], true).await.unwrap();
+    let payload = client.dump("src").await.unwrap().unwrap();
+    client.restore("src", Some(Duration::from_secs(100)), payload, true).await.unwrap();
+
+    assert!(client.copy("src", "dst", false).await.unwrap());
+    assert_eq!(Some(Bytes::from("[a,b]")), client.lrange("dst", 0, 10).await.unwrap());
+    let ttl = client.ttl("dst").await.unwrap();
+    assert!((99..=100).contains(&ttl), "{}", ttl);
+
+    // 复制的是独立的值，修改源不影响目标
+    client.push("src", vec!["c".into()], true).await.unwrap();
+    assert_eq!(Some(Bytes::from("[a,b]")), client.lrange("dst", 0, 10).await.unwrap());
+
+    // 目标已存在时只有 REPLACE 才覆盖
+    client.set("other", "x".into(), None).await.unwrap();
+    assert!(!client.copy("src", "other", false).await.unwrap());
+    assert_eq!(Some(Bytes::from("x")), client.get("other").await.unwrap());
+    assert!(client.copy("src", "other", true).await.unwrap());
+    assert_eq!(Some(Bytes::from("[a,b,c]")), client.lrange("other", 0, 10).await.unwrap());
+
+    // 源不存在时返回 false
+    assert!(!client.copy("missing", "dst2", false).await.unwrap());
+}
+
+#[tokio::test]
+async fn memory_usage() {
+    let mut client = connect().await;
+
+    assert_eq!(None, client.memory_usage("missing").await.unwrap());
+
+    client.set("short", "a".into(), None).await.unwrap();
+    client.set("long", "a".repeat(1000).into(), None).await.unwrap();
+    let short = client.memory_usage("short").await.unwrap().unwrap();
+    let long = client.memory_usage("long").await.unwrap().unwrap();
+    // 值长 999 字节，key 短 1 字节
+    assert_eq!(998, long - short);
+
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    let one = client.memory_usage("list").await.unwrap().unwrap();
+    client.push("list", vec!["b".into()], true).await.unwrap();
+    let two = client.memory_usage("list").await.unwrap().unwrap();
+    assert!(two > one + 1, "{} {}", one, two);
+}
+
+// 与 redis-cli 一样，所有参数（包括数字）都以 bulk 字符串发送
+#[tokio::test]
+async fn bulk_string_arguments() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+
+    send(&mut connection, &["SADD", "key", "a", "b", "c"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert_eq!(reply, "OK");
+
+    send(&mut connection, &["PUSH", "list", "R", "2", "x", "y"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Integer(2)), "{:?}", reply);
+
+    send(&mut connection, &["SET", "counter", "10"]).await;
+    connection.read_frame().await.unwrap();
+    send(&mut connection, &["INCRBY", "counter", "-15"]).await;
+    connection.read_frame().await.unwrap();
+
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut members = client.smembers("key".into()).await.unwrap();
+    members.sort();
+    assert_eq!(vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")], members);
+    assert_eq!(Some(Bytes::from("-5")), client.get("counter").await.unwrap());
+}
+
+#[tokio::test]
+async fn unix_socket_lifecycle() {
+    let path = std::env::temp_dir().join(format!("nano-redis-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // 上次运行残留的套接字文件：没有服务器在监听，绑定前删除
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+    let unix_listener = server_start::bind_unix(&path).await.unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        server_start::run(listener, Some(unix_listener), ServerConfig::default(), stopped).await
+    });
+
+    let mut connection = Connection::new(tokio::net::UnixStream::connect(&path).await.unwrap());
+    send(&mut connection, &["ping"]).await;
+    assert!(connection.read_frame().await.unwrap().unwrap() == "PONG");
+
+    // 有服务器在监听时不删除它的套接字
+    assert!(server_start::bind_unix(&path).await.is_err());
+    drop(connection);
+
+    // 关闭后删除套接字文件
+    stop.send(()).unwrap();
+    time::timeout(Duration::from_secs(1), server).await.unwrap().unwrap();
+    assert!(!path.exists());
+
+    // 不是套接字的文件不会被删除
+    std::fs::write(&path, b"data").unwrap();
+    assert!(server_start::bind_unix(&path).await.is_err());
+    assert_eq!(b"data".to_vec(), std::fs::read(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn idle_connection_is_closed() {
+    let addr = start_server(ServerConfig {
+        timeout: 1,
+        ..ServerConfig::default()
+    })
+    .await;
+    let mut silent = raw_connect(addr).await;
+    let mut active = Client::connect(addr).await.unwrap();
+
+    // 活跃的连接每次发送命令都会重新计时
+    for _ in 0..3 {
+        time::sleep(Duration::from_millis(500)).await;
+        active.ping(None).await.unwrap();
+    }
+
+    let closed = time::timeout(Duration::from_secs(1), silent.read_frame())
+        .await
+        .expect("idle connection was not closed");
+    assert!(matches!(closed, Ok(None) | Err(_)), "{:?}", closed);
+}
+
+#[tokio::test]
+async fn tcp_socket_options() {
+    for (tcp_nodelay, tcp_keepalive) in [(true, 60), (false, 0)] {
+        let addr = start_server(ServerConfig {
+            tcp_nodelay,
+            tcp_keepalive,
+            ..ServerConfig::default()
+        })
+        .await;
+        let mut client = Client::connect(addr).await.unwrap();
+        client.set("key", "value".into(), None).await.unwrap();
+        assert_eq!(Some(Bytes::from("value")), client.get("key").await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn bitmap() {
+    let mut client = connect().await;
+
+    assert!(!client.getbit("bits", 100).await.unwrap());
+    assert_eq!(0, client.bitcount("bits", None).await.unwrap());
+
+    // 设置第 100 位时字符串增长到 13 字节，前面补 0
+    assert!(!client.setbit("bits", 100, true).await.unwrap());
+    assert_eq!(13, client.get("bits").await.unwrap().unwrap().len());
+    assert!(client.getbit("bits", 100).await.unwrap());
+    assert!(!client.getbit("bits", 99).await.unwrap());
+    assert!(client.setbit("bits", 100, true).await.unwrap());
+
+    // 第 0 位是第一个字节的最高位
+    client.setbit("bits", 0, true).await.unwrap();
+    client.setbit("bits", 7, true).await.unwrap();
+    assert_eq!(Some(0x81), client.get("bits").await.unwrap().map(|value| value[0]));
+    assert_eq!(3, client.bitcount("bits", None).await.unwrap());
+    assert_eq!(2, client.bitcount("bits", Some((0, 0))).await.unwrap());
+    assert_eq!(1, client.bitcount("bits", Some((-1, -1))).await.unwrap());
+    assert_eq!(0, client.bitcount("bits", Some((5, 2))).await.unwrap());
+
+    assert!(client.setbit("bits", 0, false).await.unwrap());
+    assert_eq!(2, client.bitcount("bits", None).await.unwrap());
+
+    client.set("string", "foobar".into(), None).await.unwrap();
+    assert_eq!(26, client.bitcount("string", None).await.unwrap());
+    assert_eq!(6, client.bitcount("string", Some((1, 1))).await.unwrap());
+
+    let err = client.setbit("bits", 1 << 32, true).await.unwrap_err();
+    assert!(err.to_string().contains("bit offset is not an integer or out of range"), "{}", err);
+
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    let err = client.getbit("list", 0).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+}
+
+#[tokio::test]
+async fn bitpos() {
+    let mut client = connect().await;
+
+    // key 不存在时视为空字符串右侧无限个 0
+    assert_eq!(-1, client.bitpos("nobits", true, None, None).await.unwrap());
+    assert_eq!(0, client.bitpos("nobits", false, None, None).await.unwrap());
+
+    client.set("bits", Bytes::from_static(&[0xff, 0xf0, 0x00]), None).await.unwrap();
+    assert_eq!(0, client.bitpos("bits", true, None, None).await.unwrap());
+    assert_eq!(12, client.bitpos("bits", false, None, None).await.unwrap());
+    assert_eq!(8, client.bitpos("bits", true, Some(1), None).await.unwrap());
+    assert_eq!(16, client.bitpos("bits", false, Some(2), None).await.unwrap());
+    assert_eq!(-1, client.bitpos("bits", true, Some(2), None).await.unwrap());
+    assert_eq!(-1, client.bitpos("bits", true, Some(-1), Some(-1)).await.unwrap());
+    assert_eq!(-1, client.bitpos("bits", true, Some(2), Some(1)).await.unwrap());
+
+    // 全 1 的字符串中查找 0：没有给出 end 时返回末尾之后的位置，给出 end 时返回 -1
+    client.set("ones", Bytes::from_static(&[0xff, 0xff]), None).await.unwrap();
+    assert_eq!(16, client.bitpos("ones", false, None, None).await.unwrap());
+    assert_eq!(16, client.bitpos("ones", false, Some(1), None).await.unwrap());
+    assert_eq!(-1, client.bitpos("ones", false, Some(0), Some(-1)).await.unwrap());
+    assert_eq!(-1, client.bitpos("ones", false, None, Some(1)).await.unwrap());
+    assert_eq!(0, client.bitpos("ones", true, Some(0), Some(0)).await.unwrap());
+
+    let reply = client.execute(vec!["bitpos".into(), "bits".into(), "2".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("must be 1 or 0")), "{:?}", reply);
+
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    let err = client.bitpos("list", true, None, None).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+}
+
+#[tokio::test]
+async fn hmset_and_hmget() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(vec![None, None], client.hmget("hash", vec!["a".into(), "b".into()]).await.unwrap());
+
+    client
+        .hmset("hash", vec![("a".into(), "1".into()), ("b".into(), "2".into())])
+        .await
+        .unwrap();
+    client.hmset("hash", vec![("b".into(), "3".into())]).await.unwrap();
+    assert_eq!(
+        vec![Some(Bytes::from("1")), None, Some(Bytes::from("3"))],
+        client.hmget("hash", vec!["a".into(), "c".into(), "b".into()]).await.unwrap()
+    );
+
+    // 字段与值不成对
+    let mut connection = raw_connect(addr).await;
+    send(&mut connection, &["HMSET", "hash", "a", "1", "b"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(reply.to_string().contains("wrong number of arguments for 'hmset'"), "{}", reply);
+
+    client.set("string", "value".into(), None).await.unwrap();
+    let err = client.hmget("string", vec!["a".into()]).await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+}
+
+#[tokio::test]
+async fn hrandfield() {
+    let mut client = connect().await;
+
+    assert_eq!(None, client.hrandfield("missing").await.unwrap());
+    assert!(client.hrandfield_count("missing", 3).await.unwrap().is_empty());
+    assert!(client.hrandfield_count("missing", -3).await.unwrap().is_empty());
+
+    let fields: Vec<(Bytes, Bytes)> = (0..5)
+        .map(|i| (Bytes::from(format!("f{}", i)), Bytes::from(format!("v{}", i))))
+        .collect();
+    client.hmset("hash", fields.clone()).await.unwrap();
+    let names: Vec<Bytes> = fields.iter().map(|(field, _)| field.clone()).collect();
+
+    let field = client.hrandfield("hash").await.unwrap().unwrap();
+    assert!(names.contains(&field));
+
+    // 正数：字段不重复，最多返回全部字段
+    for count in [1, 3, 5, 10] {
+        let mut picked = client.hrandfield_count("hash", count).await.unwrap();
+        assert_eq!(count.min(5) as usize, picked.len());
+        assert!(picked.iter().all(|field| names.contains(field)));
+        picked.sort();
+        picked.dedup();
+        assert_eq!(count.min(5) as usize, picked.len());
+    }
+    assert!(client.hrandfield_count("hash", 0).await.unwrap().is_empty());
+
+    // 负数：正好返回 -count 个字段，可以重复
+    let picked = client.hrandfield_count("hash", -20).await.unwrap();
+    assert_eq!(20, picked.len());
+    assert!(picked.iter().all(|field| names.contains(field)));
+
+    // WITHVALUES：字段与对应的值成对返回
+    for count in [3, -8] {
+        let pairs = client.hrandfield_with_values("hash", count).await.unwrap();
+        assert_eq!(count.unsigned_abs() as usize, pairs.len());
+        assert!(pairs.iter().all(|pair| fields.contains(pair)), "{:?}", pairs);
+    }
+
+    let reply = client.execute(vec!["hrandfield".into(), "hash".into(), "1".into(), "values".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("syntax error")), "{:?}", reply);
+    let reply = client.execute(vec!["hrandfield".into(), "hash".into(), "withvalues".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("not an integer")), "{:?}", reply);
+
+    client.set("string", "value".into(), None).await.unwrap();
+    let err = client.hrandfield("string").await.unwrap_err();
+    assert!(err.to_string().contains("WRONGTYPE"), "{}", err);
+}
+
+#[tokio::test]
+async fn hrandfield_huge_negative_count() {
+    let mut client = connect().await;
+    client.hmset("hash", vec![("f".into(), "v".into())]).await.unwrap();
+
+    // 回复在数据库锁内构建，过大的 -count 必须在分配之前被拒绝
+    for count in [-4611686018427387903, -1_000_000_000, i64::MIN] {
+        let err = client.hrandfield_count("hash", count).await.unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{}", err);
+    }
+
+    // 服务器和这个数据库仍然可用
+    let values = client.hmget("hash", vec!["f".into()]).await.unwrap();
+    assert_eq!(vec![Some(Bytes::from("v"))], values);
+    client.set("key", "value".into(), None).await.unwrap();
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+}
+
+#[tokio::test]
+async fn zrange() {
+    let mut client = connect().await;
+
+    let members = vec![(3.0, "c".into()), (1.0, "a".into()), (2.0, "b".into()), (2.0, "bb".into())];
+    assert_eq!(4, client.zadd("zset", members).await.unwrap());
+    // 已有的成员只更新分数
+    assert_eq!(1, client.zadd("zset", vec![(0.5, "c".into()), (4.0, "d".into())]).await.unwrap());
+
+    let range = |values: Vec<Bytes>| {
+        values
+            .into_iter()
+            .map(|value| String::from_utf8(value.to_vec()).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["c", "a", "b", "bb", "d"], range(client.zrange("zset", 0, -1, false).await.unwrap()));
+    // 负数下标从末尾计数，超出范围的部分被截断
+    assert_eq!(vec!["bb", "d"], range(client.zrange("zset", -2, 100, false).await.unwrap()));
+    assert_eq!(vec!["c", "a"], range(client.zrange("zset", -100, 1, false).await.unwrap()));
+    assert!(client.zrange("zset", 3, 1, false).await.unwrap().is_empty());
+    assert!(client.zrange("zset", 5, 10, false).await.unwrap().is_empty());
+    assert_eq!(vec!["d", "bb"], range(client.zrange("zset", 0, 1, true).await.unwrap()));
+    assert!(client.zrange("missing", 0, -1, false).await.unwrap().is_empty());
+
+    let with_scores = client.zrange_with_scores("zset", 0, 1, false).await.unwrap();
+    assert_eq!(vec![(Bytes::from("c"), 0.5), (Bytes::from("a"), 1.0)], with_scores);
+    let reply = client.execute(vec!["zrange".into(), "zset".into(), "0".into(), "0".into(), "withscores".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Array(parts) if parts.len() == 2 && parts[1] == "0.5"), "{:?}", reply);
+
+    client.zadd("inf", vec![(f64::INFINITY, "top".into()), (f64::NEG_INFINITY, "bottom".into())]).await.unwrap();
+    let with_scores = client.zrange_with_scores("inf", 0, -1, false).await.unwrap();
+    assert_eq!(vec![(Bytes::from("bottom"), f64::NEG_INFINITY), (Bytes::from("top"), f64::INFINITY)], with_scores);
+
+    let reply = client.execute(vec!["zadd".into(), "zset".into(), "nan".into(), "x".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err == "ERR value is not a valid float"), "{:?}", reply);
+    let reply = client.execute(vec!["zrange".into(), "zset".into(), "0".into(), "1".into(), "bogus".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err == "ERR syntax error"), "{:?}", reply);
+    client.set("string", "a".into(), None).await.unwrap();
+    assert!(client.zrange("string", 0, -1, false).await.is_err());
+}
+
+#[tokio::test]
+async fn zincrby_zrem_and_zcard() {
+    let mut client = connect().await;
+
+    assert_eq!(0, client.zcard("zset").await.unwrap());
+
+    // 成员不存在时以 increment 为分数加入
+    assert_eq!(2.5, client.zincrby("zset", 2.5, "a".into()).await.unwrap());
+    assert_eq!(1.0, client.zincrby("zset", -1.5, "a".into()).await.unwrap());
+    assert_eq!(3.0, client.zincrby("zset", 3.0, "b".into()).await.unwrap());
+    assert_eq!(2, client.zcard("zset").await.unwrap());
+    // 分数变化后有序索引同步更新
+    assert_eq!(5.0, client.zincrby("zset", 4.0, "a".into()).await.unwrap());
+    let with_scores = client.zrange_with_scores("zset", 0, -1, false).await.unwrap();
+    assert_eq!(vec![(Bytes::from("b"), 3.0), (Bytes::from("a"), 5.0)], with_scores);
+
+    // inf 加上 -inf 不是数字，回复错误且分数不变
+    client.zincrby("inf", f64::INFINITY, "a".into()).await.unwrap();
+    assert!(client.zincrby("inf", f64::NEG_INFINITY, "a".into()).await.is_err());
+    assert_eq!(vec![(Bytes::from("a"), f64::INFINITY)], client.zrange_with_scores("inf", 0, -1, false).await.unwrap());
+
+    // 不存在的成员不计数
+    assert_eq!(1, client.zrem("zset", vec!["a".into(), "missing".into()]).await.unwrap());
+    assert_eq!(1, client.zcard("zset").await.unwrap());
+    assert_eq!(vec![Bytes::from("b")], client.zrange("zset", 0, -1, false).await.unwrap());
+
+    // 删除最后一个成员时 key 也被删除
+    assert_eq!(1, client.zrem("zset", vec!["b".into()]).await.unwrap());
+    assert_eq!(0, client.touch(vec!["zset".into()]).await.unwrap());
+    assert_eq!(0, client.zrem("zset", vec!["b".into()]).await.unwrap());
+
+    client.set("string", "a".into(), None).await.unwrap();
+    assert!(client.zcard("string").await.is_err());
+    assert!(client.zrem("string", vec!["a".into()]).await.is_err());
+    assert!(client.zincrby("string", 1.0, "a".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn zrangebyscore() {
+    let mut client = connect().await;
+    let members = vec![
+        (f64::NEG_INFINITY, "ninf".into()),
+        (1.0, "a".into()),
+        (2.0, "b".into()),
+        (3.0, "c".into()),
+        (5.0, "e".into()),
+        (5.0, "f".into()),
+        (7.0, "g".into()),
+    ];
+    client.zadd("zset", members).await.unwrap();
+
+    let range = |values: Vec<Bytes>| {
+        values
+            .into_iter()
+            .map(|value| String::from_utf8(value.to_vec()).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec!["ninf", "a", "b", "c", "e", "f", "g"],
+        range(client.zrangebyscore("zset", "-inf", "+inf", None).await.unwrap())
+    );
+    // ( 表示不包含这个分数
+    assert_eq!(vec!["b", "c", "e", "f"], range(client.zrangebyscore("zset", "(1", "5", None).await.unwrap()));
+    assert_eq!(vec!["a", "b", "c"], range(client.zrangebyscore("zset", "1", "(5", None).await.unwrap()));
+    assert_eq!(vec!["ninf"], range(client.zrangebyscore("zset", "-inf", "(1", None).await.unwrap()));
+    assert!(client.zrangebyscore("zset", "(5", "5", None).await.unwrap().is_empty());
+    assert!(client.zrangebyscore("zset", "6", "4", None).await.unwrap().is_empty());
+    assert!(client.zrangebyscore("missing", "-inf", "+inf", None).await.unwrap().is_empty());
+
+    // LIMIT 在区间内分页，count 为负数时返回剩下的所有成员，offset 为负数时返回空
+    assert_eq!(vec!["b", "c"], range(client.zrangebyscore("zset", "1", "+inf", Some((1, 2))).await.unwrap()));
+    assert_eq!(vec!["e", "f", "g"], range(client.zrangebyscore("zset", "1", "+inf", Some((3, -1))).await.unwrap()));
+    assert!(client.zrangebyscore("zset", "1", "+inf", Some((10, 2))).await.unwrap().is_empty());
+    assert!(client.zrangebyscore("zset", "1", "+inf", Some((-1, 2))).await.unwrap().is_empty());
+
+    let with_scores = client.zrangebyscore_with_scores("zset", "(3", "inf", Some((0, 2))).await.unwrap();
+    assert_eq!(vec![(Bytes::from("e"), 5.0), (Bytes::from("f"), 5.0)], with_scores);
+
+    let reply = client.execute(vec!["zrangebyscore".into(), "zset".into(), "(x".into(), "5".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err == "ERR min or max is not a float"), "{:?}", reply);
+    let args = ["zrangebyscore", "zset", "1", "5", "limit", "1"];
+    let reply = client.execute(args.into_iter().map(Bytes::from).collect()).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err == "ERR syntax error"), "{:?}", reply);
+}
+
+#[test]
+fn fmt_score_matches_redis() {
+    assert_eq!("1", fmt_score(1.0));
+    assert_eq!("-3", fmt_score(-3.0));
+    assert_eq!("0", fmt_score(0.0));
+    assert_eq!("1.5", fmt_score(1.5));
+    assert_eq!("0.1", fmt_score(0.1));
+    assert_eq!("0.0001", fmt_score(0.0001));
+    assert_eq!("1e-05", fmt_score(0.00001));
+    assert_eq!("1234567890123456", fmt_score(1234567890123456.0));
+    assert_eq!("1e+17", fmt_score(1e17));
+    assert_eq!("1.5e+300", fmt_score(1.5e300));
+    assert_eq!("inf", fmt_score(f64::INFINITY));
+    assert_eq!("-inf", fmt_score(f64::NEG_INFINITY));
+    // 格式化后再解析得到同一个分数
+    for score in [0.1 + 0.2, 1.0 / 3.0, f64::MAX, f64::MIN_POSITIVE, -2.5e-7] {
+        assert_eq!(score, fmt_score(score).parse::<f64>().unwrap());
+    }
+}
+
+#[tokio::test]
+async fn reset_clears_connection_state() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.client_setname("pooled").await.unwrap();
+    client.select(1).await.unwrap();
+    client.set("key", "db1".into(), None).await.unwrap();
+    client.multi().await.unwrap();
+    client.set("key", "queued".into(), None).await.unwrap_err();
+
+    client.reset().await.unwrap();
+    assert_eq!(None, client.client_getname().await.unwrap());
+    assert_eq!(None, client.get("key").await.unwrap());
+    // 事务已被放弃
+    client.discard().await.unwrap_err();
+    client.select(1).await.unwrap();
+    assert_eq!(Some(Bytes::from("db1")), client.get("key").await.unwrap());
+
+    let mut subscriber = raw_connect(addr).await;
+    send(&mut subscriber, &["subscribe", "news"]).await;
+    read_array(&mut subscriber).await;
+    send(&mut subscriber, &["reset"]).await;
+    let reply = subscriber.read_frame().await.unwrap().unwrap();
+    assert_eq!(reply, "RESET");
+    assert_eq!(0, client.publish("news", "hello".into()).await.unwrap());
+}
+
+#[tokio::test]
+async fn quit_closes_connection() {
+    let addr = start_server(ServerConfig::default()).await;
+    Client::connect(addr).await.unwrap().quit().await.unwrap();
+
+    // QUIT 之后流水线中的命令不再执行，连接在 OK 之后关闭
+    let mut connection = raw_connect(addr).await;
+    send(&mut connection, &["quit"]).await;
+    send(&mut connection, &["set", "key", "value"]).await;
+    assert!(connection.read_frame().await.unwrap().unwrap() == "OK");
+    let closed = time::timeout(Duration::from_secs(1), connection.read_frame()).await.expect("connection not closed");
+    assert!(closed.unwrap().is_none());
+
+    // 订阅模式与事务中同样立即执行
+    let mut subscriber = raw_connect(addr).await;
+    send(&mut subscriber, &["subscribe", "news"]).await;
+    read_array(&mut subscriber).await;
+    send(&mut subscriber, &["quit"]).await;
+    assert!(subscriber.read_frame().await.unwrap().unwrap() == "OK");
+    assert!(subscriber.read_frame().await.unwrap().is_none());
+
+    let mut client = Client::connect(addr).await.unwrap();
+    client.multi().await.unwrap();
+    client.quit().await.unwrap();
+
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(None, client.get("key").await.unwrap());
+}
+
+#[tokio::test]
+async fn flushdb_and_flushall() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.set("key", "db0".into(), None).await.unwrap();
+    client.select(1).await.unwrap();
+    client.set("key", "db1".into(), Some(Duration::from_secs(100))).await.unwrap();
+
+    // FLUSHDB 只清空当前数据库
+    client.flushdb(false).await.unwrap();
+    assert_eq!(None, client.get("key").await.unwrap());
+    client.select(0).await.unwrap();
+    assert_eq!(Some(Bytes::from("db0")), client.get("key").await.unwrap());
+
+    client.select(1).await.unwrap();
+    client.set("key", "db1".into(), None).await.unwrap();
+    client.flushall(false).await.unwrap();
+    assert_eq!(None, client.get("key").await.unwrap());
+    client.select(0).await.unwrap();
+    assert_eq!(None, client.get("key").await.unwrap());
+
+    let reply = client.execute(vec!["flushdb".into(), "lazy".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("syntax error")), "{:?}", reply);
+    let reply = client.execute(vec!["FLUSHALL".into(), "sync".into()]).await.unwrap();
+    assert!(reply == "OK");
+}
+
+#[tokio::test]
+async fn select_out_of_range() {
+    let addr = start_server(ServerConfig { databases: 4, ..ServerConfig::default() }).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    assert_raw_reply(&mut stream, &["select", "3"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["set", "k", "db3"], b"+OK\r\n").await;
+
+    // 越界的 SELECT 回复错误，连接仍然使用原来选择的数据库
+    assert_raw_reply(&mut stream, &["select", "4"], b"-ERR DB index is out of range\r\n").await;
+    assert_raw_reply(&mut stream, &["select", "-1"], b"-ERR DB index is out of range\r\n").await;
+    assert_raw_reply(&mut stream, &["get", "k"], b"$3\r\ndb3\r\n").await;
+
+    // MOVE 的目标数据库按同样的范围检查
+    assert_raw_reply(&mut stream, &["move", "k", "4"], b"-ERR DB index is out of range\r\n").await;
+    assert_raw_reply(&mut stream, &["move", "k", "0"], b":1\r\n").await;
+}
+
+#[tokio::test]
+async fn flushall_clears_every_configured_database() {
+    let addr = start_server(ServerConfig { databases: 3, ..ServerConfig::default() }).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+
+    // SELECT 按配置的数据库个数检查下标
+    assert!(client.select(3).await.is_err());
+
+    client.set("a", "db0".into(), None).await.unwrap();
+    other.select(2).await.unwrap();
+    other.set("b", "db2".into(), Some(Duration::from_secs(100))).await.unwrap();
+
+    client.flushall(false).await.unwrap();
+
+    // 两个数据库都已清空，两个连接仍然可用，并保留各自选择的数据库
+    assert_eq!(None, client.get("a").await.unwrap());
+    assert_eq!(None, other.get("b").await.unwrap());
+    other.set("b", "again".into(), None).await.unwrap();
+    client.select(2).await.unwrap();
+    assert_eq!(Some(Bytes::from("again")), client.get("b").await.unwrap());
+}
+
+#[tokio::test]
+async fn move_key() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    client.set("key", "value".into(), Some(Duration::from_secs(100))).await.unwrap();
+    assert!(client.move_key("key", 1).await.unwrap());
+    assert_eq!(None, client.get("key").await.unwrap());
+    // 不存在的 key 不移动
+    assert!(!client.move_key("key", 1).await.unwrap());
+
+    // 过期时间随 key 一起移动
+    client.select(1).await.unwrap();
+    assert_eq!(Some(Bytes::from("value")), client.get("key").await.unwrap());
+    let ttl = client.ttl("key").await.unwrap();
+    assert!((99..=100).contains(&ttl), "{}", ttl);
+
+    // 目标数据库中已有同名 key 时不移动
+    client.select(0).await.unwrap();
+    client.set("key", "other".into(), None).await.unwrap();
+    assert!(!client.move_key("key", 1).await.unwrap());
+    assert_eq!(Some(Bytes::from("other")), client.get("key").await.unwrap());
+
+    let err = client.move_key("key", 0).await.unwrap_err();
+    assert!(err.to_string().contains("same"), "{}", err);
+    let err = client.move_key("key", 1000).await.unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+}
+
+#[tokio::test]
+async fn flushall_async() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // 用流水线写入大量 key，再加上一个很大的集合
+    let mut connection = raw_connect(addr).await;
+    let keys = 20_000;
+    for i in 0..keys {
+        let key = format!("key:{}", i);
+        send(&mut connection, &["set", &key, "value", "ex", "100"]).await;
+    }
+    for _ in 0..keys {
+        connection.read_frame().await.unwrap().unwrap();
+    }
+    let members: Vec<String> = (0..100_000).map(|i| format!("member:{}", i)).collect();
+    client.sadd("set", members).await.unwrap();
+    assert_eq!(100_000, client.scard("set".to_string()).await.unwrap());
+
+    let start = Instant::now();
+    client.flushall(true).await.unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+
+    // 回复 OK 之后立即读取已经是空的
+    assert_eq!(None, client.get("key:0").await.unwrap());
+    assert_eq!(0, client.scard("set".to_string()).await.unwrap());
+    assert_eq!(-2, client.ttl("key:1").await.unwrap());
+    client.set("key:0", "new".into(), None).await.unwrap();
+    assert_eq!(Some(Bytes::from("new")), client.get("key:0").await.unwrap());
+}
+
+// 从游标 0 开始遍历到结束，返回所有 key（排序后）
+async fn scan_all(client: &mut Client, pattern: Option<&str>, count: Option<u64>, type_name: Option<&str>) -> Vec<String> {
+    let mut cursor = 0;
+    let mut keys = vec![];
+    loop {
+        let (next, page) = client.scan(cursor, pattern, count, type_name).await.unwrap();
+        keys.extend(page.into_iter().map(|key| String::from_utf8(key.to_vec()).unwrap()));
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[tokio::test]
+async fn scan() {
+    let mut client = connect().await;
+
+    let (next, keys) = client.scan(0, None, None, None).await.unwrap();
+    assert_eq!(0, next);
+    assert!(keys.is_empty());
+
+    let mut expected = vec![];
+    for i in 0..100 {
+        client.set(&format!("string:{}", i), "value".into(), None).await.unwrap();
+        expected.push(format!("string:{}", i));
+    }
+    expected.sort();
+    // 每次最多返回 count 个 key，遍历结束后每个 key 都被返回
+    let (next, keys) = client.scan(0, None, Some(7), None).await.unwrap();
+    assert_ne!(0, next);
+    assert_eq!(7, keys.len());
+    assert_eq!(expected, scan_all(&mut client, None, Some(7), None).await);
+    assert_eq!(expected, scan_all(&mut client, None, Some(1000), None).await);
+
+    let matched = scan_all(&mut client, Some("string:1?"), Some(3), None).await;
+    assert_eq!(10, matched.len());
+    assert!(matched.iter().all(|key| key.starts_with("string:1")), "{:?}", matched);
+
+    let reply = client.execute(vec!["scan".into(), "abc".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("invalid cursor")), "{:?}", reply);
+    let reply = client.execute(vec!["scan".into(), "0".into(), "count".into(), "0".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("syntax error")), "{:?}", reply);
+}
+
+#[tokio::test]
+async fn scan_type() {
+    let mut client = connect().await;
+
+    client.set("string", "value".into(), None).await.unwrap();
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    client.sadd("set:1", vec!["a".into()]).await.unwrap();
+    client.sadd("set:2", vec!["b".into()]).await.unwrap();
+    client.hmset("hash", vec![("field".into(), "value".into())]).await.unwrap();
+
+    assert_eq!(vec!["set:1", "set:2"], scan_all(&mut client, None, None, Some("set")).await);
+    assert_eq!(vec!["set:1", "set:2"], scan_all(&mut client, None, Some(1), Some("SET")).await);
+    assert_eq!(vec!["list"], scan_all(&mut client, None, None, Some("list")).await);
+    assert_eq!(vec!["hash"], scan_all(&mut client, None, None, Some("hash")).await);
+    assert_eq!(vec!["string"], scan_all(&mut client, None, None, Some("string")).await);
+    assert_eq!(vec!["set:2"], scan_all(&mut client, Some("*2"), None, Some("set")).await);
+    assert!(scan_all(&mut client, None, None, Some("zset")).await.is_empty());
+}
+
+#[tokio::test]
+async fn hscan() {
+    let mut client = connect().await;
+
+    let (next, fields) = client.hscan("hash", 0, None, None).await.unwrap();
+    assert_eq!(0, next);
+    assert!(fields.is_empty());
+
+    let mut expected: Vec<(Bytes, Bytes)> = (0..1000)
+        .map(|i| (Bytes::from(format!("field:{}", i)), Bytes::from(format!("value:{}", i))))
+        .collect();
+    client.hmset("hash", expected.clone()).await.unwrap();
+    expected.sort();
+
+    // 每次最多返回 COUNT 个字段，遍历结束后每个字段都与它的值一起被返回
+    let mut cursor = 0;
+    let mut fields = vec![];
+    loop {
+        let (next, page) = client.hscan("hash", cursor, None, Some(10)).await.unwrap();
+        assert!(page.len() <= 10, "{}", page.len());
+        fields.extend(page);
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    fields.sort();
+    fields.dedup();
+    assert_eq!(expected, fields);
+
+    let (_, page) = client.hscan("hash", 0, Some("field:99?"), Some(1000)).await.unwrap();
+    assert_eq!(10, page.len());
+    assert!(page.iter().all(|(field, _)| field.starts_with(b"field:99")), "{:?}", page);
+
+    client.set("string", "value".into(), None).await.unwrap();
+    assert!(client.hscan("string", 0, None, None).await.is_err());
+}
+
+#[tokio::test]
+async fn sscan() {
+    let mut client = connect().await;
+
+    let expected: Vec<String> = (0..1000).map(|i| format!("member:{}", i)).collect();
+    client.sadd("set", expected.clone()).await.unwrap();
+
+    let mut cursor = 0;
+    let mut members = vec![];
+    loop {
+        let (next, page) = client.sscan("set", cursor, None, Some(10)).await.unwrap();
+        assert!(page.len() <= 10, "{}", page.len());
+        members.extend(page.into_iter().map(|member| String::from_utf8(member.to_vec()).unwrap()));
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    members.sort();
+    members.dedup();
+    let mut expected = expected;
+    expected.sort();
+    assert_eq!(expected, members);
+
+    let (next, page) = client.sscan("set", 0, Some("member:1"), Some(1000)).await.unwrap();
+    assert_eq!(0, next);
+    assert_eq!(vec![Bytes::from("member:1")], page);
+
+    client.set("string", "value".into(), None).await.unwrap();
+    assert!(client.sscan("string", 0, None, None).await.is_err());
+    let reply = client.execute(vec!["sscan".into(), "set".into(), "0".into(), "count".into(), "0".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("syntax error")), "{:?}", reply);
+}
+
+#[tokio::test]
+async fn echo() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    assert_eq!(Bytes::from("hello"), client.echo("hello".into()).await.unwrap());
+    // 空字符串返回空的 bulk，不是 nil
+    assert_eq!(Bytes::new(), client.echo(Bytes::new()).await.unwrap());
+
+    let mut connection = raw_connect(addr).await;
+    send(&mut connection, &["ECHO"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(reply.to_string().contains("wrong number of arguments for 'echo'"), "{}", reply);
+}
+
+#[tokio::test]
+async fn debug_object() {
+    let config = ServerConfig { enable_debug: true, ..ServerConfig::default() };
+    let mut client = Client::connect(start_server(config).await).await.unwrap();
+
+    client.set("int", "12345".into(), None).await.unwrap();
+    let info = client.debug_object("int").await.unwrap();
+    assert!(info.starts_with("refcount:1 encoding:int serializedlength:"), "{}", info);
+    assert!(info.ends_with(" ttl_ms:-1"), "{}", info);
+
+    client.set("temp", "value".into(), Some(Duration::from_secs(100))).await.unwrap();
+    let info = client.debug_object("temp").await.unwrap();
+    let ttl: u64 = info.rsplit("ttl_ms:").next().unwrap().parse().unwrap();
+    assert!(ttl > 99_000 && ttl <= 100_000, "{}", info);
+    assert!(info.contains("encoding:embstr"), "{}", info);
+
+    let err = client.debug_object("missing").await.unwrap_err();
+    assert!(err.to_string().contains("no such key"), "{}", err);
+}
+
+#[tokio::test]
+async fn lazy_expiry_without_active_expire() {
+    let config = ServerConfig { enable_debug: true, ..ServerConfig::default() };
+    let mut client = Client::connect(start_server(config).await).await.unwrap();
+
+    client.debug_set_active_expire(false).await.unwrap();
+    client.set("temp", "value".into(), Some(Duration::from_millis(20))).await.unwrap();
+    time::sleep(Duration::from_millis(100)).await;
+
+    // 后台任务没有删除过期的 key，DEBUG OBJECT 不触发惰性删除，仍然能看到它
+    let info = client.debug_object("temp").await.unwrap();
+    assert!(info.ends_with(" ttl_ms:0"), "{}", info);
+
+    // 读取时发现已过期，删除并当作不存在
+    assert_eq!(None, client.get("temp").await.unwrap());
+    assert!(client.debug_object("temp").await.is_err());
+
+    // 重新打开后，后台任务继续删除过期的 key
+    client.set("other", "value".into(), Some(Duration::from_millis(20))).await.unwrap();
+    client.debug_set_active_expire(true).await.unwrap();
+    time::sleep(Duration::from_millis(100)).await;
+    assert!(client.debug_object("other").await.is_err());
+
+    let reply = client.execute(vec!["debug".into(), "set-active-expire".into(), "2".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("syntax error")), "{:?}", reply);
+}
+
+#[tokio::test]
+async fn object_refcount() {
+    let mut client = connect().await;
+
+    // 两个 key 设置为同一个小整数时共享同一份存储
+    client.set("a", "5".into(), None).await.unwrap();
+    client.set("b", "5".into(), None).await.unwrap();
+    assert_eq!(i32::MAX as i64, client.object_refcount("a").await.unwrap());
+    assert_eq!(i32::MAX as i64, client.object_refcount("b").await.unwrap());
+
+    // INCRBY 的结果在共享范围内时同样共享，超出范围后不再共享
+    client.set("counter", "9998".into(), None).await.unwrap();
+    client.incrby("counter", 1).await.unwrap();
+    assert_eq!(i32::MAX as i64, client.object_refcount("counter").await.unwrap());
+    client.incrby("counter", 1).await.unwrap();
+    assert_eq!(1, client.object_refcount("counter").await.unwrap());
+
+    // 不是规范写法的整数与其他值不共享
+    for value in ["05", "-1", "+5", "hello"] {
+        client.set("other", value.into(), None).await.unwrap();
+        assert_eq!(1, client.object_refcount("other").await.unwrap(), "{}", value);
+    }
+    client.push("list", vec!["5".into()], true).await.unwrap();
+    assert_eq!(1, client.object_refcount("list").await.unwrap());
+
+    let err = client.object_refcount("missing").await.unwrap_err();
+    assert!(err.to_string().contains("no such key"), "{}", err);
+}
+
+#[tokio::test]
+async fn object_encoding() {
+    let mut client = connect().await;
+
+    // 字符串：能解析为整数时为 int，不超过 44 字节为 embstr，更长为 raw
+    client.set("str", "12345".into(), None).await.unwrap();
+    assert_eq!(&b"int"[..], &client.object_encoding("str").await.unwrap()[..]);
+    client.set("str", "x".repeat(44).into(), None).await.unwrap();
+    assert_eq!(&b"embstr"[..], &client.object_encoding("str").await.unwrap()[..]);
+    client.set("str", "x".repeat(45).into(), None).await.unwrap();
+    assert_eq!(&b"raw"[..], &client.object_encoding("str").await.unwrap()[..]);
+
+    client.push("list", vec!["a".into(), "b".into()], true).await.unwrap();
+    assert_eq!(&b"linkedlist"[..], &client.object_encoding("list").await.unwrap()[..]);
+
+    // 集合：成员都是整数且不超过 512 个时为 intset，否则为 hashtable
+    client.sadd("set", vec!["1".into(), "2".into()]).await.unwrap();
+    assert_eq!(&b"intset"[..], &client.object_encoding("set").await.unwrap()[..]);
+    client.sadd("set", vec!["a".into()]).await.unwrap();
+    assert_eq!(&b"hashtable"[..], &client.object_encoding("set").await.unwrap()[..]);
+    client.sadd("big", (0..513).map(|i| i.to_string()).collect()).await.unwrap();
+    assert_eq!(&b"hashtable"[..], &client.object_encoding("big").await.unwrap()[..]);
+
+    assert!(client.hsetnx("hash", "field".into(), "value".into()).await.unwrap());
+    assert_eq!(&b"hashtable"[..], &client.object_encoding("hash").await.unwrap()[..]);
+
+    client.zadd("zset", vec![(1.0, "a".into())]).await.unwrap();
+    assert_eq!(&b"skiplist"[..], &client.object_encoding("zset").await.unwrap()[..]);
+
+    let err = client.object_encoding("missing").await.unwrap_err();
+    assert!(err.to_string().contains("no such key"), "{}", err);
+}
+
+#[tokio::test]
+async fn lpos() {
+    let mut client = connect().await;
+    let values = ["a", "b", "c", "1", "2", "3", "c", "c"];
+    client.push("list", values.iter().map(|v| v.to_string()).collect(), true).await.unwrap();
+
+    assert_eq!(Some(2), client.lpos("list", "c".into(), None).await.unwrap());
+    assert_eq!(Some(6), client.lpos("list", "c".into(), Some(2)).await.unwrap());
+    assert_eq!(Some(7), client.lpos("list", "c".into(), Some(-1)).await.unwrap());
+    assert_eq!(Some(6), client.lpos("list", "c".into(), Some(-2)).await.unwrap());
+    assert_eq!(None, client.lpos("list", "c".into(), Some(4)).await.unwrap());
+    assert_eq!(None, client.lpos("list", "x".into(), None).await.unwrap());
+    assert_eq!(None, client.lpos("missing", "c".into(), None).await.unwrap());
+
+    // COUNT 0 返回所有匹配
+    assert_eq!(vec![2, 6, 7], client.lpos_count("list", "c".into(), None, 0).await.unwrap());
+    assert_eq!(vec![2, 6], client.lpos_count("list", "c".into(), None, 2).await.unwrap());
+    assert_eq!(vec![7, 6, 2], client.lpos_count("list", "c".into(), Some(-1), 0).await.unwrap());
+    assert_eq!(Vec::<i64>::new(), client.lpos_count("list", "x".into(), None, 0).await.unwrap());
+
+    let err = client.lpos("list", "c".into(), Some(0)).await.unwrap_err();
+    assert!(err.to_string().contains("RANK can't be zero"), "{}", err);
+}
+
+#[tokio::test]
+async fn max_clients_accepts_after_close() {
+    let addr = start_server(ServerConfig { max_connections: 2, ..ServerConfig::default() }).await;
+
+    let mut first = Client::connect(addr).await.unwrap();
+    let mut second = Client::connect(addr).await.unwrap();
+    first.ping(None).await.unwrap();
+    second.ping(None).await.unwrap();
+
+    // 第 N+1 个连接被拒绝
+    let mut extra = raw_connect(addr).await;
+    assert!(matches!(extra.read_frame().await.unwrap(), Some(Frame::Error(_))));
+
+    // 关闭一个连接后，连接处理任务结束时释放许可，新的连接可以被接受
+    drop(first);
+    time::timeout(Duration::from_secs(1), async {
+        loop {
+            let mut client = Client::connect(addr).await.unwrap();
+            if client.ping(None).await.is_ok() {
+                return;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("connection not accepted after another one closed");
+    second.ping(None).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscriber() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut publisher = Client::connect(addr).await.unwrap();
+    let client = Client::connect(addr).await.unwrap();
+
+    let mut subscriber = client.subscribe(vec!["news".into()]).await.unwrap();
+    assert_eq!(["news".to_string()], subscriber.get_subscribed());
+
+    publisher.publish("news", "first".into()).await.unwrap();
+    // 订阅回复之前到达的消息不会丢失
+    subscriber.subscribe(&["sports".into()]).await.unwrap();
+    publisher.publish("sports", "goal".into()).await.unwrap();
+
+    let message = subscriber.next_message().await.unwrap();
+    assert_eq!(("news", Bytes::from("first")), (message.channel.as_str(), message.content));
+    let message = subscriber.next_message().await.unwrap();
+    assert_eq!(("sports", Bytes::from("goal")), (message.channel.as_str(), message.content));
+
+    // PING 之前到达的消息同样留给 next_message
+    publisher.publish("news", "second".into()).await.unwrap();
+    assert_eq!(Bytes::from(""), subscriber.ping(None).await.unwrap());
+    assert_eq!(Bytes::from("hi"), subscriber.ping(Some("hi".into())).await.unwrap());
+    let message = subscriber.next_message().await.unwrap();
+    assert_eq!(("news", Bytes::from("second")), (message.channel.as_str(), message.content));
+
+    subscriber.unsubscribe(&["news".into()]).await.unwrap();
+    assert_eq!(["sports".to_string()], subscriber.get_subscribed());
+    subscriber.unsubscribe(&[]).await.unwrap();
+    assert!(subscriber.get_subscribed().is_empty());
+    assert_eq!(0, publisher.publish("sports", "ignored".into()).await.unwrap());
+}
+
+#[tokio::test]
+async fn subscriber_connection_closed() {
+    // 回复订阅后写一半消息就断开的服务器
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0; 64];
+        let _ = socket.read(&mut request).await.unwrap();
+        socket.write_all(b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n").await.unwrap();
+        socket.write_all(b"*3\r\n$7\r\nmessage\r\n").await.unwrap();
+    });
+
+    let client = Client::connect(addr).await.unwrap();
+    let mut subscriber = client.subscribe(vec!["news".into()]).await.unwrap();
+    assert!(subscriber.next_message().await.is_none());
+}
+
+#[tokio::test]
+async fn max_clients_rejected() {
+    let addr = start_server(ServerConfig { max_connections: 2, ..ServerConfig::default() }).await;
+
+    // 先占满所有连接，PING 保证服务器已经接受了它们
+    let mut first = Client::connect(addr).await.unwrap();
+    let mut second = Client::connect(addr).await.unwrap();
+    first.ping(None).await.unwrap();
+    second.ping(None).await.unwrap();
+
+    // 多出来的连接收到错误后被关闭
+    let mut extra = raw_connect(addr).await;
+    let reply = time::timeout(Duration::from_secs(1), extra.read_frame())
+        .await
+        .expect("timed out waiting for a frame")
+        .unwrap()
+        .unwrap();
+    assert!(matches!(reply, Frame::Error(msg) if msg == "ERR max number of clients reached"));
+    assert!(extra.read_frame().await.unwrap().is_none());
+
+    // 已有的连接不受影响
+    second.ping(None).await.unwrap();
+}
+
+#[tokio::test]
+async fn sintercard() {
+    let mut client = connect().await;
+
+    client.sadd("a", vec!["1".into(), "2".into(), "3".into(), "4".into()]).await.unwrap();
+    client.sadd("b", vec!["2".into(), "3".into(), "4".into(), "5".into()]).await.unwrap();
+    client.sadd("c", vec!["3".into(), "4".into(), "6".into()]).await.unwrap();
+
+    let keys = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    assert_eq!(2, client.sintercard(keys.clone(), None).await.unwrap());
+    assert_eq!(1, client.sintercard(keys.clone(), Some(1)).await.unwrap());
+    assert_eq!(2, client.sintercard(keys.clone(), Some(0)).await.unwrap());
+    assert_eq!(3, client.sintercard(vec!["a".into(), "b".into()], Some(10)).await.unwrap());
+
+    // 任一 key 不存在时交集为空
+    assert_eq!(0, client.sintercard(vec!["a".into(), "missing".into()], None).await.unwrap());
+
+    client.set("string", "value".into(), None).await.unwrap();
+    assert!(client.sintercard(vec!["a".into(), "string".into()], None).await.is_err());
+}
+
+#[tokio::test]
+async fn subcommand_dispatch() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+
+    // 命令和子命令不区分大小写，参数保持原样
+    send(&mut connection, &["CLIENT", "SetName", "MyName"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(reply == "OK");
+    send(&mut connection, &["client", "GETNAME"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(reply == "MyName");
+
+    // 未知的子命令和参数个数不对的子命令都回复错误，连接保持可用
+    for args in [&["object", "bogus", "key"][..], &["client", "id", "extra"], &["slowlog", "len", "1"]] {
+        send(&mut connection, args).await;
+        let reply = connection.read_frame().await.unwrap().unwrap();
+        assert!(
+            matches!(&reply, Frame::Error(msg) if msg.starts_with("ERR Unknown subcommand")),
+            "{:?}",
+            reply
+        );
+    }
+
+    send(&mut connection, &["command", "count"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Integer(_)));
+}
+
+#[tokio::test]
+async fn features() {
+    // 默认配置只有 pub/sub，没有配置证书时即使编译了 tls 也不算开启
+    let mut client = connect().await;
+    assert_eq!(vec![Bytes::from("pubsub")], client.features().await.unwrap());
+
+    let config = ServerConfig {
+        enable_debug: true,
+        notify_keyspace_events: "KEA".parse().unwrap(),
+        ..ServerConfig::default()
+    };
+    let mut client = Client::connect(start_server(config).await).await.unwrap();
+    assert_eq!(
+        vec![Bytes::from("pubsub"), Bytes::from("debug"), Bytes::from("keyspace-events")],
+        client.features().await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn config_get_and_set() {
+    let mut client = connect().await;
+
+    assert_eq!(vec![("maxmemory".to_string(), "0".to_string())], client.config_get("maxmemory").await.unwrap());
+    let names: Vec<String> = client.config_get("SLOWLOG-*").await.unwrap().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(vec!["slowlog-log-slower-than", "slowlog-max-len"], names);
+    assert!(client.config_get("no-such-parameter").await.unwrap().is_empty());
+
+    client.config_set("maxmemory-policy", "allkeys-lru").await.unwrap();
+    assert_eq!("allkeys-lru", client.config_get("maxmemory-policy").await.unwrap()[0].1);
+
+    assert!(client.config_set("no-such-parameter", "1").await.is_err());
+    assert!(client.config_set("databases", "4").await.is_err());
+    assert!(client.config_set("maxmemory", "lots").await.is_err());
+    assert_eq!("0", client.config_get("maxmemory").await.unwrap()[0].1);
+
+    // 修改后的内存上限立即用于淘汰：noeviction 时写命令返回 OOM
+    client.set("key", "value".into(), None).await.unwrap();
+    client.config_set("maxmemory-policy", "noeviction").await.unwrap();
+    client.config_set("maxmemory", "1").await.unwrap();
+    let err = client.set("other", "value".into(), None).await.unwrap_err();
+    assert!(err.to_string().starts_with("OOM"));
+
+    client.config_set("maxmemory", "0").await.unwrap();
+    client.set("other", "value".into(), None).await.unwrap();
+}
+
+#[tokio::test]
+async fn lru_eviction_samples_keys() {
+    let mut client = connect().await;
+
+    assert_eq!("5", client.config_get("maxmemory-samples").await.unwrap()[0].1);
+    let err = client.config_set("maxmemory-samples", "0").await.unwrap_err();
+    assert!(err.to_string().contains("at least 1"), "{}", err);
+
+    for i in 0..10 {
+        client.set(&format!("key{}", i), "value".into(), None).await.unwrap();
+    }
+    // key0 最近被访问过，key1 最久没有被访问
+    client.get("key0").await.unwrap();
+
+    // 抽样数远大于 key 的个数时几乎一定抽到 key1，下一次写入之前淘汰它
+    let info = client.info(Some("memory".to_string())).await.unwrap();
+    let info = String::from_utf8(info.to_vec()).unwrap();
+    let used_memory: usize = info
+        .lines()
+        .find_map(|line| line.strip_prefix("used_memory:"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    client.config_set("maxmemory-samples", "100").await.unwrap();
+    client.config_set("maxmemory-policy", "allkeys-lru").await.unwrap();
+    client.config_set("maxmemory", &(used_memory - 1).to_string()).await.unwrap();
+    client.set("key10", "value".into(), None).await.unwrap();
+
+    assert_eq!(None, client.get("key1").await.unwrap());
+    for i in [0, 2, 9, 10] {
+        assert!(client.get(&format!("key{}", i)).await.unwrap().is_some(), "key{}", i);
+    }
+}
+
+#[tokio::test]
+async fn zero_databases_is_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = ServerConfig { databases: 0, ..ServerConfig::default() };
+
+    // 没有数据库时 SELECT 与淘汰都无从进行，run 直接返回而不是开始接收连接
+    let run = server_start::run(listener, None, config, std::future::pending::<()>());
+    time::timeout(Duration::from_secs(1), run).await.unwrap();
+}
+
+#[tokio::test]
+async fn object_idletime_and_freq() {
+    let mut client = connect().await;
+
+    client.set("key", "value".into(), None).await.unwrap();
+    assert_eq!(0, client.object_idletime("key").await.unwrap());
+    assert!(client.object_idletime("missing").await.is_err());
+    // 默认策略不统计访问频率
+    assert!(client.object_freq("key").await.is_err());
+
+    client.config_set("maxmemory-policy", "allkeys-lfu").await.unwrap();
+    assert!(client.object_idletime("key").await.is_err());
+    // 新 key 的计数从 5 开始，第一次访问一定加 1，之后增长越来越慢
+    assert_eq!(5, client.object_freq("key").await.unwrap());
+    client.get("key").await.unwrap();
+    assert_eq!(6, client.object_freq("key").await.unwrap());
+    for _ in 0..100 {
+        client.get("key").await.unwrap();
+    }
+    let frequency = client.object_freq("key").await.unwrap();
+    assert!((6..=20).contains(&frequency), "{}", frequency);
+}
+
+#[tokio::test]
+async fn empty_request_is_ignored() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    // 空数组没有回复，紧接着的 PING 正常执行
+    stream.write_all(b"*0\r\n*1\r\n$4\r\nPING\r\n").await.unwrap();
+    let mut response = [0; 7];
+    time::timeout(Duration::from_secs(1), stream.read_exact(&mut response)).await.unwrap().unwrap();
+    assert_eq!(b"+PONG\r\n", &response);
+}
+
+#[tokio::test]
+async fn oversized_multibulk_is_rejected() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    // 只发送长度前缀，服务器不等待元素，也不按这个长度分配内存，直接回复错误并关闭连接
+    stream.write_all(b"*999999999\r\n").await.unwrap();
+    let mut response = vec![];
+    time::timeout(Duration::from_secs(1), stream.read_to_end(&mut response)).await.unwrap().unwrap();
+    assert_eq!(&b"-ERR protocol error; invalid multibulk length\r\n"[..], &response[..]);
+
+    // 其他连接不受影响
+    let mut client = Client::connect(addr).await.unwrap();
+    assert_eq!(Bytes::from("PONG"), client.ping(None).await.unwrap());
+}
+
+#[tokio::test]
+async fn oversized_bulk_is_rejected() {
+    let addr = start_server(ServerConfig { proto_max_bulk_len: 64, ..ServerConfig::default() }).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.set("key", Bytes::from(vec![b'a'; 64]), None).await.unwrap();
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$65\r\n").await.unwrap();
+    let mut response = vec![];
+    time::timeout(Duration::from_secs(1), stream.read_to_end(&mut response)).await.unwrap().unwrap();
+    assert_eq!(&b"-ERR protocol error; invalid bulk length\r\n"[..], &response[..]);
+
+    // 上限可以在运行时修改
+    client.config_set("proto-max-bulk-len", "1048576").await.unwrap();
+    client.set("key", Bytes::from(vec![b'a'; 65]), None).await.unwrap();
+}
+
+#[tokio::test]
+async fn proto_limits_have_a_minimum() {
+    let mut client = connect().await;
+
+    // 太小的上限会让之后所有请求都被断开，CONFIG SET 拒绝它们并保留原值
+    for (name, value) in [("proto-max-multibulk-len", "0"), ("proto-max-bulk-len", "1048575")] {
+        let err = client.config_set(name, value).await.unwrap_err();
+        assert!(err.to_string().starts_with("ERR CONFIG SET failed"), "{}", err);
+        assert!(err.to_string().contains("at least"), "{}", err);
+    }
+    assert_eq!("1048576", client.config_get("proto-max-multibulk-len").await.unwrap()[0].1);
+    assert_eq!("536870912", client.config_get("proto-max-bulk-len").await.unwrap()[0].1);
+
+    client.config_set("proto-max-bulk-len", "1048576").await.unwrap();
+    client.config_set("proto-max-multibulk-len", "3").await.unwrap();
+    client.set("key", "value".into(), None).await.unwrap();
+    assert_eq!(Some("value".into()), client.get("key").await.unwrap());
+}
+
+#[tokio::test]
+async fn bad_command_keeps_connection_open() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+
+    // 选项无法解析：回复语法错误，连接不关闭
+    send(&mut connection, &["lpos", "list", "a", "bogus", "1"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Error(msg) if msg == "ERR syntax error"));
+
+    // 参数不是整数
+    send(&mut connection, &["setbit", "key", "not-a-number", "1"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Error(msg) if msg.starts_with("ERR ")));
+
+    send(&mut connection, &["ping"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(reply == "PONG");
+}
+
+// 每次读取只返回一个字节的流，写入的数据直接丢弃，用来检查 read_frame 能否把分散到达的数据拼成完整的帧
+#[derive(Debug)]
+struct OneByteAtATime {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl AsyncRead for OneByteAtATime {
+    fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.pos < self.data.len() && buf.remaining() > 0 {
+            buf.put_slice(&[self.data[self.pos]]);
+            self.pos += 1;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for OneByteAtATime {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn read_frame_one_byte_at_a_time() {
+    let data = b"*4\r\n$3\r\nset\r\n$0\r\n\r\n$5\r\nva\r\nl\r\n$-1\r\n+OK\r\n".to_vec();
+    let mut connection = Connection::new(OneByteAtATime { data, pos: 0 });
+
+    let frame = connection.read_frame().await.unwrap().unwrap();
+    let parts = match frame {
+        Frame::Array(parts) => parts,
+        frame => panic!("unexpected frame {:?}", frame),
+    };
+    assert_eq!(4, parts.len());
+    assert!(parts[0] == "set");
+    assert!(parts[1] == "");
+    // 数据中的 \r\n 按长度读取，不当作帧的结尾
+    assert!(parts[2] == "va\r\nl");
+    assert!(matches!(parts[3], Frame::Null));
+
+    let frame = connection.read_frame().await.unwrap().unwrap();
+    assert!(frame == "OK");
+    assert!(connection.read_frame().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn read_frame_keeps_leftover_bytes() {
+    // 两个帧在一次读取中到达，第二个帧留在缓冲区中由下一次 read_frame 解析
+    let data = b"+first\r\n$6\r\nsecond\r\n".to_vec();
+    let mut connection = Connection::new(std::io::Cursor::new(data));
+
+    assert!(connection.read_frame().await.unwrap().unwrap() == "first");
+    assert!(connection.read_frame().await.unwrap().unwrap() == "second");
+    assert!(connection.read_frame().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn read_frame_timeout_outcomes() {
+    let timeout = Duration::from_millis(50);
+
+    let data = b"+first\r\n".to_vec();
+    let mut connection = Connection::new(std::io::Cursor::new(data));
+    assert!(matches!(connection.read_frame_timeout(timeout).await.unwrap(), ReadOutcome::Frame(frame) if frame == "first"));
+    assert!(matches!(connection.read_frame_timeout(timeout).await.unwrap(), ReadOutcome::Closed));
+
+    // 对端没有关闭也没有发送数据
+    let (client, mut server) = tokio::io::duplex(64);
+    let mut connection = Connection::new(client);
+    assert!(matches!(connection.read_frame_timeout(timeout).await.unwrap(), ReadOutcome::TimedOut));
+
+    // 超时时读到的半个帧保留下来，数据到齐后读出完整的帧
+    server.write_all(b"$5\r\nhel").await.unwrap();
+    assert!(matches!(connection.read_frame_timeout(timeout).await.unwrap(), ReadOutcome::TimedOut));
+    server.write_all(b"lo\r\n").await.unwrap();
+    assert!(matches!(connection.read_frame_timeout(timeout).await.unwrap(), ReadOutcome::Frame(frame) if frame == "hello"));
+
+    // 在帧的中间关闭仍然是错误
+    server.write_all(b"+partial").await.unwrap();
+    drop(server);
+    assert!(connection.read_frame_timeout(timeout).await.is_err());
+}
+
+#[tokio::test]
+async fn integers_use_resp_colon_prefix() {
+    // 写出的整数是 RESP2 的 :<n>，负数带符号
+    let (client, mut server) = tokio::io::duplex(64);
+    let mut connection = Connection::new(client);
+    connection.write_frame(&Frame::Integer(-2)).await.unwrap();
+    connection.write_frame(&Frame::Integer(42)).await.unwrap();
+    let mut written = [0; 10];
+    server.read_exact(&mut written).await.unwrap();
+    assert_eq!(b":-2\r\n:42\r\n", &written);
+
+    // 读取时 : 按有符号整数解析
+    let data = b":-2\r\n:9223372036854775807\r\n".to_vec();
+    let mut connection = Connection::new(std::io::Cursor::new(data));
+    assert!(matches!(connection.read_frame().await.unwrap(), Some(Frame::Integer(-2))));
+    assert!(matches!(connection.read_frame().await.unwrap(), Some(Frame::Integer(i64::MAX))));
+
+    // 服务器的整数回复
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    assert_raw_reply(&mut stream, &["sadd", "set", "a", "b"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["scard", "set"], b":2\r\n").await;
+    assert_raw_reply(&mut stream, &["sismember", "set", "a"], b":1\r\n").await;
+    assert_raw_reply(&mut stream, &["ttl", "missing"], b":-2\r\n").await;
+}
+
+#[tokio::test]
+async fn null_bulk_and_null_array() {
+    // 空字符串是 $-1，空数组是 *-1
+    let (client, mut server) = tokio::io::duplex(64);
+    let mut connection = Connection::new(client);
+    connection.write_frame(&Frame::Null).await.unwrap();
+    connection.write_frame(&Frame::NullArray).await.unwrap();
+    let mut written = [0; 10];
+    server.read_exact(&mut written).await.unwrap();
+    assert_eq!(b"$-1\r\n*-1\r\n", &written);
+
+    let mut connection = Connection::new(std::io::Cursor::new(written.to_vec()));
+    assert!(matches!(connection.read_frame().await.unwrap(), Some(Frame::Null)));
+    assert!(matches!(connection.read_frame().await.unwrap(), Some(Frame::NullArray)));
+
+    // GET 不存在的 key 回复 $-1，被放弃的事务回复 *-1
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut other = Client::connect(addr).await.unwrap();
+    assert_raw_reply(&mut stream, &["get", "missing"], b"$-1\r\n").await;
+    assert_raw_reply(&mut stream, &["watch", "k"], b"+OK\r\n").await;
+    other.set("k", "1".into(), None).await.unwrap();
+    assert_raw_reply(&mut stream, &["multi"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["get", "k"], b"+QUEUED\r\n").await;
+    assert_raw_reply(&mut stream, &["exec"], b"*-1\r\n").await;
+}
+
+#[tokio::test]
+async fn hello() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    let info = client.hello(Some(2)).await.unwrap();
+    let proto = info.iter().find(|(key, _)| key == "proto").map(|(_, value)| value);
+    assert!(matches!(proto, Some(Frame::Integer(2))), "{:?}", info);
+    assert!(info.iter().any(|(key, value)| key == "server" && *value == "nano-redis"), "{:?}", info);
+    assert_eq!(info.len(), client.hello(None).await.unwrap().len());
+
+    let err = client.hello(Some(3)).await.unwrap_err();
+    assert!(err.to_string().contains("NOPROTO"), "{}", err);
+
+    // redis-py 等客户端按 RESP2 解析握手回复，proto 必须是 :2
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let version = env!("CARGO_PKG_VERSION");
+    let expected = format!(
+        "*10\r\n$6\r\nserver\r\n$10\r\nnano-redis\r\n$7\r\nversion\r\n${}\r\n{}\r\n\
+         $5\r\nproto\r\n:2\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n$4\r\nrole\r\n$6\r\nmaster\r\n",
+        version.len(),
+        version
+    );
+    assert_raw_reply(&mut stream, &["hello", "2"], expected.as_bytes()).await;
+}
+
+#[tokio::test]
+async fn read_frame_rejects_bulk_length_mismatch() {
+    // 声明的长度比实际数据短，数据后面不是 \r\n
+    let data = b"$3\r\nhello\r\n".to_vec();
+    let mut connection = Connection::new(std::io::Cursor::new(data));
+    assert!(connection.read_frame().await.is_err());
+}
+
+#[tokio::test]
+async fn subscribe_acks_arrive_before_messages() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut subscriber = raw_connect(addr).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // 还没有读取订阅回复就开始发布，消息也只能排在所有回复之后
+    send(&mut subscriber, &["subscribe", "a", "b", "a"]).await;
+    while client.publish("a", "hello".into()).await.unwrap() == 0 {
+        tokio::task::yield_now().await;
+    }
+
+    // 重复的频道同样回复，但订阅数不增加
+    assert_eq!(vec!["subscribe", "a", "1"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["subscribe", "b", "2"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["subscribe", "a", "2"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["message", "a", "hello"], read_array(&mut subscriber).await);
+
+    send(&mut subscriber, &["unsubscribe", "b", "c"]).await;
+    assert_eq!(vec!["unsubscribe", "b", "1"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["unsubscribe", "c", "1"], read_array(&mut subscriber).await);
+}
+
+#[tokio::test]
+async fn lmpop() {
+    let mut client = connect().await;
+    let keys = vec!["empty".to_string(), "list".to_string()];
+
+    assert_eq!(None, client.lmpop(keys.clone(), false, None).await.unwrap());
+
+    client.push("list", vec!["a".into(), "b".into(), "c".into()], true).await.unwrap();
+    // 第一个 key 不存在，从第二个弹出
+    assert_eq!(
+        Some(("list".to_string(), vec![Bytes::from("a")])),
+        client.lmpop(keys.clone(), false, None).await.unwrap()
+    );
+    assert_eq!(
+        Some(("list".to_string(), vec![Bytes::from("c")])),
+        client.lmpop(keys.clone(), true, Some(1)).await.unwrap()
+    );
+
+    // COUNT 大于列表长度时返回剩下的全部元素，列表弹空后 key 被删除
+    client.push("other", vec!["x".into()], true).await.unwrap();
+    assert_eq!(
+        Some(("list".to_string(), vec![Bytes::from("b")])),
+        client.lmpop(vec!["list".into(), "other".into()], false, Some(10)).await.unwrap()
+    );
+    assert_eq!(None, client.get("list").await.unwrap());
+    assert_eq!(
+        Some(("other".to_string(), vec![Bytes::from("x")])),
+        client.lmpop(vec!["list".into(), "other".into()], false, Some(10)).await.unwrap()
+    );
+
+    assert!(client.lmpop(keys.clone(), false, Some(0)).await.is_err());
+    client.set("string", "value".into(), None).await.unwrap();
+    assert!(client.lmpop(vec!["string".into()], false, None).await.is_err());
+}
+
+#[tokio::test]
+async fn smismember() {
+    let mut client = connect().await;
+
+    // key 不存在时全部为 false
+    assert_eq!(vec![false, false], client.smismember("set", vec!["a".into(), "b".into()]).await.unwrap());
+
+    client.sadd("set", vec!["a".into(), "c".into()]).await.unwrap();
+    assert_eq!(
+        vec![true, false, true, true],
+        client.smismember("set", vec!["a".into(), "b".into(), "c".into(), "a".into()]).await.unwrap()
+    );
+
+    client.set("string", "value".into(), None).await.unwrap();
+    assert!(client.smismember("string", vec!["a".into()]).await.is_err());
+}
+
+#[tokio::test]
+async fn set_options() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let nx = SetOptions { condition: SetCondition::IfAbsent, ..SetOptions::default() };
+    let xx = SetOptions { condition: SetCondition::IfPresent, ..SetOptions::default() };
+
+    // NX 只在 key 不存在时写入
+    assert!(client.set_with("k", "a".into(), nx).await.unwrap());
+    assert!(!client.set_with("k", "b".into(), nx).await.unwrap());
+    assert_eq!(Some(Bytes::from("a")), client.get("k").await.unwrap());
+
+    // XX 只在 key 已存在时写入
+    assert!(client.set_with("k", "b".into(), xx).await.unwrap());
+    assert!(!client.set_with("missing", "b".into(), xx).await.unwrap());
+    assert_eq!(Some(Bytes::from("b")), client.get("k").await.unwrap());
+    assert_eq!(None, client.get("missing").await.unwrap());
+
+    // GET 返回原来的值
+    assert_eq!(None, client.set_get("g", "1".into(), SetOptions::default()).await.unwrap());
+    assert_eq!(Some(Bytes::from("1")), client.set_get("g", "2".into(), SetOptions::default()).await.unwrap());
+    assert_eq!(Some(Bytes::from("2")), client.get("g").await.unwrap());
+
+    // XX GET 对不存在的 key 返回 nil 且不写入
+    assert_eq!(None, client.set_get("missing", "v".into(), xx).await.unwrap());
+    assert_eq!(None, client.get("missing").await.unwrap());
+
+    // NX GET 对已存在的 key 返回原来的值且不写入
+    assert_eq!(Some(Bytes::from("2")), client.set_get("g", "3".into(), nx).await.unwrap());
+    assert_eq!(Some(Bytes::from("2")), client.get("g").await.unwrap());
+
+    // XX GET 对已存在的 key 返回原来的值并写入
+    assert_eq!(Some(Bytes::from("2")), client.set_get("g", "4".into(), xx).await.unwrap());
+    assert_eq!(Some(Bytes::from("4")), client.get("g").await.unwrap());
+
+    // GET 遇到不是字符串的值时返回错误且不写入
+    client.push("list", vec!["x".to_string()], false).await.unwrap();
+    assert!(client.set_get("list", "v".into(), SetOptions::default()).await.is_err());
+    assert!(client.lrange("list", 0, 0).await.unwrap().is_some());
+
+    // 互斥的选项回复语法错误
+    let mut connection = raw_connect(addr).await;
+    for args in [
+        &["set", "k", "v", "nx", "xx"][..],
+        &["set", "k", "v", "ex", "10", "keepttl"][..],
+        &["set", "k", "v", "px", "10", "ex", "10"][..],
+        &["set", "k", "v", "bogus"][..],
+    ] {
+        send(&mut connection, args).await;
+        let reply = connection.read_frame().await.unwrap().unwrap();
+        assert!(matches!(reply, Frame::Error(msg) if msg == "ERR syntax error"));
+    }
+}
+
+#[tokio::test]
+async fn set_with_trailing_expiry() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+    let mut client = Client::connect(addr).await.unwrap();
+
+    // SET key value <毫秒>
+    send(&mut connection, &["set", "k", "v", "50"]).await;
+    assert!(connection.read_frame().await.unwrap().unwrap() == "OK");
+    assert_eq!(Some(Bytes::from("v")), client.get("k").await.unwrap());
+    time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(None, client.get("k").await.unwrap());
+
+    // 没有过期时间时不过期
+    send(&mut connection, &["set", "k", "v"]).await;
+    connection.read_frame().await.unwrap();
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+
+    // 过期时间为 0 或负数时回复错误，不写入
+    for ms in ["0", "-5"] {
+        send(&mut connection, &["set", "other", "v", ms]).await;
+        let reply = connection.read_frame().await.unwrap().unwrap();
+        assert!(matches!(&reply, Frame::Error(msg) if msg == "ERR invalid expire time in 'set' command"), "{:?}", reply);
+    }
+    assert_eq!(None, client.get("other").await.unwrap());
+}
+
+#[tokio::test]
+async fn set_keepttl() {
+    let mut client = connect().await;
+    let keepttl = SetOptions { keep_ttl: true, ..SetOptions::default() };
+
+    // KEEPTTL 保留原来的过期时间，普通 SET 清除过期时间
+    client.set("kept", "a".into(), Some(Duration::from_millis(200))).await.unwrap();
+    client.set("cleared", "a".into(), Some(Duration::from_millis(200))).await.unwrap();
+    assert!(client.set_with("kept", "b".into(), keepttl).await.unwrap());
+    client.set("cleared", "b".into(), None).await.unwrap();
+    assert_eq!(Some(Bytes::from("b")), client.get("kept").await.unwrap());
+
+    time::sleep(Duration::from_millis(400)).await;
+    assert_eq!(None, client.get("kept").await.unwrap());
+    assert_eq!(Some(Bytes::from("b")), client.get("cleared").await.unwrap());
+}
+
+#[tokio::test]
+async fn set_without_options_clears_ttl() {
+    let mut client = connect().await;
+
+    assert_eq!(-2, client.ttl("k").await.unwrap());
+    client.set("k", "v".into(), Some(Duration::from_secs(100))).await.unwrap();
+    assert_eq!(100, client.ttl("k").await.unwrap());
+
+    // 不带选项的 SET 清除原来的过期时间
+    client.set("k", "v2".into(), None).await.unwrap();
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+
+    // KEEPTTL 保留原来的过期时间
+    client.set("k", "v3".into(), Some(Duration::from_secs(100))).await.unwrap();
+    let keepttl = SetOptions { keep_ttl: true, ..SetOptions::default() };
+    assert!(client.set_with("k", "v4".into(), keepttl).await.unwrap());
+    assert_eq!(100, client.ttl("k").await.unwrap());
+}
+
+#[tokio::test]
+async fn sort() {
+    let mut client = connect().await;
+    let values = ["10", "9", "2", "100", "-1.5"].iter().map(|s| s.to_string()).collect();
+    client.push("list", values, true).await.unwrap();
+    let strings = |values: Vec<Bytes>| -> Vec<String> {
+        values.into_iter().map(|value| String::from_utf8(value.to_vec()).unwrap()).collect()
+    };
+
+    // 默认按数值排序，ALPHA 按字节序排序
+    let sorted = client.sort("list", false, false, None).await.unwrap();
+    assert_eq!(vec!["-1.5", "2", "9", "10", "100"], strings(sorted));
+    let sorted = client.sort("list", true, false, None).await.unwrap();
+    assert_eq!(vec!["-1.5", "10", "100", "2", "9"], strings(sorted));
+    let sorted = client.sort("list", false, true, None).await.unwrap();
+    assert_eq!(vec!["100", "10", "9", "2", "-1.5"], strings(sorted));
+
+    // LIMIT 只返回其中的一段，count 为负数时取到末尾
+    let sorted = client.sort("list", false, false, Some((1, 2))).await.unwrap();
+    assert_eq!(vec!["2", "9"], strings(sorted));
+    let sorted = client.sort("list", false, false, Some((3, -1))).await.unwrap();
+    assert_eq!(vec!["10", "100"], strings(sorted));
+    assert!(client.sort("list", false, false, Some((10, 2))).await.unwrap().is_empty());
+
+    // 集合同样可以排序，key 不存在时返回空
+    client.sadd("set", vec!["b".to_string(), "a".to_string(), "c".to_string()]).await.unwrap();
+    let sorted = client.sort("set", true, true, None).await.unwrap();
+    assert_eq!(vec!["c", "b", "a"], strings(sorted));
+    assert!(client.sort("missing", false, false, None).await.unwrap().is_empty());
+
+    // 不按 ALPHA 排序时，元素必须都能转换为数值
+    assert!(client.sort("set", false, false, None).await.is_err());
+    client.set("string", "1".into(), None).await.unwrap();
+    assert!(client.sort("string", false, false, None).await.is_err());
+}
+
+#[tokio::test]
+async fn getex_options() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut client = Client::connect(addr).await.unwrap();
+    let mut connection = raw_connect(addr).await;
+    client.set("k", "v".into(), None).await.unwrap();
+
+    // 没有选项时不修改过期时间
+    assert_eq!(Some(Bytes::from("v")), client.getex("k", Expiry::Keep).await.unwrap());
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+
+    // EX 以秒设置过期时间
+    send(&mut connection, &["getex", "k", "EX", "100"]).await;
+    assert!(connection.read_frame().await.unwrap().unwrap() == "v");
+    assert!((99..=100).contains(&client.ttl("k").await.unwrap()));
+
+    // PX 以毫秒设置过期时间
+    send(&mut connection, &["getex", "k", "px", "50000"]).await;
+    assert!(connection.read_frame().await.unwrap().unwrap() == "v");
+    assert!((49..=50).contains(&client.ttl("k").await.unwrap()));
+
+    // PERSIST 删除过期时间
+    assert_eq!(Some(Bytes::from("v")), client.getex("k", Expiry::Persist).await.unwrap());
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+
+    // key 不存在时回复 nil，不是字符串时回复 WRONGTYPE
+    assert_eq!(None, client.getex("missing", Expiry::After(Duration::from_secs(10))).await.unwrap());
+    assert_eq!(-2, client.ttl("missing").await.unwrap());
+    client.push("list", vec!["a".into()], true).await.unwrap();
+    let err = client.getex("list", Expiry::Persist).await.unwrap_err();
+    assert!(err.to_string().starts_with("WRONGTYPE"), "{}", err);
+
+    // 过期时间不是正数时回复错误
+    send(&mut connection, &["getex", "k", "ex", "0"]).await;
+    assert!(matches!(connection.read_frame().await.unwrap().unwrap(), Frame::Error(_)));
+    assert_eq!(-1, client.ttl("k").await.unwrap());
+}
+
+#[tokio::test]
+async fn expiretime() {
+    let mut client = connect().await;
+
+    assert_eq!(-2, client.expiretime("k", false).await.unwrap());
+    client.set("k", "v".into(), None).await.unwrap();
+    assert_eq!(-1, client.expiretime("k", true).await.unwrap());
+
+    // 过期时刻换算成系统时间后与预期的时间戳相差不超过 1 秒
+    let expected = std::time::SystemTime::now() + Duration::from_secs(100);
+    let expected = expected.duration_since(std::time::UNIX_EPOCH).unwrap();
+    client.set("k", "v".into(), Some(Duration::from_secs(100))).await.unwrap();
+    let secs = client.expiretime("k", false).await.unwrap();
+    assert!((secs - expected.as_secs() as i64).abs() <= 1);
+    let ms = client.expiretime("k", true).await.unwrap();
+    assert!((ms - expected.as_millis() as i64).abs() <= 1000);
+}
+
+#[tokio::test]
+async fn time() {
+    let mut client = connect().await;
+
+    let (secs, micros) = client.time().await.unwrap();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap();
+    assert!((secs as i64 - now.as_secs() as i64).abs() <= 2);
+    assert!(micros < 1_000_000);
+}
+
+#[tokio::test]
+async fn hsetnx() {
+    let mut client = connect().await;
+
+    assert!(client.hsetnx("hash", "field".into(), "a".into()).await.unwrap());
+    assert!(!client.hsetnx("hash", "field".into(), "b".into()).await.unwrap());
+    assert_eq!(vec![Some(Bytes::from("a"))], client.hmget("hash", vec!["field".into()]).await.unwrap());
+
+    client.set("string", "v".into(), None).await.unwrap();
+    assert!(client.hsetnx("string", "field".into(), "a".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn concurrent_hsetnx_has_single_winner() {
+    let addr = start_server(ServerConfig::default()).await;
+
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            tokio::spawn(async move {
+                let mut client = Client::connect(addr).await.unwrap();
+                client.hsetnx("hash", "owner".into(), Bytes::from(format!("owner-{}", i))).await.unwrap()
+            })
+        })
+        .collect();
+
+    let mut winners = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            winners += 1;
+        }
+    }
+    assert_eq!(1, winners);
+}
+
+#[tokio::test]
+async fn execute_returns_raw_reply() {
+    let mut client = connect().await;
+
+    let reply = client.execute(vec!["set".into(), "k".into(), "v".into()]).await.unwrap();
+    assert!(reply == "OK");
+    let reply = client.execute(vec!["GET".into(), "k".into()]).await.unwrap();
+    assert!(matches!(reply, Frame::Bulk(value) if value == "v"));
+
+    // 服务器回复的错误作为帧返回，连接仍然可用
+    let reply = client.execute(vec!["nosuchcommand".into()]).await.unwrap();
+    assert!(matches!(reply, Frame::Error(_)));
+    let reply = client.execute(vec!["ping".into()]).await.unwrap();
+    assert!(reply == "PONG");
+}
+
+#[tokio::test]
+async fn arity_errors() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+
+    // 命令表中的每个命令：参数太少，以及固定参数个数的命令参数太多
+    for spec in COMMANDS {
+        let required = spec.arity.unsigned_abs() as usize;
+        let mut cases = vec![];
+        if required > 1 {
+            cases.push(required - 1);
+        }
+        if spec.arity > 0 {
+            cases.push(required + 1);
+        }
+        for argc in cases {
+            let mut args = vec![spec.name];
+            args.resize(argc, "x");
+            send(&mut connection, &args).await;
+            let reply = connection.read_frame().await.unwrap().unwrap();
+            let expected = format!("ERR wrong number of arguments for '{}' command", spec.name);
+            assert!(matches!(&reply, Frame::Error(msg) if *msg == expected), "{:?}: {:?}", args, reply);
+        }
+    }
+}
+
+#[tokio::test]
+async fn command_names_ignore_case() {
+    let mut client = connect().await;
+
+    let reply = client.execute(vec!["SeT".into(), "k".into(), "v".into()]).await.unwrap();
+    assert!(reply == "OK");
+    let reply = client.execute(vec!["gEt".into(), "k".into()]).await.unwrap();
+    assert!(matches!(reply, Frame::Bulk(value) if value == "v"));
+
+    // 错误信息中的命令名统一为小写
+    let reply = client.execute(vec!["GET".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("for 'get' command")), "{:?}", reply);
+    let reply = client.execute(vec!["NoSuchCommand".into()]).await.unwrap();
+    assert!(matches!(&reply, Frame::Error(err) if err.contains("'nosuchcommand'")), "{:?}", reply);
+}
+
+#[tokio::test]
+async fn get_and_set_fast_path_match_full_parse() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    // 直接构造命令的 GET/SET 与按一般流程解析的形式回复相同
+    assert_raw_reply(&mut stream, &["SET", "k", "v"], b"+OK\r\n").await;
+    assert_raw_reply(&mut stream, &["set", "k", "w", "GET"], b"$1\r\nv\r\n").await;
+    assert_raw_reply(&mut stream, &["Get", "k"], b"$1\r\nw\r\n").await;
+    assert_raw_reply(&mut stream, &["get", "missing"], b"$-1\r\n").await;
+    assert_raw_reply(&mut stream, &["get", "k", "extra"], b"-ERR wrong number of arguments for 'get' command\r\n").await;
+
+    // 参数不是 bulk 时不走快速路径
+    let mut connection = raw_connect(addr).await;
+    let frame = Frame::Array(vec![Frame::Bulk("get".into()), Frame::Simple("k".to_string())]);
+    connection.write_frame(&frame).await.unwrap();
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(&reply, Frame::Bulk(value) if value == "w"), "{:?}", reply);
+
+    // key 不是 UTF-8 时与一般流程一样回复错误
+    stream.write_all(b"*2\r\n$3\r\nget\r\n$1\r\n\xff\r\n").await.unwrap();
+    let mut response = [0; 5];
+    time::timeout(Duration::from_secs(1), stream.read_exact(&mut response)).await.unwrap().unwrap();
+    assert_eq!(b"-ERR ", &response);
+}
+
+#[tokio::test]
+async fn unknown_subcommand_errors() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+
+    assert_raw_reply(
+        &mut stream,
+        &["config", "bogus"],
+        b"-ERR Unknown subcommand or wrong number of arguments for 'bogus'. Try CONFIG HELP.\r\n",
+    )
+    .await;
+    // 已知的子命令缺少参数同样回复这个错误
+    assert_raw_reply(
+        &mut stream,
+        &["object", "encoding"],
+        b"-ERR Unknown subcommand or wrong number of arguments for 'encoding'. Try OBJECT HELP.\r\n",
+    )
+    .await;
+    assert_raw_reply(&mut stream, &["ping"], b"+PONG\r\n").await;
+}
+
+#[tokio::test]
+async fn subcommand_help() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut connection = raw_connect(addr).await;
+
+    // DEBUG HELP 不需要 --enable-debug
+    for (command, subcommand) in [("object", "ENCODING"), ("client", "SETNAME"), ("config", "GET"), ("debug", "SLEEP")] {
+        send(&mut connection, &[command, "help"]).await;
+        let lines = read_array(&mut connection).await;
+        assert_eq!(
+            format!("{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:", command.to_uppercase()),
+            lines[0]
+        );
+        assert!(lines.iter().any(|line| line.starts_with(subcommand)));
+        assert_eq!("HELP", lines[lines.len() - 2]);
+    }
+
+    // HELP 不接受参数
+    send(&mut connection, &["object", "help", "extra"]).await;
+    let reply = connection.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Error(msg) if msg.contains("Try OBJECT HELP.")));
+}
+
+// 订阅者不读取时向频道发布大量消息，直到广播队列中未读的消息被覆盖
+async fn flood(addr: SocketAddr, channel: &str) {
+    let mut client = Client::connect(addr).await.unwrap();
+    let payload = Bytes::from(vec![b'x'; 4 * 1024]);
+    for _ in 0..10000 {
+        client.publish(channel, payload.clone()).await.unwrap();
+    }
+    client.publish(channel, "done".into()).await.unwrap();
+}
+
+// 读取订阅连接收到的消息，直到收到 "done" 或其他帧，返回之前收到的消息条数与最后一帧
+async fn drain_messages(connection: &mut Connection) -> (usize, Option<Frame>) {
+    let mut received = 0;
+    loop {
+        let frame = time::timeout(Duration::from_secs(5), connection.read_frame())
+            .await
+            .expect("timed out waiting for a frame")
+            .unwrap();
+        match frame {
+            Some(Frame::Array(parts)) if matches!(&parts[2], Frame::Bulk(message) if message == "done") => {
+                return (received, Some(Frame::Array(parts)));
+            }
+            Some(Frame::Array(_)) => received += 1,
+            frame => return (received, frame),
+        }
+    }
+}
+
+#[tokio::test]
+async fn slow_subscriber_skips_dropped_messages() {
+    let addr = start_server(ServerConfig::default()).await;
+    let mut subscriber = raw_connect(addr).await;
+    send(&mut subscriber, &["subscribe", "flood"]).await;
+    assert_eq!(vec!["subscribe", "flood", "1"], read_array(&mut subscriber).await);
+
+    flood(addr, "flood").await;
+
+    // 丢失了一部分消息，但连接仍然可用，之后的消息照常收到
+    let (received, last) = drain_messages(&mut subscriber).await;
+    assert!(received < 10000);
+    assert!(matches!(last, Some(Frame::Array(_))));
+}
+
+#[tokio::test]
+async fn slow_subscriber_disconnected() {
+    let config = ServerConfig {
+        pubsub_slow_client_policy: SlowClientPolicy::Disconnect,
+        ..ServerConfig::default()
+    };
+    let addr = start_server(config).await;
+    let mut subscriber = raw_connect(addr).await;
+    send(&mut subscriber, &["subscribe", "flood"]).await;
+    assert_eq!(vec!["subscribe", "flood", "1"], read_array(&mut subscriber).await);
+
+    flood(addr, "flood").await;
+
+    // 收到已经转发的消息之后是错误，然后连接关闭
+    let (received, last) = drain_messages(&mut subscriber).await;
+    assert!(received < 10000);
+    assert!(matches!(last, Some(Frame::Error(msg)) if msg.starts_with("ERR subscriber too slow")));
+    assert!(matches!(subscriber.read_frame().await, Ok(None) | Err(_)));
+}
+
+#[tokio::test]
+async fn subscription_limit() {
+    let config = ServerConfig {
+        pubsub_max_subscriptions: 2,
+        ..ServerConfig::default()
+    };
+    let addr = start_server(config).await;
+    let mut subscriber = raw_connect(addr).await;
+
+    // 达到上限后对新频道回复错误，不再订阅剩下的频道
+    send(&mut subscriber, &["subscribe", "a", "b", "c", "d"]).await;
+    assert_eq!(vec!["subscribe", "a", "1"], read_array(&mut subscriber).await);
+    assert_eq!(vec!["subscribe", "b", "2"], read_array(&mut subscriber).await);
+    let reply = subscriber.read_frame().await.unwrap().unwrap();
+    assert!(matches!(reply, Frame::Error(msg) if msg.starts_with("ERR max number of subscriptions")));
+
+    // 重复订阅已经订阅的频道不受上限影响
+    send(&mut subscriber, &["subscribe", "a"]).await;
+    assert_eq!(vec!["subscribe", "a", "2"], read_array(&mut subscriber).await);
+}